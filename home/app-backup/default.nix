@@ -1609,6 +1609,12 @@ in
       default = 3;
       description = "Number of timestamped backups to keep when restoring";
     };
+
+    rollbackMaxMb = mkOption {
+      type = types.int;
+      default = 1024;
+      description = "Size limit in MB of forge's restore rollback area (the newest rollback is always kept)";
+    };
   };
 
   config = mkIf cfg.enable {
@@ -1637,6 +1643,7 @@ in
       pkgs.git-lfs
       pkgs._1password-cli
       pkgs.openssh
+      pkgs.rsync
     ];
 
     # Generate the example configuration file
@@ -1655,6 +1662,25 @@ in
         # Optional settings
         LOCAL_REPO_PATH="${cfg.localRepoPath}"
         BACKUP_RETENTION=${toString cfg.backupRetention}
+        ROLLBACK_MAX_MB=${toString cfg.rollbackMaxMb}
+        #
+        # Where archives are stored: git (default), local, rsync or s3
+        # STORAGE_BACKEND="local"
+        # STORAGE_PATH="/run/media/$USER/BACKUP/app-backup"   # local
+        # RSYNC_TARGET="me@nas:/srv/backup/app-backup"        # rsync
+        # S3_ENDPOINT="http://nas:9000"                        # s3 (MinIO etc.)
+        # S3_BUCKET="backups"
+        # S3_PREFIX="laptop/"
+        # S3_REGION="us-east-1"
+        # S3_ACCESS_KEY_ID=""       # or AWS_ACCESS_KEY_ID
+        # S3_SECRET_ACCESS_KEY=""   # or AWS_SECRET_ACCESS_KEY
+        #
+        # Where forge fetches keys and passphrases: 1password (default),
+        # bitwarden, pass or file. The *_1PASSWORD references below also work
+        # as AGE_KEY_SECRET, SSH_KEY_SECRET and KEYS_PASSPHRASE_SECRET.
+        # SECRET_PROVIDER="pass"
+        # AGE_KEY_SECRET="keys/age"                    # pass entry / bw item[/field]
+        # SECRET_DIR="/run/media/$USER/KEYS/secrets"   # file provider
       '' + optionalString (cfg.ageKey1Password != null) ''
         #
         # 1Password reference for age private key
@@ -4,8 +4,14 @@
 # - NixOS config repo updates (changes from other machines)
 # - App profile updates (private-settings repo)
 # - Flake input updates (nixpkgs, home-manager, etc.)
+# - Overdue app profile backups (see 'forge apps schedule')
 #
-# Sends desktop notifications via libnotify when updates are available.
+# Sends notifications when updates are available: desktop (libnotify) by
+# default, or webhook / ntfy / journal sinks configured in notify.toml.
+#
+# Behaviour (check interval, watched inputs, quiet hours, urgency) can be
+# tuned in ~/.config/forge/notify.toml. The timer below only sets how often
+# forge-notify wakes up; check_interval_mins can space checks out further.
 { config, pkgs, lib, forge, ... }:
 
 {
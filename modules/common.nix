@@ -92,10 +92,10 @@
   # Disable NetworkManager-wait-online to speed up boot
   systemd.services.NetworkManager-wait-online.enable = lib.mkForce false;
 
-  # Timezone and locale
-  time.timeZone = "Europe/Amsterdam";
-  i18n.defaultLocale = "en_US.UTF-8";
-  i18n.extraLocaleSettings = {
+  # Timezone and locale (hosts may override, see `forge install`)
+  time.timeZone = lib.mkDefault "Europe/Amsterdam";
+  i18n.defaultLocale = lib.mkDefault "en_US.UTF-8";
+  i18n.extraLocaleSettings = lib.mkDefault {
     LC_ADDRESS = "nl_NL.UTF-8";
     LC_IDENTIFICATION = "nl_NL.UTF-8";
     LC_MEASUREMENT = "nl_NL.UTF-8";
@@ -167,14 +167,17 @@
   };
 
   # User configuration
-  # mutableUsers allows setting password with passwd after installation
+  # mutableUsers allows setting password with passwd after installation.
+  # It also means hashedPasswordFile only sets the password when the account
+  # is created; changing the file later does not change the password.
   users.mutableUsers = true;
   users.users.${username} = {
     isNormalUser = true;
     description = username;
     extraGroups = [ "networkmanager" "wheel" "video" "input" "docker" ];
     shell = pkgs.fish;
-    # No initialPassword - password set via Forge installer
+    # No initialPassword - the Forge installer sets hashedPasswordFile in the host
+    # config, which gives the account its first password at install time
   };
 
   # Enable Fish system-wide (required for login shell)
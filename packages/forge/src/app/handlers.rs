@@ -6,11 +6,13 @@ use std::mem;
 
 use super::state::*;
 use super::App;
+use crate::backup::shamir::Share;
 use crate::commands;
 use crate::commands::executor::run_capture;
 use crate::commands::update::{check_local_changes, get_default_branch};
 use crate::constants::nixos_config_dir;
 use crate::constants::MAX_INPUT_LENGTH;
+use crate::system::repo::RepoSource;
 use crate::system::hardware::{
     gpu_vendor_options, CpuInfo, CpuVendor, FormFactor, GpuInfo, GpuVendor,
 };
@@ -31,7 +33,7 @@ fn get_ram_size_gb() -> u64 {
                 if parts.len() >= 2 {
                     if let Ok(kb) = parts[1].parse::<u64>() {
                         // Convert KB to GB, rounded up
-                        return (kb + 1024 * 1024 - 1) / (1024 * 1024);
+                        return kb.div_ceil(1024 * 1024);
                     }
                 }
             }
@@ -41,6 +43,57 @@ fn get_ram_size_gb() -> u64 {
     8
 }
 
+/// Index of the layout's scheme in the layout screen
+fn layout_index(layout: &DiskLayout) -> usize {
+    PartitionScheme::ALL.iter().position(|s| *s == layout.scheme).unwrap_or(0)
+}
+
+/// Keys on a layout screen; returns true when the layout is confirmed
+fn handle_layout_key(key: KeyEvent, layout: &mut DiskLayout, selected: &mut usize) -> bool {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => {
+            *selected = selected.saturating_sub(1);
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            *selected = (*selected + 1).min(PartitionScheme::ALL.len() - 1);
+        }
+        KeyCode::Tab | KeyCode::Char(' ') => {
+            // Room to hibernate: RAM + 2GB
+            layout.swap_gb = match layout.swap_gb {
+                Some(_) => None,
+                None => Some(get_ram_size_gb() + 2),
+            };
+        }
+        KeyCode::Enter => {
+            layout.scheme = PartitionScheme::ALL[*selected];
+            return true;
+        }
+        _ => {}
+    }
+    false
+}
+
+/// Username rules shown while typing, so mistakes surface before Enter
+fn live_username_error(field: &CredentialField, username: &str) -> Option<String> {
+    if *field == CredentialField::Username && !username.is_empty() {
+        validate_username(username)
+    } else {
+        None
+    }
+}
+
+/// Enter on a disk: refuse blocked disks and make risky ones take a second Enter
+fn confirm_disk(disk: &crate::system::disk::DiskInfo, confirm: &mut bool) -> bool {
+    if disk.blocker().is_some() {
+        return false;
+    }
+    if disk.warnings().is_empty() || *confirm {
+        return true;
+    }
+    *confirm = true;
+    false
+}
+
 impl App {
     /// Handle keyboard input
     pub async fn handle_key(&mut self, key: KeyEvent) -> Result<()> {
@@ -77,13 +130,11 @@ impl App {
         // Handle commit list view
         if self.pending_updates.viewing_commits {
             match key.code {
-                KeyCode::Up | KeyCode::Char('k') => {
-                    if self.pending_updates.selected_commit > 0 {
-                        self.pending_updates.selected_commit -= 1;
-                        // Scroll up if selection above visible area
-                        if self.pending_updates.selected_commit < self.pending_updates.commit_scroll {
-                            self.pending_updates.commit_scroll = self.pending_updates.selected_commit;
-                        }
+                KeyCode::Up | KeyCode::Char('k') if self.pending_updates.selected_commit > 0 => {
+                    self.pending_updates.selected_commit -= 1;
+                    // Scroll up if selection above visible area
+                    if self.pending_updates.selected_commit < self.pending_updates.commit_scroll {
+                        self.pending_updates.commit_scroll = self.pending_updates.selected_commit;
                     }
                 }
                 KeyCode::Down | KeyCode::Char('j') => {
@@ -116,10 +167,8 @@ impl App {
         // Handle update dialog
         if self.pending_updates.has_updates() {
             match key.code {
-                KeyCode::Up | KeyCode::Char('k') => {
-                    if self.pending_updates.selected > 0 {
-                        self.pending_updates.selected -= 1;
-                    }
+                KeyCode::Up | KeyCode::Char('k') if self.pending_updates.selected > 0 => {
+                    self.pending_updates.selected -= 1;
                 }
                 KeyCode::Down | KeyCode::Char('j') => {
                     let max = get_update_dialog_option_count(&self.pending_updates) - 1;
@@ -144,9 +193,11 @@ impl App {
                 self.mode,
                 AppMode::MainMenu { .. }
                     | AppMode::Apps(AppProfileState::Menu { .. })
+                    | AppMode::Apps(AppProfileState::History { .. })
                     | AppMode::Apps(AppProfileState::Complete { .. })
                     | AppMode::Apps(AppProfileState::Status { .. })
                     | AppMode::Keys(KeysState::Complete { .. })
+                    | AppMode::Keys(KeysState::Status { .. })
                     | AppMode::Update(UpdateState::Complete { .. })
                     | AppMode::Install(InstallState::Complete { .. })
                     | AppMode::CreateHost(CreateHostState::Complete { .. })
@@ -167,18 +218,18 @@ impl App {
         }
 
         // Handle Ctrl+C to cancel running operations
-        if is_ctrl_c(&key) {
-            if matches!(
+        if is_ctrl_c(&key)
+            && matches!(
                 self.mode,
                 AppMode::Update(UpdateState::Running { .. })
                     | AppMode::Install(InstallState::Running { .. })
                     | AppMode::Apps(AppProfileState::Running { .. })
                     | AppMode::Keys(KeysState::Running { .. })
                     | AppMode::CreateHost(CreateHostState::Generating { .. })
-            ) {
-                self.cancel_operation();
-                return Ok(());
-            }
+            )
+        {
+            self.cancel_operation();
+            return Ok(());
         }
 
         // Handle local changes prompt dialog
@@ -216,16 +267,22 @@ impl App {
         // Extract values from mode to avoid borrow conflicts
         let action = match &self.mode {
             AppMode::MainMenu { selected } => Some(("main_menu", *selected, None, None)),
-            AppMode::Apps(AppProfileState::Menu { selected }) => {
+            AppMode::Apps(AppProfileState::Menu { selected, .. }) => {
                 Some(("browser_menu", *selected, None, None))
             }
+            AppMode::Apps(AppProfileState::History { selected, .. }) => {
+                Some(("app_history", *selected, None, None))
+            }
+            AppMode::Install(InstallState::Network { .. }) => Some(("install_network", 0, None, None)),
             AppMode::Install(InstallState::SelectHost { selected }) => {
                 Some(("install_host", *selected, None, None))
             }
+            AppMode::Install(InstallState::EditSource { .. }) => Some(("install_source", 0, None, None)),
             AppMode::Install(InstallState::SelectDisk {
                 host,
                 disks,
                 selected,
+                ..
             }) => Some((
                 "install_disk",
                 *selected,
@@ -235,20 +292,40 @@ impl App {
             AppMode::Install(InstallState::EnterCredentials { host, .. }) => {
                 Some(("install_credentials", 0, Some(host.clone()), None))
             }
+            AppMode::Install(InstallState::ExtraUsers { host, .. }) => {
+                Some(("install_users", 0, Some(host.clone()), None))
+            }
+            AppMode::Install(InstallState::SelectLayout { host, .. }) => {
+                Some(("install_layout", 0, Some(host.clone()), None))
+            }
             AppMode::Install(InstallState::SelectSwapMode { host, selected, .. }) => {
                 Some(("install_swap_mode", *selected, Some(host.clone()), None))
             }
+            AppMode::Install(InstallState::SelectKeySource { host, selected, .. }) => {
+                Some(("install_key_source", *selected, Some(host.clone()), None))
+            }
+            AppMode::Install(InstallState::SelectLocale { host, .. }) => {
+                Some(("install_locale", 0, Some(host.clone()), None))
+            }
             AppMode::Install(InstallState::Overview { host, disk: _, .. }) => {
                 Some(("install_overview", 0, Some(host.clone()), None))
             }
-            AppMode::Install(InstallState::Complete { success, .. }) => match key.code {
+            AppMode::Install(InstallState::Complete { success, checks, .. }) => match key.code {
                 KeyCode::Enter => Some(("complete", 0, None, None)),
-                KeyCode::Char('r') | KeyCode::Char('R') if *success => Some(("reboot", 0, None, None)),
+                // Only offer the reboot once the installed system checked out
+                KeyCode::Char('r') | KeyCode::Char('R') if *success && checks.iter().all(|c| c.passed) => {
+                    Some(("reboot", 0, None, None))
+                }
+                KeyCode::Up | KeyCode::Down => Some(("scroll", 0, None, None)),
+                _ => None,
+            },
+            AppMode::Apps(AppProfileState::Complete { undo_available, .. }) => match key.code {
+                KeyCode::Enter => Some(("complete", 0, None, None)),
+                KeyCode::Char('u') | KeyCode::Char('U') if *undo_available => Some(("app_undo", 0, None, None)),
                 KeyCode::Up | KeyCode::Down => Some(("scroll", 0, None, None)),
                 _ => None,
             },
             AppMode::Update(UpdateState::Complete { .. })
-            | AppMode::Apps(AppProfileState::Complete { .. })
             | AppMode::Keys(KeysState::Complete { .. }) => match key.code {
                 KeyCode::Enter => Some(("complete", 0, None, None)),
                 KeyCode::Up | KeyCode::Down => Some(("scroll", 0, None, None)),
@@ -261,6 +338,17 @@ impl App {
                     None
                 }
             }
+            AppMode::Keys(KeysState::Status { report, loading, .. }) => match key.code {
+                KeyCode::Enter => Some(("complete", 0, None, None)),
+                KeyCode::Char('f') | KeyCode::Char('F')
+                    if !*loading && report.as_ref().is_some_and(|r| r.fixable() > 0) =>
+                {
+                    Some(("keys_fix", 0, None, None))
+                }
+                KeyCode::Char('r') | KeyCode::Char('R') if !*loading => Some(("keys_refresh", 0, None, None)),
+                _ => None,
+            },
+            AppMode::Keys(KeysState::PaperRestore { .. }) => Some(("keys_paper_restore", 0, None, None)),
             AppMode::CreateHost(_) => Some(("create_host", 0, None, None)),
             _ => None,
         };
@@ -269,12 +357,21 @@ impl App {
             Some(("main_menu", selected, _, _)) => {
                 self.handle_main_menu_key(key, selected).await?;
             }
+            Some(("app_history", selected, _, _)) => {
+                self.handle_app_history_key(key, selected).await?;
+            }
             Some(("browser_menu", selected, _, _)) => {
                 self.handle_app_menu_key(key, selected).await?;
             }
+            Some(("install_network", _, _, _)) => {
+                self.handle_network_key(key).await?;
+            }
             Some(("install_host", selected, _, _)) => {
                 self.handle_install_host_key(key, selected).await?;
             }
+            Some(("install_source", _, _, _)) => {
+                self.handle_source_key(key).await?;
+            }
             Some(("install_disk", selected, Some(host), Some(disks))) => {
                 self.handle_install_disk_key(key, &host, &disks, selected)
                     .await?;
@@ -282,9 +379,21 @@ impl App {
             Some(("install_credentials", _, Some(host), _)) => {
                 self.handle_credentials_key(key, &host).await?;
             }
+            Some(("install_users", _, Some(_host), _)) => {
+                self.handle_extra_users_key(key).await?;
+            }
+            Some(("install_layout", _, Some(_host), _)) => {
+                self.handle_install_layout_key(key).await?;
+            }
             Some(("install_swap_mode", selected, Some(_host), _)) => {
                 self.handle_swap_mode_key(key, selected).await?;
             }
+            Some(("install_key_source", _, Some(_host), _)) => {
+                self.handle_key_source_key(key).await?;
+            }
+            Some(("install_locale", _, Some(_host), _)) => {
+                self.handle_locale_key(key).await?;
+            }
             Some(("install_overview", _, Some(host), _)) => {
                 self.handle_overview_key_action(key, &host).await?;
             }
@@ -299,8 +408,27 @@ impl App {
             Some(("scroll", _, _, _)) => {
                 self.handle_scroll(key);
             }
+            Some(("app_undo", _, _, _)) => {
+                self.mode = AppMode::Apps(AppProfileState::new_undo(false));
+                self.start_initial_command().await?;
+            }
+            Some(("keys_fix", _, _, _)) => {
+                if let AppMode::Keys(KeysState::Status { loading, .. }) = &mut self.mode {
+                    *loading = true;
+                }
+                if let Some(tx) = &self.cmd_tx {
+                    commands::keys::start_fix(tx.clone()).await?;
+                }
+            }
+            Some(("keys_refresh", _, _, _)) => {
+                self.mode = AppMode::Keys(KeysState::new_status());
+                self.start_initial_command().await?;
+            }
+            Some(("keys_paper_restore", _, _, _)) => {
+                self.handle_paper_restore_key(key).await?;
+            }
             Some(("browser_done", _, _, _)) => {
-                self.mode = AppMode::Apps(AppProfileState::Menu { selected: 0 });
+                self.mode = AppMode::Apps(AppProfileState::new_menu());
             }
             Some(("create_host", _, _, _)) => {
                 self.handle_create_host_key(key).await?;
@@ -334,7 +462,7 @@ impl App {
         if has_apps {
             if selected == idx {
                 self.pending_updates.clear();
-                self.mode = AppMode::Apps(AppProfileState::new_restore(false));
+                self.mode = AppMode::Apps(AppProfileState::new_restore(false, Vec::new(), None));
                 self.start_initial_command().await?;
                 return Ok(());
             }
@@ -397,12 +525,13 @@ impl App {
                         success: false,
                         output,
                         scroll_offset: None,
+                        checks: Vec::new(),
                     });
                 } else {
-                    // On live ISO, clone repository first to discover hosts
-                    self.mode = AppMode::Install(InstallState::CloneRepository {
+                    // On live ISO, get online, then clone repository to discover hosts
+                    self.mode = AppMode::Install(InstallState::new_network(InstallState::CloneRepository {
                         output: std::collections::VecDeque::new(),
-                    });
+                    }));
                     self.start_initial_command().await?;
                 }
             }
@@ -412,7 +541,7 @@ impl App {
             }
             2 => {
                 // App profiles
-                self.mode = AppMode::Apps(AppProfileState::Menu { selected: 0 });
+                self.mode = AppMode::Apps(AppProfileState::new_menu());
             }
             3 => {
                 // Exit
@@ -570,34 +699,60 @@ impl App {
     }
 
     async fn handle_app_menu_key(&mut self, key: KeyEvent, selected: usize) -> Result<()> {
+        let AppMode::Apps(AppProfileState::Menu { apps, .. }) = &self.mode else {
+            return Ok(());
+        };
+        let item_count = APP_MENU_ITEMS.len() + apps.len();
+        let checked: Vec<String> = apps
+            .iter()
+            .filter(|a| a.checked)
+            .map(|a| a.id.to_string())
+            .collect();
+
         match key.code {
             KeyCode::Up | KeyCode::Char('k') => {
-                if let AppMode::Apps(AppProfileState::Menu { selected }) = &mut self.mode {
+                if let AppMode::Apps(AppProfileState::Menu { selected, .. }) = &mut self.mode {
                     *selected = selected.saturating_sub(1);
                 }
             }
             KeyCode::Down | KeyCode::Char('j') => {
-                if let AppMode::Apps(AppProfileState::Menu { selected }) = &mut self.mode {
-                    *selected = (*selected + 1).min(APP_MENU_ITEMS.len() - 1);
+                if let AppMode::Apps(AppProfileState::Menu { selected, .. }) = &mut self.mode {
+                    *selected = (*selected + 1).min(item_count - 1);
+                }
+            }
+            KeyCode::Char(' ') | KeyCode::Enter if selected >= APP_MENU_ITEMS.len() => {
+                // Toggle the app under the cursor
+                if let AppMode::Apps(AppProfileState::Menu { apps, .. }) = &mut self.mode {
+                    if let Some(app) = apps.get_mut(selected - APP_MENU_ITEMS.len()) {
+                        app.checked = !app.checked;
+                    }
                 }
             }
             KeyCode::Enter => match selected {
+                0..=2 if checked.is_empty() => {
+                    // Nothing to do until at least one app is checked
+                }
                 0 => {
                     // Backup
-                    self.mode = AppMode::Apps(AppProfileState::new_backup(false));
+                    self.mode = AppMode::Apps(AppProfileState::new_backup(false, checked));
                     self.start_initial_command().await?;
                 }
                 1 => {
                     // Restore
-                    self.mode = AppMode::Apps(AppProfileState::new_restore(false));
+                    self.mode = AppMode::Apps(AppProfileState::new_restore(false, checked, None));
                     self.start_initial_command().await?;
                 }
                 2 => {
+                    // Restore from history
+                    self.mode = AppMode::Apps(AppProfileState::new_history(checked));
+                    self.start_initial_command().await?;
+                }
+                3 => {
                     // Status
                     self.mode = AppMode::Apps(AppProfileState::new_status());
                     self.start_initial_command().await?;
                 }
-                3 => {
+                4 => {
                     // Back
                     self.mode = AppMode::MainMenu { selected: 2 };
                 }
@@ -608,6 +763,36 @@ impl App {
         Ok(())
     }
 
+    async fn handle_app_history_key(&mut self, key: KeyEvent, selected: usize) -> Result<()> {
+        let AppMode::Apps(AppProfileState::History { snapshots, apps, .. }) = &self.mode else {
+            return Ok(());
+        };
+        let count = snapshots.len();
+        let chosen = snapshots.get(selected).map(|s| s.hash.clone());
+        let apps = apps.clone();
+
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                if let AppMode::Apps(AppProfileState::History { selected, .. }) = &mut self.mode {
+                    *selected = selected.saturating_sub(1);
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                if let AppMode::Apps(AppProfileState::History { selected, .. }) = &mut self.mode {
+                    *selected = (*selected + 1).min(count.saturating_sub(1));
+                }
+            }
+            KeyCode::Enter => {
+                if let Some(hash) = chosen {
+                    self.mode = AppMode::Apps(AppProfileState::new_restore(false, apps, Some(hash)));
+                    self.start_initial_command().await?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
     async fn handle_install_host_key(&mut self, key: KeyEvent, selected: usize) -> Result<()> {
         match key.code {
             KeyCode::Up | KeyCode::Char('k') => {
@@ -633,10 +818,75 @@ impl App {
                         host,
                         disks: Vec::new(),
                         selected: 0,
+                        confirm: false,
                     });
                     self.start_initial_command().await?;
                 }
             }
+            // An offline install can only use its bundle
+            KeyCode::Char('s') | KeyCode::Char('S') if self.offline.is_none() => {
+                self.mode = AppMode::Install(InstallState::EditSource {
+                    selected,
+                    url: self.repo_source.url.clone(),
+                    git_ref: self.repo_source.git_ref.clone().unwrap_or_default(),
+                    field: SourceField::Url,
+                    error: None,
+                });
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Repository and ref editor; a changed source is cloned again to list its hosts
+    async fn handle_source_key(&mut self, key: KeyEvent) -> Result<()> {
+        let AppMode::Install(InstallState::EditSource {
+            selected,
+            url,
+            git_ref,
+            field,
+            error,
+        }) = &mut self.mode
+        else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Tab | KeyCode::BackTab | KeyCode::Up | KeyCode::Down => {
+                *field = match field {
+                    SourceField::Url => SourceField::Ref,
+                    SourceField::Ref => SourceField::Url,
+                };
+            }
+            KeyCode::Char(c) => {
+                let value = match field {
+                    SourceField::Url => url,
+                    SourceField::Ref => git_ref,
+                };
+                if value.len() < MAX_INPUT_LENGTH * 2 {
+                    value.push(c);
+                }
+                *error = None;
+            }
+            KeyCode::Backspace => {
+                match field {
+                    SourceField::Url => url.pop(),
+                    SourceField::Ref => git_ref.pop(),
+                };
+                *error = None;
+            }
+            KeyCode::Enter => match RepoSource::new(Some(url), Some(git_ref)) {
+                Err(e) => *error = Some(e),
+                Ok(source) if source == self.repo_source => {
+                    self.mode = AppMode::Install(InstallState::SelectHost { selected: *selected });
+                }
+                Ok(source) => {
+                    self.repo_source = source;
+                    self.mode = AppMode::Install(InstallState::CloneRepository {
+                        output: std::collections::VecDeque::new(),
+                    });
+                    self.start_initial_command().await?;
+                }
+            },
             _ => {}
         }
         Ok(())
@@ -651,29 +901,99 @@ impl App {
     ) -> Result<()> {
         match key.code {
             KeyCode::Up | KeyCode::Char('k') => {
-                if let AppMode::Install(InstallState::SelectDisk { selected, .. }) = &mut self.mode
+                if let AppMode::Install(InstallState::SelectDisk { selected, confirm, .. }) =
+                    &mut self.mode
                 {
                     *selected = selected.saturating_sub(1);
+                    *confirm = false;
                 }
             }
             KeyCode::Down | KeyCode::Char('j') => {
-                if let AppMode::Install(InstallState::SelectDisk { selected, disks, .. }) =
+                if let AppMode::Install(InstallState::SelectDisk { selected, disks, confirm, .. }) =
                     &mut self.mode
                 {
                     if !disks.is_empty() {
                         *selected = (*selected + 1).min(disks.len() - 1);
                     }
+                    *confirm = false;
+                }
+            }
+            KeyCode::Enter if !disks.is_empty() => {
+                let AppMode::Install(InstallState::SelectDisk { confirm, .. }) = &mut self.mode
+                else {
+                    return Ok(());
+                };
+                if !confirm_disk(&disks[selected], confirm) {
+                    return Ok(());
+                }
+                self.mode = AppMode::Install(InstallState::EnterCredentials {
+                    host: host.to_string(),
+                    disk: disks[selected].clone(),
+                    credentials: InstallCredentials::for_host(host),
+                    active_field: CredentialField::Username,
+                    error: None,
+                });
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Collect typed-in paper key shares until the threshold is reached
+    async fn handle_paper_restore_key(&mut self, key: KeyEvent) -> Result<()> {
+        let AppMode::Keys(KeysState::PaperRestore {
+            shares,
+            input,
+            error,
+            force,
+        }) = &mut self.mode
+        else {
+            return Ok(());
+        };
+
+        match key.code {
+            KeyCode::Char(c) => {
+                if input.len() < MAX_INPUT_LENGTH {
+                    input.push(c);
                 }
+                *error = None;
+            }
+            KeyCode::Backspace => {
+                input.pop();
+                *error = None;
             }
             KeyCode::Enter => {
-                if !disks.is_empty() {
-                    self.mode = AppMode::Install(InstallState::EnterCredentials {
-                        host: host.to_string(),
-                        disk: disks[selected].clone(),
-                        credentials: InstallCredentials::default(),
-                        active_field: CredentialField::Username,
-                        error: None,
+                let share = match Share::decode(input) {
+                    Ok(share) => share,
+                    Err(e) => {
+                        *error = Some(e.to_string());
+                        return Ok(());
+                    }
+                };
+                if let Some(first) = shares.first().filter(|s| s.set_id != share.set_id) {
+                    *error = Some(format!(
+                        "Share is from set {:04X}, the others from {:04X}",
+                        share.set_id, first.set_id
+                    ));
+                    return Ok(());
+                }
+                if shares.iter().any(|s| s.index == share.index) {
+                    *error = Some(format!("Share {} was already entered", share.index));
+                    return Ok(());
+                }
+
+                input.clear();
+                let threshold = share.threshold as usize;
+                shares.push(share);
+                if shares.len() >= threshold {
+                    let shares = mem::take(shares);
+                    let force = *force;
+                    self.mode = AppMode::Keys(KeysState::Running {
+                        operation: KeysOp::PaperRestore { shares },
+                        output: std::collections::VecDeque::new(),
+                        force,
                     });
+                    self.start_initial_command().await?;
                 }
             }
             _ => {}
@@ -724,7 +1044,7 @@ impl App {
                         };
                         field.push(c);
                     }
-                    *error = None;
+                    *error = live_username_error(active_field, &credentials.username);
                 }
                 KeyCode::Backspace => {
                     let field = match active_field {
@@ -733,29 +1053,253 @@ impl App {
                         CredentialField::ConfirmPassword => &mut credentials.confirm_password,
                     };
                     field.pop();
-                    *error = None;
+                    *error = live_username_error(active_field, &credentials.username);
                 }
                 KeyCode::Enter => {
-                    // Validate and proceed to swap mode selection
+                    // Validate and proceed to additional users
                     if let Some(err) = validate_username(&credentials.username) {
                         *error = Some(err);
+                    } else if credentials.extra_users.iter().any(|u| u.username == credentials.username) {
+                        *error = Some(format!("'{}' is already an additional user", credentials.username));
                     } else if let Some(err) = validate_password(&credentials.password, &credentials.confirm_password) {
                         *error = Some(err);
                     } else {
-                        // All valid, proceed to swap mode selection
-                        // Get RAM size for display
-                        let ram_gb = get_ram_size_gb();
-                        self.mode = AppMode::Install(InstallState::SelectSwapMode {
-                            host: host.clone(),
-                            disk: disk.clone(),
-                            credentials: credentials.clone(),
-                            selected: 0,
-                            ram_gb,
-                        });
+                        // All valid, proceed to additional users
+                        self.mode = AppMode::Install(InstallState::extra_users(
+                            host.clone(),
+                            disk.clone(),
+                            credentials.clone(),
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_network_key(&mut self, key: KeyEvent) -> Result<()> {
+        enum NetworkAction {
+            Scan,
+            Connect(String, Option<String>),
+        }
+
+        let action = if let AppMode::Install(InstallState::Network {
+            networks,
+            selected,
+            passphrase,
+            busy,
+            error,
+            ..
+        }) = &mut self.mode
+        {
+            if busy.is_some() {
+                return Ok(());
+            }
+            let network = networks.get(*selected).cloned();
+            let action = match passphrase {
+                Some(input) => match key.code {
+                    KeyCode::Char(c) => {
+                        if input.len() < MAX_INPUT_LENGTH {
+                            input.push(c);
+                        }
+                        *error = None;
+                        None
+                    }
+                    KeyCode::Backspace => {
+                        input.pop();
+                        None
+                    }
+                    // WPA passphrases are 8 to 63 characters
+                    KeyCode::Enter if input.chars().count() < 8 => {
+                        *error = Some("Passphrase must be at least 8 characters".to_string());
+                        None
+                    }
+                    KeyCode::Enter => network.map(|n| NetworkAction::Connect(n.ssid, Some(input.clone()))),
+                    _ => None,
+                },
+                None => match key.code {
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        *selected = selected.saturating_sub(1);
+                        None
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        *selected = (*selected + 1).min(networks.len().saturating_sub(1));
+                        None
+                    }
+                    KeyCode::Char('r') | KeyCode::Char('R') => Some(NetworkAction::Scan),
+                    KeyCode::Enter => match network {
+                        Some(n) if n.secured() => {
+                            *passphrase = Some(String::new());
+                            *error = None;
+                            None
+                        }
+                        Some(n) => Some(NetworkAction::Connect(n.ssid, None)),
+                        None => None,
+                    },
+                    _ => None,
+                },
+            };
+            match &action {
+                Some(NetworkAction::Scan) => *busy = Some("Scanning...".to_string()),
+                Some(NetworkAction::Connect(ssid, _)) => {
+                    *passphrase = None;
+                    *busy = Some(format!("Connecting to {}...", ssid));
+                }
+                None => {}
+            }
+            if action.is_some() {
+                *error = None;
+            }
+            action
+        } else {
+            None
+        };
+
+        if let Some(tx) = &self.cmd_tx {
+            match action {
+                Some(NetworkAction::Scan) => {
+                    commands::network::start_scan(tx.clone(), self.wifi.clone()).await?;
+                }
+                Some(NetworkAction::Connect(ssid, passphrase)) => {
+                    commands::network::start_connect(tx.clone(), self.wifi.clone(), ssid, passphrase).await?;
+                }
+                None => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_extra_users_key(&mut self, key: KeyEvent) -> Result<()> {
+        let AppMode::Install(InstallState::ExtraUsers {
+            host,
+            disk,
+            credentials,
+            selected,
+            form,
+            error,
+        }) = &mut self.mode
+        else {
+            return Ok(());
+        };
+
+        // Add-user form
+        if let Some(user) = form {
+            match key.code {
+                KeyCode::Tab | KeyCode::Down => user.field = user.field.next(),
+                KeyCode::BackTab | KeyCode::Up => user.field = user.field.prev(),
+                KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right
+                    if matches!(user.field, UserFormField::Group(_)) =>
+                {
+                    if let UserFormField::Group(i) = user.field {
+                        user.groups[i] = !user.groups[i];
+                    }
+                }
+                KeyCode::Char(c) => {
+                    let field = match user.field {
+                        UserFormField::Username => &mut user.username,
+                        UserFormField::Password => &mut user.password,
+                        UserFormField::ConfirmPassword => &mut user.confirm_password,
+                        UserFormField::Group(_) => return Ok(()),
+                    };
+                    if field.len() < MAX_INPUT_LENGTH {
+                        let c = if user.field == UserFormField::Username {
+                            c.to_ascii_lowercase()
+                        } else {
+                            c
+                        };
+                        field.push(c);
+                    }
+                    *error = None;
+                    if user.field == UserFormField::Username && !user.username.is_empty() {
+                        *error = validate_username(&user.username);
+                    }
+                }
+                KeyCode::Backspace => {
+                    match user.field {
+                        UserFormField::Username => user.username.pop(),
+                        UserFormField::Password => user.password.pop(),
+                        UserFormField::ConfirmPassword => user.confirm_password.pop(),
+                        UserFormField::Group(_) => None,
+                    };
+                    *error = None;
+                }
+                KeyCode::Enter => {
+                    let mut taken: Vec<&str> = credentials.extra_users.iter().map(|u| u.username.as_str()).collect();
+                    taken.push(&credentials.username);
+                    match user.validate(&taken) {
+                        Ok(extra) => {
+                            credentials.extra_users.push(extra);
+                            *form = None;
+                            *error = None;
+                            *selected = credentials.extra_users.len() + 1;
+                        }
+                        Err(err) => *error = Some(err),
                     }
                 }
                 _ => {}
             }
+            return Ok(());
+        }
+
+        // List: users, "Add user", "Continue"
+        let add_row = credentials.extra_users.len();
+        *error = None;
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => *selected = selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => *selected = (*selected + 1).min(add_row + 1),
+            KeyCode::Char('a') => *form = Some(UserForm::default()),
+            KeyCode::Char('d') | KeyCode::Delete if *selected < add_row => {
+                credentials.extra_users.remove(*selected);
+            }
+            KeyCode::Enter if *selected == add_row => *form = Some(UserForm::default()),
+            KeyCode::Enter if *selected > add_row => {
+                self.mode = AppMode::Install(InstallState::SelectLayout {
+                    host: host.clone(),
+                    disk: disk.clone(),
+                    selected: layout_index(&credentials.layout),
+                    credentials: credentials.clone(),
+                });
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_install_layout_key(&mut self, key: KeyEvent) -> Result<()> {
+        if let AppMode::Install(InstallState::SelectLayout {
+            host,
+            disk,
+            credentials,
+            selected,
+        }) = &mut self.mode
+        {
+            if !handle_layout_key(key, &mut credentials.layout, selected) {
+                return Ok(());
+            }
+            let creds = credentials.clone();
+            self.mode = if creds.layout.supports_swapfile() {
+                // Proceed to swap mode selection
+                AppMode::Install(InstallState::SelectSwapMode {
+                    host: host.clone(),
+                    disk: disk.clone(),
+                    credentials: creds,
+                    selected: 0,
+                    ram_gb: get_ram_size_gb(),
+                })
+            } else {
+                // Hibernate swapfiles need the shared layout's @swap subvolume
+                AppMode::Install(InstallState::SelectKeySource {
+                    host: host.clone(),
+                    disk: disk.clone(),
+                    credentials: InstallCredentials {
+                        swap_mode: SwapMode::ZramOnly,
+                        ..creds
+                    },
+                    selected: 0,
+                    passphrase: None,
+                })
+            };
         }
         Ok(())
     }
@@ -788,13 +1332,13 @@ impl App {
                     let mut creds = credentials.clone();
                     creds.swap_mode = swap_mode;
 
-                    // Proceed to overview
-                    self.mode = AppMode::Install(InstallState::Overview {
+                    // Proceed to key provisioning selection
+                    self.mode = AppMode::Install(InstallState::SelectKeySource {
                         host: host.clone(),
                         disk: disk.clone(),
                         credentials: creds,
-                        hardware_config: None,
-                        input: String::new(),
+                        selected: 0,
+                        passphrase: None,
                     });
                 }
                 _ => {}
@@ -803,6 +1347,129 @@ impl App {
         Ok(())
     }
 
+    async fn handle_key_source_key(&mut self, key: KeyEvent) -> Result<()> {
+        if let AppMode::Install(InstallState::SelectKeySource {
+            host,
+            disk,
+            credentials,
+            selected,
+            passphrase,
+        }) = &mut self.mode
+        {
+            if let Some(input) = passphrase {
+                match key.code {
+                    KeyCode::Char(c) if input.len() < MAX_INPUT_LENGTH => input.push(c),
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Enter if !input.is_empty() => {
+                        let creds = InstallCredentials {
+                            key_source: KeySource::Archive,
+                            keys_passphrase: input.clone(),
+                            ..credentials.clone()
+                        };
+                        self.mode = AppMode::Install(InstallState::select_locale(
+                            host.clone(),
+                            disk.clone(),
+                            creds,
+                            LocaleField::Timezone,
+                        ));
+                    }
+                    _ => {}
+                }
+                return Ok(());
+            }
+
+            match key.code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    *selected = selected.saturating_sub(1);
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    *selected = (*selected + 1).min(2); // 3 options: 0, 1, 2
+                }
+                // The archive passphrase is asked for here; the ISO has no secret provider for it
+                KeyCode::Enter if *selected == 1 => {
+                    *passphrase = Some(String::new());
+                }
+                KeyCode::Enter => {
+                    let mut creds = credentials.clone();
+                    creds.key_source = if *selected == 0 { KeySource::Skip } else { KeySource::Secrets };
+                    creds.keys_passphrase.clear();
+
+                    // Proceed to timezone, locale and keyboard selection
+                    self.mode = AppMode::Install(InstallState::select_locale(
+                        host.clone(),
+                        disk.clone(),
+                        creds,
+                        LocaleField::Timezone,
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_locale_key(&mut self, key: KeyEvent) -> Result<()> {
+        if let AppMode::Install(InstallState::SelectLocale {
+            host,
+            disk,
+            credentials,
+            field,
+            choices,
+            filter,
+            selected,
+        }) = &mut self.mode
+        {
+            let visible = crate::system::locale::filter(choices, filter);
+            match key.code {
+                KeyCode::Up => {
+                    *selected = selected.saturating_sub(1);
+                }
+                KeyCode::Down => {
+                    *selected = (*selected + 1).min(visible.len().saturating_sub(1));
+                }
+                KeyCode::PageUp => {
+                    *selected = selected.saturating_sub(10);
+                }
+                KeyCode::PageDown => {
+                    *selected = (*selected + 10).min(visible.len().saturating_sub(1));
+                }
+                KeyCode::Char(c) if filter.len() < MAX_INPUT_LENGTH => {
+                    filter.push(c);
+                    *selected = 0;
+                }
+                KeyCode::Backspace => {
+                    filter.pop();
+                    *selected = 0;
+                }
+                KeyCode::Enter => {
+                    let Some(choice) = visible.get(*selected) else {
+                        return Ok(());
+                    };
+                    let mut creds = credentials.clone();
+                    if let Some(settings) = creds.locale.as_mut() {
+                        field.set(settings, choice.value.clone());
+                    }
+
+                    self.mode = match field.next() {
+                        Some(next) => AppMode::Install(InstallState::select_locale(host.clone(), disk.clone(), creds, next)),
+                        // Proceed to overview
+                        None => AppMode::Install(InstallState::Overview {
+                            host: host.clone(),
+                            disk: disk.clone(),
+                            credentials: creds,
+                            hardware_config: None,
+                            input: String::new(),
+                        }),
+                    };
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
     async fn handle_overview_key_action(&mut self, key: KeyEvent, host: &str) -> Result<()> {
         let (disk, credentials, should_start) = if let AppMode::Install(InstallState::Overview {
             disk,
@@ -822,6 +1489,10 @@ impl App {
                     input.pop();
                     (None, None, false)
                 }
+                KeyCode::Tab => {
+                    credentials.skip_wifi = !credentials.skip_wifi;
+                    (None, None, false)
+                }
                 KeyCode::Enter => {
                     if input.trim().eq_ignore_ascii_case("yes") {
                         (Some(disk.clone()), Some(credentials.clone()), true)
@@ -844,8 +1515,18 @@ impl App {
                     StepStatus::new("Configuring disk device"),
                     StepStatus::new("Running disko (partitioning)"),
                     StepStatus::new("Installing NixOS"),
-                    StepStatus::new("Setting up user account"),
+                    StepStatus::new("Setting up user accounts"),
+                    StepStatus::new("Verifying installation"),
                 ];
+                if !creds.skip_wifi {
+                    steps.insert(6, StepStatus::new("Copying Wi-Fi connections"));
+                }
+                if creds.key_source != KeySource::Skip {
+                    steps.insert(6, StepStatus::new("Provisioning keys"));
+                }
+                if self.offline.is_some() {
+                    steps[0] = StepStatus::new("Loading offline bundle");
+                }
                 steps[0].status = StepState::Running;
 
                 self.mode = AppMode::Install(InstallState::Running {
@@ -855,16 +1536,18 @@ impl App {
                     step: 0,
                     steps,
                     output: std::collections::VecDeque::new(),
+                    checks: Vec::new(),
                 });
                 if let Some(tx) = &self.cmd_tx {
                     commands::install::start_install(
                         tx.clone(),
                         host,
-                        &disk.path,
-                        &creds.username,
-                        &creds.password,
-                        creds.swap_mode.clone(),
-                    ).await?;
+                        &disk,
+                        creds,
+                        self.repo_source.clone(),
+                        self.offline.clone(),
+                    )
+                    .await?;
                 }
             }
         }
@@ -965,22 +1648,27 @@ impl App {
                     }
                 }
             }
-            AppMode::CreateHost(CreateHostState::SelectDisk { disks, selected, .. }) => {
+            AppMode::CreateHost(CreateHostState::SelectDisk { disks, selected, confirm, .. }) => {
                 match key.code {
                     KeyCode::Up | KeyCode::Char('k') => {
                         *selected = selected.saturating_sub(1);
+                        *confirm = false;
                         false
                     }
                     KeyCode::Down | KeyCode::Char('j') => {
                         if !disks.is_empty() {
                             *selected = (*selected + 1).min(disks.len() - 1);
                         }
+                        *confirm = false;
                         false
                     }
-                    KeyCode::Enter if !disks.is_empty() => true,
+                    KeyCode::Enter if !disks.is_empty() => confirm_disk(&disks[*selected], confirm),
                     _ => false,
                 }
             }
+            AppMode::CreateHost(CreateHostState::SelectLayout { layout, selected, .. }) => {
+                handle_layout_key(key, layout, selected)
+            }
             AppMode::CreateHost(CreateHostState::EnterHostname { input, error, .. }) => {
                 match key.code {
                     KeyCode::Char(c) => {
@@ -1119,6 +1807,7 @@ impl App {
                     form_factor: ff,
                     disks: Vec::new(),
                     selected: 0,
+                    confirm: false,
                 })
             }
             AppMode::CreateHost(CreateHostState::SelectDisk {
@@ -1127,25 +1816,43 @@ impl App {
                 form_factor,
                 disks,
                 selected,
+                ..
             }) => {
                 // Validate bounds before accessing - return to disk selection if invalid
                 let Some(disk) = disks.into_iter().nth(selected) else {
                     return Ok(());
                 };
-                AppMode::CreateHost(CreateHostState::EnterHostname {
+                AppMode::CreateHost(CreateHostState::SelectLayout {
                     cpu,
                     gpu,
                     form_factor,
                     disk,
-                    input: String::new(),
-                    error: None,
+                    layout: DiskLayout::default(),
+                    selected: 0,
                 })
             }
+            AppMode::CreateHost(CreateHostState::SelectLayout {
+                cpu,
+                gpu,
+                form_factor,
+                disk,
+                layout,
+                ..
+            }) => AppMode::CreateHost(CreateHostState::EnterHostname {
+                cpu,
+                gpu,
+                form_factor,
+                disk,
+                layout,
+                input: String::new(),
+                error: None,
+            }),
             AppMode::CreateHost(CreateHostState::EnterHostname {
                 cpu,
                 gpu,
                 form_factor,
                 disk,
+                layout,
                 input,
                 ..
             }) => {
@@ -1156,6 +1863,7 @@ impl App {
                         gpu,
                         form_factor,
                         disk,
+                        layout,
                         input,
                         error: Some(err),
                     })
@@ -1167,6 +1875,7 @@ impl App {
                             gpu,
                             form_factor,
                             disk,
+                            layout,
                         },
                     })
                 }
@@ -1212,7 +1921,10 @@ impl App {
                     AppMode::Install(InstallState::EnterCredentials {
                         host: config.hostname.clone(),
                         disk: config.disk.clone(),
-                        credentials: InstallCredentials::default(),
+                        credentials: InstallCredentials {
+                            layout: config.layout,
+                            ..Default::default()
+                        },
                         active_field: CredentialField::Username,
                         error: None,
                     })
@@ -1237,21 +1949,52 @@ impl App {
         let needs_disk_refresh = matches!(
             old_mode,
             AppMode::Install(InstallState::EnterCredentials { .. })
+                | AppMode::Install(InstallState::ExtraUsers { .. })
+                | AppMode::Install(InstallState::SelectLayout { .. })
                 | AppMode::Install(InstallState::SelectSwapMode { .. })
+                | AppMode::Install(InstallState::SelectKeySource { .. })
+                | AppMode::Install(InstallState::SelectLocale { .. })
                 | AppMode::Install(InstallState::Overview { .. })
-                | AppMode::CreateHost(CreateHostState::EnterHostname { .. })
+                | AppMode::CreateHost(CreateHostState::SelectLayout { .. })
         );
 
         self.mode = match old_mode {
             AppMode::Apps(AppProfileState::Menu { .. }) => AppMode::MainMenu { selected: 2 },
             AppMode::Apps(AppProfileState::Complete { .. })
-            | AppMode::Apps(AppProfileState::Status { .. }) => {
-                AppMode::Apps(AppProfileState::Menu { selected: 0 })
+            | AppMode::Apps(AppProfileState::Status { .. })
+            | AppMode::Apps(AppProfileState::History { .. }) => {
+                AppMode::Apps(AppProfileState::new_menu())
+            }
+            AppMode::Keys(KeysState::Complete { .. })
+            | AppMode::Keys(KeysState::Status { .. })
+            | AppMode::Keys(KeysState::PaperRestore { .. }) => {
+                AppMode::MainMenu { selected: 2 }
+            }
+            AppMode::Install(InstallState::Network {
+                next,
+                networks,
+                selected,
+                passphrase: Some(_),
+                busy,
+                ..
+            }) => {
+                // Cancel the passphrase prompt
+                AppMode::Install(InstallState::Network {
+                    next,
+                    networks,
+                    selected,
+                    passphrase: None,
+                    busy,
+                    error: None,
+                })
             }
-            AppMode::Keys(KeysState::Complete { .. }) => AppMode::MainMenu { selected: 2 },
+            AppMode::Install(InstallState::Network { .. }) => AppMode::MainMenu { selected: 0 },
             AppMode::Install(InstallState::SelectHost { .. }) => {
                 AppMode::MainMenu { selected: 0 }
             }
+            AppMode::Install(InstallState::EditSource { selected, .. }) => {
+                AppMode::Install(InstallState::SelectHost { selected })
+            }
             AppMode::Install(InstallState::SelectDisk { .. }) => {
                 AppMode::Install(InstallState::SelectHost { selected: 0 })
             }
@@ -1261,9 +2004,41 @@ impl App {
                     host,
                     disks: vec![disk], // Keep the selected disk
                     selected: 0,
+                    confirm: false,
                 })
             }
             AppMode::Install(InstallState::SelectSwapMode { host, disk, credentials, .. }) => {
+                // Go back to disk layout selection
+                AppMode::Install(InstallState::SelectLayout {
+                    host,
+                    disk,
+                    selected: layout_index(&credentials.layout),
+                    credentials,
+                })
+            }
+            AppMode::Install(InstallState::SelectLayout { host, disk, credentials, .. }) => {
+                // Go back to additional users
+                AppMode::Install(InstallState::extra_users(host, disk, credentials))
+            }
+            AppMode::Install(InstallState::ExtraUsers {
+                host,
+                disk,
+                credentials,
+                selected,
+                form: Some(_),
+                ..
+            }) => {
+                // Close the add-user form
+                AppMode::Install(InstallState::ExtraUsers {
+                    host,
+                    disk,
+                    credentials,
+                    selected,
+                    form: None,
+                    error: None,
+                })
+            }
+            AppMode::Install(InstallState::ExtraUsers { host, disk, credentials, .. }) => {
                 // Go back to credentials entry
                 AppMode::Install(InstallState::EnterCredentials {
                     host,
@@ -1274,16 +2049,59 @@ impl App {
                 })
             }
             AppMode::Install(InstallState::Overview { host, disk, credentials, .. }) => {
-                // Go back to swap mode selection
-                let ram_gb = get_ram_size_gb();
-                AppMode::Install(InstallState::SelectSwapMode {
+                // Go back to keyboard layout selection
+                AppMode::Install(InstallState::select_locale(host, disk, credentials, LocaleField::Keyboard))
+            }
+            AppMode::Install(InstallState::SelectLocale { host, disk, credentials, field, .. }) => {
+                match field.prev() {
+                    Some(prev) => AppMode::Install(InstallState::select_locale(host, disk, credentials, prev)),
+                    // Go back to key provisioning selection
+                    None => AppMode::Install(InstallState::SelectKeySource {
+                        host,
+                        disk,
+                        credentials,
+                        selected: 0,
+                        passphrase: None,
+                    }),
+                }
+            }
+            AppMode::Install(InstallState::SelectKeySource {
+                host,
+                disk,
+                credentials,
+                selected,
+                passphrase: Some(_),
+            }) => {
+                // Cancel the passphrase prompt
+                AppMode::Install(InstallState::SelectKeySource {
                     host,
                     disk,
                     credentials,
-                    selected: 0,
-                    ram_gb,
+                    selected,
+                    passphrase: None,
                 })
             }
+            AppMode::Install(InstallState::SelectKeySource { host, disk, credentials, .. }) => {
+                if credentials.layout.supports_swapfile() {
+                    // Go back to swap mode selection
+                    let ram_gb = get_ram_size_gb();
+                    AppMode::Install(InstallState::SelectSwapMode {
+                        host,
+                        disk,
+                        credentials,
+                        selected: 0,
+                        ram_gb,
+                    })
+                } else {
+                    // Swap mode was skipped for this layout
+                    AppMode::Install(InstallState::SelectLayout {
+                        host,
+                        disk,
+                        selected: layout_index(&credentials.layout),
+                        credentials,
+                    })
+                }
+            }
             AppMode::Install(InstallState::Complete { .. }) => AppMode::MainMenu { selected: 0 },
             AppMode::Update(UpdateState::Complete { .. }) => AppMode::MainMenu { selected: 1 },
             // CreateHost back navigation - take ownership to avoid clones
@@ -1329,7 +2147,7 @@ impl App {
                 override_menu: false,
                 selected: 0,
             }),
-            AppMode::CreateHost(CreateHostState::EnterHostname {
+            AppMode::CreateHost(CreateHostState::SelectLayout {
                 cpu,
                 gpu,
                 form_factor,
@@ -1340,6 +2158,22 @@ impl App {
                 form_factor,
                 disks: Vec::new(),
                 selected: 0,
+                confirm: false,
+            }),
+            AppMode::CreateHost(CreateHostState::EnterHostname {
+                cpu,
+                gpu,
+                form_factor,
+                disk,
+                layout,
+                ..
+            }) => AppMode::CreateHost(CreateHostState::SelectLayout {
+                cpu,
+                gpu,
+                form_factor,
+                disk,
+                selected: layout_index(&layout),
+                layout,
             }),
             AppMode::CreateHost(CreateHostState::Review { config }) => {
                 // Destructure to take ownership of all fields
@@ -1349,12 +2183,14 @@ impl App {
                     gpu,
                     form_factor,
                     disk,
+                    layout,
                 } = config;
                 AppMode::CreateHost(CreateHostState::EnterHostname {
                     cpu,
                     gpu,
                     form_factor,
                     disk,
+                    layout,
                     input: hostname,
                     error: None,
                 })
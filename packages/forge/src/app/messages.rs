@@ -5,12 +5,13 @@ use regex::Regex;
 use std::sync::LazyLock;
 
 use super::state::{
-    AppMode, AppProfileState, CommitInfo, CreateHostState, InstallState, KeysState, StepState,
+    AppMode, AppOp, AppProfileState, CommitInfo, CreateHostState, InstallState, KeysState, StepState,
     StepStatus, UpdateState,
 };
 use super::App;
 use crate::commands::errors::ParsedError;
 use crate::commands::executor::run_capture;
+use crate::commands::install_log;
 use crate::commands::CommandMessage;
 use crate::constants::{nixos_config_dir, OUTPUT_BUFFER_SIZE};
 
@@ -30,6 +31,9 @@ impl App {
             CommandMessage::Stdout(line) | CommandMessage::Stderr(line) => {
                 self.append_output(&line);
             }
+            CommandMessage::Secret(line) => {
+                self.push_output(strip_ansi_codes(&line));
+            }
             CommandMessage::StepComplete { step } => {
                 self.mark_step_complete(&step);
             }
@@ -43,7 +47,7 @@ impl App {
                 self.handle_command_done(success).await;
             }
             CommandMessage::Cancelled => {
-                self.handle_command_cancelled();
+                self.handle_command_cancelled().await;
             }
             CommandMessage::UpdatesAvailable {
                 nixos_config,
@@ -56,7 +60,11 @@ impl App {
                     self.pending_updates.app_profiles = app_profiles;
                     self.pending_updates.commits = commits
                         .into_iter()
-                        .map(|(hash, message)| CommitInfo { hash, message })
+                        .map(|commit| CommitInfo {
+                            hash: commit.hash,
+                            message: commit.message,
+                            affects_this_host: commit.affects_this_host,
+                        })
                         .collect();
                     self.pending_updates.selected = 0;
                     self.pending_updates.viewing_commits = false;
@@ -70,6 +78,85 @@ impl App {
             CommandMessage::CloneComplete { success } => {
                 self.handle_clone_complete(success);
             }
+            CommandMessage::AppSnapshots(result) => {
+                if let AppMode::Apps(AppProfileState::History {
+                    snapshots,
+                    loading,
+                    error,
+                    ..
+                }) = &mut self.mode
+                {
+                    *loading = false;
+                    match result {
+                        Ok(loaded) => *snapshots = loaded,
+                        Err(e) => *error = Some(e),
+                    }
+                }
+            }
+            CommandMessage::KeyReport(result) => {
+                if let AppMode::Keys(KeysState::Status {
+                    report,
+                    loading,
+                    error,
+                    ..
+                }) = &mut self.mode
+                {
+                    *loading = false;
+                    match result {
+                        Ok(loaded) => {
+                            *report = Some(loaded);
+                            *error = None;
+                        }
+                        Err(e) => *error = Some(e),
+                    }
+                }
+            }
+            CommandMessage::InstallVerified(results) => {
+                if let AppMode::Install(InstallState::Running { checks, .. }) = &mut self.mode {
+                    *checks = results;
+                }
+            }
+            CommandMessage::NetworkOnline => {
+                if let AppMode::Install(InstallState::Network { next, .. }) = &mut self.mode {
+                    let next = std::mem::replace(next.as_mut(), InstallState::SelectHost { selected: 0 });
+                    self.mode = AppMode::Install(next);
+                    self.start_initial_command().await?;
+                }
+            }
+            CommandMessage::WifiNetworks(result) => {
+                if let AppMode::Install(InstallState::Network {
+                    networks,
+                    selected,
+                    busy,
+                    error,
+                    ..
+                }) = &mut self.mode
+                {
+                    *busy = None;
+                    match result {
+                        Ok(found) => {
+                            *selected = found.iter().position(|n| n.in_use).unwrap_or(0);
+                            *networks = found;
+                            *error = None;
+                        }
+                        Err(e) => *error = Some(e),
+                    }
+                }
+            }
+            CommandMessage::WifiConnectFailed(message) => {
+                if let AppMode::Install(InstallState::Network { busy, error, .. }) = &mut self.mode {
+                    *busy = None;
+                    *error = Some(message);
+                }
+            }
+            CommandMessage::KeysFixed(result) => {
+                if let AppMode::Keys(KeysState::Status { fixed, error, .. }) = &mut self.mode {
+                    match result {
+                        Ok(lines) => *fixed = lines,
+                        Err(e) => *error = Some(e),
+                    }
+                }
+            }
         }
         Ok(())
     }
@@ -78,23 +165,37 @@ impl App {
         if success {
             // Re-discover hosts from the newly cloned repository
             self.hosts = crate::system::config::discover_hosts();
+            // A bundle only carries its own host's closure
+            if let Some(bundle) = &self.offline {
+                self.hosts.retain(|h| h.name == bundle.manifest.host);
+            }
             // Transition to host selection
             self.mode = AppMode::Install(InstallState::SelectHost { selected: 0 });
         } else {
-            // Clone failed - show error screen
-            use std::collections::VecDeque;
-            let mut output = VecDeque::new();
-            output.push_back("Failed to clone configuration repository.".to_string());
+            // Clone failed - show error screen, keeping the clone output
+            let mut output = match &mut self.mode {
+                AppMode::Install(InstallState::CloneRepository { output }) => std::mem::take(output),
+                _ => std::collections::VecDeque::new(),
+            };
+            output.push_back("".to_string());
+            output.push_back(format!("Failed to clone configuration repository {}.", self.repo_source));
             output.push_back("".to_string());
             output.push_back("Please check:".to_string());
-            output.push_back("  1. Internet connection (run 'nmtui' to configure WiFi)".to_string());
-            output.push_back("  2. GitHub is accessible".to_string());
+            if self.offline.is_some() {
+                output.push_back("  1. The bundle was made with 'forge bundle create' and copied completely".to_string());
+                output.push_back("  2. Its cache directory is readable".to_string());
+            } else {
+                output.push_back("  1. Internet connection (run 'nmtui' to configure WiFi)".to_string());
+                output.push_back("  2. GitHub is accessible".to_string());
+                output.push_back("  3. The repository and ref exist".to_string());
+            }
             output.push_back("".to_string());
             output.push_back("Press Enter to return to main menu.".to_string());
             self.mode = AppMode::Install(InstallState::Complete {
                 success: false,
                 output,
                 scroll_offset: None,
+                checks: Vec::new(),
             });
         }
     }
@@ -102,7 +203,11 @@ impl App {
     fn append_output(&mut self, line: &str) {
         let clean_line = strip_ansi_codes(line);
         self.log_to_screen(&clean_line);
+        self.push_output(clean_line);
+    }
 
+    /// Add a line to the current screen's output without logging it
+    fn push_output(&mut self, clean_line: String) {
         match &mut self.mode {
             AppMode::Update(UpdateState::Running { output, .. })
             | AppMode::Update(UpdateState::Complete { output, .. }) => {
@@ -240,17 +345,17 @@ impl App {
     fn mark_step_skipped(&mut self, step_name: &str) {
         self.log_to_screen(&format!("[-] Step skipped: {}", step_name));
 
-        match &mut self.mode {
-            AppMode::Update(UpdateState::Running { steps, step, .. }) => {
-                if let Some(s) = steps.iter_mut().find(|s| Self::step_matches(s, step_name)) {
-                    s.status = StepState::Skipped;
-                }
-                *step = (*step + 1).min(steps.len());
-                if *step < steps.len() {
-                    steps[*step].status = StepState::Running;
-                }
-            }
-            _ => {}
+        let (steps, step) = match &mut self.mode {
+            AppMode::Update(UpdateState::Running { steps, step, .. })
+            | AppMode::Install(InstallState::Running { steps, step, .. }) => (steps, step),
+            _ => return,
+        };
+        if let Some(s) = steps.iter_mut().find(|s| Self::step_matches(s, step_name)) {
+            s.status = StepState::Skipped;
+        }
+        *step = (*step + 1).min(steps.len());
+        if *step < steps.len() {
+            steps[*step].status = StepState::Running;
         }
     }
 
@@ -259,13 +364,17 @@ impl App {
             "\n=== Operation {} ===\n",
             if success { "COMPLETED" } else { "FAILED" }
         ));
+        self.persist_install_log().await;
 
         match &mut self.mode {
-            AppMode::Apps(AppProfileState::Running { output, .. }) => {
+            AppMode::Apps(AppProfileState::Running { output, operation, .. }) => {
+                // A failed restore may still have replaced some files
+                let undo_available = *operation == AppOp::Restore;
                 self.mode = AppMode::Apps(AppProfileState::Complete {
                     success,
                     output: output.clone(),
                     scroll_offset: None, // None = auto-scroll continues
+                    undo_available,
                 });
             }
             AppMode::Keys(KeysState::Running { output, .. }) => {
@@ -275,11 +384,12 @@ impl App {
                     scroll_offset: None, // None = auto-scroll continues
                 });
             }
-            AppMode::Install(InstallState::Running { output, .. }) => {
+            AppMode::Install(InstallState::Running { output, checks, .. }) => {
                 self.mode = AppMode::Install(InstallState::Complete {
                     success,
                     output: output.clone(),
                     scroll_offset: None, // None = auto-scroll continues
+                    checks: checks.clone(),
                 });
             }
             AppMode::Update(UpdateState::Running {
@@ -328,7 +438,6 @@ impl App {
                     steps: steps.clone(),
                     output: final_output,
                     scroll_offset: None, // None = auto-scroll continues
-                    stashed: was_stashed,
                 });
             }
             AppMode::CreateHost(CreateHostState::Generating { config, .. }) => {
@@ -341,19 +450,36 @@ impl App {
         }
     }
 
-    fn handle_command_cancelled(&mut self) {
+    /// Copy the install logs onto the target when an install ends
+    async fn persist_install_log(&mut self) {
+        let AppMode::Install(InstallState::Running { host, disk, credentials, .. }) = &self.mode else {
+            return;
+        };
+        let answers = install_log::answers(host, disk, credentials, &self.repo_source);
+        let line = match install_log::persist(&self.screen_log_path, &answers).await {
+            Ok(Some(dir)) => format!("Install logs saved to {}", dir),
+            Ok(None) => "Install logs not saved: the target system is not mounted".to_string(),
+            Err(e) => format!("Warning: Failed to save install logs: {:#}", e),
+        };
+        self.append_output(&line);
+    }
+
+    async fn handle_command_cancelled(&mut self) {
         self.log_to_screen("\n=== Operation CANCELLED ===\n");
+        self.persist_install_log().await;
 
         // Clear the cancellation token
         self.cancel_token = None;
 
         match &mut self.mode {
-            AppMode::Apps(AppProfileState::Running { output, .. }) => {
+            AppMode::Apps(AppProfileState::Running { output, operation, .. }) => {
                 output.push_back("Operation cancelled by user.".to_string());
+                let undo_available = *operation == AppOp::Restore;
                 self.mode = AppMode::Apps(AppProfileState::Complete {
                     success: false,
                     output: output.clone(),
                     scroll_offset: None,
+                    undo_available,
                 });
             }
             AppMode::Keys(KeysState::Running { output, .. }) => {
@@ -364,22 +490,22 @@ impl App {
                     scroll_offset: None,
                 });
             }
-            AppMode::Install(InstallState::Running { output, .. }) => {
+            AppMode::Install(InstallState::Running { output, checks, .. }) => {
                 output.push_back("Operation cancelled by user.".to_string());
                 self.mode = AppMode::Install(InstallState::Complete {
                     success: false,
                     output: output.clone(),
                     scroll_offset: None,
+                    checks: checks.clone(),
                 });
             }
-            AppMode::Update(UpdateState::Running { steps, output, stashed, .. }) => {
+            AppMode::Update(UpdateState::Running { steps, output, .. }) => {
                 output.push_back("Operation cancelled by user.".to_string());
                 self.mode = AppMode::Update(UpdateState::Complete {
                     success: false,
                     steps: steps.clone(),
                     output: output.clone(),
                     scroll_offset: None,
-                    stashed: *stashed,
                 });
             }
             AppMode::CreateHost(CreateHostState::Generating { config, output, .. }) => {
@@ -393,3 +519,44 @@ impl App {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{KeysOp, KeysState};
+
+    #[tokio::test]
+    async fn test_secret_lines_stay_out_of_screen_log() {
+        let log = std::env::temp_dir().join(format!("forge-screen-log-test-{}", std::process::id()));
+        let mode = AppMode::Keys(KeysState::Running {
+            operation: KeysOp::PaperBackup {
+                shares: 5,
+                threshold: 3,
+                output: None,
+            },
+            output: std::collections::VecDeque::new(),
+            force: false,
+        });
+        let mut app = App::with_screen_log(mode, log.clone());
+
+        app.handle_command_message(CommandMessage::Stdout("Split the age key".to_string())).await.unwrap();
+        app.handle_command_message(CommandMessage::Secret("  3F2A-91C0-SHARE".to_string())).await.unwrap();
+
+        let AppMode::Keys(KeysState::Running { output, .. }) = &app.mode else {
+            panic!("mode changed");
+        };
+        assert_eq!(output.iter().collect::<Vec<_>>(), ["Split the age key", "  3F2A-91C0-SHARE"]);
+        let logged = std::fs::read_to_string(&log).unwrap();
+        assert!(logged.contains("Split the age key"));
+        assert!(!logged.contains("SHARE"));
+        let _ = std::fs::remove_file(&log);
+    }
+
+    #[test]
+    fn test_offline_bundle_step_matches() {
+        let bundle = StepStatus::new("Loading offline bundle");
+        assert!(App::step_matches(&bundle, "bundle"));
+        assert!(!App::step_matches(&bundle, "network"));
+        assert!(!App::step_matches(&StepStatus::new("Checking network connectivity"), "bundle"));
+    }
+}
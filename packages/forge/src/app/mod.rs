@@ -13,6 +13,7 @@ use anyhow::Result;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
@@ -21,12 +22,16 @@ use crate::commands::{self, CommandMessage};
 use crate::constants::SPINNER_TICK_MS;
 use crate::system::config::{discover_hosts, HostConfig};
 use crate::system::hardware::{CpuVendor, GpuInfo, GpuVendor};
+use crate::system::network::{NetworkManager, WifiManager};
+use crate::system::bundle::Bundle;
+use crate::system::repo::RepoSource;
 
 // Re-export commonly used types
 pub use state::{
-    AppMode, AppOp, AppProfileState, CreateHostState, CredentialField, InstallCredentials,
-    InstallState, KeysOp, KeysState, NewHostConfig, PendingUpdates, StepState, StepStatus,
-    SwapMode, UpdateState, UpdateSummary, APP_MENU_ITEMS, MAIN_MENU_ITEMS,
+    AppChoice, AppMode, AppOp, AppProfileState, CreateHostState, CredentialField, DiskLayout,
+    InstallCredentials, InstallState, KeySource, KeysOp, KeysState, LocaleField, NewHostConfig,
+    PartitionScheme, PendingUpdates, SourceField, StepState, StepStatus, SwapMode, UpdateState, UpdateSummary,
+    UserForm, UserFormField, APP_MENU_ITEMS, MAIN_MENU_ITEMS,
 };
 
 /// Main application state
@@ -44,6 +49,12 @@ pub struct App {
     pub last_tick: Instant,
     pub error: Option<String>,
     pub hosts: Vec<HostConfig>,
+    /// Repository and ref installs clone the configuration from
+    pub repo_source: RepoSource,
+    /// Bundle for an offline install (`forge install --offline`)
+    pub offline: Option<Bundle>,
+    /// Wi-Fi for the install network screen
+    pub(crate) wifi: Arc<dyn WifiManager>,
     pub(crate) cmd_tx: Option<mpsc::Sender<CommandMessage>>,
     /// Cancellation token for running operations
     pub(crate) cancel_token: Option<CancellationToken>,
@@ -56,8 +67,11 @@ impl App {
         // Set up screen log file
         let log_dir = crate::constants::forge_data_dir();
         let _ = std::fs::create_dir_all(&log_dir);
-        let screen_log_path = log_dir.join(crate::constants::SCREEN_LOG_FILE);
+        Self::with_screen_log(initial_mode, log_dir.join(crate::constants::SCREEN_LOG_FILE))
+    }
 
+    /// App writing its screen log to `screen_log_path`
+    pub fn with_screen_log(initial_mode: AppMode, screen_log_path: PathBuf) -> Self {
         // Open log file (truncate existing)
         let mut screen_log = match File::create(&screen_log_path) {
             Ok(file) => Some(file),
@@ -85,6 +99,9 @@ impl App {
             last_tick: Instant::now(),
             error: None,
             hosts: discover_hosts(),
+            repo_source: RepoSource::default(),
+            offline: None,
+            wifi: Arc::new(NetworkManager),
             cmd_tx: None,
             cancel_token: None,
             screen_log,
@@ -140,15 +157,22 @@ impl App {
                 }
             }
             AppMode::Apps(AppProfileState::Running {
-                operation, force, ..
+                operation, force, apps, at, ..
             }) => {
                 if let Some(tx) = &self.cmd_tx {
                     match operation {
                         AppOp::Backup => {
-                            commands::apps::start_backup(tx.clone(), *force).await?;
+                            commands::apps::start_backup(tx.clone(), *force, apps.clone()).await?;
                         }
                         AppOp::Restore => {
-                            commands::apps::start_restore(tx.clone(), *force).await?;
+                            commands::apps::start_restore(tx.clone(), *force, apps.clone(), at.clone())
+                                .await?;
+                        }
+                        AppOp::Undo => {
+                            commands::apps::start_undo(tx.clone(), *force).await?;
+                        }
+                        AppOp::Schedule { every_hours } => {
+                            commands::apps::start_schedule(tx.clone(), *every_hours).await?;
                         }
                     }
                 }
@@ -158,13 +182,18 @@ impl App {
                     commands::apps::start_status(tx.clone()).await?;
                 }
             }
+            AppMode::Apps(AppProfileState::History { loading: true, .. }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    commands::apps::start_history(tx.clone()).await?;
+                }
+            }
             AppMode::Keys(KeysState::Running {
                 operation, force, ..
             }) => {
                 if let Some(tx) = &self.cmd_tx {
                     match operation {
                         KeysOp::Setup => {
-                            commands::keys::start_setup(tx.clone()).await?;
+                            commands::keys::start_setup(tx.clone(), *force).await?;
                         }
                         KeysOp::Backup => {
                             commands::keys::start_backup(tx.clone()).await?;
@@ -172,15 +201,37 @@ impl App {
                         KeysOp::Restore => {
                             commands::keys::start_restore(tx.clone(), *force).await?;
                         }
-                        KeysOp::Status => {
-                            commands::keys::start_status(tx.clone()).await?;
+                        KeysOp::Rotate { dry_run } => {
+                            commands::keys::start_rotate(tx.clone(), *dry_run).await?;
+                        }
+                        KeysOp::PaperBackup {
+                            shares,
+                            threshold,
+                            output,
+                        } => {
+                            commands::keys::start_paper_backup(tx.clone(), *shares, *threshold, output.clone())
+                                .await?;
+                        }
+                        KeysOp::PaperRestore { shares } => {
+                            commands::keys::start_paper_restore(tx.clone(), shares.clone(), *force).await?;
                         }
                     }
                 }
             }
+            AppMode::Keys(KeysState::Status { loading: true, .. }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    commands::keys::start_status(tx.clone()).await?;
+                }
+            }
+            AppMode::Install(InstallState::Network { .. }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    commands::network::start_check(tx.clone(), self.wifi.clone()).await?;
+                }
+            }
             AppMode::Install(InstallState::CloneRepository { .. }) => {
                 if let Some(tx) = &self.cmd_tx {
-                    commands::install::start_clone_repository(tx.clone()).await?;
+                    commands::install::start_clone_repository(tx.clone(), self.repo_source.clone(), self.offline.clone())
+                        .await?;
                 }
             }
             AppMode::Install(InstallState::SelectDisk { disks, .. }) => {
@@ -1,11 +1,21 @@
 //! Application state types and enums
 
 use std::collections::VecDeque;
+use std::path::PathBuf;
 
+use crate::backup::audit::KeyReport;
+use crate::backup::history::Snapshot;
+use crate::backup::profiles;
+use crate::backup::shamir::Share;
 use crate::commands::update::flake::FlakeInputChange;
+use forge::notify::attribution::host_impact_label;
 use crate::system::config::HostConfig;
 use crate::system::disk::DiskInfo;
 use crate::system::hardware::{CpuInfo, FormFactor, GpuInfo};
+use crate::system::locale::{self, Choice, LocaleSettings};
+use crate::system::users::{ExtraUser, DEFAULT_EXTRA_GROUPS, EXTRA_GROUPS};
+use crate::system::network::AccessPoint;
+use crate::system::verify::Check;
 
 /// Main menu items
 pub const MAIN_MENU_ITEMS: &[&str] = &[
@@ -19,12 +29,14 @@ pub const MAIN_MENU_ITEMS: &[&str] = &[
 pub const APP_MENU_ITEMS: &[&str] = &[
     "Backup & push to GitHub",
     "Pull & restore from GitHub",
+    "Restore an older backup",
     "Check for updates",
     "Back to main menu",
 ];
 
 /// Application mode/screen
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum AppMode {
     MainMenu { selected: usize },
     Install(InstallState),
@@ -45,6 +57,95 @@ pub enum CredentialField {
     ConfirmPassword,
 }
 
+/// Field of the repository source editor on the host-selection screen
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SourceField {
+    #[default]
+    Url,
+    Ref,
+}
+
+/// Field of the add-user form on the users screen
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum UserFormField {
+    #[default]
+    Username,
+    Password,
+    ConfirmPassword,
+    /// Index into EXTRA_GROUPS
+    Group(usize),
+}
+
+impl UserFormField {
+    pub fn next(self) -> Self {
+        match self {
+            UserFormField::Username => UserFormField::Password,
+            UserFormField::Password => UserFormField::ConfirmPassword,
+            UserFormField::ConfirmPassword => UserFormField::Group(0),
+            UserFormField::Group(i) if i + 1 < EXTRA_GROUPS.len() => UserFormField::Group(i + 1),
+            UserFormField::Group(_) => UserFormField::Username,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        match self {
+            UserFormField::Username => UserFormField::Group(EXTRA_GROUPS.len() - 1),
+            UserFormField::Password => UserFormField::Username,
+            UserFormField::ConfirmPassword => UserFormField::Password,
+            UserFormField::Group(0) => UserFormField::ConfirmPassword,
+            UserFormField::Group(i) => UserFormField::Group(i - 1),
+        }
+    }
+}
+
+/// Additional user being entered on the users screen
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserForm {
+    pub username: String,
+    pub password: String,
+    pub confirm_password: String,
+    /// Membership per entry of EXTRA_GROUPS
+    pub groups: Vec<bool>,
+    pub field: UserFormField,
+}
+
+impl Default for UserForm {
+    fn default() -> Self {
+        Self {
+            username: String::new(),
+            password: String::new(),
+            confirm_password: String::new(),
+            groups: EXTRA_GROUPS.iter().map(|g| DEFAULT_EXTRA_GROUPS.contains(g)).collect(),
+            field: UserFormField::default(),
+        }
+    }
+}
+
+impl UserForm {
+    /// Check the form against the NixOS rules and the names already in use
+    pub fn validate(&self, taken: &[&str]) -> Result<ExtraUser, String> {
+        if let Some(err) = validate_username(&self.username) {
+            return Err(err);
+        }
+        if taken.contains(&self.username.as_str()) {
+            return Err(format!("User '{}' already exists", self.username));
+        }
+        if let Some(err) = validate_password(&self.password, &self.confirm_password) {
+            return Err(err);
+        }
+        Ok(ExtraUser {
+            username: self.username.clone(),
+            password: self.password.clone(),
+            groups: EXTRA_GROUPS
+                .iter()
+                .zip(&self.groups)
+                .filter(|(_, on)| **on)
+                .map(|(g, _)| g.to_string())
+                .collect(),
+        })
+    }
+}
+
 /// Swap mode selection for installation
 #[derive(Debug, Clone, PartialEq, Default)]
 pub enum SwapMode {
@@ -55,6 +156,171 @@ pub enum SwapMode {
     HibernateSupport,
 }
 
+/// Encryption and filesystem for a host's disk
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PartitionScheme {
+    /// LUKS2 + btrfs subvolumes (shared modules/disko/default.nix)
+    #[default]
+    LuksBtrfs,
+    /// LUKS2 + a single ext4 root
+    LuksExt4,
+    /// Unencrypted btrfs subvolumes, meant for VMs
+    Btrfs,
+    /// LUKS2 + btrfs with a quota on the @home subvolume
+    LuksBtrfsHomeQuota,
+}
+
+impl PartitionScheme {
+    pub const ALL: [PartitionScheme; 4] = [
+        PartitionScheme::LuksBtrfs,
+        PartitionScheme::LuksExt4,
+        PartitionScheme::Btrfs,
+        PartitionScheme::LuksBtrfsHomeQuota,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PartitionScheme::LuksBtrfs => "LUKS + btrfs",
+            PartitionScheme::LuksExt4 => "LUKS + ext4",
+            PartitionScheme::Btrfs => "btrfs (unencrypted)",
+            PartitionScheme::LuksBtrfsHomeQuota => "LUKS + btrfs with /home quota",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            PartitionScheme::LuksBtrfs => "Encrypted, compressed subvolumes (default)",
+            PartitionScheme::LuksExt4 => "Encrypted, plain ext4 root without subvolumes",
+            PartitionScheme::Btrfs => "No encryption, for VMs and test machines",
+            PartitionScheme::LuksBtrfsHomeQuota => "Keeps /home from filling the disk",
+        }
+    }
+
+    /// Name recorded in the generated disko file
+    pub fn slug(self) -> &'static str {
+        match self {
+            PartitionScheme::LuksBtrfs => "luks-btrfs",
+            PartitionScheme::LuksExt4 => "luks-ext4",
+            PartitionScheme::Btrfs => "btrfs",
+            PartitionScheme::LuksBtrfsHomeQuota => "luks-btrfs-home-quota",
+        }
+    }
+
+    pub fn from_slug(slug: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.slug() == slug)
+    }
+
+    pub fn encrypted(self) -> bool {
+        self != PartitionScheme::Btrfs
+    }
+
+    pub fn btrfs(self) -> bool {
+        self != PartitionScheme::LuksExt4
+    }
+}
+
+/// Disk layout chosen in create-host or install
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DiskLayout {
+    pub scheme: PartitionScheme,
+    /// Size of a dedicated swap partition, None for zram only
+    pub swap_gb: Option<u64>,
+}
+
+impl DiskLayout {
+    /// The host's disko file only imports modules/disko/default.nix
+    pub fn is_shared(&self) -> bool {
+        self.scheme == PartitionScheme::LuksBtrfs && self.swap_gb.is_none()
+    }
+
+    /// Hibernating to a swapfile needs the shared layout's @swap subvolume
+    pub fn supports_swapfile(&self) -> bool {
+        self.is_shared()
+    }
+}
+
+impl std::fmt::Display for DiskLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.scheme.label())?;
+        if let Some(gb) = self.swap_gb {
+            write!(f, ", {}GB swap partition", gb)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where to get the user's keys from during installation
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum KeySource {
+    /// Don't provision keys (restore them after first boot)
+    #[default]
+    Skip,
+    /// Passphrase-encrypted keys archive in the backup storage
+    Archive,
+    /// Secret provider configured in the backup config
+    Secrets,
+}
+
+/// Setting picked on a locale selection screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LocaleField {
+    Timezone,
+    Locale,
+    Keyboard,
+}
+
+impl LocaleField {
+    pub fn title(self) -> &'static str {
+        match self {
+            LocaleField::Timezone => "Timezone",
+            LocaleField::Locale => "Locale",
+            LocaleField::Keyboard => "Keyboard Layout",
+        }
+    }
+
+    /// Screen shown after this one, None when done
+    pub fn next(self) -> Option<LocaleField> {
+        match self {
+            LocaleField::Timezone => Some(LocaleField::Locale),
+            LocaleField::Locale => Some(LocaleField::Keyboard),
+            LocaleField::Keyboard => None,
+        }
+    }
+
+    /// Screen shown before this one, None for the first
+    pub fn prev(self) -> Option<LocaleField> {
+        match self {
+            LocaleField::Timezone => None,
+            LocaleField::Locale => Some(LocaleField::Timezone),
+            LocaleField::Keyboard => Some(LocaleField::Locale),
+        }
+    }
+
+    pub fn value(self, settings: &LocaleSettings) -> &str {
+        match self {
+            LocaleField::Timezone => &settings.timezone,
+            LocaleField::Locale => &settings.locale,
+            LocaleField::Keyboard => &settings.keyboard,
+        }
+    }
+
+    pub fn set(self, settings: &mut LocaleSettings, value: String) {
+        match self {
+            LocaleField::Timezone => settings.timezone = value,
+            LocaleField::Locale => settings.locale = value,
+            LocaleField::Keyboard => settings.keyboard = value,
+        }
+    }
+
+    fn choices(self) -> Vec<Choice> {
+        match self {
+            LocaleField::Timezone => locale::timezones(),
+            LocaleField::Locale => locale::locales(),
+            LocaleField::Keyboard => locale::keyboard_layouts(),
+        }
+    }
+}
+
 /// User credentials and options collected during installation
 #[derive(Debug, Clone, Default)]
 pub struct InstallCredentials {
@@ -62,11 +328,50 @@ pub struct InstallCredentials {
     pub password: String,
     pub confirm_password: String,
     pub swap_mode: SwapMode,
+    pub layout: DiskLayout,
+    pub key_source: KeySource,
+    /// Passphrase of the keys archive, asked for when that is the key source
+    pub keys_passphrase: String,
+    /// Leave the live system's Wi-Fi connections behind
+    pub skip_wifi: bool,
+    /// Timezone, locale and keyboard; None until the locale screens are reached
+    pub locale: Option<LocaleSettings>,
+    /// Accounts created next to the primary user
+    pub extra_users: Vec<ExtraUser>,
+}
+
+impl InstallCredentials {
+    /// Fresh credentials, starting from the host's current disk layout
+    pub fn for_host(host: &str) -> Self {
+        let disko_file = crate::constants::nixos_config_dir()
+            .join("modules/disko")
+            .join(format!("{}.nix", host));
+        let layout = std::fs::read_to_string(disko_file)
+            .map(|content| crate::templates::parse_disko_layout(&content))
+            .unwrap_or_default();
+        Self {
+            layout,
+            ..Default::default()
+        }
+    }
 }
 
 /// Installation state machine
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum InstallState {
+    /// Getting online over Wi-Fi before the install (live ISO only)
+    Network {
+        /// Where the install continues once online
+        next: Box<InstallState>,
+        networks: Vec<AccessPoint>,
+        selected: usize,
+        /// Passphrase being typed for the selected network
+        passphrase: Option<String>,
+        /// Background work in progress, e.g. "Scanning..."
+        busy: Option<String>,
+        error: Option<String>,
+    },
     /// Cloning repository from GitHub (live ISO only)
     CloneRepository {
         output: VecDeque<String>,
@@ -74,10 +379,21 @@ pub enum InstallState {
     SelectHost {
         selected: usize,
     },
+    /// Changing the repository and ref hosts are installed from
+    EditSource {
+        /// Host list selection to return to
+        selected: usize,
+        url: String,
+        git_ref: String,
+        field: SourceField,
+        error: Option<String>,
+    },
     SelectDisk {
         host: String,
         disks: Vec<DiskInfo>,
         selected: usize,
+        /// Warnings for the selected disk are shown; Enter again proceeds
+        confirm: bool,
     },
     EnterCredentials {
         host: String,
@@ -86,6 +402,16 @@ pub enum InstallState {
         active_field: CredentialField,
         error: Option<String>,
     },
+    /// Additional user accounts; rows are the users, "Add user" and "Continue"
+    ExtraUsers {
+        host: String,
+        disk: DiskInfo,
+        credentials: InstallCredentials,
+        selected: usize,
+        /// Add-user form, when open
+        form: Option<UserForm>,
+        error: Option<String>,
+    },
     SelectSwapMode {
         host: String,
         disk: DiskInfo,
@@ -94,6 +420,32 @@ pub enum InstallState {
         /// Total RAM in GB (for display)
         ram_gb: u64,
     },
+    SelectLayout {
+        host: String,
+        disk: DiskInfo,
+        credentials: InstallCredentials,
+        selected: usize,
+    },
+    SelectKeySource {
+        host: String,
+        disk: DiskInfo,
+        credentials: InstallCredentials,
+        selected: usize,
+        /// Keys archive passphrase being typed
+        passphrase: Option<String>,
+    },
+    /// Searchable list for one of the locale settings
+    SelectLocale {
+        host: String,
+        disk: DiskInfo,
+        credentials: InstallCredentials,
+        field: LocaleField,
+        choices: Vec<Choice>,
+        /// Search text typed by the user
+        filter: String,
+        /// Index into the filtered choices
+        selected: usize,
+    },
     Overview {
         host: String,
         disk: DiskInfo,
@@ -108,33 +460,83 @@ pub enum InstallState {
         step: usize,
         steps: Vec<StepStatus>,
         output: VecDeque<String>,
+        /// Post-install verification results (empty until verified)
+        checks: Vec<Check>,
     },
     Complete {
         success: bool,
         output: VecDeque<String>,
         /// None = auto-scroll, Some(n) = manual scroll at position n
         scroll_offset: Option<usize>,
+        checks: Vec<Check>,
     },
 }
 
 impl InstallState {
+    /// Check connectivity first, continuing with `next` once online
+    pub fn new_network(next: InstallState) -> Self {
+        InstallState::Network {
+            next: Box::new(next),
+            networks: Vec::new(),
+            selected: 0,
+            passphrase: None,
+            busy: Some("Checking connectivity...".to_string()),
+            error: None,
+        }
+    }
+
+    /// Locale screen for `field`, detecting the live settings on first use
+    pub fn select_locale(host: String, disk: DiskInfo, mut credentials: InstallCredentials, field: LocaleField) -> Self {
+        let settings = credentials.locale.get_or_insert_with(LocaleSettings::detect);
+        let current = field.value(settings).to_string();
+        let choices = field.choices();
+        let selected = choices.iter().position(|c| c.value == current).unwrap_or(0);
+        InstallState::SelectLocale {
+            host,
+            disk,
+            credentials,
+            field,
+            choices,
+            filter: String::new(),
+            selected,
+        }
+    }
+
+    /// Users screen with the cursor on "Continue"
+    pub fn extra_users(host: String, disk: DiskInfo, credentials: InstallCredentials) -> Self {
+        InstallState::ExtraUsers {
+            selected: credentials.extra_users.len() + 1,
+            host,
+            disk,
+            credentials,
+            form: None,
+            error: None,
+        }
+    }
+
     pub fn new(hostname: Option<String>, disk: Option<String>) -> Self {
         match (hostname, disk) {
             (Some(host), Some(disk_path)) => {
-                // Direct install with provided args - go to credentials
-                let disk = DiskInfo {
-                    path: disk_path,
-                    size: "Unknown".to_string(),
-                    size_bytes: 0,
-                    model: None,
-                    partitions: vec![],
-                };
-                InstallState::EnterCredentials {
-                    host,
-                    disk,
-                    credentials: InstallCredentials::default(),
-                    active_field: CredentialField::Username,
-                    error: None,
+                // Direct install with provided args - go to credentials if the disk is
+                // a safe pick, otherwise let the disk screen explain why it isn't
+                let disks = crate::system::disk::get_available_disks().unwrap_or_default();
+                let index = disks.iter().position(|d| d.path == disk_path);
+                match index.map(|i| &disks[i]) {
+                    Some(disk) if disk.blocker().is_none() && disk.warnings().is_empty() => {
+                        InstallState::EnterCredentials {
+                            credentials: InstallCredentials::for_host(&host),
+                            host,
+                            disk: disk.clone(),
+                            active_field: CredentialField::Username,
+                            error: None,
+                        }
+                    }
+                    _ => InstallState::SelectDisk {
+                        host,
+                        disks,
+                        selected: index.unwrap_or(0),
+                        confirm: index.is_some(),
+                    },
                 }
             }
             (Some(host), None) => {
@@ -143,6 +545,7 @@ impl InstallState {
                     host,
                     disks: Vec::new(),
                     selected: 0,
+                    confirm: false,
                 }
             }
             _ => InstallState::SelectHost { selected: 0 },
@@ -165,11 +568,47 @@ pub fn validate_username(username: &str) -> Option<String> {
     if !username.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-') {
         return Some("Username can only contain lowercase letters, numbers, underscore, and hyphen".to_string());
     }
-    // Reserved usernames
-    let reserved = ["root", "nobody", "daemon", "bin", "sys", "sync", "games", "man", "lp", "mail", "news", "uucp", "proxy", "www-data", "backup", "list", "irc", "gnats", "systemd-network", "systemd-resolve"];
+    // Reserved usernames, including the system users NixOS and this config declare
+    let reserved = [
+        "root",
+        "nobody",
+        "daemon",
+        "bin",
+        "sys",
+        "sync",
+        "games",
+        "man",
+        "lp",
+        "mail",
+        "news",
+        "uucp",
+        "proxy",
+        "www-data",
+        "backup",
+        "list",
+        "irc",
+        "gnats",
+        "systemd-network",
+        "systemd-resolve",
+        "messagebus",
+        "polkituser",
+        "sshd",
+        "nscd",
+        "rtkit",
+        "avahi",
+        "geoclue",
+        "pipewire",
+        "dhcpcd",
+        "nm-openvpn",
+        "nm-iodine",
+        "microsoft-identity-broker",
+    ];
     if reserved.contains(&username) {
         return Some(format!("'{}' is a reserved username", username));
     }
+    if ["nixbld", "systemd-"].iter().any(|prefix| username.starts_with(prefix)) {
+        return Some(format!("'{}' clashes with NixOS system users", username));
+    }
     None
 }
 
@@ -195,10 +634,11 @@ pub struct NewHostConfig {
     pub gpu: GpuInfo,
     pub form_factor: FormFactor,
     pub disk: DiskInfo,
+    pub layout: DiskLayout,
 }
 
 /// Create host wizard state machine
-/// Flow: DetectingHardware → ConfirmCpu → ConfirmGpu → ConfirmFormFactor → SelectDisk → EnterHostname → Review → Generating → Complete
+/// Flow: DetectingHardware → ConfirmCpu → ConfirmGpu → ConfirmFormFactor → SelectDisk → SelectLayout → EnterHostname → Review → Generating → Complete
 #[derive(Debug, Clone)]
 pub enum CreateHostState {
     DetectingHardware,
@@ -229,12 +669,23 @@ pub enum CreateHostState {
         form_factor: FormFactor,
         disks: Vec<DiskInfo>,
         selected: usize,
+        /// Warnings for the selected disk are shown; Enter again proceeds
+        confirm: bool,
+    },
+    SelectLayout {
+        cpu: CpuInfo,
+        gpu: GpuInfo,
+        form_factor: FormFactor,
+        disk: DiskInfo,
+        layout: DiskLayout,
+        selected: usize,
     },
     EnterHostname {
         cpu: CpuInfo,
         gpu: GpuInfo,
         form_factor: FormFactor,
         disk: DiskInfo,
+        layout: DiskLayout,
         input: String,
         error: Option<String>,
     },
@@ -292,8 +743,6 @@ pub enum UpdateState {
         output: VecDeque<String>,
         /// None = auto-scroll, Some(n) = manual scroll at position n
         scroll_offset: Option<usize>,
-        /// Whether we stashed changes that need to be restored
-        stashed: bool,
     },
 }
 
@@ -320,14 +769,42 @@ impl UpdateState {
     }
 }
 
+/// An app in the backup/restore checklist
+#[derive(Debug, Clone)]
+pub struct AppChoice {
+    pub id: &'static str,
+    pub name: &'static str,
+    /// Whether the app's profile directory exists on this machine
+    pub installed: bool,
+    pub checked: bool,
+}
+
 /// App profile management state (browser sessions and logins)
 #[derive(Debug, Clone)]
 pub enum AppProfileState {
-    Menu { selected: usize },
+    /// `selected` runs over the actions in `APP_MENU_ITEMS`, then the apps
+    Menu {
+        selected: usize,
+        apps: Vec<AppChoice>,
+    },
+    /// Picking a backup snapshot to restore
+    History {
+        snapshots: Vec<Snapshot>,
+        selected: usize,
+        /// Still waiting for the snapshot list
+        loading: bool,
+        error: Option<String>,
+        /// App ids to restore (empty = all apps)
+        apps: Vec<String>,
+    },
     Running {
         operation: AppOp,
         output: VecDeque<String>,
         force: bool,
+        /// App ids to include (empty = all apps)
+        apps: Vec<String>,
+        /// Snapshot to restore instead of the latest backup
+        at: Option<String>,
     },
     Status {
         output: VecDeque<String>,
@@ -337,27 +814,78 @@ pub enum AppProfileState {
         output: VecDeque<String>,
         /// None = auto-scroll, Some(n) = manual scroll at position n
         scroll_offset: Option<usize>,
+        /// The operation was a restore, which can be undone
+        undo_available: bool,
     },
 }
 
 impl AppProfileState {
+    /// Menu with every installed app checked
     pub fn new_menu() -> Self {
-        AppProfileState::Menu { selected: 0 }
+        let home = dirs::home_dir().unwrap_or_default();
+        let apps = profiles::all()
+            .iter()
+            .map(|p| {
+                let installed = p.root(&home).exists();
+                AppChoice {
+                    id: p.id,
+                    name: p.name,
+                    installed,
+                    checked: installed,
+                }
+            })
+            .collect();
+        AppProfileState::Menu { selected: 0, apps }
     }
 
-    pub fn new_backup(force: bool) -> Self {
+    pub fn new_backup(force: bool, apps: Vec<String>) -> Self {
         AppProfileState::Running {
             operation: AppOp::Backup,
             output: VecDeque::new(),
             force,
+            apps,
+            at: None,
         }
     }
 
-    pub fn new_restore(force: bool) -> Self {
+    pub fn new_restore(force: bool, apps: Vec<String>, at: Option<String>) -> Self {
         AppProfileState::Running {
             operation: AppOp::Restore,
             output: VecDeque::new(),
             force,
+            apps,
+            at,
+        }
+    }
+
+    pub fn new_undo(force: bool) -> Self {
+        AppProfileState::Running {
+            operation: AppOp::Undo,
+            output: VecDeque::new(),
+            force,
+            apps: Vec::new(),
+            at: None,
+        }
+    }
+
+    /// Install the backup timer, or remove it with `every_hours` of None
+    pub fn new_schedule(every_hours: Option<u64>) -> Self {
+        AppProfileState::Running {
+            operation: AppOp::Schedule { every_hours },
+            output: VecDeque::new(),
+            force: false,
+            apps: Vec::new(),
+            at: None,
+        }
+    }
+
+    pub fn new_history(apps: Vec<String>) -> Self {
+        AppProfileState::History {
+            snapshots: Vec::new(),
+            selected: 0,
+            loading: true,
+            error: None,
+            apps,
         }
     }
 
@@ -372,6 +900,10 @@ impl AppProfileState {
 pub enum AppOp {
     Backup,
     Restore,
+    /// Put back the profiles replaced by the last restore
+    Undo,
+    /// Install or remove the backup timer
+    Schedule { every_hours: Option<u64> },
 }
 
 /// Key management state
@@ -382,6 +914,22 @@ pub enum KeysState {
         output: VecDeque<String>,
         force: bool,
     },
+    /// Key inspection and permissions audit
+    Status {
+        report: Option<Box<KeyReport>>,
+        /// Still inspecting the keys
+        loading: bool,
+        error: Option<String>,
+        /// Files changed by the last permissions fix
+        fixed: Vec<String>,
+    },
+    /// Typing in paper key shares
+    PaperRestore {
+        shares: Vec<Share>,
+        input: String,
+        error: Option<String>,
+        force: bool,
+    },
     Complete {
         success: bool,
         output: VecDeque<String>,
@@ -391,11 +939,11 @@ pub enum KeysState {
 }
 
 impl KeysState {
-    pub fn new_setup() -> Self {
+    pub fn new_setup(force: bool) -> Self {
         KeysState::Running {
             operation: KeysOp::Setup,
             output: VecDeque::new(),
-            force: false,
+            force,
         }
     }
 
@@ -415,13 +963,43 @@ impl KeysState {
         }
     }
 
-    pub fn new_status() -> Self {
+    pub fn new_rotate(dry_run: bool) -> Self {
+        KeysState::Running {
+            operation: KeysOp::Rotate { dry_run },
+            output: VecDeque::new(),
+            force: false,
+        }
+    }
+
+    pub fn new_paper_backup(shares: u8, threshold: u8, output: Option<PathBuf>) -> Self {
         KeysState::Running {
-            operation: KeysOp::Status,
+            operation: KeysOp::PaperBackup {
+                shares,
+                threshold,
+                output,
+            },
             output: VecDeque::new(),
             force: false,
         }
     }
+
+    pub fn new_paper_restore(force: bool) -> Self {
+        KeysState::PaperRestore {
+            shares: Vec::new(),
+            input: String::new(),
+            error: None,
+            force,
+        }
+    }
+
+    pub fn new_status() -> Self {
+        KeysState::Status {
+            report: None,
+            loading: true,
+            error: None,
+            fixed: Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -429,7 +1007,16 @@ pub enum KeysOp {
     Setup,
     Backup,
     Restore,
-    Status,
+    /// Replace the age identity and re-encrypt the backups
+    Rotate { dry_run: bool },
+    /// Print the age key as Shamir shares
+    PaperBackup {
+        shares: u8,
+        threshold: u8,
+        output: Option<PathBuf>,
+    },
+    /// Rebuild the age key from the entered shares
+    PaperRestore { shares: Vec<Share> },
 }
 
 /// Step progress status
@@ -478,6 +1065,8 @@ pub struct UpdateSummary {
 pub struct CommitInfo {
     pub hash: String,
     pub message: String,
+    /// False if the commit only touches other hosts
+    pub affects_this_host: bool,
 }
 
 /// Tracks which updates are available for the combined dialog
@@ -502,6 +1091,22 @@ impl PendingUpdates {
         self.nixos_config || self.app_profiles
     }
 
+    /// Number of pending commits that affect this host
+    pub fn commits_for_host(&self) -> usize {
+        self.commits.iter().filter(|c| c.affects_this_host).count()
+    }
+
+    /// Summary like "3 commits, 1 affects this host"
+    pub fn commit_summary(&self) -> String {
+        let count = self.commits.len();
+        format!(
+            "{} commit{}, {}",
+            count,
+            if count == 1 { "" } else { "s" },
+            host_impact_label(count, self.commits_for_host())
+        )
+    }
+
     pub fn clear(&mut self) {
         self.nixos_config = false;
         self.app_profiles = false;
@@ -558,3 +1163,55 @@ pub fn validate_hostname(hostname: &str, hosts: &[HostConfig]) -> Option<String>
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_username_nixos_rules() {
+        assert_eq!(validate_username("arnold"), None);
+        assert_eq!(validate_username("dev-user_2"), None);
+        assert!(validate_username("").is_some());
+        assert!(validate_username("2fast").is_some());
+        assert!(validate_username("Arnold").is_some());
+        assert!(validate_username("a.b").is_some());
+        assert!(validate_username(&"a".repeat(33)).is_some());
+        assert!(validate_username("root").is_some());
+        assert!(validate_username("sshd").is_some());
+        assert!(validate_username("nixbld1").is_some());
+        assert!(validate_username("systemd-oom").is_some());
+    }
+
+    #[test]
+    fn test_user_form_field_cycle() {
+        let mut field = UserFormField::Username;
+        for _ in 0..3 + EXTRA_GROUPS.len() {
+            field = field.next();
+        }
+        assert_eq!(field, UserFormField::Username);
+        assert_eq!(UserFormField::Username.prev(), UserFormField::Group(EXTRA_GROUPS.len() - 1));
+        assert_eq!(UserFormField::Group(0).prev(), UserFormField::ConfirmPassword);
+    }
+
+    #[test]
+    fn test_user_form_validate() {
+        let mut form = UserForm {
+            username: "guest".to_string(),
+            password: "secret123".to_string(),
+            confirm_password: "secret123".to_string(),
+            ..Default::default()
+        };
+        let user = form.validate(&["arnold"]).unwrap();
+        assert_eq!(user.username, "guest");
+        assert_eq!(user.groups, vec!["networkmanager".to_string()]);
+
+        form.groups = vec![true, false, true];
+        assert_eq!(form.validate(&["arnold"]).unwrap().groups, vec!["wheel".to_string(), "docker".to_string()]);
+
+        assert!(form.validate(&["arnold", "guest"]).unwrap_err().contains("already exists"));
+
+        form.confirm_password = "different".to_string();
+        assert!(form.validate(&["arnold"]).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+//! Profile archives (tar + zstd)
+//!
+//! Archives are reproducible: entries are sorted and carry a fixed mtime and
+//! owner, so an unchanged profile produces an identical archive.
+
+use anyhow::{Context, Result};
+use std::io::Read;
+use std::path::{Component, Path};
+
+/// zstd compression level for new archives
+const ZSTD_LEVEL: i32 = 9;
+
+/// Fixed mtime for archive entries (2024-01-01T00:00:00Z)
+const ARCHIVE_MTIME: u64 = 1_704_067_200;
+
+/// Compression used by an archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// `.tar.zst` (native engine)
+    Zstd,
+    /// `.tar.gz` (archives written by the old scripts)
+    Gzip,
+}
+
+impl Compression {
+    /// Detect the compression from an archive file name
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.strip_suffix(".age").unwrap_or(name);
+        if name.ends_with(".tar.zst") {
+            Some(Self::Zstd)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::Gzip)
+        } else {
+            None
+        }
+    }
+}
+
+/// A regular file read back from an archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// `/`-separated path relative to the profile directory
+    pub path: String,
+    pub data: Vec<u8>,
+    pub mode: u32,
+}
+
+/// Pack `files` (relative to `root`) plus in-memory `extra` files
+pub fn create(root: &Path, files: &[String], extra: &[(&str, &[u8])]) -> Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(Vec::new());
+
+    let mut files: Vec<&String> = files.iter().collect();
+    files.sort();
+
+    for rel in files {
+        let path = root.join(rel);
+        let metadata = std::fs::metadata(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let file = std::fs::File::open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+
+        let mut header = file_header(metadata.len(), permissions(&metadata));
+        builder
+            .append_data(&mut header, rel, file)
+            .with_context(|| format!("Failed to archive {}", rel))?;
+    }
+
+    for (name, data) in extra {
+        let mut header = file_header(data.len() as u64, 0o600);
+        builder.append_data(&mut header, name, *data)?;
+    }
+
+    let tar = builder.into_inner()?;
+    Ok(zstd::encode_all(tar.as_slice(), ZSTD_LEVEL)?)
+}
+
+/// Pack in-memory entries (e.g. the keys archive)
+pub fn pack(entries: &[Entry], compression: Compression) -> Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(Vec::new());
+
+    let mut entries: Vec<&Entry> = entries.iter().collect();
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+    for entry in entries {
+        let mut header = file_header(entry.data.len() as u64, entry.mode);
+        builder
+            .append_data(&mut header, &entry.path, entry.data.as_slice())
+            .with_context(|| format!("Failed to archive {}", entry.path))?;
+    }
+
+    let tar = builder.into_inner()?;
+    Ok(match compression {
+        Compression::Zstd => zstd::encode_all(tar.as_slice(), ZSTD_LEVEL)?,
+        Compression::Gzip => {
+            use std::io::Write;
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&tar)?;
+            encoder.finish()?
+        }
+    })
+}
+
+fn file_header(size: u64, mode: u32) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_size(size);
+    header.set_mode(mode);
+    header.set_mtime(ARCHIVE_MTIME);
+    header.set_uid(0);
+    header.set_gid(0);
+    header
+}
+
+fn permissions(metadata: &std::fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o777
+}
+
+/// Unpack an archive into memory
+///
+/// Only regular files are returned. Entries with absolute paths or `..`
+/// components are rejected.
+pub fn read(data: &[u8], compression: Compression) -> Result<Vec<Entry>> {
+    let decoder: Box<dyn Read + '_> = match compression {
+        Compression::Zstd => Box::new(zstd::Decoder::new(data)?),
+        Compression::Gzip => Box::new(flate2::read::GzDecoder::new(data)),
+    };
+
+    let mut archive = tar::Archive::new(decoder);
+    let mut entries = Vec::new();
+
+    for entry in archive.entries().context("Failed to read archive")? {
+        let mut entry = entry.context("Corrupt archive entry")?;
+        if entry.header().entry_type() != tar::EntryType::Regular {
+            continue;
+        }
+
+        let path = entry.path()?.into_owned();
+        if !is_safe_path(&path) {
+            anyhow::bail!("Archive contains unsafe path: {}", path.display());
+        }
+
+        let mode = entry.header().mode().unwrap_or(0o600) & 0o777;
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+
+        // Script archives were created with `tar -C dir .`, giving ./ prefixes
+        let path = path
+            .components()
+            .filter(|c| !matches!(c, Component::CurDir))
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        entries.push(Entry { path, data, mode });
+    }
+
+    Ok(entries)
+}
+
+fn is_safe_path(path: &Path) -> bool {
+    path.components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::test_support::ScratchDir;
+    use std::io::Write;
+
+    #[test]
+    fn test_roundtrip() {
+        let dir = ScratchDir::new("archive-roundtrip");
+        dir.write("Default/Cookies", "cookies");
+        dir.write("Local State", "state");
+
+        let files = vec!["Local State".to_string(), "Default/Cookies".to_string()];
+        let data = create(dir.path(), &files, &[(".key", b"secret")]).unwrap();
+        let entries = read(&data, Compression::Zstd).unwrap();
+
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["Default/Cookies", "Local State", ".key"]);
+        assert_eq!(entries[0].data, b"cookies");
+        assert_eq!(entries[2].mode, 0o600);
+    }
+
+    #[test]
+    fn test_archives_are_reproducible() {
+        let dir = ScratchDir::new("archive-reproducible");
+        dir.write("a", "1");
+        let files = vec!["a".to_string()];
+        assert_eq!(create(dir.path(), &files, &[]).unwrap(), create(dir.path(), &files, &[]).unwrap());
+    }
+
+    #[test]
+    fn test_pack_gzip_roundtrip() {
+        let entries = vec![
+            Entry {
+                path: "id_ed25519".to_string(),
+                data: b"ssh".to_vec(),
+                mode: 0o600,
+            },
+            Entry {
+                path: "age-key.txt".to_string(),
+                data: b"age".to_vec(),
+                mode: 0o600,
+            },
+        ];
+        let data = pack(&entries, Compression::Gzip).unwrap();
+        let read_back = read(&data, Compression::Gzip).unwrap();
+        assert_eq!(read_back[0].path, "age-key.txt");
+        assert_eq!(read_back[1], entries[0]);
+    }
+
+    #[test]
+    fn test_read_legacy_gzip() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = file_header(5, 0o644);
+        builder.append_data(&mut header, "./Local State", &b"state"[..]).unwrap();
+        let tar = builder.into_inner().unwrap();
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&tar).unwrap();
+        let gz = encoder.finish().unwrap();
+
+        let entries = read(&gz, Compression::Gzip).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, "Local State");
+    }
+
+    #[test]
+    fn test_read_rejects_parent_components() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = file_header(1, 0o644);
+        // set_path refuses "..", so write the raw name
+        header.as_old_mut().name[..9].copy_from_slice(b"../escape");
+        header.set_cksum();
+        builder.append(&header, &b"x"[..]).unwrap();
+        let tar = builder.into_inner().unwrap();
+
+        let data = zstd::encode_all(tar.as_slice(), 1).unwrap();
+        assert!(read(&data, Compression::Zstd).is_err());
+    }
+
+    #[test]
+    fn test_compression_from_name() {
+        assert_eq!(Compression::from_name("chrome-profile.tar.zst.age"), Some(Compression::Zstd));
+        assert_eq!(Compression::from_name("chrome-profile.tar.gz.age"), Some(Compression::Gzip));
+        assert_eq!(Compression::from_name("keys.txt"), None);
+    }
+}
@@ -0,0 +1,374 @@
+//! Key inspection and permissions audit
+//!
+//! Looks at the age identity and SSH key configured for app-backup:
+//! fingerprints, file modes and ownership, and whether the age key is the
+//! one the backups are encrypted to.
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use super::BackupSettings;
+
+/// Mode bits that must be clear on secrets and private directories
+const PRIVATE_MASK: u32 = 0o077;
+/// Mode bits that must be clear on files other users may read
+const SHARED_MASK: u32 = 0o022;
+
+const NIX_STORE: &str = "/nix/store";
+
+/// A file's mode and owner
+#[derive(Debug, Clone)]
+pub struct FileState {
+    pub path: PathBuf,
+    pub mode: u32,
+    pub owner: String,
+    /// Owned by the user whose home it lives in
+    pub owned: bool,
+    /// A symlink into /nix/store (mode and owner are fixed by Nix)
+    pub in_store: bool,
+}
+
+/// The age identity file
+#[derive(Debug, Clone)]
+pub struct AgeKey {
+    pub file: FileState,
+    /// Public key derived from the identity, None if it doesn't parse
+    pub public_key: Option<String>,
+    /// The public key is the configured AGE_RECIPIENT
+    pub matches_recipient: bool,
+}
+
+/// Details read from an SSH public key
+#[derive(Debug, Clone, PartialEq)]
+pub struct SshPublicKey {
+    pub key_type: String,
+    pub fingerprint: String,
+    pub comment: String,
+}
+
+/// The SSH key pair
+#[derive(Debug, Clone)]
+pub struct SshKey {
+    pub file: FileState,
+    pub public_file: Option<FileState>,
+    pub public_key: Option<SshPublicKey>,
+}
+
+/// Something wrong with a key file
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub path: PathBuf,
+    pub message: String,
+    /// Mode that fixes it, None when it needs manual attention
+    pub fix_mode: Option<u32>,
+}
+
+/// Everything the Keys screen shows
+#[derive(Debug, Clone, Default)]
+pub struct KeyReport {
+    pub age_path: Option<PathBuf>,
+    pub age: Option<AgeKey>,
+    pub ssh_path: Option<PathBuf>,
+    pub ssh: Option<SshKey>,
+    pub ssh_dir: Option<FileState>,
+    pub recipient: String,
+    pub problems: Vec<Problem>,
+}
+
+impl KeyReport {
+    /// Problems `fix_permissions` can repair
+    pub fn fixable(&self) -> usize {
+        self.problems.iter().filter(|p| p.fix_mode.is_some()).count()
+    }
+}
+
+/// Inspect the keys configured in `settings`
+pub fn inspect(settings: &BackupSettings) -> KeyReport {
+    let uid = std::fs::metadata(&settings.home).map(|m| m.uid()).ok();
+    let mut report = KeyReport {
+        age_path: settings.age_key_path.clone(),
+        ssh_path: settings.ssh_key_path.clone(),
+        recipient: settings.age_recipient.clone(),
+        ..Default::default()
+    };
+    let mut problems = Vec::new();
+
+    if let Some(path) = &settings.age_key_path {
+        if let Some(file) = file_state(path, uid) {
+            check(&file, PRIVATE_MASK, 0o600, &mut problems);
+            let public_key = std::fs::read_to_string(path).ok().and_then(|s| age_public_key(&s));
+            if public_key.is_none() {
+                problems.push(manual(path, "Not a valid age identity"));
+            }
+            let matches_recipient = public_key.as_deref() == Some(settings.age_recipient.trim());
+            if public_key.is_some() && !matches_recipient {
+                problems.push(manual(path, "Public key doesn't match AGE_RECIPIENT; backups can't be decrypted with it"));
+            }
+            report.age = Some(AgeKey {
+                file,
+                public_key,
+                matches_recipient,
+            });
+        }
+    }
+
+    if let Some(path) = &settings.ssh_key_path {
+        if let Some(dir) = path.parent().and_then(|d| file_state(d, uid)) {
+            check(&dir, PRIVATE_MASK, 0o700, &mut problems);
+            report.ssh_dir = Some(dir);
+        }
+        if let Some(file) = file_state(path, uid) {
+            check(&file, PRIVATE_MASK, 0o600, &mut problems);
+            let public_path = path.with_extension("pub");
+            let public_file = file_state(&public_path, uid);
+            match &public_file {
+                Some(public) => check(public, SHARED_MASK, 0o644, &mut problems),
+                None => problems.push(manual(&public_path, "Public key missing (ssh-keygen -y can recreate it)")),
+            }
+            let public_key = std::fs::read_to_string(&public_path).ok().and_then(|s| parse_ssh_public_key(&s));
+            report.ssh = Some(SshKey {
+                file,
+                public_file,
+                public_key,
+            });
+        }
+        // Other files in ~/.ssh that ssh refuses to use when others can write them
+        if let Some(dir) = path.parent() {
+            for name in ["authorized_keys", "config"] {
+                if let Some(file) = file_state(&dir.join(name), uid) {
+                    check(&file, SHARED_MASK, 0o600, &mut problems);
+                }
+            }
+        }
+    }
+
+    report.problems = problems;
+    report
+}
+
+/// Apply the mode fixes in `report`
+///
+/// Returns one line per file changed.
+pub fn fix_permissions(report: &KeyReport) -> Result<Vec<String>> {
+    let mut lines = Vec::new();
+    for problem in &report.problems {
+        let Some(mode) = problem.fix_mode else {
+            continue;
+        };
+        std::fs::set_permissions(&problem.path, std::fs::Permissions::from_mode(mode))
+            .with_context(|| format!("Failed to change mode of {}", problem.path.display()))?;
+        lines.push(format!("✓ {} set to {:04o}", problem.path.display(), mode));
+    }
+    Ok(lines)
+}
+
+/// Public key of the first identity in an age key file
+pub fn age_public_key(contents: &str) -> Option<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .find(|l| l.starts_with("AGE-SECRET-KEY-"))
+        .and_then(|l| age::x25519::Identity::from_str(l).ok())
+        .map(|identity| identity.to_public().to_string())
+}
+
+/// Key type, SHA256 fingerprint and comment of an OpenSSH public key line
+pub fn parse_ssh_public_key(line: &str) -> Option<SshPublicKey> {
+    let mut fields = line.split_whitespace();
+    let algorithm = fields.next()?;
+    let blob = base64::engine::general_purpose::STANDARD.decode(fields.next()?).ok()?;
+    let digest = Sha256::digest(&blob);
+    let key_type = match algorithm {
+        "ssh-ed25519" => "ED25519",
+        "sk-ssh-ed25519@openssh.com" => "ED25519-SK",
+        "ssh-rsa" => "RSA",
+        "sk-ecdsa-sha2-nistp256@openssh.com" => "ECDSA-SK",
+        a if a.starts_with("ecdsa-sha2-") => "ECDSA",
+        other => other,
+    };
+    Some(SshPublicKey {
+        key_type: key_type.to_string(),
+        fingerprint: format!("SHA256:{}", base64::engine::general_purpose::STANDARD_NO_PAD.encode(digest)),
+        comment: fields.collect::<Vec<_>>().join(" "),
+    })
+}
+
+fn file_state(path: &Path, uid: Option<u32>) -> Option<FileState> {
+    let link = std::fs::symlink_metadata(path).ok()?;
+    let in_store = link.file_type().is_symlink()
+        && std::fs::canonicalize(path).is_ok_and(|target| target.starts_with(NIX_STORE));
+    // Other symlinks (e.g. into a dotfiles checkout) are judged by their target
+    let meta = if link.file_type().is_symlink() { std::fs::metadata(path).ok()? } else { link };
+    Some(FileState {
+        path: path.to_path_buf(),
+        mode: meta.mode() & 0o7777,
+        owner: user_name(meta.uid()),
+        owned: uid.is_none_or(|uid| uid == meta.uid()),
+        in_store,
+    })
+}
+
+/// Record a problem if `file` has any of the `mask` bits set or the wrong owner
+fn check(file: &FileState, mask: u32, fix_mode: u32, problems: &mut Vec<Problem>) {
+    // Files Nix links into the home (e.g. ~/.ssh/config from programs.ssh) are
+    // root-owned and read-only by design; only secrets don't belong there
+    if file.in_store {
+        if mask == PRIVATE_MASK {
+            problems.push(manual(&file.path, "Links into /nix/store, which every user can read; keep secrets out of Nix"));
+        }
+        return;
+    }
+    if file.mode & mask != 0 {
+        problems.push(Problem {
+            path: file.path.clone(),
+            message: format!("Mode {:04o} is too open, should be {:04o}", file.mode, fix_mode),
+            fix_mode: Some(fix_mode),
+        });
+    }
+    if !file.owned {
+        problems.push(manual(&file.path, &format!("Owned by {} (fix with sudo chown)", file.owner)));
+    }
+}
+
+fn manual(path: &Path, message: &str) -> Problem {
+    Problem {
+        path: path.to_path_buf(),
+        message: message.to_string(),
+        fix_mode: None,
+    }
+}
+
+/// Login name for `uid` from /etc/passwd, or the number itself
+fn user_name(uid: u32) -> String {
+    std::fs::read_to_string("/etc/passwd")
+        .ok()
+        .and_then(|passwd| {
+            passwd.lines().find_map(|line| {
+                let fields: Vec<&str> = line.split(':').collect();
+                (fields.get(2)? == &uid.to_string()).then(|| fields[0].to_string())
+            })
+        })
+        .unwrap_or_else(|| uid.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::test_support::{test_settings, ScratchDir};
+    use age::secrecy::ExposeSecret;
+
+    const PUBLIC_KEY: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIAqhYps8ikY/efDWdUgUNKA9bhQetfUuv3to58nlLTug me@laptop";
+
+    fn settings(scratch: &ScratchDir, recipient: &str) -> BackupSettings {
+        let home = scratch.path();
+        BackupSettings {
+            age_recipient: recipient.to_string(),
+            age_key_path: Some(home.join(".config/age/key.txt")),
+            ssh_key_path: Some(home.join(".ssh/id_ed25519")),
+            ..test_settings(home)
+        }
+    }
+
+    fn chmod(path: &Path, mode: u32) {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    #[test]
+    fn test_parse_ssh_public_key() {
+        // Fingerprint as printed by `ssh-keygen -l`
+        assert_eq!(
+            parse_ssh_public_key(PUBLIC_KEY),
+            Some(SshPublicKey {
+                key_type: "ED25519".to_string(),
+                fingerprint: "SHA256:4jarmE3cHf/YDddpCgUup0/8imLwent/k3KH916Yr3I".to_string(),
+                comment: "me@laptop".to_string(),
+            })
+        );
+        assert_eq!(parse_ssh_public_key("ssh-ed25519 !!!"), None);
+    }
+
+    #[test]
+    fn test_inspect_healthy_keys() {
+        let scratch = ScratchDir::new("audit-healthy");
+        let identity = age::x25519::Identity::generate();
+        let recipient = identity.to_public().to_string();
+        let age_key = scratch.write(".config/age/key.txt", identity.to_string().expose_secret());
+        let ssh_key = scratch.write(".ssh/id_ed25519", "private");
+        let ssh_pub = scratch.write(".ssh/id_ed25519.pub", PUBLIC_KEY);
+        chmod(&age_key, 0o600);
+        chmod(&ssh_key, 0o600);
+        chmod(&ssh_pub, 0o644);
+        chmod(&scratch.path().join(".ssh"), 0o700);
+
+        let report = inspect(&settings(&scratch, &recipient));
+        let age = report.age.as_ref().unwrap();
+        assert_eq!(age.public_key.as_deref(), Some(recipient.as_str()));
+        assert!(age.matches_recipient);
+        let ssh = report.ssh.as_ref().unwrap();
+        assert_eq!(ssh.public_key.as_ref().unwrap().comment, "me@laptop");
+        assert_eq!(ssh.file.mode, 0o600);
+        assert!(report.problems.is_empty(), "{:?}", report.problems);
+    }
+
+    #[test]
+    fn test_audit_and_fix_permissions() {
+        let scratch = ScratchDir::new("audit-fix");
+        let age_key = scratch.write(".config/age/key.txt", "garbage");
+        let ssh_key = scratch.write(".ssh/id_ed25519", "private");
+        let config = scratch.write(".ssh/config", "Host *");
+        chmod(&age_key, 0o644);
+        chmod(&ssh_key, 0o640);
+        chmod(&config, 0o666);
+        chmod(&scratch.path().join(".ssh"), 0o755);
+
+        let report = inspect(&settings(&scratch, "age1other"));
+        let messages: Vec<&str> = report.problems.iter().map(|p| p.message.as_str()).collect();
+        assert!(messages.contains(&"Not a valid age identity"));
+        assert!(messages.iter().any(|m| m.starts_with("Public key missing")));
+        assert_eq!(report.fixable(), 4);
+
+        let fixed = fix_permissions(&report).unwrap();
+        assert_eq!(fixed.len(), 4);
+        let mode = |p: &Path| std::fs::metadata(p).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&age_key), 0o600);
+        assert_eq!(mode(&ssh_key), 0o600);
+        assert_eq!(mode(&config), 0o600);
+        assert_eq!(mode(&scratch.path().join(".ssh")), 0o700);
+        assert_eq!(inspect(&settings(&scratch, "age1other")).fixable(), 0);
+    }
+
+    #[test]
+    fn test_symlinks() {
+        // A link outside the store is judged by its target
+        let scratch = ScratchDir::new("audit-symlink");
+        let target = scratch.write("dotfiles/config", "Host *");
+        chmod(&target, 0o600);
+        std::fs::create_dir_all(scratch.path().join(".ssh")).unwrap();
+        let link = scratch.path().join(".ssh/config");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        let file = file_state(&link, None).unwrap();
+        assert_eq!(file.mode, 0o600);
+        assert!(!file.in_store);
+
+        // Store links (e.g. ~/.ssh/config from programs.ssh) can't be chmod/chowned
+        let store_file = FileState {
+            path: link,
+            mode: 0o444,
+            owner: "root".to_string(),
+            owned: false,
+            in_store: true,
+        };
+        let mut problems = Vec::new();
+        check(&store_file, SHARED_MASK, 0o600, &mut problems);
+        assert!(problems.is_empty());
+        check(&store_file, PRIVATE_MASK, 0o600, &mut problems);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].message.contains("/nix/store"));
+        assert_eq!(problems[0].fix_mode, None);
+    }
+}
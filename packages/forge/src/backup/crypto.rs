@@ -0,0 +1,180 @@
+//! age encryption of backup archives
+
+use age::secrecy::SecretString;
+use anyhow::{Context, Result};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use super::BackupSettings;
+
+/// Encrypt `data` to an age public key (`age1...`)
+pub fn encrypt(data: &[u8], recipient: &str) -> Result<Vec<u8>> {
+    let recipient: age::x25519::Recipient = recipient
+        .trim()
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid age recipient '{}': {}", recipient.trim(), e))?;
+
+    let encryptor = age::Encryptor::with_recipients(std::iter::once(&recipient as &dyn age::Recipient))?;
+    let mut output = Vec::with_capacity(data.len() + 256);
+    let mut writer = encryptor.wrap_output(&mut output)?;
+    writer.write_all(data)?;
+    writer.finish()?;
+    Ok(output)
+}
+
+/// Decrypt `data` with the identities in an age identity file
+pub fn decrypt(data: &[u8], identity_file: &str) -> Result<Vec<u8>> {
+    let identities = age::IdentityFile::from_buffer(identity_file.as_bytes())
+        .context("Failed to parse age identity")?
+        .into_identities()
+        .context("Failed to load age identity")?;
+
+    let decryptor = age::Decryptor::new(data).context("Not an age-encrypted file")?;
+    let mut reader = decryptor
+        .decrypt(identities.iter().map(|i| i.as_ref() as &dyn age::Identity))
+        .context("Failed to decrypt (wrong age key?)")?;
+
+    let mut output = Vec::new();
+    reader.read_to_end(&mut output)?;
+    Ok(output)
+}
+
+/// Encrypt `data` with a passphrase (age scrypt), as the keys archive is
+pub fn encrypt_with_passphrase(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let mut recipient = age::scrypt::Recipient::new(SecretString::from(passphrase.to_string()));
+    if let Some(log_n) = SCRYPT_WORK_FACTOR {
+        recipient.set_work_factor(log_n);
+    }
+
+    let encryptor = age::Encryptor::with_recipients(std::iter::once(&recipient as &dyn age::Recipient))?;
+    let mut output = Vec::with_capacity(data.len() + 256);
+    let mut writer = encryptor.wrap_output(&mut output)?;
+    writer.write_all(data)?;
+    writer.finish()?;
+    Ok(output)
+}
+
+/// Decrypt passphrase-encrypted `data`
+pub fn decrypt_with_passphrase(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let identity = age::scrypt::Identity::new(SecretString::from(passphrase.to_string()));
+    let decryptor = age::Decryptor::new(data).context("Not an age-encrypted file")?;
+    let mut reader = decryptor
+        .decrypt(std::iter::once(&identity as &dyn age::Identity))
+        .context("Failed to decrypt (wrong passphrase?)")?;
+
+    let mut output = Vec::new();
+    reader.read_to_end(&mut output)?;
+    Ok(output)
+}
+
+/// scrypt work factor override; the default takes seconds in debug builds
+const SCRYPT_WORK_FACTOR: Option<u8> = if cfg!(test) { Some(10) } else { None };
+
+/// Suffix of the identities key rotation keeps next to `AGE_KEY_PATH`
+pub const RETAINED_SUFFIX: &str = ".old";
+
+/// Load the age identity used for restore
+///
+/// Reads `AGE_KEY_PATH` if it exists, together with the previous keys
+/// rotation kept next to it (`<file>.<stamp>.old`), otherwise fetches
+/// `AGE_KEY_SECRET` from the configured secret provider.
+pub async fn load_identity(settings: &BackupSettings) -> Result<String> {
+    if let Some(path) = settings.age_key_path.as_deref().filter(|p| p.exists()) {
+        let mut identity = read_identity_file(path)?;
+        for old in retained_identities(path) {
+            identity.push('\n');
+            identity.push_str(&read_identity_file(&old)?);
+        }
+        return Ok(identity);
+    }
+
+    if let Some(reference) = &settings.age_key_secret {
+        let secrets = settings.secrets.build();
+        return secrets
+            .read(reference)
+            .await
+            .with_context(|| format!("Failed to read age key from {}", secrets.name()));
+    }
+
+    anyhow::bail!("No age key available: set AGE_KEY_PATH or AGE_KEY_SECRET in the backup config")
+}
+
+/// Identities kept by rotation next to `path`, newest first
+fn retained_identities(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.", name.to_string_lossy());
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut kept: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .map(|n| n.to_string_lossy())
+                .is_some_and(|n| n.starts_with(&prefix) && n.ends_with(RETAINED_SUFFIX))
+        })
+        .collect();
+    kept.sort_by(|a, b| b.cmp(a));
+    kept
+}
+
+fn read_identity_file(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("Failed to read age key {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::secrets::{FakeSecrets, SecretConfig};
+    use crate::backup::test_support::test_settings;
+    use age::secrecy::ExposeSecret;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let identity = age::x25519::Identity::generate();
+        let recipient = identity.to_public().to_string();
+
+        let encrypted = encrypt(b"profile data", &recipient).unwrap();
+        assert_ne!(encrypted, b"profile data");
+
+        let key = identity.to_string().expose_secret().to_string();
+        assert_eq!(decrypt(&encrypted, &key).unwrap(), b"profile data");
+    }
+
+    #[test]
+    fn test_decrypt_with_wrong_key_fails() {
+        let recipient = age::x25519::Identity::generate().to_public().to_string();
+        let other = age::x25519::Identity::generate();
+
+        let encrypted = encrypt(b"data", &recipient).unwrap();
+        assert!(decrypt(&encrypted, other.to_string().expose_secret()).is_err());
+    }
+
+    #[test]
+    fn test_passphrase_roundtrip() {
+        let encrypted = encrypt_with_passphrase(b"keys", "correct horse").unwrap();
+        assert_eq!(decrypt_with_passphrase(&encrypted, "correct horse").unwrap(), b"keys");
+        assert!(decrypt_with_passphrase(&encrypted, "wrong").is_err());
+    }
+
+    #[test]
+    fn test_invalid_recipient() {
+        assert!(encrypt(b"data", "age1your-public-key-here").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_identity_from_secret_provider() {
+        let mut settings = BackupSettings {
+            age_key_path: Some("/nonexistent/key.txt".into()),
+            secrets: SecretConfig::Fake(FakeSecrets::with(&[("age", "AGE-SECRET-KEY-1")])),
+            ..test_settings(Path::new("/nonexistent"))
+        };
+        assert!(load_identity(&settings).await.is_err());
+
+        settings.age_key_secret = Some("age".to_string());
+        assert_eq!(load_identity(&settings).await.unwrap(), "AGE-SECRET-KEY-1");
+    }
+}
@@ -0,0 +1,832 @@
+//! Backup and restore pipelines
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use super::archive::{self, Compression, Entry};
+use super::profiles::{self, wildcard_match, AppProfile, Layout};
+use super::rollback::{RollbackPoint, RollbackStore};
+use super::{crypto, format_size, history, keyring, BackupSettings};
+use crate::commands::executor::run_capture;
+use crate::commands::runner::CommandRunner;
+
+/// Firefox profile directory pattern
+const FIREFOX_PROFILE_PATTERN: &str = "*.default*";
+
+/// Firefox files that describe which profiles exist
+const FIREFOX_INI_FILES: [&str; 2] = ["profiles.ini", "installs.ini"];
+
+/// Knobs for a backup or restore run
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    /// Proceed even if the apps are running
+    pub force: bool,
+    /// Pull before and push after (backup) / pull before (restore)
+    pub sync: bool,
+    /// Back up and restore Safe Storage keys via the keyring
+    pub keyring: bool,
+}
+
+impl Options {
+    pub fn new(force: bool) -> Self {
+        Self {
+            force,
+            sync: true,
+            keyring: true,
+        }
+    }
+}
+
+/// Back up the given profiles to the configured storage
+pub async fn backup(
+    runner: &CommandRunner<'_>,
+    settings: &BackupSettings,
+    profiles: &[AppProfile],
+    options: Options,
+) -> Result<()> {
+    let (profiles, skipped) = skip_running(runner, profiles, options.force).await?;
+
+    let storage = settings.storage.build()?;
+    if options.sync {
+        runner.out(&format!("  Syncing backup storage ({})...", storage.name())).await;
+    }
+    storage.open(options.sync).await?;
+    let existing = storage.list().await?;
+
+    let mut written = Vec::new();
+    for profile in &profiles {
+        let root = profile.root(&settings.home);
+        let files = profile.collect_files(&root)?;
+        if files.is_empty() {
+            runner.out(&format!("  - {}: no profile found, skipping", profile.name)).await;
+            continue;
+        }
+
+        let key_file = profile.keyring_file();
+        let mut extra: Vec<(&str, Vec<u8>)> = Vec::new();
+        if let Some(key) = profile.keyring.as_ref().filter(|_| options.keyring) {
+            match keyring::export_key(key).await {
+                Ok(Some(secret)) => extra.push((&key_file, secret.into_bytes())),
+                Ok(None) => runner.out(&format!("  ! {}: no Safe Storage key in keyring", profile.name)).await,
+                Err(e) => runner.out(&format!("  ! {}: could not read keyring: {}", profile.name, e)).await,
+            }
+        }
+        let extra: Vec<(&str, &[u8])> = extra.iter().map(|(n, d)| (*n, d.as_slice())).collect();
+
+        let packed = archive::create(&root, &files, &extra)?;
+        let encrypted = crypto::encrypt(&packed, &settings.age_recipient)?;
+
+        storage.write(&profile.archive_name(), &encrypted).await?;
+
+        // The new archive supersedes the one written by the old scripts
+        let legacy = profile.legacy_archive_name();
+        if existing.iter().any(|f| f.name == legacy) {
+            storage.remove(&legacy).await?;
+        }
+
+        runner
+            .out(&format!(
+                "  ✓ {}: {} files, {}",
+                profile.name,
+                files.len(),
+                format_size(encrypted.len() as u64)
+            ))
+            .await;
+        written.push(profile.id);
+    }
+
+    if written.is_empty() {
+        fail_if_skipped(&skipped)?;
+        anyhow::bail!("No app profiles found to back up");
+    }
+
+    let host = forge::notify::attribution::current_hostname().unwrap_or_else(|| "unknown".to_string());
+    let message = history::commit_message(chrono::Local::now(), &host, &written);
+    let summary = storage.save(&message, options.sync).await?;
+    runner.out(&format!("  {}", summary)).await;
+
+    fail_if_skipped(&skipped)
+}
+
+/// Restore the given profiles from the configured storage
+///
+/// `snapshot` selects an older backup (any git revision, usually a hash
+/// from `forge apps history`, git storage only); `None` restores the latest
+/// one. The files
+/// being replaced are saved to a rollback point first, see [`undo_restore`].
+pub async fn restore(
+    runner: &CommandRunner<'_>,
+    settings: &BackupSettings,
+    profiles: &[AppProfile],
+    options: Options,
+    snapshot: Option<&str>,
+) -> Result<()> {
+    let (profiles, skipped) = skip_running(runner, profiles, options.force).await?;
+
+    let storage = settings.storage.build()?;
+    if options.sync {
+        runner.out(&format!("  Syncing backup storage ({})...", storage.name())).await;
+    }
+    storage.open(options.sync).await?;
+
+    let snapshot = match snapshot {
+        Some(rev) => {
+            let Some(repo) = storage.history() else {
+                anyhow::bail!("Older snapshots are only kept by the git storage backend");
+            };
+            let commit = repo.resolve(rev).await?;
+            runner.out(&format!("  Restoring snapshot {}", &commit[..commit.len().min(7)])).await;
+            let names: Vec<String> = repo.tree(&commit).await?.into_iter().map(|(name, _)| name).collect();
+            Some((commit, names))
+        }
+        None => None,
+    };
+
+    let identity = crypto::load_identity(settings).await?;
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let store = RollbackStore::new(&settings.rollback_dir);
+    let mut point = store.begin(&timestamp, snapshot.as_ref().map(|(commit, _)| &commit[..commit.len().min(7)]))?;
+
+    let stored: Vec<String> = match &snapshot {
+        Some((_, names)) => names.clone(),
+        None => storage.list().await?.into_iter().map(|f| f.name).collect(),
+    };
+
+    let mut restored = 0;
+    for profile in &profiles {
+        let found = find_archive(profile, |name| stored.iter().any(|n| n == name));
+        let Some((name, compression)) = found else {
+            runner.out(&format!("  - {}: no backup found, skipping", profile.name)).await;
+            continue;
+        };
+
+        let encrypted = match (&snapshot, storage.history()) {
+            (Some((commit, _)), Some(repo)) => repo.read_file_at(commit, &name).await?,
+            _ => storage.read(&name).await?,
+        };
+        let packed = crypto::decrypt(&encrypted, &identity)
+            .with_context(|| format!("Failed to decrypt {}", name))?;
+        let entries = archive::read(&packed, compression)?;
+
+        let root = profile.root(&settings.home);
+        let report = restore_entries(profile, &root, entries, &mut point)?;
+
+        if let (Some(key), Some(secret)) = (&profile.keyring, &report.keyring_key) {
+            if options.keyring {
+                if let Ok(Some(previous)) = keyring::export_key(key).await {
+                    point.save_keyring(profile.id, &root, &previous)?;
+                    point.commit()?;
+                }
+                if let Err(e) = keyring::import_key(key, secret).await {
+                    runner.out(&format!("  ! {}: could not import Safe Storage key: {}", profile.name, e)).await;
+                }
+            }
+        }
+
+        runner.out(&format!("  ✓ {}: {} files restored", profile.name, report.files)).await;
+        if report.saved > 0 {
+            runner.out(&format!("    {} existing files saved for undo", report.saved)).await;
+        }
+        restored += 1;
+    }
+
+    if point.is_empty() {
+        point.discard()?;
+    } else {
+        store.prune(settings.retention, settings.rollback_max_bytes)?;
+        runner.out("  Run `forge apps undo-restore` to put the previous files back").await;
+    }
+
+    if restored == 0 {
+        fail_if_skipped(&skipped)?;
+        anyhow::bail!("No app profile backups found in {}", storage.location());
+    }
+
+    fail_if_skipped(&skipped)
+}
+
+/// Undo the most recent restore from its rollback point
+///
+/// Files the restore created are removed, the ones it replaced are put back
+/// and the previous Safe Storage keys re-imported. The rollback point is
+/// removed afterwards, so running it again undoes the restore before that.
+pub async fn undo_restore(
+    runner: &CommandRunner<'_>,
+    settings: &BackupSettings,
+    options: Options,
+) -> Result<()> {
+    let store = RollbackStore::new(&settings.rollback_dir);
+    let Some(point) = store.latest()? else {
+        anyhow::bail!("No restore to undo in {}", settings.rollback_dir.display());
+    };
+
+    let from = match &point.manifest.snapshot {
+        Some(snapshot) => format!("{} (snapshot {})", point.manifest.created, snapshot),
+        None => point.manifest.created.clone(),
+    };
+    runner.out(&format!("  Undoing restore from {}", from)).await;
+
+    // A partial undo would leave the point half applied, so every app must be closed
+    let profiles: Vec<AppProfile> = point.manifest.apps.iter().filter_map(|a| profiles::find(&a.id)).collect();
+    let (_, skipped) = skip_running(runner, &profiles, options.force).await?;
+    fail_if_skipped(&skipped)?;
+
+    for app in &point.manifest.apps {
+        let name = profiles::find(&app.id).map(|p| p.name).unwrap_or(app.id.as_str());
+        let changed = point.undo_files(app)?;
+
+        let key = profiles::find(&app.id).and_then(|p| p.keyring);
+        if let (true, true, Some(key)) = (app.keyring, options.keyring, key) {
+            let secret = point.keyring_secret(&app.id)?;
+            if let Err(e) = keyring::import_key(&key, &secret).await {
+                runner.out(&format!("  ! {}: could not re-import Safe Storage key: {}", name, e)).await;
+            }
+        }
+
+        runner.out(&format!("  ✓ {}: {} files put back", name, changed)).await;
+    }
+
+    point.discard()
+}
+
+/// Split off apps that are currently running
+///
+/// Their profiles are skipped (unless forced) so only the apps being backed
+/// up or restored need to be closed. Fails if every selected app is running.
+async fn skip_running(
+    runner: &CommandRunner<'_>,
+    profiles: &[AppProfile],
+    force: bool,
+) -> Result<(Vec<AppProfile>, Vec<&'static str>)> {
+    let mut ready = Vec::new();
+    let mut skipped = Vec::new();
+
+    for profile in profiles {
+        if !is_running(profile).await {
+            ready.push(*profile);
+        } else if force {
+            runner.out(&format!("  ! {} is running, continuing anyway (--force)", profile.name)).await;
+            ready.push(*profile);
+        } else {
+            runner.out(&format!("  ! {} is running, skipping", profile.name)).await;
+            skipped.push(profile.name);
+        }
+    }
+
+    if ready.is_empty() && !skipped.is_empty() {
+        anyhow::bail!("{} running. Close it first or use --force", skipped.join(", "));
+    }
+    Ok((ready, skipped))
+}
+
+/// Names of the given apps that are running
+pub async fn running_apps(profiles: &[AppProfile]) -> Vec<&'static str> {
+    let mut running = Vec::new();
+    for profile in profiles {
+        if is_running(profile).await {
+            running.push(profile.name);
+        }
+    }
+    running
+}
+
+/// Whether any of the app's processes is running
+async fn is_running(profile: &AppProfile) -> bool {
+    for process in profile.processes {
+        if matches!(run_capture("pgrep", &["-x", process]).await, Ok((true, _, _))) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Report apps skipped because they were running as a failure
+fn fail_if_skipped(skipped: &[&str]) -> Result<()> {
+    if skipped.is_empty() {
+        return Ok(());
+    }
+    anyhow::bail!(
+        "Skipped {} (running). Close it and run again, or use --force",
+        skipped.join(", ")
+    )
+}
+
+/// Pick the archive to restore: native format first, then the scripts' format
+fn find_archive(profile: &AppProfile, exists: impl Fn(&str) -> bool) -> Option<(String, Compression)> {
+    [profile.archive_name(), profile.legacy_archive_name()]
+        .into_iter()
+        .find(|name| exists(name))
+        .and_then(|name| Compression::from_name(&name).map(|c| (name, c)))
+}
+
+/// Outcome of restoring one profile
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    /// Number of files written
+    pub files: usize,
+    /// Number of existing files saved to the rollback point
+    pub saved: usize,
+    /// Keyring key shipped in the archive
+    pub keyring_key: Option<String>,
+}
+
+/// Write archive entries into the profile directory
+///
+/// Existing files are saved to the rollback point, and the point's manifest
+/// written, before anything in the profile is touched.
+pub fn restore_entries(
+    profile: &AppProfile,
+    root: &Path,
+    entries: Vec<Entry>,
+    point: &mut RollbackPoint,
+) -> Result<RestoreReport> {
+    let mut report = RestoreReport::default();
+
+    let key_file = profile.keyring_file();
+    let (key_entries, entries): (Vec<Entry>, Vec<Entry>) =
+        entries.into_iter().partition(|e| e.path == key_file);
+    report.keyring_key = key_entries
+        .into_iter()
+        .next()
+        .map(|e| String::from_utf8_lossy(&e.data).trim().to_string());
+
+    let entries = match profile.layout {
+        Layout::Direct => entries,
+        Layout::FirefoxProfiles => map_firefox_profile(root, entries),
+    };
+
+    for entry in &entries {
+        if root.join(&entry.path).is_file() {
+            point.save_file(profile.id, root, &entry.path)?;
+            report.saved += 1;
+        } else {
+            point.record_created(profile.id, root, &entry.path);
+        }
+    }
+    point.commit()?;
+
+    for entry in &entries {
+        let target = root.join(&entry.path);
+        std::fs::create_dir_all(target.parent().unwrap_or(root))?;
+        std::fs::write(&target, &entry.data)
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+        set_mode(&target, entry.mode)?;
+        report.files += 1;
+    }
+
+    Ok(report)
+}
+
+/// Rewrite entry paths from the backed-up Firefox profile dir to the local one
+///
+/// Profile dir names are random per install, so the backup's profile is
+/// restored into the profile Firefox already uses here. `profiles.ini` and
+/// `installs.ini` are only restored when Firefox has no profiles yet.
+fn map_firefox_profile(root: &Path, entries: Vec<Entry>) -> Vec<Entry> {
+    let backup_profile = entries.iter().find_map(|e| {
+        let (dir, _) = e.path.split_once('/')?;
+        wildcard_match(FIREFOX_PROFILE_PATTERN, dir).then(|| dir.to_string())
+    });
+    let Some(backup_profile) = backup_profile else {
+        return entries;
+    };
+
+    let has_profiles = root.join("profiles.ini").exists();
+    let local_profile = local_firefox_profile(root).unwrap_or_else(|| backup_profile.clone());
+
+    entries
+        .into_iter()
+        .filter(|e| !(has_profiles && FIREFOX_INI_FILES.contains(&e.path.as_str())))
+        .map(|mut e| {
+            if let Some(rest) = e.path.strip_prefix(&format!("{}/", backup_profile)) {
+                e.path = format!("{}/{}", local_profile, rest);
+            }
+            e
+        })
+        .collect()
+}
+
+/// The local Firefox profile dir: first `Path=` in profiles.ini, else the
+/// first `*.default*` directory
+fn local_firefox_profile(root: &Path) -> Option<String> {
+    if let Ok(ini) = std::fs::read_to_string(root.join("profiles.ini")) {
+        let path = ini
+            .lines()
+            .find_map(|l| l.trim().strip_prefix("Path=").map(|p| p.trim().to_string()));
+        if let Some(path) = path.filter(|p| !p.contains("..") && root.join(p).is_dir()) {
+            return Some(path);
+        }
+    }
+
+    let mut dirs: Vec<String> = std::fs::read_dir(root)
+        .ok()?
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|name| wildcard_match(FIREFOX_PROFILE_PATTERN, name))
+        .collect();
+    dirs.sort();
+    dirs.into_iter().next()
+}
+
+fn set_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = if mode == 0 { 0o600 } else { mode };
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::profiles::{CHROME, FIREFOX};
+    use crate::backup::repo::BackupRepo;
+    use crate::backup::storage::{s3, S3Config, StorageConfig};
+    use crate::backup::test_support::{test_settings, ScratchDir};
+    use crate::commands::CommandMessage;
+    use age::secrecy::ExposeSecret;
+    use std::path::PathBuf;
+    use tokio::sync::mpsc;
+
+    fn entry(path: &str, data: &str) -> Entry {
+        Entry {
+            path: path.to_string(),
+            data: data.as_bytes().to_vec(),
+            mode: 0o644,
+        }
+    }
+
+    async fn git(args: &[&str]) -> String {
+        let (success, stdout, stderr) = run_capture("git", args).await.unwrap();
+        assert!(success, "git {:?} failed: {}", args, stderr);
+        stdout
+    }
+
+    /// Run `f` with a runner whose output is collected
+    async fn with_runner<F, Fut>(f: F) -> (Result<()>, Vec<String>)
+    where
+        F: FnOnce(mpsc::Sender<CommandMessage>) -> Fut,
+        Fut: std::future::Future<Output = Result<()>>,
+    {
+        let (tx, mut rx) = mpsc::channel(16);
+        let collector = tokio::spawn(async move {
+            let mut lines = Vec::new();
+            while let Some(msg) = rx.recv().await {
+                if let CommandMessage::Stdout(line) = msg {
+                    lines.push(line);
+                }
+            }
+            lines
+        });
+        let result = f(tx).await;
+        (result, collector.await.unwrap())
+    }
+
+    /// A rollback point in its own scratch area
+    fn point(scratch: &ScratchDir) -> RollbackPoint {
+        RollbackStore::new(scratch.path().join("rollback")).begin("20240101-000000", None).unwrap()
+    }
+
+    fn git_storage(remote: &Path, local_repo: PathBuf) -> StorageConfig {
+        StorageConfig::Git {
+            url: remote.to_string_lossy().to_string(),
+            local_repo,
+        }
+    }
+
+    fn offline_options() -> Options {
+        Options {
+            force: true,
+            sync: true,
+            keyring: false,
+        }
+    }
+
+    #[test]
+    fn test_restore_direct_saves_rollback() {
+        let home = ScratchDir::new("engine-direct");
+        let root = CHROME.root(home.path());
+        home.write(".config/google-chrome/Local State", "old");
+
+        let entries = vec![
+            entry("Local State", "new"),
+            entry("Default/Cookies", "cookies"),
+            entry(".chrome-safe-storage-key", "key123\n"),
+        ];
+        let mut point = point(&home);
+        let report = restore_entries(&CHROME, &root, entries, &mut point).unwrap();
+
+        assert_eq!(report.files, 2);
+        assert_eq!(report.saved, 1);
+        assert_eq!(report.keyring_key.as_deref(), Some("key123"));
+        assert_eq!(std::fs::read_to_string(root.join("Local State")).unwrap(), "new");
+        assert!(!root.join(".chrome-safe-storage-key").exists());
+
+        let app = &point.manifest.apps[0];
+        assert_eq!(app.saved, vec!["Local State"]);
+        assert_eq!(app.created, vec!["Default/Cookies"]);
+        assert_eq!(point.undo_files(app).unwrap(), 2);
+        assert_eq!(std::fs::read_to_string(root.join("Local State")).unwrap(), "old");
+        assert!(!root.join("Default/Cookies").exists());
+    }
+
+    #[test]
+    fn test_restore_maps_firefox_profile() {
+        let home = ScratchDir::new("engine-firefox");
+        let root = FIREFOX.root(home.path());
+        home.write(".mozilla/firefox/profiles.ini", "[Profile0]\nPath=zz99.default-release\n");
+        home.write(".mozilla/firefox/zz99.default-release/prefs.js", "old");
+
+        let entries = vec![
+            entry("ab12.default-release/prefs.js", "new"),
+            entry("ab12.default-release/sessionstore-backups/recovery.jsonlz4", "r"),
+            entry("profiles.ini", "[Profile0]\nPath=ab12.default-release\n"),
+        ];
+        let report = restore_entries(&FIREFOX, &root, entries, &mut point(&home)).unwrap();
+
+        assert_eq!(report.files, 2);
+        assert_eq!(std::fs::read_to_string(root.join("zz99.default-release/prefs.js")).unwrap(), "new");
+        assert!(root.join("zz99.default-release/sessionstore-backups/recovery.jsonlz4").exists());
+        assert!(!root.join("ab12.default-release").exists());
+        assert!(std::fs::read_to_string(root.join("profiles.ini")).unwrap().contains("zz99"));
+    }
+
+    #[test]
+    fn test_restore_firefox_without_local_profile() {
+        let home = ScratchDir::new("engine-firefox-new");
+        let root = FIREFOX.root(home.path());
+
+        let entries = vec![
+            entry("ab12.default-release/prefs.js", "new"),
+            entry("profiles.ini", "[Profile0]\nPath=ab12.default-release\n"),
+        ];
+        let report = restore_entries(&FIREFOX, &root, entries, &mut point(&home)).unwrap();
+
+        assert_eq!(report.files, 2);
+        assert_eq!(report.saved, 0);
+        assert!(root.join("ab12.default-release/prefs.js").exists());
+        assert!(root.join("profiles.ini").exists());
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_restore_through_bare_remote() {
+        let scratch = ScratchDir::new("engine-remote");
+        let remote = scratch.path().join("remote.git");
+        git(&["init", "-q", "--bare", remote.to_str().unwrap()]).await;
+
+        let identity = age::x25519::Identity::generate();
+        let key_path = scratch.write("key.txt", identity.to_string().expose_secret());
+
+        // Back up from the first machine
+        let home = scratch.path().join("home1");
+        scratch.write("home1/.config/google-chrome/Default/Cookies", "chrome cookies");
+        scratch.write("home1/.config/google-chrome/Local State", "state");
+        scratch.write("home1/.mozilla/firefox/ab12.default-release/logins.json", "logins");
+        scratch.write("home1/.mozilla/firefox/profiles.ini", "[Profile0]\nPath=ab12.default-release\n");
+
+        let settings = BackupSettings {
+            age_recipient: identity.to_public().to_string(),
+            age_key_path: Some(key_path),
+            storage: git_storage(&remote, home.join(".local/share/app-backup")),
+            ..test_settings(&home)
+        };
+
+        let (result, output) = with_runner(|tx| {
+            let settings = settings.clone();
+            async move {
+                let runner = CommandRunner::new(&tx);
+                backup(&runner, &settings, &[CHROME, FIREFOX], offline_options()).await
+            }
+        })
+        .await;
+        result.unwrap();
+        assert!(output.iter().any(|l| l.contains("✓ Chrome: 2 files")));
+        assert!(output.iter().any(|l| l.contains("✓ Firefox: 2 files")));
+
+        let remote_dir = format!("--git-dir={}", remote.display());
+        let tree = git(&[&remote_dir, "ls-tree", "--name-only", "HEAD"]).await;
+        assert!(tree.contains("chrome-profile.tar.zst.age"));
+        assert!(tree.contains("firefox-profile.tar.zst.age"));
+        assert!(git(&[&remote_dir, "log", "-1", "--format=%s"]).await.starts_with("Backup "));
+        assert_eq!(
+            git(&[&remote_dir, "log", "-1", "--format=%(trailers:key=Forge-Apps,valueonly)"]).await.trim(),
+            "chrome, firefox"
+        );
+
+        // Restore on a second machine with an existing Firefox profile
+        let home2 = scratch.path().join("home2");
+        scratch.write("home2/.config/google-chrome/Local State", "old state");
+        scratch.write("home2/.mozilla/firefox/profiles.ini", "[Profile0]\nPath=zz99.default-release\n");
+        std::fs::create_dir_all(home2.join(".mozilla/firefox/zz99.default-release")).unwrap();
+
+        let settings2 = BackupSettings {
+            storage: git_storage(&remote, home2.join(".local/share/app-backup")),
+            rollback_dir: scratch.path().join("rollback2"),
+            home: home2.clone(),
+            ..settings.clone()
+        };
+        let (result, output) = with_runner(|tx| {
+            let settings = settings2.clone();
+            async move {
+                let runner = CommandRunner::new(&tx);
+                restore(&runner, &settings, &[CHROME, FIREFOX], offline_options(), None).await
+            }
+        })
+        .await;
+        result.unwrap();
+        assert!(output.iter().any(|l| l.contains("✓ Chrome: 2 files restored")));
+
+        assert_eq!(
+            std::fs::read_to_string(home2.join(".config/google-chrome/Default/Cookies")).unwrap(),
+            "chrome cookies"
+        );
+        assert_eq!(
+            std::fs::read_to_string(home2.join(".mozilla/firefox/zz99.default-release/logins.json")).unwrap(),
+            "logins"
+        );
+        assert!(output.iter().any(|l| l.contains("1 existing files saved for undo")));
+
+        // Undo puts the second machine's profiles back
+        let (result, output) = with_runner(|tx| {
+            let settings = settings2.clone();
+            async move {
+                let runner = CommandRunner::new(&tx);
+                undo_restore(&runner, &settings, offline_options()).await
+            }
+        })
+        .await;
+        result.unwrap();
+        assert!(output.iter().any(|l| l.contains("✓ Chrome: 2 files put back")));
+        assert_eq!(
+            std::fs::read_to_string(home2.join(".config/google-chrome/Local State")).unwrap(),
+            "old state"
+        );
+        assert!(!home2.join(".config/google-chrome/Default/Cookies").exists());
+        assert!(!home2.join(".mozilla/firefox/zz99.default-release/logins.json").exists());
+
+        let (result, _) = with_runner(|tx| {
+            let settings = settings2.clone();
+            async move {
+                let runner = CommandRunner::new(&tx);
+                undo_restore(&runner, &settings, offline_options()).await
+            }
+        })
+        .await;
+        assert!(result.unwrap_err().to_string().contains("No restore to undo"));
+    }
+
+    #[tokio::test]
+    async fn test_restore_older_snapshot() {
+        let scratch = ScratchDir::new("engine-snapshot");
+        let remote = scratch.path().join("remote.git");
+        git(&["init", "-q", "--bare", remote.to_str().unwrap()]).await;
+
+        let identity = age::x25519::Identity::generate();
+        let key_path = scratch.write("key.txt", identity.to_string().expose_secret());
+        let home = scratch.path().join("home");
+        let settings = BackupSettings {
+            age_recipient: identity.to_public().to_string(),
+            age_key_path: Some(key_path),
+            storage: git_storage(&remote, home.join(".local/share/app-backup")),
+            ..test_settings(&home)
+        };
+
+        // A good backup, then a corrupted one on top
+        for contents in ["good", "corrupted"] {
+            scratch.write("home/.config/google-chrome/Local State", contents);
+            let (result, _) = with_runner(|tx| {
+                let settings = settings.clone();
+                async move {
+                    let runner = CommandRunner::new(&tx);
+                    backup(&runner, &settings, &[CHROME], offline_options()).await
+                }
+            })
+            .await;
+            result.unwrap();
+        }
+
+        let repo = BackupRepo::new(home.join(".local/share/app-backup"));
+        let snapshots = history::list(&repo, 10).await.unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[1].apps, vec!["chrome"]);
+        assert!(snapshots[1].size > 0);
+
+        let older = snapshots[1].hash.clone();
+        let (result, output) = with_runner(|tx| {
+            let settings = settings.clone();
+            async move {
+                let runner = CommandRunner::new(&tx);
+                restore(&runner, &settings, &[CHROME], offline_options(), Some(&older)).await
+            }
+        })
+        .await;
+        result.unwrap();
+        assert!(output.iter().any(|l| l.contains("Restoring snapshot")));
+        assert_eq!(
+            std::fs::read_to_string(home.join(".config/google-chrome/Local State")).unwrap(),
+            "good"
+        );
+
+        let (result, _) = with_runner(|tx| {
+            let settings = settings.clone();
+            async move {
+                let runner = CommandRunner::new(&tx);
+                restore(&runner, &settings, &[CHROME], offline_options(), Some("0000000")).await
+            }
+        })
+        .await;
+        assert!(result.unwrap_err().to_string().contains("Snapshot '0000000' not found"));
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_restore_through_other_backends() {
+        let scratch = ScratchDir::new("engine-backends");
+        let (endpoint, objects) = s3::test_support::serve().await;
+        let backends = [
+            StorageConfig::Local {
+                path: scratch.path().join("usb/app-backup"),
+            },
+            StorageConfig::S3(S3Config {
+                endpoint,
+                bucket: "backups".to_string(),
+                prefix: "laptop/".to_string(),
+                region: "us-east-1".to_string(),
+                access_key_id: "minio".to_string(),
+                secret_access_key: "minio-secret".to_string(),
+            }),
+        ];
+        std::fs::create_dir_all(scratch.path().join("usb")).unwrap();
+
+        let identity = age::x25519::Identity::generate();
+        let key_path = scratch.write("key.txt", identity.to_string().expose_secret());
+        let home = scratch.path().join("home");
+
+        for storage in backends {
+            scratch.write("home/.config/google-chrome/Local State", "state");
+            let settings = BackupSettings {
+                storage,
+                age_recipient: identity.to_public().to_string(),
+                age_key_path: Some(key_path.clone()),
+                ..test_settings(&home)
+            };
+
+            let (result, _) = with_runner(|tx| {
+                let settings = settings.clone();
+                async move {
+                    let runner = CommandRunner::new(&tx);
+                    backup(&runner, &settings, &[CHROME], offline_options()).await
+                }
+            })
+            .await;
+            result.unwrap();
+
+            scratch.write("home/.config/google-chrome/Local State", "changed");
+            let (result, _) = with_runner(|tx| {
+                let settings = settings.clone();
+                async move {
+                    let runner = CommandRunner::new(&tx);
+                    restore(&runner, &settings, &[CHROME], offline_options(), None).await
+                }
+            })
+            .await;
+            result.unwrap();
+            assert_eq!(
+                std::fs::read_to_string(home.join(".config/google-chrome/Local State")).unwrap(),
+                "state"
+            );
+
+            let (result, _) = with_runner(|tx| {
+                let settings = settings.clone();
+                async move {
+                    let runner = CommandRunner::new(&tx);
+                    restore(&runner, &settings, &[CHROME], offline_options(), Some("HEAD~1")).await
+                }
+            })
+            .await;
+            assert!(result.unwrap_err().to_string().contains("only kept by the git storage backend"));
+        }
+
+        assert!(scratch.path().join("usb/app-backup/chrome-profile.tar.zst.age").exists());
+        assert!(objects.lock().unwrap().contains_key("backups/laptop/chrome-profile.tar.zst.age"));
+    }
+
+    #[tokio::test]
+    async fn test_backup_without_profiles_fails() {
+        let scratch = ScratchDir::new("engine-empty");
+        let remote = scratch.path().join("remote.git");
+        git(&["init", "-q", "--bare", remote.to_str().unwrap()]).await;
+
+        let settings = BackupSettings {
+            age_recipient: age::x25519::Identity::generate().to_public().to_string(),
+            storage: git_storage(&remote, scratch.path().join("repo")),
+            ..test_settings(&scratch.path().join("home"))
+        };
+
+        let (result, _) = with_runner(|tx| async move {
+            let runner = CommandRunner::new(&tx);
+            backup(&runner, &settings, &[CHROME, FIREFOX], offline_options()).await
+        })
+        .await;
+        assert!(result.unwrap_err().to_string().contains("No app profiles found"));
+    }
+}
@@ -0,0 +1,208 @@
+//! Backup snapshots from the repository history
+//!
+//! Every backup is one commit, so older profile states can be restored from
+//! git history. Backup commits carry `Forge-Host` and `Forge-Apps` trailers;
+//! for commits written by the old scripts the apps are inferred from the
+//! archives the commit touched.
+
+use anyhow::Result;
+use chrono::{DateTime, Local, TimeZone};
+
+use super::format_size;
+use super::repo::BackupRepo;
+
+/// Trailer naming the host a backup was made on
+const HOST_TRAILER: &str = "Forge-Host";
+
+/// Trailer listing the app ids included in a backup
+const APPS_TRAILER: &str = "Forge-Apps";
+
+/// Record and field separators in the `git log` format
+const RECORD_SEP: char = '\x1e';
+const FIELD_SEP: char = '\x1f';
+
+/// A backup commit that can be restored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Abbreviated commit hash (used with `--at`)
+    pub hash: String,
+    /// Commit time (unix seconds)
+    pub timestamp: i64,
+    /// Host the backup was made on, if recorded
+    pub host: Option<String>,
+    /// App ids included in the backup
+    pub apps: Vec<String>,
+    /// Total size of the encrypted archives at this snapshot
+    pub size: u64,
+}
+
+impl Snapshot {
+    /// Local date and time of the backup
+    pub fn date(&self) -> String {
+        Local
+            .timestamp_opt(self.timestamp, 0)
+            .single()
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "unknown date".to_string())
+    }
+
+    /// One-line description for lists
+    pub fn summary(&self) -> String {
+        format!(
+            "{}  {}  {:<12}  {:<24}  {}",
+            self.hash,
+            self.date(),
+            self.host.as_deref().unwrap_or("unknown host"),
+            self.apps.join(", "),
+            format_size(self.size)
+        )
+    }
+}
+
+/// Commit message for a backup, with trailers for the history view
+pub fn commit_message(time: DateTime<Local>, host: &str, apps: &[&str]) -> String {
+    format!(
+        "Backup {}\n\n{}: {}\n{}: {}\n",
+        time.format("%Y-%m-%d %H:%M"),
+        HOST_TRAILER,
+        host,
+        APPS_TRAILER,
+        apps.join(", ")
+    )
+}
+
+/// List the newest `limit` backup snapshots
+pub async fn list(repo: &BackupRepo, limit: usize) -> Result<Vec<Snapshot>> {
+    // A fresh clone of an empty remote has no history yet
+    if repo.resolve("HEAD").await.is_err() {
+        return Ok(Vec::new());
+    }
+
+    let format = format!(
+        "--format={r}%h{f}%ct{f}%(trailers:key={host},valueonly,separator=%x2C){f}%(trailers:key={apps},valueonly,separator=%x2C){f}",
+        r = "%x1e",
+        f = "%x1f",
+        host = HOST_TRAILER,
+        apps = APPS_TRAILER,
+    );
+    let limit = format!("-n{}", limit);
+    let output = repo.git_ok(&["log", &limit, &format, "--name-only"]).await?;
+
+    let mut snapshots = parse_log(&output);
+    for snapshot in &mut snapshots {
+        snapshot.size = repo
+            .tree(&snapshot.hash)
+            .await?
+            .iter()
+            .filter(|(name, _)| name.ends_with(".age"))
+            .map(|(_, size)| size)
+            .sum();
+    }
+    Ok(snapshots)
+}
+
+/// Parse the `git log` output produced by [`list`]
+///
+/// Commits that didn't touch any profile archive are left out.
+fn parse_log(output: &str) -> Vec<Snapshot> {
+    output
+        .split(RECORD_SEP)
+        .filter_map(|record| {
+            let mut fields = record.splitn(5, FIELD_SEP);
+            let hash = fields.next()?.trim().to_string();
+            let timestamp = fields.next()?.trim().parse().ok()?;
+            let host = Some(fields.next()?.trim().to_string()).filter(|h| !h.is_empty());
+            let trailer_apps = split_list(fields.next()?);
+            let files = fields.next().unwrap_or_default();
+
+            let apps = if trailer_apps.is_empty() {
+                let mut apps: Vec<String> = files.lines().filter_map(app_from_archive).collect();
+                apps.sort();
+                apps.dedup();
+                apps
+            } else {
+                trailer_apps
+            };
+
+            (!hash.is_empty() && !apps.is_empty()).then_some(Snapshot {
+                hash,
+                timestamp,
+                host,
+                apps,
+                size: 0,
+            })
+        })
+        .collect()
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// App id from an archive name like `chrome-profile.tar.zst.age`
+fn app_from_archive(name: &str) -> Option<String> {
+    let (id, rest) = name.trim().split_once("-profile.tar.")?;
+    rest.ends_with(".age").then(|| id.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commit_message_has_trailers() {
+        let time = Local.with_ymd_and_hms(2024, 5, 1, 14, 32, 0).unwrap();
+        let message = commit_message(time, "kraken", &["chrome", "firefox"]);
+        assert!(message.starts_with("Backup 2024-05-01 14:32\n\n"));
+        assert!(message.contains("Forge-Host: kraken\n"));
+        assert!(message.contains("Forge-Apps: chrome, firefox\n"));
+    }
+
+    #[test]
+    fn test_parse_log_with_trailers() {
+        let output = "\x1ea1b2c3d\x1f1714574000\x1fkraken\x1fchrome,firefox\x1f\n\nchrome-profile.tar.zst.age\nfirefox-profile.tar.zst.age\n";
+        let snapshots = parse_log(output);
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].hash, "a1b2c3d");
+        assert_eq!(snapshots[0].timestamp, 1714574000);
+        assert_eq!(snapshots[0].host.as_deref(), Some("kraken"));
+        assert_eq!(snapshots[0].apps, vec!["chrome", "firefox"]);
+    }
+
+    #[test]
+    fn test_parse_log_infers_apps_from_script_commits() {
+        let output = "\x1eb2c3d4e\x1f1714570000\x1f\x1f\x1f\n\nchrome-profile.tar.gz.age\nkeys.tar.gz.age\n\
+                      \x1ec3d4e5f\x1f1714560000\x1f\x1f\x1f\n\nREADME.md\n";
+        let snapshots = parse_log(output);
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].hash, "b2c3d4e");
+        assert_eq!(snapshots[0].host, None);
+        assert_eq!(snapshots[0].apps, vec!["chrome"]);
+    }
+
+    #[test]
+    fn test_app_from_archive() {
+        assert_eq!(app_from_archive("slack-profile.tar.zst.age"), Some("slack".to_string()));
+        assert_eq!(app_from_archive("keys.tar.gz.age"), None);
+        assert_eq!(app_from_archive("chrome-profile.tar.zst"), None);
+    }
+
+    #[test]
+    fn test_summary() {
+        let snapshot = Snapshot {
+            hash: "a1b2c3d".to_string(),
+            timestamp: 1714574000,
+            host: None,
+            apps: vec!["chrome".to_string()],
+            size: 2048,
+        };
+        let summary = snapshot.summary();
+        assert!(summary.starts_with("a1b2c3d  "));
+        assert!(summary.contains("unknown host"));
+        assert!(summary.ends_with("2.0 KB"));
+    }
+}
@@ -0,0 +1,85 @@
+//! "Safe Storage" keys in the Secret Service
+//!
+//! Chrome and Electron apps encrypt cookies and saved passwords with a key
+//! kept in the desktop keyring, so the key is backed up alongside the
+//! profile. Without it the restored cookies can't be decrypted.
+
+use anyhow::{Context, Result};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use super::profiles::KeyringKey;
+use crate::commands::executor::run_capture;
+
+/// Secret Service schema Chromium-based apps store their key under
+const SAFE_STORAGE_SCHEMA: &str = "chrome_libsecret_os_crypt_password_v2";
+
+fn attributes(key: &KeyringKey) -> [&'static str; 4] {
+    ["xdg:schema", SAFE_STORAGE_SCHEMA, "application", key.application]
+}
+
+/// Read an app's Safe Storage key, if the keyring has one
+pub async fn export_key(key: &KeyringKey) -> Result<Option<String>> {
+    let mut args = vec!["search", "--all"];
+    args.extend(attributes(key));
+    let (success, stdout, _) = run_capture("secret-tool", &args).await?;
+    if !success {
+        return Ok(None);
+    }
+    Ok(parse_secret(&stdout))
+}
+
+/// Store an app's Safe Storage key in the keyring
+pub async fn import_key(key: &KeyringKey, secret: &str) -> Result<()> {
+    let label = format!("--label={}", key.label);
+    let mut child = Command::new("secret-tool")
+        .args(["store", &label])
+        .args(attributes(key))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run secret-tool")?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(secret.as_bytes()).await?;
+    }
+
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "secret-tool store failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Extract the `secret = ...` value from `secret-tool search` output
+fn parse_secret(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        (key.trim() == "secret" && !value.trim().is_empty()).then(|| value.trim().to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_secret() {
+        let output = "[/org/freedesktop/secrets/collection/login/7]\n\
+                      label = Chrome Safe Storage\n\
+                      secret = abc123==\n\
+                      created = 2024-01-01 00:00:00\n";
+        assert_eq!(parse_secret(output), Some("abc123==".to_string()));
+    }
+
+    #[test]
+    fn test_parse_secret_missing() {
+        assert_eq!(parse_secret("label = Chrome Safe Storage\n"), None);
+        assert_eq!(parse_secret(""), None);
+    }
+}
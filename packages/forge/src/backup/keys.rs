@@ -0,0 +1,356 @@
+//! Keys archive (age identity and SSH key)
+//!
+//! Same format as the `keys-backup` / `keys-restore` scripts: a tar.gz with
+//! `age-key.txt`, `id_ed25519` and `id_ed25519.pub`, encrypted with a
+//! passphrase and kept as `keys.tar.gz.age` next to the profile archives.
+
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::Path;
+
+use super::archive::{self, Compression, Entry};
+use super::{crypto, format_size, BackupSettings};
+use crate::commands::executor::run_capture;
+use crate::commands::runner::CommandRunner;
+
+/// Name of the keys archive in the backup storage
+pub const KEYS_ARCHIVE: &str = "keys.tar.gz.age";
+
+const AGE_KEY_ENTRY: &str = "age-key.txt";
+const SSH_KEY_ENTRY: &str = "id_ed25519";
+const SSH_PUB_ENTRY: &str = "id_ed25519.pub";
+
+/// Fetch the keys archive passphrase from the secret provider
+///
+/// The TUI has no way to prompt for it, so it must be configured.
+pub async fn load_passphrase(settings: &BackupSettings) -> Result<String> {
+    let Some(reference) = &settings.keys_passphrase_secret else {
+        anyhow::bail!("KEYS_PASSPHRASE_SECRET not set in backup config");
+    };
+    let secrets = settings.secrets.build();
+    let passphrase = secrets
+        .read(reference)
+        .await
+        .with_context(|| format!("Failed to read keys passphrase from {}", secrets.name()))?;
+    Ok(passphrase.trim_end_matches('\n').to_string())
+}
+
+/// Fetch the age and SSH keys from the secret provider
+///
+/// Existing keys are left alone unless `force` is set.
+pub async fn setup(runner: &CommandRunner<'_>, settings: &BackupSettings, force: bool) -> Result<()> {
+    let secrets = settings.secrets.build();
+    runner.out(&format!("  Retrieving keys from {}", secrets.name())).await;
+    secrets.check().await?;
+
+    let targets = [
+        (&settings.age_key_secret, &settings.age_key_path, "Age key", "AGE_KEY"),
+        (&settings.ssh_key_secret, &settings.ssh_key_path, "SSH key", "SSH_KEY"),
+    ];
+    let mut created = 0;
+    for (reference, path, label, var) in targets {
+        let (Some(reference), Some(path)) = (reference, path) else {
+            runner
+                .out(&format!("  ! {} setup not configured (need both {}_SECRET and {}_PATH)", label, var, var))
+                .await;
+            continue;
+        };
+        if path.exists() && !force {
+            runner
+                .out(&format!("  ! {} already exists at {} - skipping (use --force to overwrite)", label, path.display()))
+                .await;
+            continue;
+        }
+
+        let mut secret = secrets.read(reference).await?;
+        if !secret.ends_with('\n') {
+            secret.push('\n');
+        }
+        write_key(path, secret.as_bytes(), 0o600)?;
+        runner.out(&format!("  ✓ {} saved to {}", label, path.display())).await;
+        created += 1;
+
+        if var == "SSH_KEY" {
+            let key = path.to_string_lossy();
+            let (success, stdout, stderr) = run_capture("ssh-keygen", &["-y", "-f", key.as_ref()]).await?;
+            if !success {
+                anyhow::bail!("Failed to derive SSH public key: {}", stderr.trim());
+            }
+            let public = path.with_extension("pub");
+            write_key(&public, stdout.as_bytes(), 0o644)?;
+            runner.out(&format!("  ✓ Public key saved to {}", public.display())).await;
+        }
+    }
+
+    if created == 0 {
+        runner.out("  No keys created").await;
+    }
+    Ok(())
+}
+
+/// Back up the local keys to the configured storage
+pub async fn backup(runner: &CommandRunner<'_>, settings: &BackupSettings, passphrase: &str) -> Result<()> {
+    let entries = collect(settings)?;
+    if entries.is_empty() {
+        anyhow::bail!("No keys found to back up. Run 'forge keys setup' first.");
+    }
+    for entry in &entries {
+        runner.out(&format!("  Adding {}", entry.path)).await;
+    }
+
+    let storage = settings.storage.build()?;
+    runner.out(&format!("  Syncing backup storage ({})...", storage.name())).await;
+    storage.open(true).await?;
+
+    let packed = archive::pack(&entries, Compression::Gzip)?;
+    let encrypted = crypto::encrypt_with_passphrase(&packed, passphrase)?;
+    storage.write(KEYS_ARCHIVE, &encrypted).await?;
+    runner.out(&format!("  ✓ Keys archive: {}", format_size(encrypted.len() as u64))).await;
+
+    let message = format!("Keys backup {}", chrono::Local::now().format("%Y-%m-%d %H:%M"));
+    let summary = storage.save(&message, true).await?;
+    runner.out(&format!("  {}", summary)).await;
+    Ok(())
+}
+
+/// Restore keys from the configured storage
+///
+/// Existing keys are left alone unless `force` is set.
+pub async fn restore(
+    runner: &CommandRunner<'_>,
+    settings: &BackupSettings,
+    passphrase: &str,
+    force: bool,
+) -> Result<()> {
+    let storage = settings.storage.build()?;
+    runner.out(&format!("  Syncing backup storage ({})...", storage.name())).await;
+    storage.open(true).await?;
+
+    let encrypted = storage
+        .read(KEYS_ARCHIVE)
+        .await
+        .with_context(|| format!("Keys backup not found in {}", storage.location()))?;
+    let packed = crypto::decrypt_with_passphrase(&encrypted, passphrase)?;
+    let entries = archive::read(&packed, Compression::Gzip)?;
+
+    let mut restored = 0;
+    for line in restore_entries(settings, &entries, force)? {
+        restored += usize::from(line.starts_with('✓'));
+        runner.out(&format!("  {}", line)).await;
+    }
+
+    // Older archives may lack the public key; derive it from the private one
+    if let Some(ssh_key) = &settings.ssh_key_path {
+        let public = ssh_key.with_extension("pub");
+        if ssh_key.exists() && !public.exists() {
+            let key = ssh_key.to_string_lossy();
+            let (success, stdout, _) = run_capture("ssh-keygen", &["-y", "-f", key.as_ref()]).await?;
+            if success {
+                write_key(&public, stdout.as_bytes(), 0o644)?;
+                runner.out(&format!("  ✓ Generated SSH public key {}", public.display())).await;
+            }
+        }
+    }
+
+    if restored == 0 {
+        runner.out("  No keys restored").await;
+    }
+    Ok(())
+}
+
+/// Swap the age identity inside an encrypted keys archive
+///
+/// Used by key rotation so a later keys restore brings back the new key.
+pub fn replace_age_key(encrypted: &[u8], passphrase: &str, identity: &str) -> Result<Vec<u8>> {
+    let packed = crypto::decrypt_with_passphrase(encrypted, passphrase)?;
+    let mut entries = archive::read(&packed, Compression::Gzip)?;
+    entries.retain(|e| e.path != AGE_KEY_ENTRY);
+    entries.push(Entry {
+        path: AGE_KEY_ENTRY.to_string(),
+        data: identity.as_bytes().to_vec(),
+        mode: 0o600,
+    });
+    let packed = archive::pack(&entries, Compression::Gzip)?;
+    crypto::encrypt_with_passphrase(&packed, passphrase)
+}
+
+/// The keys configured in `settings` that exist locally
+fn collect(settings: &BackupSettings) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut add = |path: &Path, name: &str, mode: u32| -> Result<()> {
+        if path.is_file() {
+            let data = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+            entries.push(Entry {
+                path: name.to_string(),
+                data,
+                mode,
+            });
+        }
+        Ok(())
+    };
+
+    if let Some(path) = &settings.age_key_path {
+        add(path, AGE_KEY_ENTRY, 0o600)?;
+    }
+    if let Some(path) = &settings.ssh_key_path {
+        add(path, SSH_KEY_ENTRY, 0o600)?;
+        if path.is_file() {
+            add(&path.with_extension("pub"), SSH_PUB_ENTRY, 0o644)?;
+        }
+    }
+    Ok(entries)
+}
+
+/// Write archive entries to the configured key paths
+///
+/// Returns one line per key: `✓ ...` when written, `! ...` when skipped.
+fn restore_entries(settings: &BackupSettings, entries: &[Entry], force: bool) -> Result<Vec<String>> {
+    let find = |name: &str| entries.iter().find(|e| e.path == name);
+    let mut lines = Vec::new();
+
+    let targets = [
+        (AGE_KEY_ENTRY, settings.age_key_path.clone(), "Age key"),
+        (SSH_KEY_ENTRY, settings.ssh_key_path.clone(), "SSH key"),
+    ];
+    for (name, path, label) in targets {
+        let (Some(entry), Some(path)) = (find(name), path) else {
+            continue;
+        };
+        if path.exists() && !force {
+            lines.push(format!("! {} already exists at {} - skipping (use --force to overwrite)", label, path.display()));
+            continue;
+        }
+        write_key(&path, &entry.data, 0o600)?;
+        lines.push(format!("✓ Restored {} to {}", label, path.display()));
+
+        if name == SSH_KEY_ENTRY {
+            if let Some(public) = find(SSH_PUB_ENTRY) {
+                write_key(&path.with_extension("pub"), &public.data, 0o644)?;
+            }
+        }
+    }
+    Ok(lines)
+}
+
+pub(crate) fn write_key(path: &Path, data: &[u8], mode: u32) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    // Create with the final mode so the key is never readable with umask permissions;
+    // an existing file keeps its old mode on open, so tighten it before writing
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(mode)
+        .open(path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    file.set_permissions(std::fs::Permissions::from_mode(mode))?;
+    file.write_all(data).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::secrets::{FakeSecrets, SecretConfig};
+    use crate::backup::storage::StorageConfig;
+    use crate::backup::test_support::{test_settings, ScratchDir};
+    use crate::commands::CommandMessage;
+    use tokio::sync::mpsc;
+
+    fn settings(scratch: &ScratchDir, home: &str) -> BackupSettings {
+        let home = scratch.path().join(home);
+        BackupSettings {
+            storage: StorageConfig::Local {
+                path: scratch.path().join("usb"),
+            },
+            age_key_path: Some(home.join(".config/age/key.txt")),
+            ssh_key_path: Some(home.join(".ssh/id_ed25519")),
+            ..test_settings(&home)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_keys_roundtrip_through_local_storage() {
+        let scratch = ScratchDir::new("keys-roundtrip");
+        scratch.write("home1/.config/age/key.txt", "AGE-SECRET-KEY-1");
+        scratch.write("home1/.ssh/id_ed25519", "ssh private");
+        scratch.write("home1/.ssh/id_ed25519.pub", "ssh-ed25519 AAAA me@laptop");
+
+        let (tx, _rx) = mpsc::channel::<CommandMessage>(64);
+        let runner = CommandRunner::new(&tx);
+        backup(&runner, &settings(&scratch, "home1"), "passphrase").await.unwrap();
+        assert!(scratch.path().join("usb").join(KEYS_ARCHIVE).exists());
+
+        // A second machine that already has an age key
+        scratch.write("home2/.config/age/key.txt", "existing");
+        let settings2 = settings(&scratch, "home2");
+        restore(&runner, &settings2, "passphrase", false).await.unwrap();
+
+        let home2 = scratch.path().join("home2");
+        assert_eq!(std::fs::read_to_string(home2.join(".config/age/key.txt")).unwrap(), "existing");
+        assert_eq!(std::fs::read_to_string(home2.join(".ssh/id_ed25519")).unwrap(), "ssh private");
+        assert!(std::fs::read_to_string(home2.join(".ssh/id_ed25519.pub")).unwrap().starts_with("ssh-ed25519"));
+        let mode = std::fs::metadata(home2.join(".ssh/id_ed25519")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        restore(&runner, &settings2, "passphrase", true).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(home2.join(".config/age/key.txt")).unwrap(),
+            "AGE-SECRET-KEY-1"
+        );
+
+        assert!(restore(&runner, &settings2, "wrong", true).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_setup_from_secret_provider() {
+        let scratch = ScratchDir::new("keys-setup");
+        let ssh = scratch.path().join("ssh-source");
+        let (success, _, stderr) = run_capture(
+            "ssh-keygen",
+            &["-q", "-t", "ed25519", "-N", "", "-C", "me@laptop", "-f", ssh.to_str().unwrap()],
+        )
+        .await
+        .unwrap();
+        assert!(success, "{}", stderr);
+
+        let fake = FakeSecrets::with(&[
+            ("age", "AGE-SECRET-KEY-1"),
+            ("ssh", &std::fs::read_to_string(&ssh).unwrap()),
+            ("passphrase", "correct horse\n"),
+        ]);
+        let mut settings = settings(&scratch, "home");
+        settings.secrets = SecretConfig::Fake(fake);
+        settings.age_key_secret = Some("age".to_string());
+        settings.ssh_key_secret = Some("ssh".to_string());
+        settings.keys_passphrase_secret = Some("passphrase".to_string());
+        scratch.write("home/.config/age/key.txt", "existing");
+
+        let (tx, _rx) = mpsc::channel::<CommandMessage>(64);
+        let runner = CommandRunner::new(&tx);
+        setup(&runner, &settings, false).await.unwrap();
+
+        let home = scratch.path().join("home");
+        assert_eq!(std::fs::read_to_string(home.join(".config/age/key.txt")).unwrap(), "existing");
+        assert!(std::fs::read_to_string(home.join(".ssh/id_ed25519.pub")).unwrap().starts_with("ssh-ed25519 "));
+
+        setup(&runner, &settings, true).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(home.join(".config/age/key.txt")).unwrap(),
+            "AGE-SECRET-KEY-1\n"
+        );
+        assert_eq!(load_passphrase(&settings).await.unwrap(), "correct horse");
+    }
+
+    #[tokio::test]
+    async fn test_backup_without_keys_fails() {
+        let scratch = ScratchDir::new("keys-none");
+        let (tx, _rx) = mpsc::channel::<CommandMessage>(64);
+        let runner = CommandRunner::new(&tx);
+        let err = backup(&runner, &settings(&scratch, "home"), "passphrase").await.unwrap_err();
+        assert!(err.to_string().contains("No keys found"));
+    }
+}
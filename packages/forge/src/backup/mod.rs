@@ -0,0 +1,252 @@
+//! Native app profile backup engine
+//!
+//! Replaces the `app-backup` / `app-restore` shell scripts: the essential
+//! files of each app profile are packed into a tar+zstd archive, encrypted
+//! with age to the configured recipient and written to the configured
+//! storage backend (a git repository by default, see [`storage`]). Restore
+//! reverses this, first saving the files it replaces into a local rollback
+//! point so it can be undone.
+//!
+//! Archives written by the scripts (`*-profile.tar.gz.age`) can still be
+//! restored.
+
+pub mod archive;
+pub mod audit;
+pub mod crypto;
+pub mod engine;
+pub mod history;
+pub mod keyring;
+pub mod keys;
+pub mod paper;
+pub mod profiles;
+pub mod provision;
+pub mod repo;
+pub mod rollback;
+pub mod rotate;
+pub mod schedule;
+pub mod secrets;
+pub mod shamir;
+pub mod storage;
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::constants::{app_backup_config_path, FORGE_ROLLBACK_DIR};
+use crate::system::config::{expand_tilde, load_browser_config, BrowserBackupConfig};
+use secrets::SecretConfig;
+use storage::StorageConfig;
+
+/// Everything the engine needs to know, resolved from the backup config
+#[derive(Debug, Clone)]
+pub struct BackupSettings {
+    /// Where the encrypted archives are kept
+    pub storage: StorageConfig,
+    /// age public key archives are encrypted to
+    pub age_recipient: String,
+    /// Local age identity file (tried first on restore)
+    pub age_key_path: Option<PathBuf>,
+    /// Secret reference for the age identity (fallback on restore)
+    pub age_key_secret: Option<String>,
+    /// SSH private key included in keys backups
+    pub ssh_key_path: Option<PathBuf>,
+    /// Secret reference for the SSH key (used by keys setup)
+    pub ssh_key_secret: Option<String>,
+    /// Secret reference for the keys archive passphrase
+    pub keys_passphrase_secret: Option<String>,
+    /// Where the secret references are looked up
+    pub secrets: SecretConfig,
+    /// Number of restore rollback points to keep
+    pub retention: usize,
+    /// Local rollback area for undoing restores
+    pub rollback_dir: PathBuf,
+    /// Size limit of the rollback area (the newest point is always kept)
+    pub rollback_max_bytes: u64,
+    /// Home directory the profiles live under
+    pub home: PathBuf,
+}
+
+impl BackupSettings {
+    /// Load settings from `~/.config/app-backup/config`
+    pub fn load() -> Result<Self> {
+        let path = app_backup_config_path();
+        let config = load_browser_config(&path)
+            .with_context(|| format!("Failed to read backup config {}", path.display()))?;
+        let home = dirs::home_dir().context("Could not determine home directory")?;
+        Self::from_config(&config, &home)
+    }
+
+    /// Resolve a parsed config against a home directory
+    pub fn from_config(config: &BrowserBackupConfig, home: &Path) -> Result<Self> {
+        if config.age_recipient.is_empty() {
+            anyhow::bail!("AGE_RECIPIENT not set in backup config");
+        }
+
+        Ok(Self {
+            storage: StorageConfig::from_config(config, home)?,
+            age_recipient: config.age_recipient.clone(),
+            age_key_path: config
+                .age_key_path
+                .as_deref()
+                .filter(|p| !p.is_empty())
+                .map(|p| resolve_home(p, home)),
+            age_key_secret: config.age_key_secret.clone().filter(|r| !r.is_empty()),
+            ssh_key_path: config
+                .ssh_key_path
+                .as_deref()
+                .filter(|p| !p.is_empty())
+                .map(|p| resolve_home(p, home)),
+            ssh_key_secret: config.ssh_key_secret.clone().filter(|r| !r.is_empty()),
+            keys_passphrase_secret: config.keys_passphrase_secret.clone().filter(|r| !r.is_empty()),
+            secrets: SecretConfig::from_config(config, home)?,
+            retention: config.backup_retention as usize,
+            rollback_dir: home.join(FORGE_ROLLBACK_DIR),
+            rollback_max_bytes: config.rollback_max_mb.saturating_mul(1024 * 1024),
+            home: home.to_path_buf(),
+        })
+    }
+}
+
+/// Expand a leading `~/` against the given home directory
+fn resolve_home(path: &str, home: &Path) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home.join(rest),
+        None => PathBuf::from(expand_tilde(path)),
+    }
+}
+
+/// Human-readable byte size
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    //! Scratch directories and settings for backup tests
+
+    use std::path::{Path, PathBuf};
+
+    use super::secrets::SecretConfig;
+    use super::storage::StorageConfig;
+    use super::BackupSettings;
+
+    /// A directory under the system temp dir, removed on drop
+    pub struct ScratchDir(PathBuf);
+
+    impl ScratchDir {
+        pub fn new(name: &str) -> Self {
+            let nanos = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.subsec_nanos())
+                .unwrap_or(0);
+            let path = std::env::temp_dir().join(format!("forge-{}-{}-{}", name, std::process::id(), nanos));
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.0
+        }
+
+        /// Write a file, creating parent directories
+        pub fn write(&self, rel: &str, contents: &str) -> PathBuf {
+            let path = self.0.join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, contents).unwrap();
+            path
+        }
+    }
+
+    impl Drop for ScratchDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Settings with everything under `home`: local storage, no keys or secrets
+    pub fn test_settings(home: &Path) -> BackupSettings {
+        BackupSettings {
+            storage: StorageConfig::Local {
+                path: home.join("backup"),
+            },
+            age_recipient: String::new(),
+            age_key_path: None,
+            age_key_secret: None,
+            ssh_key_path: None,
+            ssh_key_secret: None,
+            keys_passphrase_secret: None,
+            secrets: SecretConfig::OnePassword,
+            retention: 3,
+            rollback_dir: home.join("rollback"),
+            rollback_max_bytes: u64::MAX,
+            home: home.to_path_buf(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_from_config() {
+        let config = BrowserBackupConfig {
+            repo: "git@example.com:me/settings.git".to_string(),
+            age_recipient: "age1abc".to_string(),
+            age_key_secret: Some(String::new()),
+            age_key_path: Some("~/.config/age/key.txt".to_string()),
+            local_repo_path: "~/.local/share/app-backup".to_string(),
+            backup_retention: 3,
+            rollback_max_mb: 10,
+            ssh_key_path: Some("~/.ssh/id_ed25519".to_string()),
+            ..Default::default()
+        };
+
+        let settings = BackupSettings::from_config(&config, Path::new("/home/me")).unwrap();
+        assert_eq!(
+            settings.storage,
+            StorageConfig::Git {
+                url: "git@example.com:me/settings.git".to_string(),
+                local_repo: PathBuf::from("/home/me/.local/share/app-backup"),
+            }
+        );
+        assert_eq!(settings.age_key_path, Some(PathBuf::from("/home/me/.config/age/key.txt")));
+        assert_eq!(settings.age_key_secret, None);
+        assert_eq!(settings.ssh_key_path, Some(PathBuf::from("/home/me/.ssh/id_ed25519")));
+        assert_eq!(settings.retention, 3);
+        assert_eq!(settings.rollback_dir, PathBuf::from("/home/me/.local/share/forge/rollback"));
+        assert_eq!(settings.rollback_max_bytes, 10 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_settings_require_repo_and_recipient() {
+        let config = BrowserBackupConfig {
+            age_recipient: "age1abc".to_string(),
+            ..Default::default()
+        };
+        assert!(BackupSettings::from_config(&config, Path::new("/home/me")).is_err());
+
+        let config = BrowserBackupConfig {
+            repo: "git@example.com:me/settings.git".to_string(),
+            ..Default::default()
+        };
+        assert!(BackupSettings::from_config(&config, Path::new("/home/me")).is_err());
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MB");
+    }
+}
@@ -0,0 +1,221 @@
+//! Paper backup of the age identity
+//!
+//! Splits the age secret key into Shamir shares (see [`shamir`]) printed as
+//! text blocks, so the key can be rebuilt from any `threshold` of them even
+//! when every device and the secret provider are gone. Restore checks the
+//! rebuilt key against the configured recipient before writing it.
+
+use age::secrecy::ExposeSecret;
+use anyhow::{Context, Result};
+use bech32::{FromBase32, ToBase32, Variant};
+use std::path::Path;
+use std::str::FromStr;
+
+use super::keys::write_key;
+use super::shamir::{self, Share};
+use super::{crypto, BackupSettings};
+use crate::commands::runner::CommandRunner;
+
+const SECRET_KEY_HRP: &str = "age-secret-key-";
+/// Share groups printed per line
+const GROUPS_PER_LINE: usize = 4;
+
+/// Split the age key into `count` shares and print them
+///
+/// With `output` the sheets are also written to that file for printing.
+pub async fn backup(
+    runner: &CommandRunner<'_>,
+    settings: &BackupSettings,
+    count: u8,
+    threshold: u8,
+    output: Option<&Path>,
+) -> Result<()> {
+    let identity = crypto::load_identity(settings).await?;
+    let identity = parse_identity(&identity)?;
+    if identity.to_public().to_string() != settings.age_recipient {
+        anyhow::bail!("The age key doesn't match AGE_RECIPIENT in the backup config");
+    }
+
+    let secret = secret_bytes(&identity)?;
+    let shares = shamir::split(&secret, count, threshold)?;
+    let sheets: Vec<String> = shares
+        .iter()
+        .map(|share| sheet(share, count, &settings.age_recipient))
+        .collect();
+
+    runner.out(&format!("  Split the age key into {} shares, any {} restore it", count, threshold)).await;
+    runner.out("  Keep each share in a different place.").await;
+    // Shown on screen only: all shares together in the screen log would defeat the split
+    for sheet in &sheets {
+        runner.out("").await;
+        for line in sheet.lines() {
+            runner.secret(&format!("  {}", line)).await;
+        }
+    }
+
+    if let Some(path) = output {
+        write_key(path, sheets.join("\n\n").as_bytes(), 0o600)?;
+        runner.out("").await;
+        runner.out(&format!("  ✓ Sheets written to {}", path.display())).await;
+        runner.out("  Delete the file once the sheets are printed.").await;
+    }
+    Ok(())
+}
+
+/// Rebuild the age key from typed-in shares and save it to AGE_KEY_PATH
+///
+/// An existing key file is left alone unless `force` is set.
+pub async fn restore(runner: &CommandRunner<'_>, settings: &BackupSettings, shares: &[Share], force: bool) -> Result<()> {
+    let Some(path) = &settings.age_key_path else {
+        anyhow::bail!("AGE_KEY_PATH not set in backup config");
+    };
+    if path.exists() && !force {
+        anyhow::bail!("{} already exists (use --force to overwrite)", path.display());
+    }
+
+    runner.out(&format!("  Combining {} shares...", shares.len())).await;
+    let identity = identity_from_bytes(&shamir::combine(shares)?)?;
+    let recipient = identity.to_public().to_string();
+    if recipient != settings.age_recipient {
+        anyhow::bail!(
+            "Rebuilt key belongs to {}, not AGE_RECIPIENT {} - were the shares from an older key?",
+            recipient,
+            settings.age_recipient
+        );
+    }
+    runner.out(&format!("  ✓ Key matches recipient {}", recipient)).await;
+
+    let contents = format!("# public key: {}\n{}\n", recipient, identity.to_string().expose_secret());
+    write_key(path, contents.as_bytes(), 0o600)?;
+    runner.out(&format!("  ✓ Age key saved to {}", path.display())).await;
+    Ok(())
+}
+
+/// Printable block for one share
+fn sheet(share: &Share, count: u8, recipient: &str) -> String {
+    let rule = "=".repeat(48);
+    let mut lines = vec![
+        rule.clone(),
+        format!("forge age key share {} of {}", share.index, count),
+        format!("Any {} shares restore the key", share.threshold),
+        format!("Set {:04X}", share.set_id),
+        format!("Recipient {}", recipient),
+        "-".repeat(48),
+    ];
+    let encoded = share.encode();
+    let groups: Vec<&str> = encoded.split('-').collect();
+    for chunk in groups.chunks(GROUPS_PER_LINE) {
+        lines.push(chunk.join("-"));
+    }
+    lines.push(rule);
+    lines.join("\n")
+}
+
+fn parse_identity(contents: &str) -> Result<age::x25519::Identity> {
+    let line = contents
+        .lines()
+        .map(str::trim)
+        .find(|l| l.starts_with("AGE-SECRET-KEY-"))
+        .context("No AGE-SECRET-KEY line in the age key")?;
+    age::x25519::Identity::from_str(line).map_err(|e| anyhow::anyhow!("Invalid age key: {}", e))
+}
+
+/// Raw 32 key bytes of an identity
+fn secret_bytes(identity: &age::x25519::Identity) -> Result<Vec<u8>> {
+    let encoded = identity.to_string();
+    let (_, data, _) = bech32::decode(&encoded.expose_secret().to_lowercase()).context("Invalid age key encoding")?;
+    Ok(Vec::<u8>::from_base32(&data)?)
+}
+
+fn identity_from_bytes(bytes: &[u8]) -> Result<age::x25519::Identity> {
+    let encoded = bech32::encode(SECRET_KEY_HRP, bytes.to_base32(), Variant::Bech32)?;
+    age::x25519::Identity::from_str(&encoded.to_uppercase())
+        .map_err(|e| anyhow::anyhow!("Shares don't form a valid age key: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::test_support::{test_settings, ScratchDir};
+    use crate::commands::CommandMessage;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn paper_settings(dir: &Path, recipient: &str) -> BackupSettings {
+        BackupSettings {
+            age_recipient: recipient.to_string(),
+            age_key_path: Some(dir.join("age/key.txt")),
+            ..test_settings(dir)
+        }
+    }
+
+    #[test]
+    fn test_identity_bytes_roundtrip() {
+        let identity = age::x25519::Identity::generate();
+        let bytes = secret_bytes(&identity).unwrap();
+        assert_eq!(bytes.len(), 32);
+        let rebuilt = identity_from_bytes(&bytes).unwrap();
+        assert_eq!(rebuilt.to_public().to_string(), identity.to_public().to_string());
+    }
+
+    #[test]
+    fn test_sheet_layout() {
+        let share = shamir::split(&[1u8; 32], 5, 3).unwrap().remove(1);
+        let text = sheet(&share, 5, "age1xyz");
+        assert!(text.contains("share 2 of 5"));
+        assert!(text.contains("Any 3 shares"));
+        assert!(text.contains("Recipient age1xyz"));
+
+        // The share lines alone decode back to the share
+        let body: String = text.lines().skip(6).take_while(|l| !l.starts_with('=')).collect();
+        assert_eq!(Share::decode(&body).unwrap(), share);
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_restore() {
+        let dir = ScratchDir::new("paper");
+        let identity = age::x25519::Identity::generate();
+        let recipient = identity.to_public().to_string();
+        let key_file = dir.write("age/key.txt", &format!("# created: today\n{}\n", identity.to_string().expose_secret()));
+        let settings = paper_settings(dir.path(), &recipient);
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1000);
+
+        let sheets = dir.path().join("sheets.txt");
+        backup(&CommandRunner::new(&tx), &settings, 5, 3, Some(&sheets)).await.unwrap();
+        let printed = std::fs::read_to_string(&sheets).unwrap();
+        assert_eq!(std::fs::metadata(&sheets).unwrap().permissions().mode() & 0o777, 0o600);
+        assert!(!printed.contains("AGE-SECRET-KEY"));
+
+        let shares: Vec<Share> = printed
+            .split("\n\n")
+            .map(|block| {
+                let body: String = block.lines().skip(6).take_while(|l| !l.starts_with('=')).collect();
+                Share::decode(&body).unwrap()
+            })
+            .collect();
+        assert_eq!(shares.len(), 5);
+        let picked = [shares[4].clone(), shares[0].clone(), shares[2].clone()];
+
+        let err = restore(&CommandRunner::new(&tx), &settings, &picked, false).await.unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+
+        std::fs::remove_file(&key_file).unwrap();
+        restore(&CommandRunner::new(&tx), &settings, &picked, false).await.unwrap();
+        let restored = std::fs::read_to_string(&key_file).unwrap();
+        assert!(restored.contains(identity.to_string().expose_secret()));
+
+        // Shares of another key are rejected instead of written
+        let other = paper_settings(dir.path(), &age::x25519::Identity::generate().to_public().to_string());
+        let err = restore(&CommandRunner::new(&tx), &other, &picked, true).await.unwrap_err();
+        assert!(err.to_string().contains("not AGE_RECIPIENT"));
+
+        // Share text only travels as Secret lines, which stay out of the screen log
+        let share_lines: Vec<String> = shares.iter().map(|s| s.encode()[..10].to_string()).collect();
+        rx.close();
+        while let Ok(message) = rx.try_recv() {
+            if let CommandMessage::Stdout(line) | CommandMessage::Stderr(line) = message {
+                assert!(!line.contains("AGE-SECRET-KEY"));
+                assert!(share_lines.iter().all(|share| !line.contains(share.as_str())), "{}", line);
+            }
+        }
+    }
+}
@@ -0,0 +1,377 @@
+//! App profile registry
+//!
+//! Only the files needed to restore logins and sessions are backed up, not
+//! caches or extensions. The browser lists match what the shell scripts
+//! archived.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// How the files inside a profile directory are laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// Files restore to the same relative paths
+    Direct,
+    /// Firefox-style `<random>.default*` profile dirs: the backed-up profile
+    /// is mapped onto whichever profile exists locally
+    FirefoxProfiles,
+}
+
+/// A Safe Storage key kept in the Secret Service
+///
+/// Chromium and Electron apps encrypt cookies with it, so it has to travel
+/// with the profile.
+#[derive(Debug, Clone, Copy)]
+pub struct KeyringKey {
+    /// `application` attribute of the secret
+    pub application: &'static str,
+    /// Label used when storing the secret
+    pub label: &'static str,
+}
+
+/// An app whose profile can be backed up
+#[derive(Debug, Clone, Copy)]
+pub struct AppProfile {
+    /// Identifier used in archive names
+    pub id: &'static str,
+    /// Display name
+    pub name: &'static str,
+    /// Profile directory (relative to home)
+    pub dir: &'static str,
+    /// Essential files (relative to `dir`); `*` matches within one path segment
+    pub files: &'static [&'static str],
+    pub layout: Layout,
+    /// Process names that must not be running during backup/restore
+    pub processes: &'static [&'static str],
+    /// Cookie encryption key held in the Secret Service, if any
+    pub keyring: Option<KeyringKey>,
+}
+
+/// Google Chrome
+pub const CHROME: AppProfile = AppProfile {
+    id: "chrome",
+    name: "Chrome",
+    dir: ".config/google-chrome",
+    files: &[
+        "Default/Cookies",
+        "Default/Cookies-journal",
+        "Default/Login Data",
+        "Default/Login Data-journal",
+        "Default/Web Data",
+        "Default/Web Data-journal",
+        "Default/Preferences",
+        "Default/Secure Preferences",
+        "Default/Current Session",
+        "Default/Current Tabs",
+        "Default/Last Session",
+        "Default/Last Tabs",
+        "Default/Bookmarks",
+        "Default/Favicons",
+        "Default/Favicons-journal",
+        "Local State",
+    ],
+    layout: Layout::Direct,
+    processes: &["chrome"],
+    keyring: Some(KeyringKey {
+        application: "chrome",
+        label: "Chrome Safe Storage",
+    }),
+};
+
+/// Mozilla Firefox
+pub const FIREFOX: AppProfile = AppProfile {
+    id: "firefox",
+    name: "Firefox",
+    dir: ".mozilla/firefox",
+    files: &[
+        "*.default*/cookies.sqlite",
+        "*.default*/cookies.sqlite-wal",
+        "*.default*/logins.json",
+        "*.default*/key4.db",
+        "*.default*/cert9.db",
+        "*.default*/prefs.js",
+        "*.default*/sessionstore.jsonlz4",
+        "*.default*/sessionstore-backups/recovery.jsonlz4",
+        "*.default*/signons.sqlite",
+        "*.default*/formhistory.sqlite",
+        "*.default*/places.sqlite",
+        "*.default*/favicons.sqlite",
+        "profiles.ini",
+        "installs.ini",
+    ],
+    layout: Layout::FirefoxProfiles,
+    processes: &["firefox", "firefox-bin", ".firefox-wrapped"],
+    keyring: None,
+};
+
+/// Slack desktop app
+pub const SLACK: AppProfile = AppProfile {
+    id: "slack",
+    name: "Slack",
+    dir: ".config/Slack",
+    files: &[
+        "Cookies",
+        "Cookies-journal",
+        "Local State",
+        "Preferences",
+        "Local Storage/leveldb/*",
+        "storage/root-state.json",
+    ],
+    layout: Layout::Direct,
+    processes: &["slack", ".slack-wrapped"],
+    keyring: Some(KeyringKey {
+        application: "Slack",
+        label: "Slack Safe Storage",
+    }),
+};
+
+/// Microsoft Teams (teams-for-linux)
+pub const TEAMS: AppProfile = AppProfile {
+    id: "teams",
+    name: "Teams",
+    dir: ".config/teams-for-linux",
+    files: &[
+        "Partitions/teams-4-linux/Cookies",
+        "Partitions/teams-4-linux/Cookies-journal",
+        "Partitions/teams-4-linux/Local Storage/leveldb/*",
+        "Partitions/teams-4-linux/Preferences",
+        "Local State",
+        "config.json",
+    ],
+    layout: Layout::Direct,
+    processes: &["teams-for-linux", ".teams-for-linux-wrapped"],
+    keyring: Some(KeyringKey {
+        application: "teams-for-linux",
+        label: "teams-for-linux Safe Storage",
+    }),
+};
+
+/// All known app profiles
+pub fn all() -> &'static [AppProfile] {
+    &[CHROME, FIREFOX, SLACK, TEAMS]
+}
+
+/// Look up an app by id
+pub fn find(id: &str) -> Option<AppProfile> {
+    all().iter().find(|p| p.id.eq_ignore_ascii_case(id.trim())).copied()
+}
+
+/// Resolve a list of app ids; an empty list selects every app
+pub fn select(ids: &[String]) -> Result<Vec<AppProfile>> {
+    if ids.is_empty() {
+        return Ok(all().to_vec());
+    }
+
+    let mut selected: Vec<AppProfile> = Vec::new();
+    for id in ids {
+        let Some(profile) = find(id) else {
+            let known: Vec<&str> = all().iter().map(|p| p.id).collect();
+            anyhow::bail!("Unknown app '{}' (known apps: {})", id.trim(), known.join(", "));
+        };
+        if !selected.iter().any(|p| p.id == profile.id) {
+            selected.push(profile);
+        }
+    }
+    Ok(selected)
+}
+
+impl AppProfile {
+    /// Archive file name in the backup repository
+    pub fn archive_name(&self) -> String {
+        format!("{}-profile.tar.zst.age", self.id)
+    }
+
+    /// Archive file name used by the old shell scripts
+    pub fn legacy_archive_name(&self) -> String {
+        format!("{}-profile.tar.gz.age", self.id)
+    }
+
+    /// Name of the keyring key file inside the archive
+    pub fn keyring_file(&self) -> String {
+        format!(".{}-safe-storage-key", self.id)
+    }
+
+    /// Absolute profile directory
+    pub fn root(&self, home: &Path) -> PathBuf {
+        home.join(self.dir)
+    }
+
+    /// Find the essential files present under `root`
+    ///
+    /// Returns sorted, `/`-separated paths relative to `root`.
+    pub fn collect_files(&self, root: &Path) -> Result<Vec<String>> {
+        let mut found = Vec::new();
+        for pattern in self.files {
+            found.extend(expand_pattern(root, pattern)?);
+        }
+        found.sort();
+        found.dedup();
+        Ok(found)
+    }
+}
+
+/// Expand a pattern segment by segment, only listing directories that
+/// contain a wildcard (profile dirs can hold gigabytes of cache)
+fn expand_pattern(root: &Path, pattern: &str) -> Result<Vec<String>> {
+    let mut candidates = vec![String::new()];
+
+    for segment in pattern.split('/') {
+        let mut next = Vec::new();
+        for prefix in &candidates {
+            let dir = root.join(prefix);
+            if segment.contains('*') {
+                let Ok(entries) = std::fs::read_dir(&dir) else {
+                    continue;
+                };
+                let mut names: Vec<String> = entries
+                    .flatten()
+                    .map(|e| e.file_name().to_string_lossy().to_string())
+                    .filter(|name| wildcard_match(segment, name))
+                    .collect();
+                names.sort();
+                next.extend(names.into_iter().map(|name| join_rel(prefix, &name)));
+            } else if dir.join(segment).exists() {
+                next.push(join_rel(prefix, segment));
+            }
+        }
+        candidates = next;
+    }
+
+    Ok(candidates
+        .into_iter()
+        .filter(|rel| root.join(rel).is_file())
+        .collect())
+}
+
+fn join_rel(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", prefix, name)
+    }
+}
+
+/// Match a name against a pattern where `*` matches any run of characters
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == name;
+    }
+
+    let first = parts[0];
+    let last = parts[parts.len() - 1];
+    if !name.starts_with(first) || name.len() < first.len() + last.len() {
+        return false;
+    }
+
+    let mut rest = &name[first.len()..];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::test_support::ScratchDir;
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.default*", "abcd1234.default-release"));
+        assert!(wildcard_match("*.default*", "x.default"));
+        assert!(!wildcard_match("*.default*", "Crash Reports"));
+        assert!(wildcard_match("profiles.ini", "profiles.ini"));
+        assert!(!wildcard_match("a*a", "a"));
+    }
+
+    #[test]
+    fn test_archive_names() {
+        assert_eq!(CHROME.archive_name(), "chrome-profile.tar.zst.age");
+        assert_eq!(FIREFOX.legacy_archive_name(), "firefox-profile.tar.gz.age");
+        // Same name the scripts used for Chrome's key
+        assert_eq!(CHROME.keyring_file(), ".chrome-safe-storage-key");
+    }
+
+    #[test]
+    fn test_select_apps() {
+        assert_eq!(select(&[]).unwrap().len(), all().len());
+
+        let ids: Vec<&str> = select(&["Firefox".to_string(), "slack".to_string(), "firefox".to_string()])
+            .unwrap()
+            .iter()
+            .map(|p| p.id)
+            .collect();
+        assert_eq!(ids, vec!["firefox", "slack"]);
+
+        let err = select(&["opera".to_string()]).unwrap_err().to_string();
+        assert!(err.contains("Unknown app 'opera'"));
+        assert!(err.contains("chrome, firefox, slack, teams"));
+    }
+
+    #[test]
+    fn test_registry_ids_are_unique() {
+        let mut ids: Vec<&str> = all().iter().map(|p| p.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), all().len());
+    }
+
+    #[test]
+    fn test_collect_slack_leveldb() {
+        let home = ScratchDir::new("profiles-slack");
+        home.write(".config/Slack/Cookies", "c");
+        home.write(".config/Slack/Local Storage/leveldb/000003.log", "l");
+        home.write(".config/Slack/Local Storage/leveldb/CURRENT", "m");
+        home.write(".config/Slack/Cache/Cache_Data/data_0", "cache");
+
+        let files = SLACK.collect_files(&SLACK.root(home.path())).unwrap();
+        assert_eq!(
+            files,
+            vec!["Cookies", "Local Storage/leveldb/000003.log", "Local Storage/leveldb/CURRENT"]
+        );
+    }
+
+    #[test]
+    fn test_collect_chrome_files() {
+        let home = ScratchDir::new("profiles-chrome");
+        let root = CHROME.root(home.path());
+        home.write(".config/google-chrome/Default/Cookies", "c");
+        home.write(".config/google-chrome/Default/Login Data", "l");
+        home.write(".config/google-chrome/Local State", "s");
+        home.write(".config/google-chrome/Default/Cache/data_0", "cache");
+
+        let files = CHROME.collect_files(&root).unwrap();
+        assert_eq!(files, vec!["Default/Cookies", "Default/Login Data", "Local State"]);
+    }
+
+    #[test]
+    fn test_collect_firefox_files() {
+        let home = ScratchDir::new("profiles-firefox");
+        let root = FIREFOX.root(home.path());
+        home.write(".mozilla/firefox/profiles.ini", "[Profile0]");
+        home.write(".mozilla/firefox/ab12.default-release/cookies.sqlite", "c");
+        home.write(".mozilla/firefox/ab12.default-release/sessionstore-backups/recovery.jsonlz4", "r");
+        home.write(".mozilla/firefox/ab12.default-release/cache2/entries", "cache");
+        home.write(".mozilla/firefox/Crash Reports/events", "x");
+
+        let files = FIREFOX.collect_files(&root).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                "ab12.default-release/cookies.sqlite",
+                "ab12.default-release/sessionstore-backups/recovery.jsonlz4",
+                "profiles.ini",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect_missing_profile_is_empty() {
+        let home = ScratchDir::new("profiles-missing");
+        assert!(CHROME.collect_files(&CHROME.root(home.path())).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,155 @@
+//! Key provisioning for fresh installs
+//!
+//! Puts the age and SSH keys into the new system's home during `forge
+//! install`, so the parts of the home configuration that need secrets work
+//! on first boot. The backup settings come from the host being installed
+//! (the live ISO has no backup config of its own) and the keys archive
+//! passphrase from the installer. The keys are fetched into a private
+//! staging home with the regular keys restore / setup code and their
+//! permissions fixed there; the installer then copies them into place as
+//! root with the primary user's ownership (see [`plan`]).
+
+use anyhow::{Context, Result};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use super::{audit, keys, BackupSettings};
+use crate::commands::runner::CommandRunner;
+use crate::system::config::BrowserBackupConfig;
+
+/// Where the keys come from
+#[derive(Clone, PartialEq)]
+pub enum Source {
+    /// The passphrase-encrypted keys archive in the backup storage
+    Archive { passphrase: String },
+    /// The secret provider (like `forge keys setup`)
+    Secrets,
+}
+
+/// A directory or key file to put into the new home
+#[derive(Debug, Clone, PartialEq)]
+pub struct Copy {
+    /// Staged file, None for directories
+    pub source: Option<PathBuf>,
+    pub target: PathBuf,
+    pub mode: u32,
+}
+
+/// Fetch the keys into `staging_home`, laid out as in the real home
+///
+/// Returns the settings resolved against the staging home, for [`plan`].
+pub async fn stage(
+    runner: &CommandRunner<'_>,
+    config: &BrowserBackupConfig,
+    staging_home: &Path,
+    source: &Source,
+) -> Result<BackupSettings> {
+    let settings = BackupSettings::from_config(config, staging_home)?;
+    for key in [&settings.age_key_path, &settings.ssh_key_path].into_iter().flatten() {
+        if !key.starts_with(staging_home) {
+            anyhow::bail!("Key path {} must be inside the home directory (use ~/...)", key.display());
+        }
+    }
+
+    match source {
+        Source::Archive { passphrase } => keys::restore(runner, &settings, passphrase, true).await?,
+        Source::Secrets => keys::setup(runner, &settings, true).await?,
+    }
+
+    let report = audit::inspect(&settings);
+    audit::fix_permissions(&report)?;
+    for problem in report.problems.iter().filter(|p| p.fix_mode.is_none()) {
+        runner.out(&format!("  ! {}: {}", problem.path.display(), problem.message)).await;
+    }
+    Ok(settings)
+}
+
+/// Directories and files to create under `target_home`, parents first
+///
+/// Modes are taken from the staged copies.
+pub fn plan(settings: &BackupSettings, target_home: &Path) -> Result<Vec<Copy>> {
+    let mut files = Vec::new();
+    if let Some(path) = &settings.age_key_path {
+        files.push(path.clone());
+    }
+    if let Some(path) = &settings.ssh_key_path {
+        files.push(path.clone());
+        files.push(path.with_extension("pub"));
+    }
+
+    let mut copies: Vec<Copy> = Vec::new();
+    for file in files.into_iter().filter(|f| f.is_file()) {
+        let rel = file
+            .strip_prefix(&settings.home)
+            .with_context(|| format!("{} is outside the staging home", file.display()))?;
+
+        let mut dir = PathBuf::new();
+        for component in rel.parent().into_iter().flat_map(|p| p.components()) {
+            dir.push(component);
+            let target = target_home.join(&dir);
+            if !copies.iter().any(|c| c.target == target) {
+                copies.push(Copy {
+                    source: None,
+                    target,
+                    mode: mode_of(&settings.home.join(&dir))?,
+                });
+            }
+        }
+        copies.push(Copy {
+            source: Some(file.clone()),
+            target: target_home.join(rel),
+            mode: mode_of(&file)?,
+        });
+    }
+    Ok(copies)
+}
+
+fn mode_of(path: &Path) -> Result<u32> {
+    let metadata = std::fs::metadata(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(metadata.permissions().mode() & 0o777)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::test_support::{test_settings, ScratchDir};
+
+    #[test]
+    fn test_plan_lists_parents_first() {
+        let dir = ScratchDir::new("provision");
+        let home = dir.path().join("staging");
+        let settings = BackupSettings {
+            age_key_path: Some(home.join(".config/age/key.txt")),
+            ssh_key_path: Some(home.join(".ssh/id_ed25519")),
+            ..test_settings(&home)
+        };
+        for (rel, mode) in [(".config/age/key.txt", 0o600), (".ssh/id_ed25519", 0o600), (".ssh/id_ed25519.pub", 0o644)] {
+            let path = dir.write(&format!("staging/{}", rel), "key");
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        }
+        std::fs::set_permissions(home.join(".ssh"), std::fs::Permissions::from_mode(0o700)).unwrap();
+
+        let target = Path::new("/mnt/home/me");
+        let copies = plan(&settings, target).unwrap();
+        let listed: Vec<(&Path, bool, u32)> = copies
+            .iter()
+            .map(|c| (c.target.as_path(), c.source.is_some(), c.mode))
+            .collect();
+        let dir_mode = mode_of(&home.join(".config")).unwrap();
+        assert_eq!(
+            listed,
+            vec![
+                (Path::new("/mnt/home/me/.config"), false, dir_mode),
+                (Path::new("/mnt/home/me/.config/age"), false, mode_of(&home.join(".config/age")).unwrap()),
+                (Path::new("/mnt/home/me/.config/age/key.txt"), true, 0o600),
+                (Path::new("/mnt/home/me/.ssh"), false, 0o700),
+                (Path::new("/mnt/home/me/.ssh/id_ed25519"), true, 0o600),
+                (Path::new("/mnt/home/me/.ssh/id_ed25519.pub"), true, 0o644),
+            ]
+        );
+
+        // Keys that weren't fetched are left out
+        std::fs::remove_file(home.join(".config/age/key.txt")).unwrap();
+        assert_eq!(plan(&settings, target).unwrap().len(), 3);
+    }
+}
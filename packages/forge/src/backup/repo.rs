@@ -0,0 +1,238 @@
+//! Local clone of the backup repository
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::commands::executor::run_capture;
+
+/// GitHub rejects files above this size unless they're stored with LFS
+pub const LFS_THRESHOLD_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Git operations on the local backup repository
+pub struct BackupRepo {
+    path: PathBuf,
+}
+
+impl BackupRepo {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the local clone exists
+    pub fn exists(&self) -> bool {
+        self.path.join(".git").exists()
+    }
+
+    async fn git(&self, args: &[&str]) -> Result<(bool, String, String)> {
+        let path = self.path.to_string_lossy();
+        let mut full = vec!["-C", path.as_ref()];
+        full.extend_from_slice(args);
+        run_capture("git", &full).await
+    }
+
+    /// Run git and fail with its stderr if it doesn't succeed
+    pub(super) async fn git_ok(&self, args: &[&str]) -> Result<String> {
+        let (success, stdout, stderr) = self.git(args).await?;
+        if !success {
+            anyhow::bail!("git {} failed: {}", args.join(" "), stderr.trim());
+        }
+        Ok(stdout)
+    }
+
+    /// Clone the repository, or bring an existing clone up to date
+    ///
+    /// Local modifications are discarded: the clone only ever holds
+    /// generated archives.
+    pub async fn sync(&self, url: &str) -> Result<()> {
+        if !self.exists() {
+            if let Some(parent) = self.path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            let path = self.path.to_string_lossy();
+            let (success, _, stderr) = run_capture("git", &["clone", url, path.as_ref()]).await?;
+            if !success {
+                anyhow::bail!("Failed to clone {}: {}", url, stderr.trim());
+            }
+            return Ok(());
+        }
+
+        // An unborn branch (fresh clone of an empty remote) has nothing to reset
+        if self.git(&["rev-parse", "--verify", "HEAD"]).await?.0 {
+            self.git_ok(&["reset", "--hard", "HEAD"]).await?;
+        }
+        if self.has_upstream().await? {
+            self.git_ok(&["pull", "--rebase"]).await?;
+        }
+        Ok(())
+    }
+
+    async fn has_upstream(&self) -> Result<bool> {
+        Ok(self.git(&["rev-parse", "--abbrev-ref", "@{u}"]).await?.0)
+    }
+
+    /// Stage everything and commit; returns false if there was nothing to commit
+    pub async fn commit_all(&self, message: &str) -> Result<bool> {
+        self.git_ok(&["add", "-A"]).await?;
+
+        let status = self.git_ok(&["status", "--porcelain"]).await?;
+        if status.trim().is_empty() {
+            return Ok(false);
+        }
+
+        let mut args: Vec<String> = Vec::new();
+        // Machines without a git identity (fresh installs) can still commit
+        if self.git(&["config", "user.email"]).await?.1.trim().is_empty() {
+            let host = forge::notify::attribution::current_hostname().unwrap_or_else(|| "localhost".to_string());
+            args.extend(["-c".to_string(), "user.name=forge".to_string()]);
+            args.extend(["-c".to_string(), format!("user.email=forge@{}", host)]);
+        }
+        args.extend(["commit".to_string(), "-q".to_string(), "-m".to_string(), message.to_string()]);
+
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        self.git_ok(&args).await?;
+        Ok(true)
+    }
+
+    /// Push the current branch, setting its upstream
+    pub async fn push(&self) -> Result<()> {
+        self.git_ok(&["push", "-q", "-u", "origin", "HEAD"]).await?;
+        Ok(())
+    }
+
+    /// Store a file with Git LFS
+    pub async fn track_with_lfs(&self, name: &str) -> Result<()> {
+        self.git_ok(&["lfs", "install", "--local"]).await?;
+        self.git_ok(&["lfs", "track", name]).await?;
+        Ok(())
+    }
+
+    /// Short hash of HEAD
+    pub async fn head(&self) -> Result<String> {
+        Ok(self.git_ok(&["rev-parse", "--short", "HEAD"]).await?.trim().to_string())
+    }
+
+    /// Resolve a revision to a commit hash
+    pub async fn resolve(&self, rev: &str) -> Result<String> {
+        // Revisions come from the command line (--at); never let one read as an option
+        if rev.is_empty() || rev.starts_with('-') {
+            anyhow::bail!("Invalid snapshot '{}'", rev);
+        }
+        let spec = format!("{}^{{commit}}", rev);
+        let (success, stdout, _) = self
+            .git(&["rev-parse", "--verify", "--quiet", "--end-of-options", &spec])
+            .await?;
+        if !success {
+            anyhow::bail!("Snapshot '{}' not found in {}", rev, self.path.display());
+        }
+        Ok(stdout.trim().to_string())
+    }
+
+    /// Files at the top of a revision's tree, with their sizes
+    pub async fn tree(&self, rev: &str) -> Result<Vec<(String, u64)>> {
+        let output = self.git_ok(&["ls-tree", "-l", rev]).await?;
+        Ok(parse_ls_tree(&output))
+    }
+
+    /// Contents of a file as of a revision
+    ///
+    /// Files stored with Git LFS are smudged to their real contents.
+    pub async fn read_file_at(&self, rev: &str, name: &str) -> Result<Vec<u8>> {
+        let spec = format!("{}:{}", rev, name);
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.path)
+            .args(["show", &spec])
+            .output()
+            .await
+            .context("Failed to run git")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "git show {} failed: {}",
+                spec,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        if output.stdout.starts_with(LFS_POINTER_PREFIX) {
+            return self.lfs_smudge(&output.stdout).await;
+        }
+        Ok(output.stdout)
+    }
+
+    async fn lfs_smudge(&self, pointer: &[u8]) -> Result<Vec<u8>> {
+        let mut child = Command::new("git")
+            .arg("-C")
+            .arg(&self.path)
+            .args(["lfs", "smudge"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run git lfs")?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(pointer).await?;
+        }
+
+        let output = child.wait_with_output().await?;
+        if !output.status.success() {
+            anyhow::bail!("git lfs smudge failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(output.stdout)
+    }
+}
+
+/// First bytes of a Git LFS pointer file
+const LFS_POINTER_PREFIX: &[u8] = b"version https://git-lfs.github.com/spec/v1";
+
+/// Parse `git ls-tree -l` output into (name, size) pairs, skipping subtrees
+fn parse_ls_tree(output: &str) -> Vec<(String, u64)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (meta, name) = line.split_once('\t')?;
+            let fields: Vec<&str> = meta.split_whitespace().collect();
+            if fields.get(1) != Some(&"blob") {
+                return None;
+            }
+            let size = fields.get(3)?.parse().ok()?;
+            Some((name.to_string(), size))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resolve_rejects_options() {
+        let repo = BackupRepo::new(std::env::temp_dir().join("forge-missing-backup-repo"));
+        for rev in ["--output=/tmp/x", "-h", ""] {
+            let err = repo.resolve(rev).await.unwrap_err().to_string();
+            assert!(err.starts_with("Invalid snapshot"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_parse_ls_tree() {
+        let output = "100644 blob 3f2a1b     1234\tchrome-profile.tar.zst.age\n\
+                      040000 tree 9c8d7e        -\tdocs\n\
+                      100644 blob 1a2b3c       42\tREADME.md\n";
+        assert_eq!(
+            parse_ls_tree(output),
+            vec![
+                ("chrome-profile.tar.zst.age".to_string(), 1234),
+                ("README.md".to_string(), 42),
+            ]
+        );
+    }
+}
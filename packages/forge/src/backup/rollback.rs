@@ -0,0 +1,402 @@
+//! Local rollback area for restores
+//!
+//! Before a restore writes anything, the files it is about to replace are
+//! copied into a rollback point under `~/.local/share/forge/rollback/`,
+//! together with a manifest of the files it will create. Undoing a restore
+//! copies the saved files back and removes the created ones.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+
+/// Manifest file inside each rollback point
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// File holding an app's previous keyring secret
+const KEYRING_FILE: &str = ".safe-storage-key";
+
+/// Directory holding the saved copies of an app's files
+const FILES_DIR: &str = "files";
+
+/// What a rollback point knows about one app
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppRollback {
+    pub id: String,
+    /// Profile directory the restore wrote to
+    pub root: PathBuf,
+    /// Files that existed and were saved (relative to `root`)
+    pub saved: Vec<String>,
+    /// Files the restore created (removed on undo)
+    pub created: Vec<String>,
+    /// Whether the previous keyring secret was saved
+    #[serde(default)]
+    pub keyring: bool,
+}
+
+/// Manifest of a rollback point
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// When the restore ran (`YYYYmmdd-HHMMSS`)
+    pub created: String,
+    /// Snapshot that was restored, if not the latest
+    pub snapshot: Option<String>,
+    pub apps: Vec<AppRollback>,
+}
+
+/// The rollback area
+pub struct RollbackStore {
+    root: PathBuf,
+}
+
+/// One restore's worth of saved files
+pub struct RollbackPoint {
+    pub dir: PathBuf,
+    pub manifest: Manifest,
+}
+
+impl RollbackStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Start a rollback point for a restore
+    pub fn begin(&self, timestamp: &str, snapshot: Option<&str>) -> Result<RollbackPoint> {
+        create_private_dir(&self.root)?;
+
+        // Two restores within the same second get distinct points
+        let mut dir = self.root.join(timestamp);
+        let mut n = 1;
+        while dir.exists() {
+            dir = self.root.join(format!("{}-{}", timestamp, n));
+            n += 1;
+        }
+        create_private_dir(&dir)?;
+
+        Ok(RollbackPoint {
+            dir,
+            manifest: Manifest {
+                created: timestamp.to_string(),
+                snapshot: snapshot.map(str::to_string),
+                apps: Vec::new(),
+            },
+        })
+    }
+
+    /// Rollback points, newest first
+    pub fn points(&self) -> Result<Vec<RollbackPoint>> {
+        let Ok(entries) = std::fs::read_dir(&self.root) else {
+            return Ok(Vec::new());
+        };
+
+        let mut points = Vec::new();
+        for entry in entries.flatten() {
+            let dir = entry.path();
+            let Ok(content) = std::fs::read_to_string(dir.join(MANIFEST_FILE)) else {
+                continue;
+            };
+            let manifest: Manifest = serde_json::from_str(&content)
+                .with_context(|| format!("Corrupt rollback manifest in {}", dir.display()))?;
+            points.push(RollbackPoint { dir, manifest });
+        }
+
+        // Directory names are zero-padded timestamps, so name order is age order
+        points.sort_by(|a, b| b.dir.cmp(&a.dir));
+        Ok(points)
+    }
+
+    /// Point directories whose restore never wrote a manifest
+    fn uncommitted(&self) -> Vec<PathBuf> {
+        let Ok(entries) = std::fs::read_dir(&self.root) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|dir| dir.is_dir() && !dir.join(MANIFEST_FILE).exists())
+            .collect()
+    }
+
+    /// The most recent rollback point
+    pub fn latest(&self) -> Result<Option<RollbackPoint>> {
+        Ok(self.points()?.into_iter().next())
+    }
+
+    /// Drop points left without a manifest by a failed restore, then old
+    /// points beyond `keep`, then the oldest ones until the area fits in
+    /// `max_bytes`; the newest point is always kept
+    pub fn prune(&self, keep: usize, max_bytes: u64) -> Result<usize> {
+        let mut removed = 0;
+        for dir in self.uncommitted() {
+            std::fs::remove_dir_all(&dir)
+                .with_context(|| format!("Failed to remove rollback point {}", dir.display()))?;
+            removed += 1;
+        }
+
+        let mut points = self.points()?;
+
+        while points.len() > keep.max(1) {
+            if let Some(old) = points.pop() {
+                old.discard()?;
+                removed += 1;
+            }
+        }
+
+        let mut total: u64 = points.iter().map(|p| p.size()).sum();
+        while total > max_bytes && points.len() > 1 {
+            if let Some(old) = points.pop() {
+                total -= old.size();
+                old.discard()?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+}
+
+impl RollbackPoint {
+    fn app_mut(&mut self, id: &str, root: &Path) -> &mut AppRollback {
+        let index = match self.manifest.apps.iter().position(|a| a.id == id) {
+            Some(index) => index,
+            None => {
+                self.manifest.apps.push(AppRollback {
+                    id: id.to_string(),
+                    root: root.to_path_buf(),
+                    ..Default::default()
+                });
+                self.manifest.apps.len() - 1
+            }
+        };
+        &mut self.manifest.apps[index]
+    }
+
+    /// Save an existing file before it gets overwritten
+    pub fn save_file(&mut self, id: &str, root: &Path, rel: &str) -> Result<()> {
+        let source = root.join(rel);
+        let target = self.dir.join(id).join(FILES_DIR).join(rel);
+        create_private_dir(target.parent().unwrap_or(&self.dir))?;
+        std::fs::copy(&source, &target)
+            .with_context(|| format!("Failed to save {} for undo", source.display()))?;
+        self.app_mut(id, root).saved.push(rel.to_string());
+        Ok(())
+    }
+
+    /// Note a file the restore is about to create
+    pub fn record_created(&mut self, id: &str, root: &Path, rel: &str) {
+        self.app_mut(id, root).created.push(rel.to_string());
+    }
+
+    /// Save an app's keyring secret before it gets replaced
+    pub fn save_keyring(&mut self, id: &str, root: &Path, secret: &str) -> Result<()> {
+        use std::os::unix::fs::OpenOptionsExt;
+        use std::io::Write;
+
+        let dir = self.dir.join(id);
+        create_private_dir(&dir)?;
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(dir.join(KEYRING_FILE))?;
+        file.write_all(secret.as_bytes())?;
+        self.app_mut(id, root).keyring = true;
+        Ok(())
+    }
+
+    /// The saved keyring secret of an app
+    pub fn keyring_secret(&self, id: &str) -> Result<String> {
+        let path = self.dir.join(id).join(KEYRING_FILE);
+        std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))
+    }
+
+    /// Write the manifest (done before any profile file is touched)
+    pub fn commit(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(&self.manifest)?;
+        std::fs::write(self.dir.join(MANIFEST_FILE), content)
+            .with_context(|| format!("Failed to write rollback manifest in {}", self.dir.display()))
+    }
+
+    /// Whether the restore changed anything
+    pub fn is_empty(&self) -> bool {
+        self.manifest.apps.is_empty()
+    }
+
+    /// Total size of the saved files
+    pub fn size(&self) -> u64 {
+        dir_size(&self.dir)
+    }
+
+    /// Put an app's files back the way they were before the restore
+    ///
+    /// Returns the number of files put back or removed.
+    pub fn undo_files(&self, app: &AppRollback) -> Result<usize> {
+        let mut changed = 0;
+
+        for rel in &app.created {
+            let path = app.root.join(rel);
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+                changed += 1;
+            }
+        }
+
+        for rel in &app.saved {
+            let source = self.dir.join(&app.id).join(FILES_DIR).join(rel);
+            let target = app.root.join(rel);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(&source, &target)
+                .with_context(|| format!("Failed to put back {}", target.display()))?;
+            changed += 1;
+        }
+
+        Ok(changed)
+    }
+
+    /// Remove the rollback point
+    pub fn discard(self) -> Result<()> {
+        std::fs::remove_dir_all(&self.dir)
+            .with_context(|| format!("Failed to remove rollback point {}", self.dir.display()))
+    }
+}
+
+/// Create a directory (and parents) readable only by the user
+fn create_private_dir(path: &Path) -> Result<()> {
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(path)
+        .with_context(|| format!("Failed to create {}", path.display()))
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&entry.path()),
+            _ => entry.metadata().map(|m| m.len()).unwrap_or(0),
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::test_support::ScratchDir;
+
+    #[test]
+    fn test_save_and_undo() {
+        let scratch = ScratchDir::new("rollback-undo");
+        let root = scratch.path().join("profile");
+        scratch.write("profile/Local State", "old");
+
+        let store = RollbackStore::new(scratch.path().join("rollback"));
+        let mut point = store.begin("20240101-000000", Some("a1b2c3d")).unwrap();
+        point.save_file("chrome", &root, "Local State").unwrap();
+        point.record_created("chrome", &root, "Default/Cookies");
+        point.commit().unwrap();
+
+        // The restore then overwrites and creates files
+        scratch.write("profile/Local State", "new");
+        scratch.write("profile/Default/Cookies", "new");
+
+        let latest = store.latest().unwrap().unwrap();
+        assert_eq!(latest.manifest.snapshot.as_deref(), Some("a1b2c3d"));
+        let app = latest.manifest.apps[0].clone();
+        assert_eq!(latest.undo_files(&app).unwrap(), 2);
+
+        assert_eq!(std::fs::read_to_string(root.join("Local State")).unwrap(), "old");
+        assert!(!root.join("Default/Cookies").exists());
+    }
+
+    #[test]
+    fn test_points_are_newest_first_and_unique() {
+        let scratch = ScratchDir::new("rollback-order");
+        let store = RollbackStore::new(scratch.path());
+        for ts in ["20240101-000000", "20240102-000000", "20240102-000000"] {
+            let mut point = store.begin(ts, None).unwrap();
+            point.record_created("chrome", scratch.path(), "a");
+            point.commit().unwrap();
+        }
+
+        let points = store.points().unwrap();
+        assert_eq!(points.len(), 3);
+        assert!(points[0].dir.ends_with("20240102-000000-1"));
+        assert!(points[2].dir.ends_with("20240101-000000"));
+    }
+
+    #[test]
+    fn test_uncommitted_points_are_ignored() {
+        let scratch = ScratchDir::new("rollback-uncommitted");
+        let store = RollbackStore::new(scratch.path());
+        store.begin("20240101-000000", None).unwrap();
+        assert!(store.latest().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_prune_removes_uncommitted_points() {
+        let scratch = ScratchDir::new("rollback-prune-uncommitted");
+        let root = scratch.path().join("profile");
+        scratch.write("profile/big", &"x".repeat(1000));
+        let store = RollbackStore::new(scratch.path().join("rollback"));
+
+        // A restore that failed after saving files but before the manifest
+        let mut failed = store.begin("20240101-000000", None).unwrap();
+        failed.save_file("chrome", &root, "big").unwrap();
+        let mut point = store.begin("20240102-000000", None).unwrap();
+        point.record_created("chrome", &root, "a");
+        point.commit().unwrap();
+
+        assert_eq!(store.prune(3, u64::MAX).unwrap(), 1);
+        assert!(!failed.dir.exists());
+        assert_eq!(store.points().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_prune_by_count_and_size() {
+        let scratch = ScratchDir::new("rollback-prune");
+        let root = scratch.path().join("profile");
+        scratch.write("profile/big", &"x".repeat(1000));
+
+        let store = RollbackStore::new(scratch.path().join("rollback"));
+        for ts in ["20240101-000000", "20240102-000000", "20240103-000000", "20240104-000000"] {
+            let mut point = store.begin(ts, None).unwrap();
+            point.save_file("chrome", &root, "big").unwrap();
+            point.commit().unwrap();
+        }
+
+        assert_eq!(store.prune(3, u64::MAX).unwrap(), 1);
+        assert_eq!(store.points().unwrap().len(), 3);
+
+        // Size limit below one point still keeps the newest
+        assert_eq!(store.prune(3, 10).unwrap(), 2);
+        let points = store.points().unwrap();
+        assert_eq!(points.len(), 1);
+        assert!(points[0].dir.ends_with("20240104-000000"));
+    }
+
+    #[test]
+    fn test_keyring_secret_roundtrip() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let scratch = ScratchDir::new("rollback-keyring");
+        let store = RollbackStore::new(scratch.path());
+        let mut point = store.begin("20240101-000000", None).unwrap();
+        point.save_keyring("chrome", scratch.path(), "secret").unwrap();
+
+        assert!(point.manifest.apps[0].keyring);
+        assert_eq!(point.keyring_secret("chrome").unwrap(), "secret");
+        let mode = std::fs::metadata(point.dir.join("chrome").join(KEYRING_FILE))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
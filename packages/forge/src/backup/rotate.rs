@@ -0,0 +1,354 @@
+//! age key rotation
+//!
+//! Generates a new age identity and re-encrypts every archive in the backup
+//! storage to it, e.g. after a laptop holding the old key was lost. The new
+//! key is stored before any archive is rewritten, so a failure part way
+//! never leaves backups encrypted to a key that was not saved.
+
+use age::secrecy::ExposeSecret;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::keys::{self, KEYS_ARCHIVE};
+use super::{crypto, format_size, BackupSettings};
+use crate::commands::runner::CommandRunner;
+use crate::system::config::set_config_value;
+
+/// Where rotation records its results
+pub struct Rotation {
+    /// Backup config whose AGE_RECIPIENT is updated
+    pub config_path: PathBuf,
+    /// Audit log one JSON line per rotation is appended to
+    pub audit_log: PathBuf,
+    /// Only list the files that would be re-encrypted
+    pub dry_run: bool,
+}
+
+/// Audit log entry
+#[derive(Debug, Serialize)]
+struct AuditEntry<'a> {
+    time: String,
+    host: Option<String>,
+    backend: &'a str,
+    location: String,
+    old_recipient: &'a str,
+    new_recipient: &'a str,
+    files: Vec<&'a str>,
+}
+
+/// Rotate the age identity and re-encrypt the stored archives
+///
+/// `passphrase` is needed when a keys archive exists, so it can be updated
+/// to hold the new identity.
+pub async fn rotate(
+    runner: &CommandRunner<'_>,
+    settings: &BackupSettings,
+    rotation: &Rotation,
+    passphrase: Option<&str>,
+) -> Result<()> {
+    let storage = settings.storage.build()?;
+    runner.out(&format!("  Syncing backup storage ({})...", storage.name())).await;
+    storage.open(true).await?;
+
+    let stored = storage.list().await?;
+    let has_keys_archive = stored.iter().any(|f| f.name == KEYS_ARCHIVE);
+    let archives: Vec<_> = stored.iter().filter(|f| f.name != KEYS_ARCHIVE).collect();
+
+    runner.out("").await;
+    runner.out(&format!("  Files affected in {}:", storage.location())).await;
+    for file in &archives {
+        runner.out(&format!("    {} ({})", file.name, format_size(file.size))).await;
+    }
+    if has_keys_archive {
+        runner.out(&format!("    {} (age key inside replaced)", KEYS_ARCHIVE)).await;
+    }
+    if archives.is_empty() && !has_keys_archive {
+        runner.out("    (none)").await;
+    }
+    if rotation.dry_run {
+        runner.out("").await;
+        runner.out("  Dry run: nothing was changed").await;
+        return Ok(());
+    }
+
+    if settings.age_key_path.is_none() && settings.age_key_secret.is_none() {
+        anyhow::bail!("Nowhere to store the new key: set AGE_KEY_PATH or AGE_KEY_SECRET in the backup config");
+    }
+    if has_keys_archive && passphrase.is_none() {
+        anyhow::bail!("KEYS_PASSPHRASE_SECRET is needed to update {}", KEYS_ARCHIVE);
+    }
+
+    // Decrypt everything up front: a file the old key can't open stops the
+    // rotation before anything changes
+    runner.out("").await;
+    runner.out("  Decrypting with the current key...").await;
+    let old_identity = crypto::load_identity(settings).await?;
+    let mut plaintexts = Vec::new();
+    for file in &archives {
+        let data = storage.read(&file.name).await?;
+        let plain = crypto::decrypt(&data, &old_identity).with_context(|| format!("Failed to decrypt {}", file.name))?;
+        plaintexts.push((file.name.as_str(), plain));
+    }
+    let keys_archive = if has_keys_archive {
+        Some(storage.read(KEYS_ARCHIVE).await?)
+    } else {
+        None
+    };
+
+    let identity = age::x25519::Identity::generate();
+    let recipient = identity.to_public().to_string();
+    let identity_file = identity_file(&identity);
+    runner.out(&format!("  New recipient: {}", recipient)).await;
+
+    // Copies that don't sit next to a kept .old file carry the previous keys,
+    // so snapshots from before the rotation can still be restored
+    let with_previous = with_previous_keys(&identity_file, &old_identity);
+
+    if let Some(path) = &settings.age_key_path {
+        let kept = save_identity(path, &identity_file)?;
+        if let Some(kept) = kept {
+            runner.out(&format!("  ✓ Previous key kept at {}", kept.display())).await;
+        }
+        runner.out(&format!("  ✓ New key written to {}", path.display())).await;
+    }
+    if let Some(reference) = &settings.age_key_secret {
+        let secrets = settings.secrets.build();
+        secrets
+            .write(reference, &with_previous)
+            .await
+            .with_context(|| format!("Failed to store the new age key in {}", secrets.name()))?;
+        runner.out(&format!("  ✓ New key stored in {} ({})", secrets.name(), reference)).await;
+    }
+
+    for (name, plain) in &plaintexts {
+        storage.write(name, &crypto::encrypt(plain, &recipient)?).await?;
+        runner.out(&format!("  ✓ Re-encrypted {}", name)).await;
+    }
+    if let (Some(encrypted), Some(passphrase)) = (&keys_archive, passphrase) {
+        storage.write(KEYS_ARCHIVE, &keys::replace_age_key(encrypted, passphrase, &with_previous)?).await?;
+        runner.out(&format!("  ✓ Updated {}", KEYS_ARCHIVE)).await;
+    }
+
+    let message = format!("Rotate age key\n\nNew recipient: {}", recipient);
+    let summary = storage.save(&message, true).await?;
+    runner.out(&format!("  {}", summary)).await;
+
+    set_config_value(&rotation.config_path, "AGE_RECIPIENT", &recipient)
+        .with_context(|| format!("Failed to update {}", rotation.config_path.display()))?;
+    runner.out(&format!("  ✓ AGE_RECIPIENT updated in {}", rotation.config_path.display())).await;
+
+    let mut files: Vec<&str> = plaintexts.iter().map(|(name, _)| *name).collect();
+    if has_keys_archive {
+        files.push(KEYS_ARCHIVE);
+    }
+    let entry = AuditEntry {
+        time: chrono::Local::now().to_rfc3339(),
+        host: forge::notify::attribution::current_hostname(),
+        backend: storage.name(),
+        location: storage.location(),
+        old_recipient: settings.age_recipient.trim(),
+        new_recipient: &recipient,
+        files,
+    };
+    append_audit(&rotation.audit_log, &entry)?;
+    runner.out(&format!("  ✓ Logged to {}", rotation.audit_log.display())).await;
+
+    runner.out("").await;
+    runner.out("  Set ageRecipient in your NixOS config to the new recipient").await;
+    runner.out("  so the next rebuild's config.example matches.").await;
+    if storage.history().is_some() {
+        runner.out("  Older snapshots stay encrypted to the previous key, which restore keeps using.").await;
+    }
+    Ok(())
+}
+
+/// An identity file in the same layout `age-keygen` writes
+fn identity_file(identity: &age::x25519::Identity) -> String {
+    format!(
+        "# created: {}\n# public key: {}\n{}\n",
+        chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        identity.to_public(),
+        identity.to_string().expose_secret()
+    )
+}
+
+/// `identity_file` followed by the secret keys in `previous`
+fn with_previous_keys(identity_file: &str, previous: &str) -> String {
+    let keys: Vec<&str> = previous
+        .lines()
+        .map(str::trim)
+        .filter(|l| l.starts_with("AGE-SECRET-KEY-"))
+        .collect();
+    if keys.is_empty() {
+        return identity_file.to_string();
+    }
+    format!(
+        "{}# previous keys, for snapshots made before a rotation\n{}\n",
+        identity_file,
+        keys.join("\n")
+    )
+}
+
+/// Write the new identity, moving an existing one aside
+///
+/// Returns where the previous key was kept; `crypto::load_identity` still
+/// reads it, so older snapshots can be restored.
+fn save_identity(path: &Path, identity_file: &str) -> Result<Option<PathBuf>> {
+    let mut kept = None;
+    if path.exists() {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        let old = path.with_file_name(format!("{}.{}{}", name, stamp, crypto::RETAINED_SUFFIX));
+        std::fs::rename(path, &old).with_context(|| format!("Failed to move {} aside", path.display()))?;
+        kept = Some(old);
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    keys::write_key(path, identity_file.as_bytes(), 0o600)?;
+    Ok(kept)
+}
+
+fn append_audit(path: &Path, entry: &AuditEntry) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::secrets::{FakeSecrets, SecretConfig};
+    use crate::backup::archive::{self, Compression};
+    use crate::backup::storage::StorageConfig;
+    use crate::backup::test_support::{test_settings, ScratchDir};
+    use std::os::unix::fs::PermissionsExt;
+    use crate::commands::CommandMessage;
+    use tokio::sync::mpsc;
+
+    fn setup(scratch: &ScratchDir) -> (BackupSettings, Rotation, age::x25519::Identity) {
+        let identity = age::x25519::Identity::generate();
+        let recipient = identity.to_public().to_string();
+        let key_path = scratch.write("home/.config/age/key.txt", identity.to_string().expose_secret());
+        std::fs::create_dir_all(scratch.path().join("usb/backup")).unwrap();
+        std::fs::write(
+            scratch.path().join("usb/backup/chrome-profile.tar.zst.age"),
+            crypto::encrypt(b"chrome", &recipient).unwrap(),
+        )
+        .unwrap();
+        let config_path = scratch.write("config", &format!("AGE_RECIPIENT=\"{}\"\n", recipient));
+
+        let settings = BackupSettings {
+            storage: StorageConfig::Local {
+                path: scratch.path().join("usb/backup"),
+            },
+            age_recipient: recipient,
+            age_key_path: Some(key_path),
+            ..test_settings(&scratch.path().join("home"))
+        };
+        let rotation = Rotation {
+            config_path,
+            audit_log: scratch.path().join("forge/key-rotations.log"),
+            dry_run: false,
+        };
+        (settings, rotation, identity)
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_changes_nothing() {
+        let scratch = ScratchDir::new("rotate-dry-run");
+        let (settings, mut rotation, _) = setup(&scratch);
+        rotation.dry_run = true;
+        let before = std::fs::read(scratch.path().join("usb/backup/chrome-profile.tar.zst.age")).unwrap();
+
+        let (tx, mut rx) = mpsc::channel::<CommandMessage>(64);
+        rotate(&CommandRunner::new(&tx), &settings, &rotation, None).await.unwrap();
+        drop(tx);
+
+        let mut output = Vec::new();
+        while let Some(CommandMessage::Stdout(line)) = rx.recv().await {
+            output.push(line);
+        }
+        assert!(output.iter().any(|l| l.contains("chrome-profile.tar.zst.age")));
+        assert_eq!(std::fs::read(scratch.path().join("usb/backup/chrome-profile.tar.zst.age")).unwrap(), before);
+        assert!(!rotation.audit_log.exists());
+    }
+
+    #[tokio::test]
+    async fn test_rotate_reencrypts_archives() {
+        let scratch = ScratchDir::new("rotate");
+        let (settings, rotation, old) = setup(&scratch);
+        let old_key = old.to_string().expose_secret().to_string();
+        let keys = archive::pack(
+            &[archive::Entry {
+                path: "age-key.txt".to_string(),
+                data: old_key.clone().into_bytes(),
+                mode: 0o600,
+            }],
+            Compression::Gzip,
+        )
+        .unwrap();
+        std::fs::write(
+            scratch.path().join("usb/backup").join(KEYS_ARCHIVE),
+            crypto::encrypt_with_passphrase(&keys, "passphrase").unwrap(),
+        )
+        .unwrap();
+
+        let fake = FakeSecrets::default();
+        let mut settings = settings;
+        settings.secrets = SecretConfig::Fake(fake.clone());
+        settings.age_key_secret = Some("op://Private/age/key".to_string());
+
+        let (tx, _rx) = mpsc::channel::<CommandMessage>(64);
+        let runner = CommandRunner::new(&tx);
+        assert!(rotate(&runner, &settings, &rotation, None).await.is_err());
+        rotate(&runner, &settings, &rotation, Some("passphrase")).await.unwrap();
+
+        let new_key = std::fs::read_to_string(settings.age_key_path.as_ref().unwrap()).unwrap();
+        let new_recipient = crate::backup::audit::age_public_key(&new_key).unwrap();
+        assert_ne!(new_recipient, settings.age_recipient);
+        let stored = fake.get("op://Private/age/key").unwrap();
+        assert!(stored.starts_with(&new_key) && stored.contains(&old_key));
+        assert_eq!(crate::backup::audit::age_public_key(&stored), Some(new_recipient.clone()));
+
+        let archive = std::fs::read(scratch.path().join("usb/backup/chrome-profile.tar.zst.age")).unwrap();
+        assert_eq!(crypto::decrypt(&archive, &new_key).unwrap(), b"chrome");
+        assert!(crypto::decrypt(&archive, &old_key).is_err());
+
+        let keys = std::fs::read(scratch.path().join("usb/backup").join(KEYS_ARCHIVE)).unwrap();
+        let entries = archive::read(&crypto::decrypt_with_passphrase(&keys, "passphrase").unwrap(), Compression::Gzip).unwrap();
+        assert_eq!(entries[0].data, stored.as_bytes());
+
+        let config = std::fs::read_to_string(&rotation.config_path).unwrap();
+        assert_eq!(config, format!("AGE_RECIPIENT=\"{}\"\n", new_recipient));
+
+        let kept: Vec<_> = std::fs::read_dir(scratch.path().join("home/.config/age"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|n| n.ends_with(".old"))
+            .collect();
+        assert_eq!(kept.len(), 1);
+        let mode = std::fs::metadata(settings.age_key_path.as_ref().unwrap()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // Snapshots from before the rotation still decrypt with the kept key
+        let snapshot = crypto::encrypt(b"old snapshot", &settings.age_recipient).unwrap();
+        let identity = crypto::load_identity(&settings).await.unwrap();
+        assert_eq!(crypto::decrypt(&snapshot, &identity).unwrap(), b"old snapshot");
+        assert_eq!(crypto::decrypt(&snapshot, &stored).unwrap(), b"old snapshot");
+
+        let log = std::fs::read_to_string(&rotation.audit_log).unwrap();
+        let entry: serde_json::Value = serde_json::from_str(log.lines().next().unwrap()).unwrap();
+        assert_eq!(entry["new_recipient"], new_recipient.as_str());
+        assert_eq!(entry["files"], serde_json::json!(["chrome-profile.tar.zst.age", KEYS_ARCHIVE]));
+    }
+}
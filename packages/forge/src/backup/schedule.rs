@@ -0,0 +1,276 @@
+//! Scheduled app profile backups
+//!
+//! `forge apps schedule` installs a systemd user timer that wakes forge up
+//! every hour. Each run backs up once `--every` hours have passed since the
+//! last successful backup, unless an app is open, the machine is on battery
+//! or it is offline; skipped runs are simply retried at the next wake-up.
+//! Outcomes go to the backup record that forge-notify reads to warn about
+//! overdue backups.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use forge::notify::backups::BackupRecord;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+
+use super::storage::StorageConfig;
+use super::{engine, profiles, BackupSettings};
+use crate::commands::executor::run_capture;
+use crate::commands::runner::CommandRunner;
+use crate::commands::CommandMessage;
+use crate::system::diagnostics;
+
+/// Name of the systemd service and timer units
+pub const UNIT_NAME: &str = "forge-app-backup";
+/// User units directory relative to home
+const UNIT_SUBDIR: &str = ".config/systemd/user";
+/// forge on the system profile, which survives updates (store paths don't)
+const SYSTEM_FORGE_BIN: &str = "/run/current-system/sw/bin/forge";
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+/// Directory the unit files are written to
+pub fn unit_dir(home: &Path) -> PathBuf {
+    home.join(UNIT_SUBDIR)
+}
+
+/// Whether the timer unit is installed
+pub fn is_enabled(home: &Path) -> bool {
+    unit_dir(home).join(format!("{}.timer", UNIT_NAME)).exists()
+}
+
+/// Write and start the timer
+pub async fn enable(runner: &CommandRunner<'_>, home: &Path, every_hours: u64) -> Result<()> {
+    let dir = unit_dir(home);
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let exe = forge_bin()?;
+    for (ext, contents) in [("service", service_unit(&exe, every_hours)), ("timer", timer_unit())] {
+        let path = dir.join(format!("{}.{}", UNIT_NAME, ext));
+        std::fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+        runner.out(&format!("  ✓ Wrote {}", path.display())).await;
+    }
+
+    systemctl(&["daemon-reload"]).await?;
+    systemctl(&["enable", "--now", &format!("{}.timer", UNIT_NAME)]).await?;
+    runner.out(&format!("  ✓ Enabled {}.timer", UNIT_NAME)).await;
+    Ok(())
+}
+
+/// Stop the timer and remove the unit files
+pub async fn disable(runner: &CommandRunner<'_>, home: &Path) -> Result<()> {
+    if !is_enabled(home) {
+        runner.out("  No backup schedule installed").await;
+        return Ok(());
+    }
+
+    systemctl(&["disable", "--now", &format!("{}.timer", UNIT_NAME)]).await?;
+    for ext in ["timer", "service"] {
+        let path = unit_dir(home).join(format!("{}.{}", UNIT_NAME, ext));
+        if path.exists() {
+            std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+            runner.out(&format!("  ✓ Removed {}", path.display())).await;
+        }
+    }
+    systemctl(&["daemon-reload"]).await?;
+    Ok(())
+}
+
+/// Back up if one is due and nothing is in the way (run by the timer)
+pub async fn run(every_hours: u64) -> Result<()> {
+    let now = Utc::now();
+    let mut record = BackupRecord::load().unwrap_or_default();
+    if !record.due(chrono::Duration::hours(every_hours as i64), now) {
+        tracing::info!("Scheduled backup: last backup is recent, nothing to do");
+        return Ok(());
+    }
+
+    let profiles = profiles::select(&[])?;
+    let settings = BackupSettings::load();
+    // Only a local directory can be backed up to without a network
+    let offline = match &settings {
+        Ok(settings) if !matches!(settings.storage, StorageConfig::Local { .. }) => {
+            !diagnostics::has_default_route().await
+        }
+        _ => false,
+    };
+    if let Some(reason) = skip_reason(Path::new(POWER_SUPPLY_DIR), &engine::running_apps(&profiles).await, offline) {
+        tracing::info!("Scheduled backup skipped: {}", reason);
+        record.record_skip(now, &reason);
+        return record.save();
+    }
+
+    tracing::info!("Scheduled backup starting");
+    let result = match settings {
+        Ok(settings) => backup_logged(&settings, &profiles).await,
+        Err(e) => Err(e),
+    };
+    record_result(&result);
+    result
+}
+
+/// Store the outcome of a backup in the backup record
+pub fn record_result(result: &Result<()>) {
+    let mut record = BackupRecord::load().unwrap_or_default();
+    match result {
+        Ok(()) => record.record_success(Utc::now()),
+        Err(e) => record.record_failure(Utc::now(), &format!("{:#}", e)),
+    }
+    if let Err(e) = record.save() {
+        tracing::warn!("Failed to save backup record: {}", e);
+    }
+}
+
+/// Run a backup with its output going to the log instead of the TUI
+async fn backup_logged(settings: &BackupSettings, profiles: &[profiles::AppProfile]) -> Result<()> {
+    let (tx, mut rx) = mpsc::channel::<CommandMessage>(64);
+    let logger = tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            match message {
+                CommandMessage::Stdout(line) => tracing::info!("Scheduled backup: {}", line.trim()),
+                CommandMessage::Stderr(line) => tracing::warn!("Scheduled backup: {}", line.trim()),
+                _ => {}
+            }
+        }
+    });
+
+    let result = engine::backup(&CommandRunner::new(&tx), settings, profiles, engine::Options::new(false)).await;
+    drop(tx);
+    let _ = logger.await;
+    result
+}
+
+/// Why a scheduled run should not back up right now
+fn skip_reason(power_supply_dir: &Path, running: &[&str], offline: bool) -> Option<String> {
+    if offline {
+        return Some("offline".to_string());
+    }
+    if on_battery(power_supply_dir) {
+        return Some("running on battery".to_string());
+    }
+    if !running.is_empty() {
+        return Some(format!("{} running", running.join(", ")));
+    }
+    None
+}
+
+/// True if the machine has a battery and no mains power connected
+fn on_battery(power_supply_dir: &Path) -> bool {
+    let Ok(entries) = std::fs::read_dir(power_supply_dir) else {
+        return false;
+    };
+
+    let mut has_battery = false;
+    for entry in entries.flatten() {
+        let read = |name: &str| std::fs::read_to_string(entry.path().join(name)).unwrap_or_default();
+        match read("type").trim() {
+            "Mains" | "USB" if read("online").trim() == "1" => return false,
+            "Battery" => has_battery = true,
+            _ => {}
+        }
+    }
+    has_battery
+}
+
+fn service_unit(exe: &Path, every_hours: u64) -> String {
+    format!(
+        "[Unit]\n\
+         Description=Forge scheduled app profile backup\n\
+         \n\
+         [Service]\n\
+         Type=oneshot\n\
+         ExecStart={} apps schedule --run --every {}\n",
+        exe.display(),
+        every_hours
+    )
+}
+
+fn timer_unit() -> String {
+    "[Unit]\n\
+     Description=Hourly check for a due Forge app backup\n\
+     \n\
+     [Timer]\n\
+     OnBootSec=15min\n\
+     OnUnitActiveSec=1h\n\
+     \n\
+     [Install]\n\
+     WantedBy=timers.target\n"
+        .to_string()
+}
+
+/// forge binary for the timer to run
+fn forge_bin() -> Result<PathBuf> {
+    let system = Path::new(SYSTEM_FORGE_BIN);
+    if system.exists() {
+        return Ok(system.to_path_buf());
+    }
+    std::env::current_exe().context("Could not determine the forge binary path")
+}
+
+async fn systemctl(args: &[&str]) -> Result<()> {
+    let mut full = vec!["--user"];
+    full.extend(args);
+    let (success, _, stderr) = run_capture("systemctl", &full).await?;
+    if !success {
+        anyhow::bail!("systemctl --user {} failed: {}", args.join(" "), stderr.trim());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::test_support::ScratchDir;
+
+    fn supply(dir: &ScratchDir, name: &str, kind: &str, online: Option<&str>) {
+        dir.write(&format!("{}/type", name), &format!("{}\n", kind));
+        if let Some(online) = online {
+            dir.write(&format!("{}/online", name), &format!("{}\n", online));
+        }
+    }
+
+    #[test]
+    fn test_on_battery() {
+        let desktop = ScratchDir::new("power-desktop");
+        assert!(!on_battery(desktop.path()));
+        assert!(!on_battery(&desktop.path().join("missing")));
+
+        let laptop = ScratchDir::new("power-laptop");
+        supply(&laptop, "BAT0", "Battery", None);
+        supply(&laptop, "AC", "Mains", Some("0"));
+        assert!(on_battery(laptop.path()));
+
+        supply(&laptop, "AC", "Mains", Some("1"));
+        assert!(!on_battery(laptop.path()));
+    }
+
+    #[test]
+    fn test_skip_reason() {
+        let laptop = ScratchDir::new("power-skip");
+        supply(&laptop, "BAT0", "Battery", None);
+        supply(&laptop, "AC", "Mains", Some("1"));
+
+        assert_eq!(skip_reason(laptop.path(), &[], false), None);
+        assert_eq!(
+            skip_reason(laptop.path(), &["Firefox", "Slack"], false).as_deref(),
+            Some("Firefox, Slack running")
+        );
+        assert_eq!(skip_reason(laptop.path(), &[], true).as_deref(), Some("offline"));
+
+        supply(&laptop, "AC", "Mains", Some("0"));
+        assert_eq!(skip_reason(laptop.path(), &[], false).as_deref(), Some("running on battery"));
+    }
+
+    #[test]
+    fn test_units() {
+        let service = service_unit(Path::new("/run/current-system/sw/bin/forge"), 6);
+        assert!(service.contains("ExecStart=/run/current-system/sw/bin/forge apps schedule --run --every 6\n"));
+        assert!(service.contains("Type=oneshot"));
+
+        let timer = timer_unit();
+        assert!(timer.contains("OnUnitActiveSec=1h"));
+        // Monotonic timers have nothing for Persistent= to catch up on
+        assert!(!timer.contains("Persistent="));
+        assert!(!service.contains("network-online.target"));
+        assert!(timer.contains("WantedBy=timers.target"));
+    }
+}
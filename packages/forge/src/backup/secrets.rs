@@ -0,0 +1,497 @@
+//! Secret providers for keys and passphrases
+//!
+//! Keys setup, keys backup/restore and app profile restore fetch secrets
+//! through [`SecretProvider`]. SECRET_PROVIDER in the backup config picks the
+//! password manager; references (AGE_KEY_SECRET etc.) use its naming:
+//!
+//! - `1password`: `op://vault/item/field`
+//! - `bitwarden`: `item` (password), `item/notes` or `item/<custom field>`
+//! - `pass`: the entry name, e.g. `keys/age`
+//! - `file`: a file name under SECRET_DIR (e.g. on an encrypted USB stick)
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use futures::future::BoxFuture;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use crate::commands::executor::{command_exists, run_capture, run_capture_with_input};
+use crate::system::config::BrowserBackupConfig;
+
+/// A password manager or other place secrets are kept
+pub trait SecretProvider: Send + Sync {
+    /// Short name for messages (`1Password`, `pass`, ...)
+    fn name(&self) -> &'static str;
+
+    /// Fail early, with a hint, if the provider can't be used right now
+    fn check<'a>(&'a self) -> BoxFuture<'a, Result<()>>;
+
+    fn read<'a>(&'a self, reference: &'a str) -> BoxFuture<'a, Result<String>>;
+
+    /// Replace the secret `reference` points at
+    fn write<'a>(&'a self, reference: &'a str, value: &'a str) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Which secret provider to use, resolved from the backup config
+#[derive(Debug, Clone)]
+pub enum SecretConfig {
+    OnePassword,
+    Bitwarden,
+    Pass,
+    File {
+        dir: PathBuf,
+    },
+    #[cfg(test)]
+    Fake(FakeSecrets),
+}
+
+impl SecretConfig {
+    pub fn from_config(config: &BrowserBackupConfig, home: &Path) -> Result<Self> {
+        Ok(match config.secret_provider.as_str() {
+            "" | "1password" | "op" => SecretConfig::OnePassword,
+            "bitwarden" | "bw" => SecretConfig::Bitwarden,
+            "pass" => SecretConfig::Pass,
+            "file" => {
+                let Some(dir) = config.secret_dir.as_deref().filter(|d| !d.is_empty()) else {
+                    anyhow::bail!("SECRET_DIR must be set for the file secret provider");
+                };
+                SecretConfig::File {
+                    dir: super::resolve_home(dir, home),
+                }
+            }
+            other => anyhow::bail!(
+                "Unknown SECRET_PROVIDER '{}' (expected 1password, bitwarden, pass or file)",
+                other
+            ),
+        })
+    }
+
+    pub fn build(&self) -> Box<dyn SecretProvider> {
+        match self {
+            SecretConfig::OnePassword => Box::new(OnePassword),
+            SecretConfig::Bitwarden => Box::new(Bitwarden),
+            SecretConfig::Pass => Box::new(Pass),
+            SecretConfig::File { dir } => Box::new(FileSecrets { dir: dir.clone() }),
+            #[cfg(test)]
+            SecretConfig::Fake(fake) => Box::new(fake.clone()),
+        }
+    }
+}
+
+/// 1Password CLI (`op`)
+pub struct OnePassword;
+
+impl SecretProvider for OnePassword {
+    fn name(&self) -> &'static str {
+        "1Password"
+    }
+
+    fn check<'a>(&'a self) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            if !command_exists("op").await {
+                anyhow::bail!("1Password CLI (op) not found");
+            }
+            let (success, _, _) = run_capture("op", &["account", "list"]).await?;
+            if !success {
+                anyhow::bail!("Not signed in to 1Password. Open the 1Password app and sign in.");
+            }
+            Ok(())
+        })
+    }
+
+    fn read<'a>(&'a self, reference: &'a str) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let (success, stdout, stderr) = run_capture("op", &["read", reference]).await?;
+            if !success || stdout.trim().is_empty() {
+                anyhow::bail!("Failed to read {} from 1Password: {}", reference, stderr.trim());
+            }
+            Ok(stdout)
+        })
+    }
+
+    fn write<'a>(&'a self, reference: &'a str, value: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let Some((vault, item, field)) = parse_op_reference(reference) else {
+                anyhow::bail!("Unsupported 1Password reference '{}'", reference);
+            };
+            let (success, stdout, stderr) =
+                run_capture("op", &["item", "get", &item, "--vault", &vault, "--format", "json"]).await?;
+            if !success {
+                anyhow::bail!("Failed to read {} from 1Password: {}", reference, stderr.trim());
+            }
+            let (args, template) = op_edit(&stdout, &vault, &item, &field, value)?;
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            let (success, _, stderr) = run_capture_with_input("op", &args, &template).await?;
+            if !success {
+                anyhow::bail!("Failed to update {} in 1Password: {}", reference, stderr.trim());
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Split an `op://vault/item/[section/]field` reference into vault, item and
+/// the field name `op item edit` expects
+fn parse_op_reference(reference: &str) -> Option<(String, String, String)> {
+    let parts: Vec<&str> = reference.strip_prefix("op://")?.split('/').collect();
+    match parts.as_slice() {
+        [vault, item, field] => Some((vault.to_string(), item.to_string(), field.to_string())),
+        [vault, item, section, field] => Some((vault.to_string(), item.to_string(), format!("{}.{}", section, field))),
+        _ => None,
+    }
+}
+
+/// Arguments and stdin template for `op item edit` setting `field` to `value`
+///
+/// The value only goes into the JSON template piped to stdin, never onto the
+/// command line where other users could read it.
+fn op_edit(item_json: &str, vault: &str, item: &str, field: &str, value: &str) -> Result<(Vec<String>, String)> {
+    let mut template: serde_json::Value =
+        serde_json::from_str(item_json).context("Unexpected output from op item get")?;
+    let (section, name) = match field.split_once('.') {
+        Some((section, name)) => (Some(section), name),
+        None => (None, field),
+    };
+    let matches = |value: &serde_json::Value, wanted: &str| value["id"] == wanted || value["label"] == wanted;
+    let entry = template["fields"]
+        .as_array_mut()
+        .and_then(|fields| {
+            fields
+                .iter_mut()
+                .find(|f| matches(f, name) && section.is_none_or(|s| matches(&f["section"], s)))
+        })
+        .with_context(|| format!("1Password item has no field '{}'", field))?;
+    entry["value"] = serde_json::Value::String(value.to_string());
+
+    let args = ["item", "edit", item, "--vault", vault].map(str::to_string).to_vec();
+    Ok((args, template.to_string()))
+}
+
+/// Bitwarden CLI (`bw`); the vault must be unlocked with BW_SESSION exported
+pub struct Bitwarden;
+
+impl Bitwarden {
+    async fn item(&self, item: &str) -> Result<serde_json::Value> {
+        let (success, stdout, stderr) = run_capture("bw", &["get", "item", item]).await?;
+        if !success {
+            anyhow::bail!("Failed to read {} from Bitwarden: {}", item, stderr.trim());
+        }
+        serde_json::from_str(&stdout).context("Unexpected output from bw get item")
+    }
+}
+
+impl SecretProvider for Bitwarden {
+    fn name(&self) -> &'static str {
+        "Bitwarden"
+    }
+
+    fn check<'a>(&'a self) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            if !command_exists("bw").await {
+                anyhow::bail!("Bitwarden CLI (bw) not found");
+            }
+            let (_, stdout, _) = run_capture("bw", &["status"]).await?;
+            let status: serde_json::Value = serde_json::from_str(&stdout).unwrap_or_default();
+            if status["status"] != "unlocked" {
+                anyhow::bail!("Bitwarden vault is locked. Run 'bw unlock' and export BW_SESSION.");
+            }
+            Ok(())
+        })
+    }
+
+    fn read<'a>(&'a self, reference: &'a str) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let (item, field) = parse_bw_reference(reference);
+            let item = self.item(item).await?;
+            match bw_field(&item, field) {
+                Some(value) if !value.is_empty() => Ok(value),
+                _ => anyhow::bail!("Bitwarden item {} has no {}", reference, field),
+            }
+        })
+    }
+
+    fn write<'a>(&'a self, reference: &'a str, value: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (name, field) = parse_bw_reference(reference);
+            let mut item = self.item(name).await?;
+            set_bw_field(&mut item, field, value)?;
+            let id = item["id"].as_str().context("Bitwarden item has no id")?.to_string();
+
+            // `bw edit` takes the item as base64 JSON, same as `bw encode` makes
+            let encoded = base64::engine::general_purpose::STANDARD.encode(item.to_string());
+            let (success, _, stderr) = run_capture_with_input("bw", &["edit", "item", &id], &encoded).await?;
+            if !success {
+                anyhow::bail!("Failed to update {} in Bitwarden: {}", reference, stderr.trim());
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Split `item[/field]`; the field defaults to the login password
+fn parse_bw_reference(reference: &str) -> (&str, &str) {
+    reference.split_once('/').unwrap_or((reference, "password"))
+}
+
+fn bw_field(item: &serde_json::Value, field: &str) -> Option<String> {
+    let value = match field {
+        "password" => &item["login"]["password"],
+        "notes" => &item["notes"],
+        name => item["fields"]
+            .as_array()?
+            .iter()
+            .find(|f| f["name"] == name)
+            .map(|f| &f["value"])?,
+    };
+    value.as_str().map(str::to_string)
+}
+
+fn set_bw_field(item: &mut serde_json::Value, field: &str, value: &str) -> Result<()> {
+    let value = serde_json::Value::String(value.to_string());
+    match field {
+        "password" => item["login"]["password"] = value,
+        "notes" => item["notes"] = value,
+        name => {
+            let field = item["fields"]
+                .as_array_mut()
+                .and_then(|fields| fields.iter_mut().find(|f| f["name"] == name))
+                .with_context(|| format!("Bitwarden item has no field '{}'", name))?;
+            field["value"] = value;
+        }
+    }
+    Ok(())
+}
+
+/// The standard unix password manager (`pass`)
+pub struct Pass;
+
+impl SecretProvider for Pass {
+    fn name(&self) -> &'static str {
+        "pass"
+    }
+
+    fn check<'a>(&'a self) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            if !command_exists("pass").await {
+                anyhow::bail!("pass not found");
+            }
+            Ok(())
+        })
+    }
+
+    fn read<'a>(&'a self, reference: &'a str) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let (success, stdout, stderr) = run_capture("pass", &["show", reference]).await?;
+            if !success {
+                anyhow::bail!("Failed to read {} from pass: {}", reference, stderr.trim());
+            }
+            Ok(stdout)
+        })
+    }
+
+    fn write<'a>(&'a self, reference: &'a str, value: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (success, _, stderr) =
+                run_capture_with_input("pass", &["insert", "--multiline", "--force", reference], value).await?;
+            if !success {
+                anyhow::bail!("Failed to update {} in pass: {}", reference, stderr.trim());
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Plain files in a directory, e.g. on an encrypted USB stick
+pub struct FileSecrets {
+    dir: PathBuf,
+}
+
+impl FileSecrets {
+    fn path(&self, reference: &str) -> Result<PathBuf> {
+        let relative = Path::new(reference);
+        if reference.is_empty() || relative.is_absolute() || relative.components().any(|c| c.as_os_str() == "..") {
+            anyhow::bail!("Invalid secret file name '{}'", reference);
+        }
+        Ok(self.dir.join(relative))
+    }
+}
+
+impl SecretProvider for FileSecrets {
+    fn name(&self) -> &'static str {
+        "secret files"
+    }
+
+    fn check<'a>(&'a self) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            if !self.dir.is_dir() {
+                anyhow::bail!("{} not found (is the drive mounted?)", self.dir.display());
+            }
+            Ok(())
+        })
+    }
+
+    fn read<'a>(&'a self, reference: &'a str) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let path = self.path(reference)?;
+            std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))
+        })
+    }
+
+    fn write<'a>(&'a self, reference: &'a str, value: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let path = self.path(reference)?;
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, value).with_context(|| format!("Failed to write {}", path.display()))?;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+            Ok(())
+        })
+    }
+}
+
+/// In-memory provider for tests
+#[cfg(test)]
+#[derive(Debug, Clone, Default)]
+pub struct FakeSecrets {
+    values: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, String>>>,
+}
+
+#[cfg(test)]
+impl FakeSecrets {
+    pub fn with(values: &[(&str, &str)]) -> Self {
+        let fake = Self::default();
+        for (reference, value) in values {
+            fake.values.lock().unwrap().insert(reference.to_string(), value.to_string());
+        }
+        fake
+    }
+
+    pub fn get(&self, reference: &str) -> Option<String> {
+        self.values.lock().unwrap().get(reference).cloned()
+    }
+}
+
+#[cfg(test)]
+impl SecretProvider for FakeSecrets {
+    fn name(&self) -> &'static str {
+        "fake"
+    }
+
+    fn check<'a>(&'a self) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn read<'a>(&'a self, reference: &'a str) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move { self.get(reference).with_context(|| format!("No secret {}", reference)) })
+    }
+
+    fn write<'a>(&'a self, reference: &'a str, value: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            self.values.lock().unwrap().insert(reference.to_string(), value.to_string());
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::test_support::ScratchDir;
+
+    #[test]
+    fn test_provider_from_config() {
+        let home = Path::new("/home/me");
+        let mut config = BrowserBackupConfig::default();
+        assert!(matches!(SecretConfig::from_config(&config, home).unwrap(), SecretConfig::OnePassword));
+
+        config.secret_provider = "file".to_string();
+        assert!(SecretConfig::from_config(&config, home).is_err());
+        config.secret_dir = Some("~/usb/secrets".to_string());
+        let SecretConfig::File { dir } = SecretConfig::from_config(&config, home).unwrap() else {
+            panic!("expected file provider");
+        };
+        assert_eq!(dir, PathBuf::from("/home/me/usb/secrets"));
+
+        config.secret_provider = "keychain".to_string();
+        assert!(SecretConfig::from_config(&config, home).is_err());
+    }
+
+    #[test]
+    fn test_parse_op_reference() {
+        assert_eq!(
+            parse_op_reference("op://Private/age key/password"),
+            Some(("Private".to_string(), "age key".to_string(), "password".to_string()))
+        );
+        assert_eq!(
+            parse_op_reference("op://Private/backup/keys/age"),
+            Some(("Private".to_string(), "backup".to_string(), "keys.age".to_string()))
+        );
+        assert_eq!(parse_op_reference("Private/age"), None);
+    }
+
+    #[test]
+    fn test_op_edit_keeps_value_off_command_line() {
+        let item = serde_json::json!({
+            "id": "abcd",
+            "fields": [
+                { "id": "password", "label": "password", "value": "old" },
+                { "id": "x1", "label": "age", "section": { "id": "s1", "label": "keys" }, "value": "old" },
+            ],
+        })
+        .to_string();
+        let secret = "AGE-SECRET-KEY-1NEW";
+
+        let (args, template) = op_edit(&item, "Private", "backup", "keys.age", secret).unwrap();
+        assert_eq!(args, ["item", "edit", "backup", "--vault", "Private"]);
+        assert!(args.iter().all(|arg| !arg.contains(secret)));
+        let template: serde_json::Value = serde_json::from_str(&template).unwrap();
+        assert_eq!(template["fields"][1]["value"], secret);
+        assert_eq!(template["fields"][0]["value"], "old");
+
+        let (_, template) = op_edit(&item, "Private", "backup", "password", secret).unwrap();
+        assert!(template.contains(secret));
+        assert!(op_edit(&item, "Private", "backup", "other.age", secret).is_err());
+    }
+
+    #[test]
+    fn test_bitwarden_fields() {
+        assert_eq!(parse_bw_reference("age key"), ("age key", "password"));
+        assert_eq!(parse_bw_reference("ssh/notes"), ("ssh", "notes"));
+
+        let mut item = serde_json::json!({
+            "id": "1234",
+            "notes": "ssh private",
+            "login": { "password": "hunter2" },
+            "fields": [{ "name": "age", "value": "AGE-SECRET-KEY-1" }],
+        });
+        assert_eq!(bw_field(&item, "password").as_deref(), Some("hunter2"));
+        assert_eq!(bw_field(&item, "notes").as_deref(), Some("ssh private"));
+        assert_eq!(bw_field(&item, "age").as_deref(), Some("AGE-SECRET-KEY-1"));
+        assert_eq!(bw_field(&item, "missing"), None);
+
+        set_bw_field(&mut item, "age", "AGE-SECRET-KEY-2").unwrap();
+        assert_eq!(bw_field(&item, "age").as_deref(), Some("AGE-SECRET-KEY-2"));
+        assert!(set_bw_field(&mut item, "missing", "x").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_file_secrets() {
+        let scratch = ScratchDir::new("secrets-file");
+        let provider = SecretConfig::File {
+            dir: scratch.path().join("usb"),
+        }
+        .build();
+        assert!(provider.check().await.is_err());
+
+        std::fs::create_dir_all(scratch.path().join("usb")).unwrap();
+        provider.check().await.unwrap();
+        provider.write("keys/age.txt", "AGE-SECRET-KEY-1\n").await.unwrap();
+        assert_eq!(provider.read("keys/age.txt").await.unwrap(), "AGE-SECRET-KEY-1\n");
+        let mode = std::fs::metadata(scratch.path().join("usb/keys/age.txt")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        assert!(provider.read("../outside").await.is_err());
+        assert!(provider.read("/etc/passwd").await.is_err());
+    }
+}
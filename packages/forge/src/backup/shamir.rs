@@ -0,0 +1,267 @@
+//! Shamir secret sharing over GF(256)
+//!
+//! Splits a secret byte-wise into shares so that any `threshold` of them
+//! rebuild it and fewer reveal nothing. Shares are written as uppercase
+//! base32 in dash-separated groups: easy to type back in, and only uses the
+//! QR alphanumeric character set.
+
+use anyhow::Result;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// Share format version
+const VERSION: u8 = 1;
+/// Version, threshold, index and 2-byte set id
+const HEADER_LEN: usize = 5;
+const CHECKSUM_LEN: usize = 4;
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// One share of a split secret
+#[derive(Debug, Clone, PartialEq)]
+pub struct Share {
+    /// Shares from the same split carry the same id
+    pub set_id: u16,
+    pub threshold: u8,
+    /// x coordinate, 1-based
+    pub index: u8,
+    pub data: Vec<u8>,
+}
+
+impl Share {
+    /// Encode as dash-separated groups of four base32 characters
+    pub fn encode(&self) -> String {
+        let mut payload = vec![VERSION, self.threshold, self.index];
+        payload.extend(self.set_id.to_be_bytes());
+        payload.extend(&self.data);
+        let checksum = Sha256::digest(&payload);
+        payload.extend(&checksum[..CHECKSUM_LEN]);
+
+        let encoded = base32_encode(&payload);
+        encoded
+            .as_bytes()
+            .chunks(4)
+            .map(|c| String::from_utf8_lossy(c).to_string())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+
+    /// Parse a typed-in share; case, spaces and dashes don't matter
+    pub fn decode(text: &str) -> Result<Self> {
+        let cleaned: String = text
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        let Some(payload) = base32_decode(&cleaned) else {
+            anyhow::bail!("Share contains characters that aren't A-Z or 2-7");
+        };
+        if payload.len() <= HEADER_LEN + CHECKSUM_LEN {
+            anyhow::bail!("Share is too short");
+        }
+
+        let (body, checksum) = payload.split_at(payload.len() - CHECKSUM_LEN);
+        if Sha256::digest(body)[..CHECKSUM_LEN] != *checksum {
+            anyhow::bail!("Share checksum doesn't match (typo?)");
+        }
+        if body[0] != VERSION {
+            anyhow::bail!("Unsupported share version {}", body[0]);
+        }
+        let share = Share {
+            threshold: body[1],
+            index: body[2],
+            set_id: u16::from_be_bytes([body[3], body[4]]),
+            data: body[HEADER_LEN..].to_vec(),
+        };
+        if share.index == 0 || share.threshold < 2 {
+            anyhow::bail!("Share header is invalid");
+        }
+        Ok(share)
+    }
+}
+
+/// Split `secret` into `count` shares, any `threshold` of which rebuild it
+pub fn split(secret: &[u8], count: u8, threshold: u8) -> Result<Vec<Share>> {
+    if threshold < 2 {
+        anyhow::bail!("Threshold must be at least 2");
+    }
+    if count < threshold {
+        anyhow::bail!("Need at least as many shares ({}) as the threshold ({})", count, threshold);
+    }
+
+    let mut rng = rand::thread_rng();
+    let set_id = rng.next_u32() as u16;
+    let mut shares: Vec<Share> = (1..=count)
+        .map(|index| Share {
+            set_id,
+            threshold,
+            index,
+            data: Vec::with_capacity(secret.len()),
+        })
+        .collect();
+
+    // One random polynomial per byte, with the byte as constant term
+    let mut coefficients = vec![0u8; threshold as usize];
+    for &byte in secret {
+        coefficients[0] = byte;
+        rng.fill_bytes(&mut coefficients[1..]);
+        for share in &mut shares {
+            share.data.push(evaluate(&coefficients, share.index));
+        }
+    }
+    Ok(shares)
+}
+
+/// Rebuild the secret from at least `threshold` shares of one split
+pub fn combine(shares: &[Share]) -> Result<Vec<u8>> {
+    let Some(first) = shares.first() else {
+        anyhow::bail!("No shares given");
+    };
+    let mut used: Vec<&Share> = Vec::new();
+    for share in shares {
+        if share.set_id != first.set_id || share.threshold != first.threshold {
+            anyhow::bail!("Shares come from different backups");
+        }
+        if share.data.len() != first.data.len() {
+            anyhow::bail!("Shares have different lengths");
+        }
+        if !used.iter().any(|s| s.index == share.index) {
+            used.push(share);
+        }
+    }
+    if used.len() < first.threshold as usize {
+        anyhow::bail!("Need {} different shares, got {}", first.threshold, used.len());
+    }
+    used.truncate(first.threshold as usize);
+
+    // Lagrange interpolation at x = 0
+    let mut secret = vec![0u8; first.data.len()];
+    for (i, share) in used.iter().enumerate() {
+        let mut basis = 1u8;
+        for (j, other) in used.iter().enumerate() {
+            if i != j {
+                basis = mul(basis, div(other.index, other.index ^ share.index));
+            }
+        }
+        for (byte, &y) in secret.iter_mut().zip(&share.data) {
+            *byte ^= mul(y, basis);
+        }
+    }
+    Ok(secret)
+}
+
+/// Evaluate a polynomial at `x` (Horner's method)
+fn evaluate(coefficients: &[u8], x: u8) -> u8 {
+    coefficients.iter().rev().fold(0, |acc, &c| mul(acc, x) ^ c)
+}
+
+/// Multiplication in GF(256) with the AES polynomial
+fn mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+fn div(a: u8, b: u8) -> u8 {
+    // b^254 is the inverse of b
+    let mut inverse = 1;
+    for _ in 0..254 {
+        inverse = mul(inverse, b);
+    }
+    mul(a, inverse)
+}
+
+fn base32_encode(data: &[u8]) -> String {
+    let mut out = String::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+fn base32_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in text.bytes() {
+        let value = BASE32.iter().position(|&b| b == c)? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_arithmetic() {
+        // Known AES field products
+        assert_eq!(mul(0x57, 0x83), 0xc1);
+        assert_eq!(mul(0x57, 0x13), 0xfe);
+        for a in 1..=255u8 {
+            assert_eq!(mul(div(1, a), a), 1);
+        }
+    }
+
+    #[test]
+    fn test_split_and_combine() {
+        let secret: Vec<u8> = (0..32).collect();
+        let shares = split(&secret, 5, 3).unwrap();
+        assert_eq!(shares.len(), 5);
+        assert!(shares.iter().all(|s| s.data != secret));
+
+        assert_eq!(combine(&shares[..3]).unwrap(), secret);
+        assert_eq!(combine(&[shares[4].clone(), shares[1].clone(), shares[2].clone()]).unwrap(), secret);
+        assert_eq!(combine(&shares).unwrap(), secret);
+
+        let err = combine(&[shares[0].clone(), shares[0].clone(), shares[1].clone()]).unwrap_err();
+        assert!(err.to_string().contains("Need 3 different shares"));
+
+        let other = split(&secret, 5, 3).unwrap();
+        assert!(combine(&[shares[0].clone(), shares[1].clone(), other[2].clone()]).is_err());
+
+        assert!(split(&secret, 2, 3).is_err());
+        assert!(split(&secret, 3, 1).is_err());
+    }
+
+    #[test]
+    fn test_share_encoding() {
+        let share = split(&[7u8; 32], 3, 2).unwrap().remove(1);
+        let text = share.encode();
+        assert!(text.chars().all(|c| c.is_ascii_uppercase() || ('2'..='7').contains(&c) || c == '-'));
+        assert_eq!(Share::decode(&text).unwrap(), share);
+
+        // Typed back in lowercase without dashes
+        let typed = text.replace('-', " ").to_lowercase();
+        assert_eq!(Share::decode(&typed).unwrap(), share);
+
+        let mut typo = text.clone().into_bytes();
+        typo[6] = if typo[6] == b'A' { b'B' } else { b'A' };
+        let err = Share::decode(&String::from_utf8(typo).unwrap()).unwrap_err();
+        assert!(err.to_string().contains("checksum"));
+        assert!(Share::decode("0000").is_err());
+    }
+}
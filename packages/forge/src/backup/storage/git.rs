@@ -0,0 +1,167 @@
+//! Git repository backend (the default)
+//!
+//! Archives are committed to a local clone and pushed; every backup is one
+//! commit, which is what makes older snapshots restorable.
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use std::path::Path;
+
+use super::{check_name, list_dir, write_atomic_via, StorageBackend, StoredFile};
+use crate::backup::repo::{BackupRepo, LFS_THRESHOLD_BYTES};
+
+/// Archives in a git repository
+pub struct GitStorage {
+    url: String,
+    repo: BackupRepo,
+}
+
+impl GitStorage {
+    pub fn new(url: &str, local_repo: &Path) -> Self {
+        Self {
+            url: url.to_string(),
+            repo: BackupRepo::new(local_repo),
+        }
+    }
+
+    /// Compare the clone with its remote
+    async fn remote_status(&self, lines: &mut Vec<String>) -> Result<()> {
+        if self.repo.git_ok(&["remote", "get-url", "origin"]).await.is_err() {
+            lines.push("No remote 'origin' configured".to_string());
+            return Ok(());
+        }
+        if self.repo.git_ok(&["fetch", "-q", "origin"]).await.is_err() {
+            lines.push("Unable to reach remote; showing local status only".to_string());
+            return Ok(());
+        }
+
+        let Ok(upstream) = self.repo.git_ok(&["rev-parse", "--abbrev-ref", "@{u}"]).await else {
+            lines.push("Remote branch not found (nothing pushed yet?)".to_string());
+            return Ok(());
+        };
+        let range = format!("HEAD..{}", upstream.trim());
+        let commits = self.repo.git_ok(&["log", "--oneline", &range]).await?;
+
+        if commits.trim().is_empty() {
+            lines.push("App profiles are up to date".to_string());
+        } else {
+            lines.push("Remote has newer profiles:".to_string());
+            lines.extend(commits.lines().map(|c| format!("  {}", c)));
+            lines.push("Run 'forge apps restore' to update".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl StorageBackend for GitStorage {
+    fn name(&self) -> &'static str {
+        "git"
+    }
+
+    fn location(&self) -> String {
+        self.url.clone()
+    }
+
+    fn open<'a>(&'a self, refresh: bool) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            if refresh || !self.repo.exists() {
+                self.repo.sync(&self.url).await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn list<'a>(&'a self) -> BoxFuture<'a, Result<Vec<StoredFile>>> {
+        Box::pin(async move { list_dir(self.repo.path()) })
+    }
+
+    fn read<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Vec<u8>>> {
+        Box::pin(async move {
+            check_name(name)?;
+            let path = self.repo.path().join(name);
+            std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))
+        })
+    }
+
+    fn write<'a>(&'a self, name: &'a str, data: &'a [u8]) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            check_name(name)?;
+            // Stage the temporary file in .git so a crash can't leave it for `git add -A`
+            let tmp = self.repo.path().join(".git").join(format!("{}.tmp", name));
+            write_atomic_via(&tmp, &self.repo.path().join(name), data)?;
+            if data.len() as u64 > LFS_THRESHOLD_BYTES {
+                tracing::info!("{} exceeds 100 MB, storing with Git LFS", name);
+                self.repo.track_with_lfs(name).await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn remove<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            check_name(name)?;
+            let path = self.repo.path().join(name);
+            if path.exists() {
+                std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+            Ok(())
+        })
+    }
+
+    fn save<'a>(&'a self, message: &'a str, publish: bool) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let summary = if self.repo.commit_all(message).await? {
+                let subject = message.lines().next().unwrap_or_default();
+                format!("Committed {} ({})", self.repo.head().await?, subject)
+            } else {
+                "No changes to commit".to_string()
+            };
+
+            if publish {
+                self.repo.push().await?;
+                return Ok(format!("{}, pushed to {}", summary, self.url));
+            }
+            Ok(summary)
+        })
+    }
+
+    fn status<'a>(&'a self) -> BoxFuture<'a, Result<Vec<String>>> {
+        Box::pin(async move {
+            let mut lines = vec![format!("Repository: {}", self.url)];
+            if !self.repo.exists() {
+                lines.push("Local clone not found. Run 'forge apps restore' to clone.".to_string());
+                return Ok(lines);
+            }
+            lines.push(format!("Local clone: {}", self.repo.path().display()));
+            self.remote_status(&mut lines).await?;
+            Ok(lines)
+        })
+    }
+
+    fn history(&self) -> Option<&BackupRepo> {
+        Some(&self.repo)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::test_support::ScratchDir;
+
+    #[tokio::test]
+    async fn test_write_keeps_temp_file_out_of_work_tree() {
+        let scratch = ScratchDir::new("git-storage-write");
+        std::fs::create_dir_all(scratch.path().join(".git")).unwrap();
+        let storage = GitStorage::new("git@example.com:me/settings.git", scratch.path());
+
+        storage.write("chrome-profile.tar.zst.age", b"data").await.unwrap();
+
+        let mut names: Vec<String> = std::fs::read_dir(scratch.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, [".git", "chrome-profile.tar.zst.age"]);
+        assert_eq!(std::fs::read(scratch.path().join("chrome-profile.tar.zst.age")).unwrap(), b"data");
+    }
+}
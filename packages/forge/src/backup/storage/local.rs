@@ -0,0 +1,142 @@
+//! Local directory backend (e.g. a mounted USB drive)
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use std::path::{Path, PathBuf};
+
+use super::{check_name, list_dir, write_atomic, StorageBackend, StoredFile};
+use crate::backup::format_size;
+
+/// Archives in a plain directory
+pub struct LocalStorage {
+    path: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+}
+
+impl StorageBackend for LocalStorage {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn location(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn open<'a>(&'a self, _refresh: bool) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            // Only create the backup dir itself: a missing parent usually
+            // means the drive isn't mounted
+            let parent = self.path.parent().unwrap_or(&self.path);
+            if !parent.is_dir() {
+                anyhow::bail!("{} not found (is the drive mounted?)", parent.display());
+            }
+            std::fs::create_dir_all(&self.path)
+                .with_context(|| format!("Failed to create {}", self.path.display()))
+        })
+    }
+
+    fn list<'a>(&'a self) -> BoxFuture<'a, Result<Vec<StoredFile>>> {
+        Box::pin(async move { list_dir(&self.path) })
+    }
+
+    fn read<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Vec<u8>>> {
+        Box::pin(async move {
+            check_name(name)?;
+            let path = self.path.join(name);
+            std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))
+        })
+    }
+
+    fn write<'a>(&'a self, name: &'a str, data: &'a [u8]) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            check_name(name)?;
+            write_atomic(&self.path.join(name), data)
+        })
+    }
+
+    fn remove<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            check_name(name)?;
+            let path = self.path.join(name);
+            if path.exists() {
+                std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+            Ok(())
+        })
+    }
+
+    fn save<'a>(&'a self, _message: &'a str, _publish: bool) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            // Data must be on the drive before anyone unplugs it
+            let _ = crate::commands::executor::run_capture("sync", &[]).await;
+            Ok(format!("Saved to {}", self.path.display()))
+        })
+    }
+
+    fn status<'a>(&'a self) -> BoxFuture<'a, Result<Vec<String>>> {
+        Box::pin(async move {
+            let mut lines = vec![format!("Directory: {}", self.path.display())];
+            if !self.path.is_dir() {
+                lines.push("Not available (is the drive mounted?)".to_string());
+                return Ok(lines);
+            }
+
+            let files = list_dir(&self.path)?;
+            let total: u64 = files.iter().map(|f| f.size).sum();
+            lines.push(format!("{} archives, {}", files.len(), format_size(total)));
+
+            let newest = files
+                .iter()
+                .filter_map(|f| std::fs::metadata(self.path.join(&f.name)).ok()?.modified().ok())
+                .max();
+            if let Some(newest) = newest {
+                let time: chrono::DateTime<chrono::Local> = newest.into();
+                lines.push(format!("Last written {}", time.format("%Y-%m-%d %H:%M")));
+            }
+            Ok(lines)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::test_support::ScratchDir;
+
+    #[tokio::test]
+    async fn test_local_roundtrip() {
+        let scratch = ScratchDir::new("storage-local");
+        let storage = LocalStorage::new(&scratch.path().join("backup"));
+        storage.open(true).await.unwrap();
+
+        storage.write("chrome-profile.tar.zst.age", b"archive").await.unwrap();
+        storage.write("keys.tar.gz.age", b"keys").await.unwrap();
+        scratch.write("backup/notes.txt", "not an archive");
+
+        let names: Vec<String> = storage.list().await.unwrap().into_iter().map(|f| f.name).collect();
+        assert_eq!(names, vec!["chrome-profile.tar.zst.age", "keys.tar.gz.age"]);
+        assert_eq!(storage.read("keys.tar.gz.age").await.unwrap(), b"keys");
+
+        storage.remove("keys.tar.gz.age").await.unwrap();
+        assert_eq!(storage.list().await.unwrap().len(), 1);
+        assert!(storage.read("../notes.txt").await.is_err());
+
+        let status = storage.status().await.unwrap();
+        assert!(status.iter().any(|l| l == "1 archives, 7 B"));
+    }
+
+    #[tokio::test]
+    async fn test_local_requires_mounted_parent() {
+        let scratch = ScratchDir::new("storage-unmounted");
+        let storage = LocalStorage::new(&scratch.path().join("usb/backup"));
+        let err = storage.open(false).await.unwrap_err();
+        assert!(err.to_string().contains("is the drive mounted?"));
+    }
+}
@@ -0,0 +1,256 @@
+//! Backup storage backends
+//!
+//! Encrypted archives (app profiles and keys) can live in a git repository
+//! (the default), a local directory such as a mounted USB drive, an
+//! rsync/SSH target, or an S3-compatible bucket. The backend is chosen with
+//! `STORAGE_BACKEND` in the backup config.
+
+mod git;
+mod local;
+mod rsync;
+pub(crate) mod s3;
+
+pub use git::GitStorage;
+pub use local::LocalStorage;
+pub use rsync::RsyncStorage;
+pub use s3::{S3Config, S3Storage};
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use std::path::{Path, PathBuf};
+
+use super::repo::BackupRepo;
+use crate::system::config::BrowserBackupConfig;
+
+/// An archive stored in a backend
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredFile {
+    pub name: String,
+    pub size: u64,
+}
+
+/// Somewhere encrypted backup archives can be kept
+pub trait StorageBackend: Send + Sync {
+    /// Short name used in messages (`git`, `local`, ...)
+    fn name(&self) -> &'static str;
+
+    /// Where the archives go, for messages
+    fn location(&self) -> String;
+
+    /// Get ready to read or write; `refresh` fetches the latest state first
+    fn open<'a>(&'a self, refresh: bool) -> BoxFuture<'a, Result<()>>;
+
+    /// Archives currently stored
+    fn list<'a>(&'a self) -> BoxFuture<'a, Result<Vec<StoredFile>>>;
+
+    /// Contents of an archive
+    fn read<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Vec<u8>>>;
+
+    /// Store an archive, replacing any previous version
+    fn write<'a>(&'a self, name: &'a str, data: &'a [u8]) -> BoxFuture<'a, Result<()>>;
+
+    /// Delete an archive if it exists
+    fn remove<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<()>>;
+
+    /// Record the archives written since `open` as one backup
+    ///
+    /// `publish` also uploads them for backends that stage locally. Returns
+    /// a line describing what happened.
+    fn save<'a>(&'a self, message: &'a str, publish: bool) -> BoxFuture<'a, Result<String>>;
+
+    /// Backend-specific status lines for `forge apps status`
+    fn status<'a>(&'a self) -> BoxFuture<'a, Result<Vec<String>>>;
+
+    /// Git history of the backups, for backends that keep one
+    fn history(&self) -> Option<&BackupRepo> {
+        None
+    }
+}
+
+/// Backend selection resolved from the backup config
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageConfig {
+    /// Git repository, cloned to `local_repo` (the default)
+    Git { url: String, local_repo: PathBuf },
+    /// Plain directory, e.g. on a mounted USB drive
+    Local { path: PathBuf },
+    /// rsync over SSH, staged in `staging`
+    Rsync { target: String, staging: PathBuf },
+    /// S3-compatible bucket
+    S3(S3Config),
+}
+
+impl StorageConfig {
+    /// Resolve the `STORAGE_*` settings of a parsed backup config
+    pub fn from_config(config: &BrowserBackupConfig, home: &Path) -> Result<Self> {
+        let local_repo = super::resolve_home(&config.local_repo_path, home);
+        let required = |value: &Option<String>, key: &str| -> Result<String> {
+            value
+                .clone()
+                .filter(|v| !v.trim().is_empty())
+                .with_context(|| format!("{} not set in backup config (needed by STORAGE_BACKEND={})", key, config.storage_backend))
+        };
+
+        Ok(match config.storage_backend.as_str() {
+            "" | "git" => {
+                if config.repo.is_empty() {
+                    anyhow::bail!("APP_BACKUP_REPO not set in backup config");
+                }
+                StorageConfig::Git {
+                    url: config.repo.clone(),
+                    local_repo,
+                }
+            }
+            "local" => StorageConfig::Local {
+                path: super::resolve_home(&required(&config.storage_path, "STORAGE_PATH")?, home),
+            },
+            "rsync" => StorageConfig::Rsync {
+                target: required(&config.rsync_target, "RSYNC_TARGET")?,
+                staging: local_repo,
+            },
+            "s3" => StorageConfig::S3(S3Config {
+                endpoint: required(&config.s3_endpoint, "S3_ENDPOINT")?.trim_end_matches('/').to_string(),
+                bucket: required(&config.s3_bucket, "S3_BUCKET")?,
+                prefix: config.s3_prefix.clone().unwrap_or_default(),
+                region: config.s3_region.clone().unwrap_or_else(|| "us-east-1".to_string()),
+                access_key_id: config
+                    .s3_access_key_id
+                    .clone()
+                    .or_else(|| std::env::var("AWS_ACCESS_KEY_ID").ok())
+                    .unwrap_or_default(),
+                secret_access_key: config
+                    .s3_secret_access_key
+                    .clone()
+                    .or_else(|| std::env::var("AWS_SECRET_ACCESS_KEY").ok())
+                    .unwrap_or_default(),
+            }),
+            other => anyhow::bail!("Unknown STORAGE_BACKEND '{}' (expected git, local, rsync or s3)", other),
+        })
+    }
+
+    /// Create the backend described by this config
+    pub fn build(&self) -> Result<Box<dyn StorageBackend>> {
+        Ok(match self {
+            StorageConfig::Git { url, local_repo } => Box::new(GitStorage::new(url, local_repo)),
+            StorageConfig::Local { path } => Box::new(LocalStorage::new(path)),
+            StorageConfig::Rsync { target, staging } => Box::new(RsyncStorage::new(target, staging)),
+            StorageConfig::S3(config) => Box::new(S3Storage::new(config.clone())?),
+        })
+    }
+}
+
+/// Whether a name is a plain file name (no directories, no traversal)
+fn is_plain_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains('/')
+}
+
+/// Reject archive names that would escape the storage location
+fn check_name(name: &str) -> Result<()> {
+    if !is_plain_name(name) {
+        anyhow::bail!("Invalid archive name '{}'", name);
+    }
+    Ok(())
+}
+
+/// Encrypted archives in a directory, sorted by name
+fn list_dir(dir: &Path) -> Result<Vec<StoredFile>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", dir.display())),
+    };
+
+    let mut files: Vec<StoredFile> = entries
+        .flatten()
+        .filter(|e| e.path().is_file())
+        .map(|e| StoredFile {
+            name: e.file_name().to_string_lossy().to_string(),
+            size: e.metadata().map(|m| m.len()).unwrap_or(0),
+        })
+        .filter(|f| f.name.ends_with(".age"))
+        .collect();
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+/// Write a file via a temporary name so readers never see half an archive
+fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    write_atomic_via(&path.with_extension("tmp"), path, data)
+}
+
+/// Like [`write_atomic`], with the temporary file at `tmp` (same filesystem as `path`)
+fn write_atomic_via(tmp: &Path, path: &Path, data: &[u8]) -> Result<()> {
+    std::fs::write(tmp, data).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(tmp, path).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(backend: &str) -> BrowserBackupConfig {
+        BrowserBackupConfig {
+            repo: "git@example.com:me/settings.git".to_string(),
+            local_repo_path: "~/.local/share/app-backup".to_string(),
+            storage_backend: backend.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_git_is_the_default() {
+        let storage = StorageConfig::from_config(&config(""), Path::new("/home/me")).unwrap();
+        assert_eq!(
+            storage,
+            StorageConfig::Git {
+                url: "git@example.com:me/settings.git".to_string(),
+                local_repo: PathBuf::from("/home/me/.local/share/app-backup"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_backend_settings_are_required() {
+        let err = StorageConfig::from_config(&config("local"), Path::new("/home/me")).unwrap_err();
+        assert!(err.to_string().contains("STORAGE_PATH not set"));
+
+        let config = BrowserBackupConfig {
+            storage_path: Some("~/usb/backup".to_string()),
+            ..config("local")
+        };
+        assert_eq!(
+            StorageConfig::from_config(&config, Path::new("/home/me")).unwrap(),
+            StorageConfig::Local {
+                path: PathBuf::from("/home/me/usb/backup")
+            }
+        );
+
+        let err = StorageConfig::from_config(&self::config("dropbox"), Path::new("/home/me")).unwrap_err();
+        assert!(err.to_string().contains("Unknown STORAGE_BACKEND 'dropbox'"));
+    }
+
+    #[test]
+    fn test_s3_settings() {
+        let config = BrowserBackupConfig {
+            s3_endpoint: Some("http://nas:9000/".to_string()),
+            s3_bucket: Some("backups".to_string()),
+            s3_access_key_id: Some("key".to_string()),
+            s3_secret_access_key: Some("secret".to_string()),
+            ..config("s3")
+        };
+        let StorageConfig::S3(s3) = StorageConfig::from_config(&config, Path::new("/home/me")).unwrap() else {
+            panic!("expected s3 storage");
+        };
+        assert_eq!(s3.endpoint, "http://nas:9000");
+        assert_eq!(s3.region, "us-east-1");
+        assert_eq!(s3.prefix, "");
+    }
+
+    #[test]
+    fn test_plain_names() {
+        assert!(is_plain_name("chrome-profile.tar.zst.age"));
+        assert!(!is_plain_name("../keys.tar.gz.age"));
+        assert!(!is_plain_name("a/b"));
+        assert!(!is_plain_name(""));
+    }
+}
@@ -0,0 +1,206 @@
+//! rsync/SSH backend
+//!
+//! Archives are staged in a local directory and mirrored to the target
+//! (`user@host:/path` or any other rsync destination) with rsync.
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use std::path::{Path, PathBuf};
+
+use super::{check_name, list_dir, write_atomic, StorageBackend, StoredFile};
+use crate::commands::executor::run_capture;
+
+/// Only encrypted archives are ever transferred
+const FILTER_ARGS: [&str; 2] = ["--include=*.age", "--exclude=*"];
+
+/// Archives mirrored to an rsync target
+pub struct RsyncStorage {
+    target: String,
+    staging: PathBuf,
+}
+
+impl RsyncStorage {
+    pub fn new(target: &str, staging: &Path) -> Self {
+        Self {
+            target: target.trim_end_matches('/').to_string(),
+            staging: staging.to_path_buf(),
+        }
+    }
+
+    /// Arguments copying the target's archives into the staging dir
+    fn pull_args(&self) -> Vec<String> {
+        let mut args = vec!["-a".to_string(), "--delete".to_string()];
+        args.extend(FILTER_ARGS.iter().map(|a| a.to_string()));
+        args.push(format!("{}/", self.target));
+        args.push(format!("{}/", self.staging.display()));
+        args
+    }
+
+    /// Arguments copying the staged archives to the target
+    fn push_args(&self) -> Vec<String> {
+        let mut args = vec!["-a".to_string(), "--mkpath".to_string()];
+        args.extend(FILTER_ARGS.iter().map(|a| a.to_string()));
+        args.push(format!("{}/", self.staging.display()));
+        args.push(format!("{}/", self.target));
+        args
+    }
+
+    async fn rsync(&self, args: &[String]) -> Result<(bool, String, String)> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run_capture("rsync", &args).await
+    }
+}
+
+impl StorageBackend for RsyncStorage {
+    fn name(&self) -> &'static str {
+        "rsync"
+    }
+
+    fn location(&self) -> String {
+        self.target.clone()
+    }
+
+    fn open<'a>(&'a self, refresh: bool) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            std::fs::create_dir_all(&self.staging)
+                .with_context(|| format!("Failed to create {}", self.staging.display()))?;
+            if !refresh {
+                return Ok(());
+            }
+
+            let (success, _, stderr) = self.rsync(&self.pull_args()).await?;
+            // A target that doesn't exist yet just has no backups
+            if !success && !stderr.contains("No such file or directory") {
+                anyhow::bail!("Failed to fetch backups from {}: {}", self.target, stderr.trim());
+            }
+            Ok(())
+        })
+    }
+
+    fn list<'a>(&'a self) -> BoxFuture<'a, Result<Vec<StoredFile>>> {
+        Box::pin(async move { list_dir(&self.staging) })
+    }
+
+    fn read<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Vec<u8>>> {
+        Box::pin(async move {
+            check_name(name)?;
+            let path = self.staging.join(name);
+            std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))
+        })
+    }
+
+    fn write<'a>(&'a self, name: &'a str, data: &'a [u8]) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            check_name(name)?;
+            write_atomic(&self.staging.join(name), data)
+        })
+    }
+
+    /// Only removes the staged copy: uploads never delete on the target
+    fn remove<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            check_name(name)?;
+            let path = self.staging.join(name);
+            if path.exists() {
+                std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+            Ok(())
+        })
+    }
+
+    fn save<'a>(&'a self, _message: &'a str, publish: bool) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            if !publish {
+                return Ok(format!("Staged in {}", self.staging.display()));
+            }
+            let (success, _, stderr) = self.rsync(&self.push_args()).await?;
+            if !success {
+                anyhow::bail!("Failed to upload backups to {}: {}", self.target, stderr.trim());
+            }
+            Ok(format!("Uploaded to {}", self.target))
+        })
+    }
+
+    fn status<'a>(&'a self) -> BoxFuture<'a, Result<Vec<String>>> {
+        Box::pin(async move {
+            let mut lines = vec![format!("Target: {}", self.target)];
+            let target = format!("{}/", self.target);
+            let (success, stdout, stderr) = run_capture("rsync", &["--list-only", &target]).await?;
+            if !success {
+                lines.push(format!("Unable to reach target: {}", stderr.trim()));
+                return Ok(lines);
+            }
+
+            let remote = parse_list_only(&stdout);
+            let staged = list_dir(&self.staging)?;
+            let differs = remote.len() != staged.len()
+                || remote.iter().any(|r| !staged.iter().any(|s| s.name == r.name && s.size == r.size));
+
+            lines.push(format!("{} archives on target", remote.len()));
+            lines.push(if differs {
+                "Target differs from the local copy. Run 'forge apps restore' to update".to_string()
+            } else {
+                "Local copy matches the target".to_string()
+            });
+            Ok(lines)
+        })
+    }
+}
+
+/// Parse `rsync --list-only` output into the archives it lists
+fn parse_list_only(output: &str) -> Vec<StoredFile> {
+    output
+        .lines()
+        .filter(|line| line.starts_with('-'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let size = fields.get(1)?.replace([',', '.'], "").parse().ok()?;
+            let name = fields.get(4..)?.join(" ");
+            name.ends_with(".age").then_some(StoredFile { name, size })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transfer_args() {
+        let storage = RsyncStorage::new("me@nas:/srv/backup/", Path::new("/home/me/.local/share/app-backup"));
+        assert_eq!(
+            storage.pull_args(),
+            vec![
+                "-a",
+                "--delete",
+                "--include=*.age",
+                "--exclude=*",
+                "me@nas:/srv/backup/",
+                "/home/me/.local/share/app-backup/",
+            ]
+        );
+        assert_eq!(storage.push_args()[1], "--mkpath");
+        assert_eq!(storage.push_args().last().unwrap(), "me@nas:/srv/backup/");
+    }
+
+    #[test]
+    fn test_parse_list_only() {
+        let output = "drwxr-xr-x          4,096 2024/05/01 14:32:00 .\n\
+                      -rw-r--r--      1,234,567 2024/05/01 14:32:00 chrome-profile.tar.zst.age\n\
+                      -rw-r--r--            512 2024/05/01 14:30:00 keys.tar.gz.age\n\
+                      -rw-r--r--             42 2024/05/01 14:30:00 README.md\n";
+        assert_eq!(
+            parse_list_only(output),
+            vec![
+                StoredFile {
+                    name: "chrome-profile.tar.zst.age".to_string(),
+                    size: 1_234_567
+                },
+                StoredFile {
+                    name: "keys.tar.gz.age".to_string(),
+                    size: 512
+                },
+            ]
+        );
+    }
+}
@@ -1,18 +1,23 @@
 //! Forge Background Update Checker
 //!
-//! A lightweight daemon that checks for updates and sends desktop notifications.
+//! A lightweight daemon that checks for updates (and overdue app backups)
+//! and sends notifications
+//! (desktop, webhook, ntfy or journal, as configured).
 //! Designed to run as a systemd user service.
 //!
 //! Usage:
-//!   forge-notify [--once]     Run check once and exit
-//!   forge-notify --help       Show help
+//!   forge-notify [--once]            Run check once and exit
+//!   forge-notify --config <FILE>     Use an alternative notify.toml
+//!   forge-notify --help              Show help
 
 use anyhow::Result;
+use chrono::{Local, Utc};
 use clap::Parser;
 use forge::notify;
-use forge::notify::constants::NOTIFICATION_TIMEOUT_MS;
-use forge::notify::paths::{forge_data_dir, FORGE_LOG_FILE};
-use notify_rust::{Notification, Urgency};
+use forge::notify::config::NotifyConfig;
+use forge::notify::paths::{forge_data_dir, notify_config_path, FORGE_LOG_FILE};
+use forge::notify::sinks::{self, Message};
+use std::path::{Path, PathBuf};
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
 /// Forge Background Update Checker
@@ -25,6 +30,14 @@ struct Cli {
     /// Show verbose output
     #[arg(short, long)]
     verbose: bool,
+
+    /// Run a single check and exit (the default; kept for the systemd unit)
+    #[arg(long)]
+    once: bool,
+
+    /// Path to the config file (default: ~/.config/forge/notify.toml)
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
 }
 
 #[tokio::main]
@@ -36,8 +49,11 @@ async fn main() -> Result<()> {
 
     tracing::info!("forge-notify starting");
 
+    let config_path = cli.config.unwrap_or_else(notify_config_path);
+    let config = load_config(&config_path);
+
     // Run the update check
-    match run_check().await {
+    match run_check(&config).await {
         Ok(notified) => {
             if notified {
                 tracing::info!("Notification sent");
@@ -56,28 +72,75 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Load and validate the config, falling back to defaults on any error
+fn load_config(path: &Path) -> NotifyConfig {
+    let config = match NotifyConfig::load(path) {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!("Failed to load config: {:#}", e);
+            tracing::warn!("Using default settings");
+            return NotifyConfig::default();
+        }
+    };
+
+    let errors = config.validate();
+    if errors.is_empty() {
+        tracing::debug!("Loaded config from {}", path.display());
+        return config;
+    }
+
+    for error in &errors {
+        tracing::error!("Config error in {}: {}", path.display(), error);
+    }
+    tracing::warn!("Using default settings");
+    NotifyConfig::default()
+}
+
 /// Run the update check and send notification if needed
-async fn run_check() -> Result<bool> {
+async fn run_check(config: &NotifyConfig) -> Result<bool> {
     // Load existing state
     let mut state = notify::state::NotifyState::load().unwrap_or_default();
 
+    // Respect the configured check interval (the timer may fire more often)
+    let now = Utc::now();
+    if !config.check_due(state.last_check, now) {
+        tracing::info!("Last check was less than {} minutes ago, skipping", config.check_interval_mins);
+        return Ok(false);
+    }
+
     // Check for all updates
-    let status = notify::check_all_updates().await?;
+    let mut status = notify::check_all_updates(config).await?;
+    state.last_check = Some(now);
+
+    if config.checks.config.ignore_other_hosts {
+        status.retain_this_host();
+    }
+    state.clear_resolved(&status);
 
     tracing::debug!(
-        "Check results: config={}, apps={}, flake={}",
+        "Check results: config={}, apps={}, flake={}, stale_backup={}",
         status.config_updates.len(),
         status.app_updates,
-        status.flake_updates.len()
+        status.flake_updates.len(),
+        status.stale_backup.is_some()
     );
 
     // Check if we should notify
     if !state.should_notify(&status) {
+        state.save()?;
+        return Ok(false);
+    }
+
+    // Hold back during quiet hours; the next check outside the window notifies
+    if config.in_quiet_hours(Local::now().time()) {
+        tracing::info!("Updates available but inside quiet hours, not notifying");
+        state.save()?;
         return Ok(false);
     }
 
     // Send notification
-    send_notification(&status)?;
+    let message = Message::from_status(&status, config);
+    sinks::send_all(&sinks::build_sinks(config), &message).await?;
 
     // Update state
     state.mark_notified(&status);
@@ -86,21 +149,6 @@ async fn run_check() -> Result<bool> {
     Ok(true)
 }
 
-/// Send a desktop notification
-fn send_notification(status: &notify::UpdateStatus) -> Result<()> {
-    let summary = status.summary();
-
-    Notification::new()
-        .summary("Forge Updates Available")
-        .body(&format!("{}\n\nRun 'forge update' to apply.", summary))
-        .icon("software-update-available")
-        .urgency(Urgency::Normal)
-        .timeout(NOTIFICATION_TIMEOUT_MS)
-        .show()?;
-
-    Ok(())
-}
-
 /// Set up logging to file
 fn setup_logging(verbose: bool) -> Result<()> {
     let log_dir = forge_data_dir();
@@ -4,55 +4,138 @@ use anyhow::Result;
 use std::time::Duration;
 use tokio::sync::mpsc;
 
-use super::executor::run_capture;
 use super::runner::{spawn_with_error_handling, CommandRunner};
 use super::CommandMessage;
+use crate::backup::history::{self, Snapshot};
+use crate::backup::{engine, format_size, profiles, schedule, BackupSettings};
+use forge::notify::backups::BackupRecord;
 use forge::notify::checks;
 
 /// Start app backup
-pub async fn start_backup(tx: mpsc::Sender<CommandMessage>, force: bool) -> Result<()> {
+pub async fn start_backup(tx: mpsc::Sender<CommandMessage>, force: bool, apps: Vec<String>) -> Result<()> {
     spawn_with_error_handling(tx, "App backup", "Backup", move |tx| async move {
         let runner = CommandRunner::new(&tx);
-        let args: Vec<&str> = if force {
-            vec!["--push", "--force"]
-        } else {
-            vec!["--push"]
-        };
-        runner
-            .run_simple_operation(
-                "App Profile Backup",
-                "app-backup",
-                &args,
-                "App profiles backed up successfully",
-                "Backup failed",
-            )
-            .await?;
+        runner.header("App Profile Backup").await;
+
+        let settings = BackupSettings::load()?;
+        let profiles = profiles::select(&apps)?;
+        let result = engine::backup(&runner, &settings, &profiles, engine::Options::new(force)).await;
+        schedule::record_result(&result);
+        result?;
+
+        runner.out("").await;
+        runner.out("  App profiles backed up successfully").await;
+        runner.footer().await;
+        runner.done(true).await?;
         Ok(())
     })
 }
 
 /// Start app restore
-pub async fn start_restore(tx: mpsc::Sender<CommandMessage>, force: bool) -> Result<()> {
+///
+/// `at` restores an older snapshot instead of the latest backup.
+pub async fn start_restore(
+    tx: mpsc::Sender<CommandMessage>,
+    force: bool,
+    apps: Vec<String>,
+    at: Option<String>,
+) -> Result<()> {
     spawn_with_error_handling(tx, "App restore", "Restore", move |tx| async move {
         let runner = CommandRunner::new(&tx);
-        let args: Vec<&str> = if force {
-            vec!["--pull", "--force"]
-        } else {
-            vec!["--pull"]
-        };
-        runner
-            .run_simple_operation(
-                "App Profile Restore",
-                "app-restore",
-                &args,
-                "App profiles restored successfully",
-                "Restore failed",
-            )
-            .await?;
+        runner.header("App Profile Restore").await;
+
+        let settings = BackupSettings::load()?;
+        let profiles = profiles::select(&apps)?;
+        let options = engine::Options::new(force);
+        engine::restore(&runner, &settings, &profiles, options, at.as_deref()).await?;
+
+        runner.out("").await;
+        runner.out("  App profiles restored successfully").await;
+        runner.out("  Restart the apps to pick up the restored sessions").await;
+        runner.footer().await;
+        runner.done(true).await?;
+        Ok(())
+    })
+}
+
+/// Start undoing the most recent app restore
+pub async fn start_undo(tx: mpsc::Sender<CommandMessage>, force: bool) -> Result<()> {
+    spawn_with_error_handling(tx, "Undo restore", "Undo", move |tx| async move {
+        let runner = CommandRunner::new(&tx);
+        runner.header("Undo App Profile Restore").await;
+
+        let settings = BackupSettings::load()?;
+        engine::undo_restore(&runner, &settings, engine::Options::new(force)).await?;
+
+        runner.out("").await;
+        runner.out("  Previous app profiles put back").await;
+        runner.out("  Restart the apps to pick up the previous sessions").await;
+        runner.footer().await;
+        runner.done(true).await?;
+        Ok(())
+    })
+}
+
+/// Start installing or removing the backup timer
+///
+/// `every_hours` of None removes the schedule.
+pub async fn start_schedule(tx: mpsc::Sender<CommandMessage>, every_hours: Option<u64>) -> Result<()> {
+    spawn_with_error_handling(tx, "Backup schedule", "Schedule", move |tx| async move {
+        let runner = CommandRunner::new(&tx);
+        runner.header("App Backup Schedule").await;
+
+        let home = dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))?;
+        match every_hours {
+            Some(hours) => {
+                schedule::enable(&runner, &home, hours).await?;
+                runner.out("").await;
+                runner
+                    .out(&format!(
+                        "  App profiles are backed up every {} hours, when no app is open and on mains power",
+                        hours
+                    ))
+                    .await;
+            }
+            None => {
+                schedule::disable(&runner, &home).await?;
+                runner.out("").await;
+                runner.out("  Scheduled backups turned off").await;
+            }
+        }
+        runner.footer().await;
+        runner.done(true).await?;
         Ok(())
     })
 }
 
+/// Number of snapshots shown in the history picker
+const HISTORY_LIMIT: usize = 50;
+
+/// Load backup snapshots for the history picker
+pub async fn start_history(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
+    tokio::spawn(async move {
+        let result = load_history().await.map_err(|e| format!("{:#}", e));
+        let _ = tx.send(CommandMessage::AppSnapshots(result)).await;
+    });
+    Ok(())
+}
+
+async fn load_history() -> Result<Vec<Snapshot>> {
+    let settings = BackupSettings::load()?;
+    let storage = settings.storage.build()?;
+
+    // Offline is fine as long as there is a local clone to read
+    if let Err(e) = storage.open(true).await {
+        tracing::warn!("Could not update backup storage: {:#}", e);
+        storage.open(false).await?;
+    }
+
+    let Some(repo) = storage.history() else {
+        anyhow::bail!("Backup history is only kept by the git storage backend");
+    };
+    history::list(repo, HISTORY_LIMIT).await
+}
+
 /// Start app status check
 pub async fn start_status(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
     tokio::spawn(async move {
@@ -97,181 +180,49 @@ async fn run_status(tx: &mpsc::Sender<CommandMessage>) -> Result<()> {
     let runner = CommandRunner::new(tx);
     runner.header("App Profile Status").await;
 
-    let local_repo = crate::constants::app_backup_data_dir();
-
-    if !local_repo.join(".git").exists() {
-        runner.out("  Local repository not found.").await;
-        runner.out("  Run 'forge apps restore' to clone.").await;
-        runner.footer().await;
-        return Ok(());
-    }
-
-    // Check if remote is configured
-    let (remote_ok, _, _) = run_capture(
-        "git",
-        &[
-            "-C",
-            local_repo.to_str().unwrap_or("."),
-            "remote",
-            "get-url",
-            "origin",
-        ],
-    )
-    .await?;
-
-    if !remote_ok {
-        runner.out("  No remote 'origin' configured").await;
-        runner.out("").await;
-        list_local_files(&runner, &local_repo).await;
-        runner.footer().await;
-        return Ok(());
-    }
-
-    // Fetch from remote (quietly)
-    runner.out("  Checking for updates...").await;
-    let (fetch_ok, _, _) = run_capture(
-        "git",
-        &["-C", local_repo.to_str().unwrap_or("."), "fetch", "origin"],
-    )
-    .await?;
-
-    if !fetch_ok {
-        runner.out("  Unable to reach remote; showing local status only").await;
-        runner.out("").await;
-        list_local_files(&runner, &local_repo).await;
-        runner.footer().await;
-        return Ok(());
-    }
-
-    // Compare heads
-    let (_, local_head, _) = run_capture(
-        "git",
-        &["-C", local_repo.to_str().unwrap_or("."), "rev-parse", "HEAD"],
-    )
-    .await?;
-
-    let (remote_ok, remote_head, _) = run_capture(
-        "git",
-        &[
-            "-C",
-            local_repo.to_str().unwrap_or("."),
-            "rev-parse",
-            "origin/main",
-        ],
-    )
-    .await?;
-
-    if !remote_ok {
-        // Try origin/master
-        let (master_ok, master_head, _) = run_capture(
-            "git",
-            &[
-                "-C",
-                local_repo.to_str().unwrap_or("."),
-                "rev-parse",
-                "origin/master",
-            ],
-        )
-        .await?;
-
-        if !master_ok {
-            runner.out("  Remote branch not found (origin/main or origin/master)").await;
-            runner.out("").await;
-            list_local_files(&runner, &local_repo).await;
+    let settings = match BackupSettings::load() {
+        Ok(settings) => settings,
+        Err(e) => {
+            runner.err(&format!("  {:#}", e)).await;
             runner.footer().await;
             return Ok(());
         }
-
-        // Use master head
-        check_and_show_status(&runner, &local_repo, &local_head, &master_head).await?;
-    } else {
-        check_and_show_status(&runner, &local_repo, &local_head, &remote_head).await?;
+    };
+    let storage = settings.storage.build()?;
+    runner.out(&format!("  Backend: {} ({})", storage.name(), storage.location())).await;
+    runner.out("  Checking for updates...").await;
+    runner.out("").await;
+    for line in storage.status().await? {
+        runner.out(&format!("  {}", line)).await;
     }
 
     runner.out("").await;
-    list_local_files(&runner, &local_repo).await;
-    runner.footer().await;
-
-    Ok(())
-}
-
-async fn check_and_show_status(
-    runner: &CommandRunner<'_>,
-    local_repo: &std::path::Path,
-    local_head: &str,
-    remote_head: &str,
-) -> Result<()> {
-    if local_head.trim() == remote_head.trim() {
-        runner.out("").await;
-        runner.out("  App profiles are up to date").await;
-    } else {
-        runner.out("").await;
-        runner.out("  Remote has newer profiles").await;
-        runner.out("").await;
-        runner.out("  Remote commits:").await;
-
-        // Get commit log
-        let (_, commits, _) = run_capture(
-            "git",
-            &[
-                "-C",
-                local_repo.to_str().unwrap_or("."),
-                "log",
-                "--oneline",
-                &format!("{}..{}", local_head.trim(), remote_head.trim()),
-            ],
-        )
-        .await?;
-
-        for line in commits.lines() {
-            runner.out(&format!("    {}", line)).await;
-        }
-
-        runner.out("").await;
-        runner.out("  Run 'forge apps restore' to update").await;
+    let record = BackupRecord::load().unwrap_or_default();
+    let last = record
+        .last_success
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "never".to_string());
+    runner.out(&format!("  Last backup: {}", last)).await;
+    if let Some(reason) = &record.last_skip {
+        runner.out(&format!("  Last scheduled run skipped: {}", reason)).await;
     }
-    Ok(())
-}
+    if let Some(error) = &record.last_error {
+        runner.err(&format!("  Last backup failed: {}", error)).await;
+    }
+    let home = dirs::home_dir().unwrap_or_default();
+    let scheduled = if schedule::is_enabled(&home) { "on" } else { "off (see 'forge apps schedule')" };
+    runner.out(&format!("  Scheduled backups: {}", scheduled)).await;
 
-async fn list_local_files(runner: &CommandRunner<'_>, local_repo: &std::path::Path) {
+    runner.out("").await;
     runner.out("  Local files:").await;
-
-    // Use find to list .age files
-    let (success, file_list, _) = run_capture(
-        "find",
-        &[
-            local_repo.to_str().unwrap_or("."),
-            "-maxdepth",
-            "1",
-            "-name",
-            "*.age",
-            "-exec",
-            "ls",
-            "-lh",
-            "{}",
-            ";",
-        ],
-    )
-    .await
-    .unwrap_or((false, String::new(), String::new()));
-
-    if success && !file_list.trim().is_empty() {
-        for line in file_list.lines() {
-            // Extract just filename and size from ls output
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 9 {
-                let size = parts[4];
-                let filename = parts.last().unwrap_or(&"");
-                // Get just the filename without path
-                let name = std::path::Path::new(filename)
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or(filename);
-                runner.out(&format!("    {} ({})", name, size)).await;
-            }
-        }
-    } else {
+    let files = storage.list().await.unwrap_or_default();
+    if files.is_empty() {
         runner.out("    (no backup files)").await;
     }
+    for file in files {
+        runner.out(&format!("    {} ({})", file.name, format_size(file.size))).await;
+    }
     runner.out("").await;
+    runner.footer().await;
+    Ok(())
 }
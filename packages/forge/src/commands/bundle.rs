@@ -0,0 +1,341 @@
+//! Offline install bundle creation
+//!
+//! `forge bundle create <host>` runs on a machine with network and prints its
+//! progress to the terminal. It builds the host's system, copies the closure
+//! (plus disko, git, forge and the flake inputs) into a signed binary cache and
+//! stores the configuration as a git bundle; see [`crate::system::bundle`].
+//!
+//! The install changes the configuration (disk device, LUKS password file,
+//! GPU, users, locale), so the system it builds is never exactly the bundled
+//! one. The cache therefore also holds the build closure of the system and
+//! of its disko script: every derivation plus every output it depends on, so
+//! whichever derivations those choices change can be rebuilt on the ISO.
+
+use anyhow::{Context, Result};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+
+use super::install::run_git;
+use crate::constants::HOSTS_SUBDIR;
+use crate::system::bundle::{self, Manifest, BUNDLE_VERSION, CACHE_DIR, LAUNCHER, MANIFEST, REPO_BUNDLE};
+use crate::system::repo::{self, RepoSource};
+
+/// Nix config for building the bundle
+const NIX_CONFIG_VALUE: &str = "experimental-features = nix-command flakes";
+/// Derivations realised per nix-store call (keeps the argument list short)
+const REALISE_CHUNK: usize = 500;
+
+/// Create the bundle for `host` in `output`, returning the bundle directory
+pub async fn create(host: &str, source: &RepoSource, output: &Path, tarball: bool) -> Result<PathBuf> {
+    if host.is_empty() || !host.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        anyhow::bail!("Invalid host name '{}'", host);
+    }
+    let name = bundle::dir_name(host);
+    let dir = output.join(&name);
+    if dir.exists() {
+        anyhow::bail!("{} already exists; remove it or choose another --output", dir.display());
+    }
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let dir = std::fs::canonicalize(&dir)?;
+
+    let work = std::env::temp_dir().join(format!("forge-bundle-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&work);
+    std::fs::create_dir_all(&work).with_context(|| format!("Failed to create {}", work.display()))?;
+    std::fs::set_permissions(&work, std::fs::Permissions::from_mode(0o700))?;
+
+    let result = fill(host, source, &dir, &work).await;
+    // The work directory holds the cache's signing key
+    if let Err(e) = std::fs::remove_dir_all(&work) {
+        tracing::warn!("Failed to remove {}: {}", work.display(), e);
+    }
+    if let Err(e) = result {
+        let _ = std::fs::remove_dir_all(&dir);
+        return Err(e);
+    }
+
+    if tarball {
+        let archive = output.join(format!("{}.tar", name));
+        println!("Writing {}...", archive.display());
+        write_tarball(&dir, &name, &archive)?;
+    }
+
+    println!();
+    println!("Bundle ready: {}", dir.display());
+    println!("Copy it to the install medium and run on the ISO:");
+    println!("  sh {}", dir.join(LAUNCHER).display());
+    Ok(dir)
+}
+
+async fn fill(host: &str, source: &RepoSource, dir: &Path, work: &Path) -> Result<()> {
+    // Configuration repository
+    let repo_dir = work.join("nixos-config");
+    let repo_str = repo_dir.to_string_lossy().to_string();
+    println!("Cloning {}...", source);
+    let (success, _, stderr) = run_git(&source.clone_script(&repo_str)).await?;
+    if !success {
+        anyhow::bail!("Failed to clone {}: {}", source, stderr.trim());
+    }
+    let (_, check, _) = run_git(&source.check_script(&repo_str)).await?;
+    let commit = source.verify(&check, true).map_err(anyhow::Error::msg)?;
+    println!("  Commit: {}", commit);
+
+    if !repo_dir.join(HOSTS_SUBDIR).join(host).join("default.nix").exists() {
+        anyhow::bail!("Host '{}' not found in {}", host, source);
+    }
+
+    let repo_bundle = dir.join(REPO_BUNDLE);
+    println!("Writing {}...", REPO_BUNDLE);
+    let (success, _, stderr) = run_git(&format!(
+        "git -C '{}' bundle create '{}' HEAD --branches --tags",
+        repo_str,
+        repo_bundle.display()
+    ))
+    .await?;
+    if !success {
+        anyhow::bail!("git bundle failed: {}", stderr.trim());
+    }
+
+    // Signing key for the binary cache
+    let key_name = format!("forge-bundle-{}", host);
+    let secret_key = nix_key(&["generate-secret", "--key-name", &key_name], None)
+        .await
+        .context("Failed to generate a signing key")?;
+    let public_key = nix_key(&["convert-secret-to-public"], Some(&secret_key))
+        .await
+        .context("Failed to derive the public key")?;
+    let key_file = work.join("secret-key");
+    std::fs::write(&key_file, secret_key.trim())?;
+    std::fs::set_permissions(&key_file, std::fs::Permissions::from_mode(0o600))?;
+
+    // Everything nixos-install needs from a substituter
+    println!("Building {} (this may take a while)...", host);
+    let build = format!("{}#nixosConfigurations.{}.config.system.build", repo_str, host);
+    let toplevel = format!("{}.toplevel", build);
+    let disko_script = format!("{}.diskoScript", build);
+    let system = nix_build(&[&toplevel]).await?;
+    println!("  System: {}", system);
+    nix_build(&[&disko_script]).await?;
+    let disko = nix_build(&[&format!("{}#disko", repo_str)]).await?;
+    let git = nix_build(&["--inputs-from", &repo_str, "nixpkgs#git^out"]).await?;
+    let forge = nix_build(&[&format!("{}#forge", repo_str)]).await?;
+
+    // Build closure, so install-time changes can be rebuilt offline
+    println!("Fetching the build closure (this is much larger than the system)...");
+    let roots = nix(&["path-info", "--derivation", &toplevel, &disko_script]).await?;
+    let roots = store_paths(&roots);
+    let drv_closure = nix_store(&[&["-qR"], roots.as_slice()].concat()).await?;
+    for chunk in derivations(&drv_closure).chunks(REALISE_CHUNK) {
+        nix_store(&[&["--realise"], chunk].concat()).await?;
+    }
+    let closure = nix_store(&[&["-qR", "--include-outputs"], roots.as_slice()].concat()).await?;
+
+    let cache = dir.join(CACHE_DIR);
+    let cache_url = format!("file://{}?secret-key={}&compression=zstd", cache.display(), key_file.display());
+    println!("Copying the closure to {}...", cache.display());
+    nix(&["copy", "--to", &cache_url, &system, &disko, &git, &forge]).await?;
+    println!("Copying the build closure...");
+    let paths = store_paths(&closure).join("\n");
+    nix_with_input(&["copy", "--derivation", "--stdin", "--to", &cache_url], &paths).await?;
+    println!("Copying the flake inputs...");
+    nix(&["flake", "archive", "--to", &cache_url, &repo_str]).await?;
+
+    let manifest = Manifest {
+        version: BUNDLE_VERSION,
+        host: host.to_string(),
+        repository: source.url.clone(),
+        git_ref: source.git_ref.clone(),
+        commit,
+        system,
+        git,
+        forge,
+        public_key: public_key.trim().to_string(),
+        created: chrono::Local::now().to_rfc3339(),
+    };
+    std::fs::write(dir.join(MANIFEST), serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("Failed to write {}", MANIFEST))?;
+    let launcher = dir.join(LAUNCHER);
+    std::fs::write(&launcher, bundle::launcher_script(&manifest))
+        .with_context(|| format!("Failed to write {}", LAUNCHER))?;
+    std::fs::set_permissions(&launcher, std::fs::Permissions::from_mode(0o755))?;
+    println!("  Source: {} ({})", source, repo::short(&manifest.commit));
+    Ok(())
+}
+
+/// nix with flakes enabled for this child only
+fn nix_command() -> Command {
+    let mut cmd = Command::new("nix");
+    cmd.env("NIX_CONFIG", NIX_CONFIG_VALUE);
+    cmd
+}
+
+/// Run nix with its progress on the terminal, returning stdout
+async fn nix(args: &[&str]) -> Result<String> {
+    tracing::info!("Running: nix {:?}", args);
+    let output = nix_command()
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .output()
+        .await
+        .context("Failed to execute nix")?;
+    if !output.status.success() {
+        anyhow::bail!("nix {} failed", args.first().copied().unwrap_or_default());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Like [`nix`], with `input` on stdin
+async fn nix_with_input(args: &[&str], input: &str) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+    tracing::info!("Running: nix {:?}", args);
+    let mut child = nix_command()
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .context("Failed to execute nix")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes()).await?;
+    }
+    if !child.wait().await?.success() {
+        anyhow::bail!("nix {} failed", args.first().copied().unwrap_or_default());
+    }
+    Ok(())
+}
+
+/// Run nix-store with its progress on the terminal, returning stdout
+async fn nix_store(args: &[&str]) -> Result<String> {
+    // The path lists run into thousands; log the operation only
+    tracing::info!("Running: nix-store {}", args.first().copied().unwrap_or_default());
+    let output = Command::new("nix-store")
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .output()
+        .await
+        .context("Failed to execute nix-store")?;
+    if !output.status.success() {
+        anyhow::bail!("nix-store {} failed", args.first().copied().unwrap_or_default());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Store paths printed one per line
+fn store_paths(output: &str) -> Vec<&str> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("/nix/store/"))
+        .collect()
+}
+
+/// The derivations among printed store paths
+fn derivations(output: &str) -> Vec<&str> {
+    store_paths(output).into_iter().filter(|path| path.ends_with(".drv")).collect()
+}
+
+/// Run `nix key <args>` with `input` on stdin, returning stdout
+async fn nix_key(args: &[&str], input: Option<&str>) -> Result<String> {
+    use tokio::io::AsyncWriteExt;
+    let mut child = nix_command()
+        .arg("key")
+        .args(args)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to execute nix")?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input.as_bytes()).await?;
+    }
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Build `args` and return the (first) output path
+async fn nix_build(args: &[&str]) -> Result<String> {
+    let mut full = vec!["build", "--no-link", "--print-out-paths"];
+    full.extend_from_slice(args);
+    let stdout = nix(&full).await?;
+    store_paths(&stdout)
+        .first()
+        .map(|path| path.to_string())
+        .with_context(|| format!("nix build {} printed no output path", args.join(" ")))
+}
+
+/// Pack the bundle directory into an uncompressed tar (the NARs are compressed already)
+fn write_tarball(dir: &Path, name: &str, archive: &Path) -> Result<()> {
+    let file = std::fs::File::create(archive).with_context(|| format!("Failed to create {}", archive.display()))?;
+    let mut builder = tar::Builder::new(std::io::BufWriter::new(file));
+    builder
+        .append_dir_all(name, dir)
+        .with_context(|| format!("Failed to add {} to {}", dir.display(), archive.display()))?;
+    builder.into_inner()?.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closure_paths() {
+        let output = "/nix/store/aaaa-nixos-system-x1yoga.drv\n\
+                      /nix/store/bbbb-linux-6.12.tar.xz\n\
+                      \n\
+                      warning: Git tree is dirty\n\
+                      /nix/store/cccc-nvidia-x11-565.drv\n\
+                      /nix/store/dddd-gcc-13.3.0\n";
+        assert_eq!(
+            store_paths(output),
+            [
+                "/nix/store/aaaa-nixos-system-x1yoga.drv",
+                "/nix/store/bbbb-linux-6.12.tar.xz",
+                "/nix/store/cccc-nvidia-x11-565.drv",
+                "/nix/store/dddd-gcc-13.3.0"
+            ]
+        );
+        assert_eq!(
+            derivations(output),
+            ["/nix/store/aaaa-nixos-system-x1yoga.drv", "/nix/store/cccc-nvidia-x11-565.drv"]
+        );
+    }
+
+    #[test]
+    fn test_write_tarball() {
+        let tmp = std::env::temp_dir().join(format!("forge-bundle-tar-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let dir = tmp.join("forge-bundle-x1yoga");
+        std::fs::create_dir_all(dir.join(CACHE_DIR)).unwrap();
+        std::fs::write(dir.join(MANIFEST), "{}").unwrap();
+        std::fs::write(dir.join(CACHE_DIR).join("nix-cache-info"), "StoreDir: /nix/store\n").unwrap();
+
+        let archive = tmp.join("forge-bundle-x1yoga.tar");
+        write_tarball(&dir, "forge-bundle-x1yoga", &archive).unwrap();
+
+        let mut entries: Vec<String> = tar::Archive::new(std::fs::File::open(&archive).unwrap())
+            .entries()
+            .unwrap()
+            .map(|e| e.unwrap().path().unwrap().to_string_lossy().trim_end_matches('/').to_string())
+            .collect();
+        entries.sort();
+        assert_eq!(
+            entries,
+            [
+                "forge-bundle-x1yoga",
+                "forge-bundle-x1yoga/cache",
+                "forge-bundle-x1yoga/cache/nix-cache-info",
+                "forge-bundle-x1yoga/manifest.json"
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
@@ -180,7 +180,7 @@ async fn run_create_host(
     .await?;
 
     let disko_path = format!("{}/modules/disko/{}.nix", config_dir, config.hostname);
-    let disko_config = templates::generate_disko_config(&config.hostname, &config.disk.path);
+    let disko_config = templates::generate_disko_config(&config.hostname, &config.disk, &config.layout);
     fs::write(&disko_path, disko_config)
         .with_context(|| format!("Failed to write disko config: {}", disko_path))?;
 
@@ -35,6 +35,7 @@ pub enum ErrorCategory {
 /// Parsed error with user-friendly information
 #[derive(Debug, Clone)]
 pub struct ParsedError {
+    pub category: ErrorCategory,
     /// Short summary (one line)
     pub summary: String,
     /// Longer description if available
@@ -75,6 +76,22 @@ impl ParsedError {
         Self::generic(stderr, context)
     }
 
+    /// For network errors, name the likely cause found by probing the network
+    pub async fn with_network_diagnosis(mut self) -> Self {
+        if !matches!(self.category, ErrorCategory::Network { .. }) {
+            return self;
+        }
+        let report = crate::system::diagnostics::diagnose().await;
+        if let (Some(cause), Some(suggestion)) = (report.cause, report.suggestion) {
+            self.detail = Some(match self.detail {
+                Some(detail) => format!("{}\nLikely cause: {}", detail, cause),
+                None => format!("Likely cause: {}", cause),
+            });
+            self.suggestion = format!("{} Run 'forge doctor network' for the full report.", suggestion);
+        }
+        self
+    }
+
     fn generic(stderr: &str, context: ErrorContext) -> Self {
         // Try to extract the first meaningful error line
         let first_error = stderr
@@ -91,6 +108,7 @@ impl ParsedError {
             .to_string();
 
         Self {
+            category: ErrorCategory::Unknown,
             summary: format!("{} failed", context.operation),
             detail: if detail.is_empty() {
                 None
@@ -122,7 +140,7 @@ fn parse_github_api_error(stderr: &str) -> Option<ParsedError> {
             .map(|m| m.as_str().trim_end_matches(".git").to_string());
 
         let (summary, suggestion) = match code {
-            504 | 503 | 502 => (
+            502..=504 => (
                 format!("GitHub API timeout (HTTP {})", code),
                 "GitHub's API is temporarily unavailable. Try again in a few minutes.".to_string(),
             ),
@@ -145,6 +163,10 @@ fn parse_github_api_error(stderr: &str) -> Option<ParsedError> {
         };
 
         return Some(ParsedError {
+            category: ErrorCategory::GitHubApi {
+                code: Some(code),
+                input: input.clone(),
+            },
             summary,
             detail: input.map(|i| format!("Repository: {}", i)),
             suggestion,
@@ -185,6 +207,9 @@ fn parse_network_error(stderr: &str) -> Option<ParsedError> {
     for (pattern, summary, suggestion) in patterns {
         if stderr.to_lowercase().contains(&pattern.to_lowercase()) {
             return Some(ParsedError {
+                category: ErrorCategory::Network {
+                    detail: pattern.to_string(),
+                },
                 summary: summary.to_string(),
                 detail: None,
                 suggestion: suggestion.to_string(),
@@ -217,6 +242,7 @@ fn parse_nix_eval_error(stderr: &str) -> Option<ParsedError> {
         };
 
         return Some(ParsedError {
+            category: ErrorCategory::NixEval { file, line },
             summary: "Nix evaluation error".to_string(),
             detail: Some(detail),
             suggestion: "Check the Nix expression at the indicated location.".to_string(),
@@ -245,6 +271,9 @@ fn parse_nix_build_error(stderr: &str) -> Option<ParsedError> {
             });
 
         return Some(ParsedError {
+            category: ErrorCategory::NixBuild {
+                derivation: derivation.clone(),
+            },
             summary: "Nix build failed".to_string(),
             detail: derivation.map(|d| format!("Failed: {}", d)),
             suggestion: "Check the build output above for compiler or dependency errors.".to_string(),
@@ -287,6 +316,9 @@ fn parse_git_error(stderr: &str) -> Option<ParsedError> {
     for (pattern, summary, suggestion) in patterns {
         if stderr_lower.contains(pattern) {
             return Some(ParsedError {
+                category: ErrorCategory::Git {
+                    operation: summary.to_string(),
+                },
                 summary: summary.to_string(),
                 detail: None,
                 suggestion: suggestion.to_string(),
@@ -308,6 +340,7 @@ fn parse_permission_error(stderr: &str) -> Option<ParsedError> {
             .filter(|s| !s.is_empty());
 
         return Some(ParsedError {
+            category: ErrorCategory::Permission { path: path.clone() },
             summary: "Permission denied".to_string(),
             detail: path,
             suggestion: "Try running with sudo or check file ownership.".to_string(),
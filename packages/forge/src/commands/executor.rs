@@ -35,6 +35,7 @@ where
 }
 
 /// Execute a command with explicit timeout
+#[allow(dead_code)]
 pub async fn run_command_with_timeout(
     tx: &mpsc::Sender<CommandMessage>,
     cmd: &str,
@@ -44,23 +45,10 @@ pub async fn run_command_with_timeout(
     run_command_filtered_with_timeout(tx, cmd, args, timeout_secs, |_| true).await
 }
 
-/// Execute a command and transform/filter output lines
+/// Execute a command with timeout, transforming/filtering output lines
 /// The transform function returns Option<String>:
 /// - None: skip the line
 /// - Some(line): output the (possibly modified) line
-pub async fn run_command_transformed<F>(
-    tx: &mpsc::Sender<CommandMessage>,
-    cmd: &str,
-    args: &[&str],
-    transform: F,
-) -> Result<bool>
-where
-    F: Fn(&str) -> Option<String> + Send + Sync + 'static,
-{
-    run_command_transformed_with_timeout(tx, cmd, args, None, transform).await
-}
-
-/// Execute a command with transform and timeout
 pub async fn run_command_transformed_with_timeout<F>(
     tx: &mpsc::Sender<CommandMessage>,
     cmd: &str,
@@ -234,75 +222,6 @@ where
     Ok(success)
 }
 
-/// Execute a command without logging arguments (for sensitive data like passwords)
-pub async fn run_command_sensitive(
-    tx: &mpsc::Sender<CommandMessage>,
-    cmd: &str,
-    args: &[&str],
-) -> Result<bool> {
-    // Only log command name, not arguments (which may contain passwords)
-    tracing::info!("Running command: {} [args hidden]", cmd);
-
-    let mut child = Command::new(cmd)
-        .args(args)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .with_context(|| format!("Failed to spawn command: {}", cmd))?;
-
-    let stdout = child
-        .stdout
-        .take()
-        .ok_or_else(|| anyhow::anyhow!("Failed to capture stdout for command: {}", cmd))?;
-    let stderr = child
-        .stderr
-        .take()
-        .ok_or_else(|| anyhow::anyhow!("Failed to capture stderr for command: {}", cmd))?;
-
-    let tx_out = tx.clone();
-    let stdout_task = tokio::spawn(async move {
-        let mut reader = BufReader::new(stdout).lines();
-        while let Ok(Some(line)) = reader.next_line().await {
-            if let Err(e) = tx_out.send(CommandMessage::Stdout(line)).await {
-                tracing::warn!("Failed to send stdout to channel: {}", e);
-                break;
-            }
-        }
-    });
-
-    let tx_err = tx.clone();
-    let stderr_task = tokio::spawn(async move {
-        let mut reader = BufReader::new(stderr).lines();
-        while let Ok(Some(line)) = reader.next_line().await {
-            if let Err(e) = tx_err.send(CommandMessage::Stderr(line)).await {
-                tracing::warn!("Failed to send stderr to channel: {}", e);
-                break;
-            }
-        }
-    });
-
-    let timeout = Duration::from_secs(DEFAULT_COMMAND_TIMEOUT_SECS);
-    let status = tokio::time::timeout(timeout, child.wait())
-        .await
-        .with_context(|| format!("Command timed out after {}s: {}", timeout.as_secs(), cmd))?
-        .with_context(|| format!("Failed to wait for command: {}", cmd))?;
-
-    match tokio::time::timeout(Duration::from_secs(5), stdout_task).await {
-        Ok(Ok(())) => {}
-        Ok(Err(e)) => tracing::warn!("stdout reader task panicked: {}", e),
-        Err(_) => tracing::warn!("stdout reader task timed out for command: {}", cmd),
-    }
-    match tokio::time::timeout(Duration::from_secs(5), stderr_task).await {
-        Ok(Ok(())) => {}
-        Ok(Err(e)) => tracing::warn!("stderr reader task panicked: {}", e),
-        Err(_) => tracing::warn!("stderr reader task timed out for command: {}", cmd),
-    }
-
-    let success = status.success();
-    tracing::info!("Command completed with success={}", success);
-    Ok(success)
-}
-
 /// Execute a command with sudo
 #[allow(dead_code)]
 pub async fn run_sudo(
@@ -331,6 +250,47 @@ pub async fn run_capture(cmd: &str, args: &[&str]) -> Result<(bool, String, Stri
     Ok((output.status.success(), stdout, stderr))
 }
 
+/// Execute a command and capture output without logging its arguments
+pub async fn run_capture_sensitive(cmd: &str, args: &[&str]) -> Result<(bool, String, String)> {
+    tracing::info!("Capturing command: {} [args hidden]", cmd);
+
+    let output = Command::new(cmd)
+        .args(args)
+        .output()
+        .await
+        .with_context(|| format!("Failed to execute command: {}", cmd))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+
+    Ok((output.status.success(), stdout, stderr))
+}
+
+/// Execute a command with `input` on stdin and capture output
+///
+/// Keeps secrets off the command line, where other users could see them.
+pub async fn run_capture_with_input(cmd: &str, args: &[&str], input: &str) -> Result<(bool, String, String)> {
+    use tokio::io::AsyncWriteExt;
+    tracing::info!("Capturing command with input: {} {:?}", cmd, args);
+
+    let mut child = Command::new(cmd)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to execute command: {}", cmd))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes()).await?;
+    }
+    let output = child.wait_with_output().await?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+
+    Ok((output.status.success(), stdout, stderr))
+}
+
 /// Check if a command exists
 pub async fn command_exists(cmd: &str) -> bool {
     Command::new("which")
@@ -1,28 +1,43 @@
 //! Fresh NixOS installation command
 //!
 //! This module handles the complete NixOS installation process, broken down into steps:
-//! 1. Network check
+//! 1. Network check (or load the offline bundle)
 //! 2. Enable flakes
 //! 3. Clone/prepare configuration repository
-//! 4. Configure disk device
-//! 5. Run disko (partition and format)
+//! 4. Configure disk device (plus GPU, locale and user accounts)
+//! 5. Run disko (partition and format), then stage password hashes
 //! 6. Install NixOS
-//! 7. Set user password
+//! 7. Provision keys (optional)
+//! 8. Copy Wi-Fi connections (optional)
+//! 9. Set user passwords
+//! 10. Verify the installed system
 
 use anyhow::{Context, Result};
+use std::io::Write as _;
 use std::os::unix::fs::PermissionsExt;
 use std::sync::LazyLock;
 use tokio::sync::mpsc;
 
 use super::errors::{ErrorContext, ParsedError};
-use super::executor::{run_capture, run_command_sensitive};
+use super::executor::run_capture;
+use super::install_log;
 use super::runner::CommandRunner;
 use super::CommandMessage;
-use crate::app::SwapMode;
+use crate::app::{DiskLayout, InstallCredentials, KeySource, SwapMode};
+use crate::backup::provision;
+use crate::system::bundle::Bundle;
 use crate::constants::{
-    self, INSTALL_MOUNT_POINT, INSTALL_SYMLINK_PATH, NIXOS_CONFIG_HOME_DIR,
+    self, INSTALL_MOUNT_POINT, INSTALL_NM_CONNECTIONS_PATH, INSTALL_SYMLINK_PATH, NIXOS_CONFIG_HOME_DIR,
     PRIMARY_USER_GID, PRIMARY_USER_UID,
 };
+use crate::system::diagnostics;
+use crate::system::disk::DiskInfo;
+use crate::system::locale::{self, LocaleSettings};
+use crate::system::network::{self, WIFI_CONNECTION_TYPE};
+use crate::system::repo::{self, RepoSource};
+use crate::system::users::{self, ExtraUser};
+use crate::system::verify;
+use crate::templates;
 
 // =============================================================================
 // Install Constants
@@ -31,10 +46,7 @@ use crate::constants::{
 /// Path to the temporary LUKS password file (used by disko)
 const LUKS_PASSWORD_FILE: &str = "/tmp/luks-password";
 
-/// GitHub repository URL for the NixOS configuration
-const REPO_URL: &str = "https://github.com/Arnld81nl/nixos-config.git";
-
-/// Default username used in the configuration
+/// Default username used in the configuration, unless flake.nix says otherwise
 const DEFAULT_USERNAME: &str = "arnold";
 
 /// Nix config used during install (enable flakes + disable sandbox for disk ops)
@@ -50,6 +62,12 @@ static DISK_DEVICE_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
         .expect("Disk device regex pattern is statically validated")
 });
 
+/// Regex to find the default username of mkNixosSystem in flake.nix.
+static FLAKE_DEFAULT_USERNAME_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r#"mkNixosSystem\s*=\s*\{[^}]*username\s*\?\s*"([^"]+)""#)
+        .expect("flake default username regex pattern is statically validated")
+});
+
 /// Regex to match the LUKS content section where we need to inject passwordFile.
 static LUKS_NAME_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
     regex::Regex::new(r#"(name = "cryptroot";)"#)
@@ -91,7 +109,7 @@ fn get_ram_size_gb() -> u64 {
                 if parts.len() >= 2 {
                     if let Ok(kb) = parts[1].parse::<u64>() {
                         // Convert KB to GB, rounding up
-                        return (kb + 1024 * 1024 - 1) / (1024 * 1024);
+                        return kb.div_ceil(1024 * 1024);
                     }
                 }
             }
@@ -114,22 +132,20 @@ fn get_symlink_target(username: &str) -> String {
 pub async fn start_install(
     tx: mpsc::Sender<CommandMessage>,
     hostname: &str,
-    disk: &str,
-    username: &str,
-    password: &str,
-    swap_mode: SwapMode,
+    disk: &DiskInfo,
+    credentials: InstallCredentials,
+    source: RepoSource,
+    offline: Option<Bundle>,
 ) -> Result<()> {
     let hostname = hostname.to_string();
-    let disk = disk.to_string();
-    let username = username.to_string();
-    let password = password.to_string();
+    let disk = disk.clone();
 
     tokio::spawn(async move {
-        if let Err(e) = run_install(&tx, &hostname, &disk, &username, &password, &swap_mode).await {
+        if let Err(e) = run_install(&tx, &hostname, &disk, &credentials, &source, offline.as_ref()).await {
             let error_msg = format!("{:#}", e); // Full error chain with context
             tracing::error!("Installation failed: {}", error_msg);
             // Display error to user
-            let _ = tx.send(CommandMessage::Stderr(format!("\n*** INSTALLATION ERROR ***"))).await;
+            let _ = tx.send(CommandMessage::Stderr("\n*** INSTALLATION ERROR ***".to_string())).await;
             let _ = tx.send(CommandMessage::Stderr(error_msg.clone())).await;
             let _ = tx.send(CommandMessage::Stderr("".to_string())).await;
             let _ = tx
@@ -151,48 +167,68 @@ pub async fn start_install(
 
 /// Clone the repository to /tmp/nixos-config for host discovery
 /// This is called before the install wizard to populate the host list
-pub async fn start_clone_repository(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
+pub async fn start_clone_repository(
+    tx: mpsc::Sender<CommandMessage>,
+    source: RepoSource,
+    offline: Option<Bundle>,
+) -> Result<()> {
     tokio::spawn(async move {
         let temp_config = constants::temp_config_dir();
         let temp_config_str = temp_config.to_string_lossy().to_string();
 
-        // Check if already cloned
+        // Check if already cloned (from the same source)
         let hosts_dir = temp_config.join(constants::HOSTS_SUBDIR);
         if hosts_dir.exists() {
-            let _ = tx.send(CommandMessage::Stdout("Using existing configuration...".to_string())).await;
-            let _ = tx.send(CommandMessage::CloneComplete { success: true }).await;
-            return;
+            match verify_clone(&source, &temp_config_str, false).await {
+                Ok(commit) => {
+                    let _ = tx
+                        .send(CommandMessage::Stdout(format!(
+                            "Using existing configuration ({})...",
+                            repo::short(&commit)
+                        )))
+                        .await;
+                    let _ = tx.send(CommandMessage::CloneComplete { success: true }).await;
+                    return;
+                }
+                Err(e) => {
+                    let _ = tx
+                        .send(CommandMessage::Stdout(format!("Existing configuration not used: {}", e)))
+                        .await;
+                }
+            }
         }
 
-        let _ = tx.send(CommandMessage::Stdout("Checking network connectivity...".to_string())).await;
+        if let Some(bundle) = &offline {
+            let _ = tx
+                .send(CommandMessage::Stdout(format!("Loading offline bundle {}...", bundle.dir.display())))
+                .await;
+            if let Err(e) = prepare_offline(bundle).await {
+                let _ = tx.send(CommandMessage::Stderr(format!("{:#}", e))).await;
+                let _ = tx.send(CommandMessage::CloneComplete { success: false }).await;
+                return;
+            }
+        } else {
+            let _ = tx.send(CommandMessage::Stdout("Checking network connectivity...".to_string())).await;
 
-        // Check network
-        let (net_ok, _, _) = match run_capture("ping", &["-c", "1", "-W", "5", "github.com"]).await {
-            Ok(result) => result,
-            Err(_) => {
-                let _ = tx.send(CommandMessage::Stderr("Network check failed".to_string())).await;
+            // Check network
+            if !diagnostics::github_reachable().await {
+                let _ = tx.send(CommandMessage::Stderr("No internet connection. Please configure WiFi with nmtui.".to_string())).await;
                 let _ = tx.send(CommandMessage::CloneComplete { success: false }).await;
                 return;
             }
-        };
 
-        if !net_ok {
-            let _ = tx.send(CommandMessage::Stderr("No internet connection. Please configure WiFi with nmtui.".to_string())).await;
-            let _ = tx.send(CommandMessage::CloneComplete { success: false }).await;
-            return;
+            // Enable flakes and disable sandbox for disk operations
+            std::env::set_var("NIX_CONFIG", NIX_CONFIG_VALUE);
         }
 
-        let _ = tx.send(CommandMessage::Stdout("Cloning configuration repository...".to_string())).await;
-
-        // Enable flakes and disable sandbox for disk operations
-        std::env::set_var("NIX_CONFIG", NIX_CONFIG_VALUE);
+        let _ = tx.send(CommandMessage::Stdout(format!("Cloning {}...", source))).await;
 
         // Remove any partial clone
         let _ = std::fs::remove_dir_all(&temp_config);
 
         // Clone repository
-        let clone_cmd = format!("git clone --depth 1 {} {}", REPO_URL, temp_config_str);
-        let (success, stdout, stderr) = match run_capture("nix-shell", &["-p", "git", "--run", &clone_cmd]).await {
+        let clone_cmd = source.clone_script(&temp_config_str);
+        let (success, stdout, stderr) = match run_git(&clone_cmd).await {
             Ok(result) => result,
             Err(e) => {
                 let _ = tx.send(CommandMessage::Stderr(format!("Clone command failed: {}", e))).await;
@@ -207,17 +243,74 @@ pub async fn start_clone_repository(tx: mpsc::Sender<CommandMessage>) -> Result<
         if !stderr.is_empty() && !success {
             let _ = tx.send(CommandMessage::Stderr(stderr)).await;
         }
-
-        if success {
-            let _ = tx.send(CommandMessage::Stdout("Repository cloned successfully".to_string())).await;
+        if !success {
+            let _ = tx.send(CommandMessage::CloneComplete { success: false }).await;
+            return;
         }
 
-        let _ = tx.send(CommandMessage::CloneComplete { success }).await;
+        // Make sure we got what was asked for
+        match verify_clone(&source, &temp_config_str, true).await {
+            Ok(commit) => {
+                let _ = tx
+                    .send(CommandMessage::Stdout(format!(
+                        "Repository cloned successfully ({})",
+                        repo::short(&commit)
+                    )))
+                    .await;
+                let _ = tx.send(CommandMessage::CloneComplete { success: true }).await;
+            }
+            Err(e) => {
+                let _ = tx.send(CommandMessage::Stderr(format!("Checkout verification failed: {}", e))).await;
+                let _ = tx.send(CommandMessage::CloneComplete { success: false }).await;
+            }
+        }
     });
 
     Ok(())
 }
 
+/// Confirm the clone at `dir` came from `source` and has the requested commit checked out
+async fn verify_clone(source: &RepoSource, dir: &str, fresh: bool) -> Result<String, String> {
+    let script = source.check_script(dir);
+    match run_git(&script).await {
+        Ok((true, stdout, _)) => source.verify(&stdout, fresh),
+        Ok((false, _, stderr)) => Err(stderr
+            .lines()
+            .rev()
+            .map(str::trim)
+            .find(|l| !l.is_empty())
+            .unwrap_or("git failed")
+            .to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Run a shell script that needs git, capturing its output
+pub async fn run_git(script: &str) -> Result<(bool, String, String)> {
+    let cmd = repo::git_shell(script);
+    let args: Vec<&str> = cmd.iter().map(String::as_str).collect();
+    run_capture(args[0], &args[1..]).await
+}
+
+/// Use the bundle's cache as the only substituter and fetch git from it
+async fn prepare_offline(bundle: &Bundle) -> Result<()> {
+    std::env::set_var("NIX_CONFIG", bundle.nix_config(NIX_CONFIG_VALUE));
+    let (success, _, stderr) = run_capture("nix-store", &["--realise", &bundle.manifest.git]).await?;
+    if !success {
+        anyhow::bail!("Failed to load git from the bundle: {}", stderr.trim());
+    }
+    repo::use_git(&bundle.manifest.git);
+    Ok(())
+}
+
+/// NIX_CONFIG for the install, pointing Nix at the bundle when offline
+fn install_nix_config(offline: Option<&Bundle>) -> String {
+    match offline {
+        Some(bundle) => bundle.nix_config(NIX_CONFIG_VALUE),
+        None => NIX_CONFIG_VALUE.to_string(),
+    }
+}
+
 // =============================================================================
 // Installation Steps
 // =============================================================================
@@ -226,9 +319,14 @@ pub async fn start_clone_repository(tx: mpsc::Sender<CommandMessage>) -> Result<
 async fn step_check_network(runner: &CommandRunner<'_>) -> Result<bool> {
     runner.out("Checking network connectivity...").await;
 
-    let (success, _, _) = run_capture("ping", &["-c", "1", "-W", "5", "github.com"]).await?;
-    if !success {
-        runner.step_failed("network", "No network connection", "Network check").await?;
+    if !diagnostics::github_reachable().await {
+        runner.out("  No connection to github.com, diagnosing...").await;
+        let report = diagnostics::diagnose().await;
+        for line in report.lines() {
+            runner.out(&format!("  {}", line)).await;
+        }
+        let cause = report.cause.unwrap_or_else(|| "HTTPS to github.com fails".to_string());
+        runner.step_failed("network", &format!("No network connection: {}", cause), "Network check").await?;
         runner.done(false).await?;
         return Ok(false);
     }
@@ -237,10 +335,25 @@ async fn step_check_network(runner: &CommandRunner<'_>) -> Result<bool> {
     Ok(true)
 }
 
+/// Step 1 (offline): Check the bundle and load git from it instead of checking the network
+async fn step_load_bundle(runner: &CommandRunner<'_>, bundle: &Bundle) -> Result<bool> {
+    runner.out(&format!("Loading offline bundle {}...", bundle.dir.display())).await;
+    runner.out(&format!("  Host: {}", bundle.manifest.host)).await;
+    runner.out(&format!("  Made from: {} ({})", bundle.manifest.repository, repo::short(&bundle.manifest.commit))).await;
+    runner.out(&format!("  Created: {}", bundle.manifest.created)).await;
+    if let Err(e) = prepare_offline(bundle).await {
+        runner.step_failed("bundle", &format!("{:#}", e), "Load offline bundle").await?;
+        runner.done(false).await?;
+        return Ok(false);
+    }
+    runner.step_complete("bundle").await?;
+    Ok(true)
+}
+
 /// Step 2: Enable Nix flakes and disable sandbox for disk operations
-async fn step_enable_flakes(runner: &CommandRunner<'_>) -> Result<bool> {
+async fn step_enable_flakes(runner: &CommandRunner<'_>, nix_config: &str) -> Result<bool> {
     runner.out("Enabling Nix flakes...").await;
-    std::env::set_var("NIX_CONFIG", NIX_CONFIG_VALUE);
+    std::env::set_var("NIX_CONFIG", nix_config);
     runner.step_complete("flakes").await?;
     Ok(true)
 }
@@ -249,6 +362,7 @@ async fn step_enable_flakes(runner: &CommandRunner<'_>) -> Result<bool> {
 async fn step_prepare_repository(
     runner: &CommandRunner<'_>,
     hostname: &str,
+    source: &RepoSource,
 ) -> Result<Option<std::path::PathBuf>> {
     let temp_config = constants::temp_config_dir();
     let temp_config_str = temp_config.to_string_lossy().to_string();
@@ -261,20 +375,12 @@ async fn step_prepare_repository(
     if host_exists_in_temp {
         runner.out("Using existing configuration (host already created)...").await;
     } else {
-        runner.out("Cloning configuration repository...").await;
+        runner.out(&format!("Cloning {}...", source)).await;
         let _ = std::fs::remove_dir_all(&temp_config);
 
-        let success = runner
-            .run(
-                "nix-shell",
-                &[
-                    "-p",
-                    "git",
-                    "--run",
-                    &format!("git clone --depth 1 {} {}", REPO_URL, temp_config_str),
-                ],
-            )
-            .await?;
+        let clone = repo::git_shell(&source.clone_script(&temp_config_str));
+        let args: Vec<&str> = clone.iter().map(String::as_str).collect();
+        let success = runner.run(args[0], &args[1..]).await?;
 
         if !success {
             runner.step_failed("repository", "Failed to clone repository", "Clone repository").await?;
@@ -283,6 +389,25 @@ async fn step_prepare_repository(
         }
     }
 
+    // The configuration must come from the requested repository and ref
+    match verify_clone(source, &temp_config_str, !host_exists_in_temp).await {
+        Ok(commit) => {
+            runner.out(&format!("  Source: {}", source)).await;
+            runner.out(&format!("  Commit: {}", commit)).await;
+        }
+        Err(e) => {
+            runner
+                .step_failed(
+                    "repository",
+                    &format!("{} does not match {}: {}", temp_config_str, source, e),
+                    "Verify repository checkout",
+                )
+                .await?;
+            runner.done(false).await?;
+            return Ok(None);
+        }
+    }
+
     runner.step_complete("repository").await?;
     Ok(Some(temp_config))
 }
@@ -292,11 +417,13 @@ async fn step_configure_disk(
     runner: &CommandRunner<'_>,
     temp_config: &std::path::Path,
     hostname: &str,
-    disk: &str,
+    disk_info: &DiskInfo,
     username: &str,
+    layout: &DiskLayout,
     swap_mode: &SwapMode,
 ) -> Result<bool> {
     let temp_config_str = temp_config.to_string_lossy();
+    let disk = disk_info.path.as_str();
     runner.out(&format!("Configuring disk device {}...", disk)).await;
 
     // Validate disk path format
@@ -321,6 +448,17 @@ async fn step_configure_disk(
         return Ok(false);
     }
 
+    // Re-check the disk right before wiping it: something may have mounted it since selection
+    let current = crate::system::disk::get_available_disks()
+        .unwrap_or_default()
+        .into_iter()
+        .find(|d| d.path == disk);
+    if let Some(reason) = current.as_ref().and_then(|d| d.blocker()) {
+        runner.step_failed("disk", &format!("{}: {}", disk, reason), "Disk validation").await?;
+        runner.done(false).await?;
+        return Ok(false);
+    }
+
     // Check disko config file exists
     let disko_file = format!("{}/modules/disko/{}.nix", temp_config_str, hostname);
     if !std::path::Path::new(&disko_file).exists() {
@@ -337,8 +475,13 @@ async fn step_configure_disk(
     }
 
     // Update disko config with disk device
-    let disko_content = std::fs::read_to_string(&disko_file)
+    let mut disko_content = std::fs::read_to_string(&disko_file)
         .with_context(|| format!("Failed to read disko config: {}", disko_file))?;
+    if templates::parse_disko_layout(&disko_content) != *layout {
+        // A different layout was picked - regenerate the host's disko file
+        disko_content = templates::generate_disko_config(hostname, disk_info, layout);
+    }
+    runner.out(&format!("Disk layout: {}", layout)).await;
     let updated_content = update_disk_device(&disko_content, disk);
     std::fs::write(&disko_file, &updated_content)
         .with_context(|| format!("Failed to write disko config: {}", disko_file))?;
@@ -372,14 +515,15 @@ async fn step_configure_disk(
         runner.out("Using zram-only swap (no hibernate)").await;
     }
 
-    // Update flake.nix with username if it differs from default
-    if username != DEFAULT_USERNAME {
+    // Update flake.nix with username if it differs from the repository's default
+    let flake_file = format!("{}/flake.nix", temp_config_str);
+    let flake_content = std::fs::read_to_string(&flake_file)
+        .with_context(|| format!("Failed to read flake.nix: {}", flake_file))?;
+    let default_username = flake_default_username(&flake_content);
+    if username != default_username {
         runner.out(&format!("Configuring username '{}'...", username)).await;
 
-        let flake_file = format!("{}/flake.nix", temp_config_str);
-        let flake_content = std::fs::read_to_string(&flake_file)
-            .with_context(|| format!("Failed to read flake.nix: {}", flake_file))?;
-        let updated_flake = update_flake_username(&flake_content, hostname, username);
+        let updated_flake = update_flake_username(&flake_content, hostname, username, default_username);
         std::fs::write(&flake_file, &updated_flake)
             .with_context(|| format!("Failed to write flake.nix: {}", flake_file))?;
     }
@@ -483,11 +627,14 @@ async fn step_run_disko(
     temp_config: &std::path::Path,
     hostname: &str,
     password: &str,
+    encrypted: bool,
 ) -> Result<bool> {
     let temp_config_str = temp_config.to_string_lossy();
 
     runner.out("Running disko to partition and format...").await;
-    runner.out("Using provided passphrase for LUKS encryption...").await;
+    if encrypted {
+        runner.out("Using provided passphrase for LUKS encryption...").await;
+    }
 
     // Write password to temp file for disko
     std::fs::write(LUKS_PASSWORD_FILE, password.as_bytes())
@@ -556,6 +703,11 @@ async fn step_run_disko(
         return Ok(false);
     }
 
+    if !encrypted {
+        runner.step_complete("disko").await?;
+        return Ok(true);
+    }
+
     // Get the LUKS UUID and update config to use by-uuid instead of by-partlabel
     // This matches what the NixOS graphical installer does and is more reliable
     runner.out("").await;
@@ -698,12 +850,146 @@ async fn step_configure_hibernate(
     Ok(true)
 }
 
+/// Step 4c: Write the chosen timezone, locale and keyboard layout into the host config
+async fn step_configure_locale(
+    runner: &CommandRunner<'_>,
+    temp_config: &std::path::Path,
+    hostname: &str,
+    settings: Option<&LocaleSettings>,
+) -> Result<bool> {
+    let Some(settings) = settings else {
+        return Ok(true); // Keep the repo defaults
+    };
+
+    let host_config_file = temp_config.join("hosts").join(hostname).join("default.nix");
+    if !host_config_file.exists() {
+        runner.err("Host config not found, locale settings not applied").await;
+        return Ok(true);
+    }
+
+    let content = std::fs::read_to_string(&host_config_file)
+        .with_context(|| format!("Failed to read host config: {}", host_config_file.display()))?;
+    std::fs::write(&host_config_file, locale::inject_host_settings(&content, settings))
+        .with_context(|| format!("Failed to write host config: {}", host_config_file.display()))?;
+
+    runner.out("Locale settings configured:").await;
+    runner.out(&format!("  - Timezone: {}", settings.timezone)).await;
+    runner.out(&format!("  - Locale:   {}", settings.locale)).await;
+    runner.out(&format!("  - Keyboard: {}", settings.keyboard)).await;
+
+    Ok(true)
+}
+
+/// Step 4d: Point each account at its password hash file and declare additional users
+async fn step_configure_users(
+    runner: &CommandRunner<'_>,
+    temp_config: &std::path::Path,
+    hostname: &str,
+    username: &str,
+    extra_users: &[ExtraUser],
+) -> Result<bool> {
+    let host_config_file = temp_config.join("hosts").join(hostname).join("default.nix");
+    if !host_config_file.exists() {
+        runner.err("Host config not found, user accounts not declared").await;
+        return Ok(true);
+    }
+
+    let content = std::fs::read_to_string(&host_config_file)
+        .with_context(|| format!("Failed to read host config: {}", host_config_file.display()))?;
+    std::fs::write(&host_config_file, users::inject_host_users(&content, username, extra_users))
+        .with_context(|| format!("Failed to write host config: {}", host_config_file.display()))?;
+
+    runner.out("User accounts configured:").await;
+    runner.out(&format!("  - {} (primary)", username)).await;
+    for user in extra_users {
+        runner.out(&format!("  - {} ({})", user.username, user.groups.join(", "))).await;
+    }
+
+    Ok(true)
+}
+
+/// Hash every account's password in-process, so no plaintext reaches a command line
+async fn hash_passwords(credentials: &InstallCredentials) -> Result<Vec<(String, String)>> {
+    let accounts: Vec<(String, String)> = std::iter::once((credentials.username.clone(), credentials.password.clone()))
+        .chain(credentials.extra_users.iter().map(|u| (u.username.clone(), u.password.clone())))
+        .collect();
+    // Deliberately slow hashing, keep it off the async workers
+    tokio::task::spawn_blocking(move || {
+        accounts
+            .into_iter()
+            .map(|(name, password)| {
+                let hash = users::hash_password(&password);
+                (name, hash)
+            })
+            .collect()
+    })
+    .await
+    .context("Password hashing task failed")
+}
+
+/// Step 5c: Put the password hashes on the target before nixos-install creates the accounts
+async fn step_stage_password_hashes(runner: &CommandRunner<'_>, hashes: &[(String, String)]) -> Result<bool> {
+    let staging = std::env::temp_dir().join(format!("forge-passwords-{}", std::process::id()));
+    let result = stage_password_hashes(runner, &staging, hashes).await;
+    if staging.exists() {
+        if let Err(e) = std::fs::remove_dir_all(&staging) {
+            runner.err(&format!("Warning: Failed to remove {}: {}", staging.display(), e)).await;
+        }
+    }
+    // The host config points hashedPasswordFile at these files; without them
+    // the accounts would be created without a usable password
+    if let Err(e) = result {
+        runner.err(&format!("Staging password hashes failed: {:#}", e)).await;
+        runner
+            .step_failed("user", &format!("Failed to stage password hashes: {:#}", e), "Stage password hashes")
+            .await?;
+        runner.done(false).await?;
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+async fn stage_password_hashes(
+    runner: &CommandRunner<'_>,
+    staging: &std::path::Path,
+    hashes: &[(String, String)],
+) -> Result<()> {
+    std::fs::create_dir_all(staging).with_context(|| format!("Failed to create {}", staging.display()))?;
+    std::fs::set_permissions(staging, std::fs::Permissions::from_mode(0o700))?;
+
+    let target_dir = format!("{}{}", INSTALL_MOUNT_POINT, users::PASSWORD_DIR);
+    if !runner.run("sudo", &["install", "-d", "-m", "700", &target_dir]).await? {
+        anyhow::bail!("Failed to create {}", target_dir);
+    }
+
+    for (username, hash) in hashes {
+        let staged = staging.join(username);
+        std::fs::write(&staged, format!("{}\n", hash))
+            .with_context(|| format!("Failed to write {}", staged.display()))?;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+
+        let target = format!("{}{}", INSTALL_MOUNT_POINT, users::password_file(username));
+        let (success, _, stderr) = run_capture(
+            "sudo",
+            &["install", "-m", "600", "-o", "root", "-g", "root", &staged.to_string_lossy(), &target],
+        )
+        .await?;
+        if !success {
+            anyhow::bail!("Failed to install {}: {}", target, stderr.trim());
+        }
+        runner.out(&format!("  ✓ Password hash for {}", username)).await;
+    }
+    Ok(())
+}
+
 /// Step 6: Install NixOS
 async fn step_install_nixos(
     runner: &CommandRunner<'_>,
     temp_config: &std::path::Path,
     hostname: &str,
     username: &str,
+    origin: &str,
+    nix_config: &str,
 ) -> Result<bool> {
     let temp_config_str = temp_config.to_string_lossy();
 
@@ -766,7 +1052,7 @@ async fn step_install_nixos(
 
     // Copy configuration using sudo cp -r
     runner.out("  Copying configuration...").await;
-    let copy_ok = runner.run("sudo", &["cp", "-r", &temp_config_str.to_string(), &config_dir]).await?;
+    let copy_ok = runner.run("sudo", &["cp", "-r", temp_config_str.as_ref(), &config_dir]).await?;
     if !copy_ok {
         runner.err(&format!("ERROR: Failed to copy {} to {}", temp_config_str, config_dir)).await;
         runner.step_failed("NixOS", "Failed to copy configuration", "NixOS installation").await?;
@@ -779,13 +1065,9 @@ async fn step_install_nixos(
 
     // Add config dir to git's safe.directory to bypass ownership check
     // This is required because the ISO user doesn't own the mounted filesystem
-    let _ = runner.run(
-        "sudo",
-        &[
-            "nix-shell", "-p", "git", "--run",
-            &format!("git config --global --add safe.directory {}", config_dir),
-        ],
-    ).await;
+    let safe_directory = repo::git_shell(&format!("git config --global --add safe.directory {}", config_dir));
+    let args: Vec<&str> = safe_directory.iter().map(String::as_str).collect();
+    let _ = runner.run("sudo", &args).await;
 
     // Create symlink using sudo
     runner.out("  Setting up symlink...").await;
@@ -797,7 +1079,7 @@ async fn step_install_nixos(
     }
 
     // Initialize git repo (optional, log failures)
-    init_git_repo(runner, &config_dir).await;
+    init_git_repo(runner, &config_dir, origin).await;
 
     // Set ownership
     set_config_ownership(runner, config_parent, &config_dir).await;
@@ -812,7 +1094,7 @@ async fn step_install_nixos(
             "sudo",
             &[
                 "env",
-                &format!("NIX_CONFIG={}", NIX_CONFIG_VALUE),
+                &format!("NIX_CONFIG={}", nix_config),
                 "nix",
                 "flake",
                 "check",
@@ -837,18 +1119,28 @@ async fn step_install_nixos(
         ))
         .await;
     runner.out("  (This may take 10-30 minutes...)").await;
+    // Keep a copy of the output for the install logs on the target
+    let install_log = open_nixos_install_log();
     let success = runner
-        .run_with_timeout(
+        .run_transformed_with_timeout(
             "sudo",
             &[
                 "env",
-                &format!("NIX_CONFIG={}", NIX_CONFIG_VALUE),
+                &format!("NIX_CONFIG={}", nix_config),
                 "nixos-install",
                 "--flake",
                 &flake_ref,
                 "--no-root-passwd",
             ],
             1800, // 30 minutes
+            move |line| {
+                if let Some(file) = &install_log {
+                    if let Ok(mut file) = file.lock() {
+                        let _ = writeln!(file, "{}", line);
+                    }
+                }
+                Some(line.to_string())
+            },
         )
         .await?;
 
@@ -863,41 +1155,304 @@ async fn step_install_nixos(
     Ok(true)
 }
 
-/// Step 7: Set user password
-async fn step_set_user_password(
+/// Open the nixos-install log in the install log staging directory
+fn open_nixos_install_log() -> Option<std::sync::Mutex<std::fs::File>> {
+    let path = install_log::staging_dir().join(install_log::NIXOS_INSTALL_LOG);
+    match std::fs::File::create(&path) {
+        Ok(file) => Some(std::sync::Mutex::new(file)),
+        Err(e) => {
+            tracing::warn!("Failed to create {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Step 7: Copy the user's age and SSH keys into the new home
+///
+/// Optional: a failure marks the step failed and is repeated in the
+/// completion message, but the install goes on since the keys can still be
+/// restored after first boot. Returns false when requested keys are missing.
+async fn step_provision_keys(
     runner: &CommandRunner<'_>,
-    username: &str,
-    password: &str,
+    temp_config: &std::path::Path,
+    hostname: &str,
+    credentials: &InstallCredentials,
 ) -> Result<bool> {
-    runner.out("Setting up user account...").await;
+    let source = match credentials.key_source {
+        KeySource::Skip => return Ok(true),
+        KeySource::Archive => provision::Source::Archive {
+            passphrase: credentials.keys_passphrase.clone(),
+        },
+        KeySource::Secrets => provision::Source::Secrets,
+    };
+
+    runner.out("Provisioning keys...").await;
+    let staging = std::env::temp_dir().join(format!("forge-keys-{}", std::process::id()));
+    let result = provision_keys(runner, temp_config, hostname, &credentials.username, &staging, &source).await;
+    if staging.exists() {
+        if let Err(e) = std::fs::remove_dir_all(&staging) {
+            runner.err(&format!("Warning: Failed to remove {}: {}", staging.display(), e)).await;
+        }
+    }
+
+    match result {
+        Ok(()) => {
+            runner.step_complete("keys").await?;
+            Ok(true)
+        }
+        Err(e) => {
+            runner.err(&format!("Key provisioning failed: {:#}", e)).await;
+            runner.out("  Restore them after first boot with 'forge keys restore'.").await;
+            runner.step_failed("keys", &format!("{:#}", e), "Key provisioning").await?;
+            Ok(false)
+        }
+    }
+}
+
+/// The backup settings of the host being installed
+///
+/// Taken from the home configuration's generated config example, since the
+/// user's own ~/.config/app-backup/config doesn't exist before first boot.
+async fn host_backup_config(
+    temp_config: &std::path::Path,
+    hostname: &str,
+    username: &str,
+) -> Result<crate::system::config::BrowserBackupConfig> {
+    let attr = format!(
+        "{}#nixosConfigurations.{}.config.home-manager.users.{}.xdg.configFile.\"app-backup/config.example\".text",
+        temp_config.to_string_lossy(),
+        hostname,
+        username
+    );
+    let (success, stdout, stderr) = run_capture("nix", &["eval", "--raw", &attr]).await?;
+    if !success {
+        anyhow::bail!("Failed to read the host's backup settings: {}", stderr.trim());
+    }
+    Ok(crate::system::config::parse_browser_config(&stdout))
+}
+
+async fn provision_keys(
+    runner: &CommandRunner<'_>,
+    temp_config: &std::path::Path,
+    hostname: &str,
+    username: &str,
+    staging: &std::path::Path,
+    source: &provision::Source,
+) -> Result<()> {
+    std::fs::create_dir_all(staging).with_context(|| format!("Failed to create {}", staging.display()))?;
+    std::fs::set_permissions(staging, std::fs::Permissions::from_mode(0o700))?;
+
+    runner.out("  Reading the host's backup settings...").await;
+    let config = host_backup_config(temp_config, hostname, username).await?;
+    let settings = provision::stage(runner, &config, staging, source).await?;
+    let target_home = std::path::Path::new(INSTALL_MOUNT_POINT).join("home").join(username);
+    let copies = provision::plan(&settings, &target_home)?;
+    if copies.is_empty() {
+        anyhow::bail!("No keys were fetched");
+    }
+
+    let (uid, gid) = (PRIMARY_USER_UID.to_string(), PRIMARY_USER_GID.to_string());
+    for copy in &copies {
+        let mode = format!("{:o}", copy.mode);
+        let target = copy.target.to_string_lossy();
+        let mut args = vec!["install", "-m", &mode, "-o", &uid, "-g", &gid];
+        let source = copy.source.as_ref().map(|p| p.to_string_lossy());
+        match &source {
+            Some(source) => args.push(source),
+            None => args.push("-d"),
+        }
+        args.push(&target);
+
+        let (success, _, stderr) = run_capture("sudo", &args).await?;
+        if !success {
+            anyhow::bail!("Failed to install {}: {}", copy.target.display(), stderr.trim());
+        }
+        if copy.source.is_some() {
+            runner.out(&format!("  ✓ {} ({})", copy.target.display(), mode)).await;
+        }
+    }
+    Ok(())
+}
+
+/// Step 8: Copy the live system's Wi-Fi connections, secrets included
+///
+/// Best effort like key provisioning: without them the new system only
+/// lacks network until the user reconnects.
+async fn step_copy_wifi(runner: &CommandRunner<'_>) -> Result<()> {
+    runner.out("Copying Wi-Fi connections...").await;
+    let staging = std::env::temp_dir().join(format!("forge-wifi-{}", std::process::id()));
+    let result = copy_wifi(runner, &staging).await;
+    if staging.exists() {
+        if let Err(e) = std::fs::remove_dir_all(&staging) {
+            runner.err(&format!("Warning: Failed to remove {}: {}", staging.display(), e)).await;
+        }
+    }
+
+    match result {
+        Ok(0) => {
+            runner.out("  No saved Wi-Fi connections").await;
+            runner.step_skipped("Wi-Fi").await
+        }
+        Ok(_) => runner.step_complete("Wi-Fi").await,
+        Err(e) => {
+            runner.err(&format!("Warning: Copying Wi-Fi connections failed: {:#}", e)).await;
+            runner.out("  Reconnect with 'nmtui' after first boot.").await;
+            runner.step_skipped("Wi-Fi").await
+        }
+    }
+}
+
+/// Returns the number of connections copied
+async fn copy_wifi(runner: &CommandRunner<'_>, staging: &std::path::Path) -> Result<usize> {
+    let (success, stdout, stderr) =
+        run_capture("nmcli", &["-t", "-f", "NAME,UUID,TYPE,FILENAME", "connection", "show"]).await?;
+    if !success {
+        anyhow::bail!("nmcli failed: {}", stderr.trim());
+    }
+    let connections: Vec<_> = network::parse_connections(&stdout)
+        .into_iter()
+        .filter(|c| c.kind == WIFI_CONNECTION_TYPE && !c.filename.is_empty())
+        .collect();
+    if connections.is_empty() {
+        return Ok(0);
+    }
+
+    std::fs::create_dir_all(staging).with_context(|| format!("Failed to create {}", staging.display()))?;
+    std::fs::set_permissions(staging, std::fs::Permissions::from_mode(0o700))?;
+    if !runner.run("sudo", &["install", "-d", "-m", "755", INSTALL_NM_CONNECTIONS_PATH]).await? {
+        anyhow::bail!("Failed to create {}", INSTALL_NM_CONNECTIONS_PATH);
+    }
+
+    for connection in &connections {
+        // Keyfiles are root-only, so read them through sudo
+        let (success, keyfile, stderr) = run_capture("sudo", &["cat", &connection.filename]).await?;
+        if !success {
+            anyhow::bail!("Failed to read {}: {}", connection.filename, stderr.trim());
+        }
+        let (_, psk, _) = run_capture(
+            "nmcli",
+            &["--show-secrets", "-g", "802-11-wireless-security.psk", "connection", "show", &connection.uuid],
+        )
+        .await?;
+        let keyfile = network::with_psk(&keyfile, &network::unescape_terse(psk.trim_end_matches('\n')));
+
+        let file_name = std::path::Path::new(&connection.filename)
+            .file_name()
+            .with_context(|| format!("Invalid keyfile path {}", connection.filename))?;
+        let staged = staging.join(file_name);
+        std::fs::write(&staged, keyfile).with_context(|| format!("Failed to write {}", staged.display()))?;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+
+        let target = std::path::Path::new(INSTALL_NM_CONNECTIONS_PATH).join(file_name);
+        let (success, _, stderr) = run_capture(
+            "sudo",
+            &["install", "-m", "600", "-o", "root", "-g", "root", &staged.to_string_lossy(), &target.to_string_lossy()],
+        )
+        .await?;
+        if !success {
+            anyhow::bail!("Failed to install {}: {}", target.display(), stderr.trim());
+        }
+        runner.out(&format!("  ✓ {}", connection.name)).await;
+    }
+    Ok(connections.len())
+}
 
+/// Step 9: Set user passwords
+///
+/// nixos-install already created the accounts from their hash files; applying the
+/// hashes with `chpasswd -e` covers accounts that existed before.
+async fn step_set_user_passwords(runner: &CommandRunner<'_>, usernames: &[&str]) -> Result<bool> {
+    runner.out("Setting up user accounts...").await;
+
+    // Runs entirely inside the target, reading the staged hash files
     // Use sudo because nix run doesn't preserve root privileges
     // nixos-enter needs root to create mount namespaces
-    let escaped_password = password.replace('\'', "'\"'\"'");
-    let chpasswd_script = format!(
-        "echo '{}:{}' | sudo nixos-enter --root /mnt -c 'chpasswd'",
-        username, escaped_password
+    let script = format!(
+        "for u in {users}; do [ -s {dir}/$u ] || {{ echo \"No password hash for $u\" >&2; exit 1; }}; done; \
+        for u in {users}; do printf '%s:%s\\n' \"$u\" \"$(cat {dir}/$u)\"; done | chpasswd -e",
+        users = usernames.join(" "),
+        dir = users::PASSWORD_DIR
     );
-    let success = run_command_sensitive(runner.tx(), "sh", &["-c", &chpasswd_script]).await?;
+    let success = runner.run("sudo", &["nixos-enter", "--root", INSTALL_MOUNT_POINT, "-c", &script]).await?;
 
     if !success {
-        runner.out("Warning: Failed to set user password. You can set it after first boot with 'passwd'.").await;
+        runner.err("Failed to set user passwords.").await;
+        runner.out("  The system is still mounted at /mnt; set them with 'sudo nixos-enter --root /mnt -c passwd <user>'.").await;
+        runner.step_failed("user", "Failed to set user passwords", "Set user passwords").await?;
+        runner.done(false).await?;
+        return Ok(false);
     }
 
     runner.step_complete("user").await?;
     Ok(true)
 }
 
+/// Step 10: Check the installed system before offering a reboot
+async fn step_verify_install(
+    runner: &CommandRunner<'_>,
+    tx: &mpsc::Sender<CommandMessage>,
+    hostname: &str,
+    username: &str,
+    usernames: &[&str],
+    encrypted: bool,
+    nix_config: &str,
+) -> Result<Vec<verify::Check>> {
+    runner.out("Verifying installation...").await;
+
+    let config_dir = get_config_dir(username);
+    let symlink_target = get_symlink_target(username);
+    let expected = verify::Expected {
+        hostname,
+        config_dir: &config_dir,
+        symlink_target: &symlink_target,
+        usernames,
+        encrypted,
+        nix_config,
+    };
+    let checks = verify::verify(&expected).await;
+    for check in &checks {
+        runner.out(&format!("  {}", check.line())).await;
+    }
+
+    tx.send(CommandMessage::InstallVerified(checks.clone())).await?;
+    runner.step_complete("verify").await?;
+    Ok(checks)
+}
+
 /// Show installation completion message
-async fn show_completion_message(runner: &CommandRunner<'_>, username: &str) -> Result<()> {
+async fn show_completion_message(
+    runner: &CommandRunner<'_>,
+    username: &str,
+    encrypted: bool,
+    keys_provisioned: bool,
+    checks: &[verify::Check],
+) -> Result<()> {
     runner.out("\n").await;
+    let failed: Vec<&verify::Check> = checks.iter().filter(|c| !c.passed).collect();
+    if !failed.is_empty() {
+        runner.out("Installation finished, but verification failed:").await;
+        for check in failed {
+            runner.out(&format!("  {}: {}", check.name, check.detail)).await;
+        }
+        runner.out("").await;
+        runner.out("Fix these from a shell (the system is still mounted at /mnt) before rebooting.").await;
+        return Ok(());
+    }
     runner.out("Installation complete!").await;
     runner.out("").await;
     runner.out("Next steps:").await;
-    runner.out("  1. Reboot: reboot").await;
-    runner.out("  2. Enter your LUKS passphrase at boot").await;
-    runner.out("  3. Select a shell from the boot menu").await;
-    runner.out(&format!("  4. Login as '{}' with your chosen password", username)).await;
+    let mut steps = vec!["Reboot: reboot".to_string()];
+    if encrypted {
+        steps.push("Enter your LUKS passphrase at boot".to_string());
+    }
+    steps.push("Select a shell from the boot menu".to_string());
+    steps.push(format!("Login as '{}' with your chosen password", username));
+    if !keys_provisioned {
+        steps.push("Restore your keys (provisioning failed): forge keys restore".to_string());
+    }
+    for (i, step) in steps.iter().enumerate() {
+        runner.out(&format!("  {}. {}", i + 1, step)).await;
+    }
     Ok(())
 }
 
@@ -930,29 +1485,19 @@ async fn setup_config_symlink_sudo(runner: &CommandRunner<'_>, symlink_target: &
 }
 
 /// Initialize git repository in the config directory
-async fn init_git_repo(runner: &CommandRunner<'_>, config_dir: &str) {
+async fn init_git_repo(runner: &CommandRunner<'_>, config_dir: &str, origin: &str) {
     // Use sudo for git operations since the config dir is owned by root at this point
     // Add safe.directory to bypass Git's ownership check (required for install from ISO)
-    match runner
-        .run(
-            "sudo",
-            &[
-                "nix-shell",
-                "-p",
-                "git",
-                "--run",
-                &format!(
-                    "git config --global --add safe.directory {} && \
-                    cd {} && git init -b main && git remote add origin {} && git add -A && \
-                    git -c user.name='NixOS Install' -c user.email='install@localhost' \
-                    commit -m 'Initial configuration' && git fetch origin && \
-                    git branch --set-upstream-to=origin/main main",
-                    config_dir, config_dir, REPO_URL
-                ),
-            ],
-        )
-        .await
-    {
+    let script = repo::git_shell(&format!(
+        "git config --global --add safe.directory {} && \
+        cd {} && git init -b main && git remote add origin {} && git add -A && \
+        git -c user.name='NixOS Install' -c user.email='install@localhost' \
+        commit -m 'Initial configuration' && git fetch origin && \
+        git branch --set-upstream-to=origin/main main",
+        config_dir, config_dir, origin
+    ));
+    let args: Vec<&str> = script.iter().map(String::as_str).collect();
+    match runner.run("sudo", &args).await {
         Ok(true) => tracing::info!("Git repository initialized successfully"),
         Ok(false) => tracing::warn!("Git repository initialization returned non-zero exit - continuing"),
         Err(e) => tracing::warn!("Git repository initialization error: {} - continuing", e),
@@ -987,31 +1532,44 @@ async fn set_config_ownership(
 async fn run_install(
     tx: &mpsc::Sender<CommandMessage>,
     hostname: &str,
-    disk: &str,
-    username: &str,
-    password: &str,
-    swap_mode: &SwapMode,
+    disk: &DiskInfo,
+    credentials: &InstallCredentials,
+    source: &RepoSource,
+    offline: Option<&Bundle>,
 ) -> Result<()> {
     let runner = CommandRunner::new(tx);
+    let nix_config = install_nix_config(offline);
+    let username = credentials.username.as_str();
+    let password = credentials.password.as_str();
+    let hashes = hash_passwords(credentials).await?;
+    let swap_mode = &credentials.swap_mode;
+    let layout = &credentials.layout;
+    if let Err(e) = install_log::reset_staging() {
+        tracing::warn!("Install logs won't include the nixos-install output: {:#}", e);
+    }
 
-    // Step 1: Check network
-    if !step_check_network(&runner).await? {
+    // Step 1: Check network, or load the offline bundle
+    let ready = match offline {
+        Some(bundle) => step_load_bundle(&runner, bundle).await?,
+        None => step_check_network(&runner).await?,
+    };
+    if !ready {
         return Ok(());
     }
 
     // Step 2: Enable flakes
-    if !step_enable_flakes(&runner).await? {
+    if !step_enable_flakes(&runner, &nix_config).await? {
         return Ok(());
     }
 
     // Step 3: Prepare repository
-    let temp_config = match step_prepare_repository(&runner, hostname).await? {
+    let temp_config = match step_prepare_repository(&runner, hostname, source).await? {
         Some(path) => path,
         None => return Ok(()),
     };
 
     // Step 4: Configure disk (including swap mode)
-    if !step_configure_disk(&runner, &temp_config, hostname, disk, username, swap_mode).await? {
+    if !step_configure_disk(&runner, &temp_config, hostname, disk, username, layout, swap_mode).await? {
         return Ok(());
     }
 
@@ -1020,8 +1578,18 @@ async fn run_install(
         return Ok(());
     }
 
+    // Step 4c: Timezone, locale and keyboard layout
+    if !step_configure_locale(&runner, &temp_config, hostname, credentials.locale.as_ref()).await? {
+        return Ok(());
+    }
+
+    // Step 4d: User accounts
+    if !step_configure_users(&runner, &temp_config, hostname, username, &credentials.extra_users).await? {
+        return Ok(());
+    }
+
     // Step 5: Run disko
-    if !step_run_disko(&runner, &temp_config, hostname, password).await? {
+    if !step_run_disko(&runner, &temp_config, hostname, password, layout.scheme.encrypted()).await? {
         return Ok(());
     }
 
@@ -1030,16 +1598,39 @@ async fn run_install(
         return Ok(());
     }
 
+    // Step 5c: Password hashes, read when nixos-install creates the accounts
+    if !step_stage_password_hashes(&runner, &hashes).await? {
+        return Ok(());
+    }
+
     // Step 6: Install NixOS
-    if !step_install_nixos(&runner, &temp_config, hostname, username).await? {
+    // The installed repository tracks where the configuration came from, not the bundle
+    let origin = offline.map_or(source.url.as_str(), |bundle| bundle.manifest.repository.as_str());
+    if !step_install_nixos(&runner, &temp_config, hostname, username, origin, &nix_config).await? {
         return Ok(());
     }
 
-    // Step 7: Set user password
-    step_set_user_password(&runner, username, password).await?;
+    // Step 7: Provision keys (if selected)
+    let keys_provisioned = step_provision_keys(&runner, &temp_config, hostname, credentials).await?;
+
+    // Step 8: Copy Wi-Fi connections (unless opted out)
+    if !credentials.skip_wifi {
+        step_copy_wifi(&runner).await?;
+    }
+
+    // Step 9: Set user passwords
+    let usernames: Vec<&str> = hashes.iter().map(|(name, _)| name.as_str()).collect();
+    if !step_set_user_passwords(&runner, &usernames).await? {
+        return Ok(());
+    }
+
+    // Step 10: Verify the installed system
+    let checks =
+        step_verify_install(&runner, tx, hostname, username, &usernames, layout.scheme.encrypted(), &nix_config)
+            .await?;
 
     // Show completion message
-    show_completion_message(&runner, username).await?;
+    show_completion_message(&runner, username, layout.scheme.encrypted(), keys_provisioned, &checks).await?;
 
     runner.done(true).await?;
     Ok(())
@@ -1175,10 +1766,19 @@ fn inject_swap_filesystem_config(content: &str) -> String {
     content.to_string()
 }
 
+/// Default username of mkNixosSystem in flake.nix (forks may use their own)
+fn flake_default_username(content: &str) -> &str {
+    FLAKE_DEFAULT_USERNAME_RE
+        .captures(content)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str())
+        .unwrap_or(DEFAULT_USERNAME)
+}
+
 /// Update flake.nix to set username for a specific host configuration
 /// Only modifies the file if username differs from the default
-fn update_flake_username(content: &str, hostname: &str, username: &str) -> String {
-    if username == DEFAULT_USERNAME {
+fn update_flake_username(content: &str, hostname: &str, username: &str, default_username: &str) -> String {
+    if username == default_username {
         // No modification needed for default username
         return content.to_string();
     }
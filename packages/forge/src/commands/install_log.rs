@@ -0,0 +1,240 @@
+//! Install logs kept on the installed system
+//!
+//! The screen log lives in the live user's home and disappears with the ISO
+//! session. When an install ends (or fails with the target still mounted) the
+//! screen log, the answers given (without secrets), the nixos-install output and
+//! a hardware snapshot are copied to /var/log/forge/install-<timestamp>/ on the
+//! target.
+
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use super::executor::run_capture;
+use crate::app::{InstallCredentials, KeySource, SwapMode};
+use crate::constants::{INSTALL_LOG_PATH, INSTALL_MOUNT_POINT};
+use crate::system::disk::DiskInfo;
+use crate::system::repo::RepoSource;
+
+const REDACTED: &str = "<redacted>";
+
+/// File names inside an install log directory
+pub const SCREEN_LOG: &str = "install.log";
+pub const ANSWERS: &str = "answers.txt";
+pub const NIXOS_INSTALL_LOG: &str = "nixos-install.log";
+pub const HARDWARE: &str = "hardware.txt";
+
+/// Commands whose output makes up the hardware snapshot
+const HARDWARE_COMMANDS: &[(&str, &[&str])] = &[
+    ("uname", &["-a"]),
+    ("lsblk", &["-o", "NAME,SIZE,TYPE,FSTYPE,MODEL,TRAN,PTTYPE,MOUNTPOINT"]),
+    ("lspci", &["-nn"]),
+    ("lsusb", &[]),
+    ("ip", &["-br", "link"]),
+];
+
+/// Files read for the hardware snapshot
+const HARDWARE_FILES: &[&str] = &["/proc/cpuinfo", "/proc/meminfo", "/sys/class/dmi/id/product_name"];
+
+/// Where logs are collected on the live system while the install runs
+pub fn staging_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("forge-install-log-{}", std::process::id()))
+}
+
+/// Start an install with an empty staging directory
+pub fn reset_staging() -> Result<PathBuf> {
+    let dir = staging_dir();
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).with_context(|| format!("Failed to clear {}", dir.display()))?;
+    }
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+    Ok(dir)
+}
+
+/// The choices an install was run with; passwords are never written
+pub fn answers(host: &str, disk: &DiskInfo, credentials: &InstallCredentials, source: &RepoSource) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "repository: {}", source.url);
+    let _ = writeln!(out, "ref: {}", source.git_ref.as_deref().unwrap_or("(default branch)"));
+    let _ = writeln!(out, "host: {}", host);
+    let _ = writeln!(
+        out,
+        "disk: {} ({}{})",
+        disk.path,
+        disk.size,
+        disk.model.as_deref().map(|m| format!(", {}", m)).unwrap_or_default()
+    );
+    let _ = writeln!(out, "layout: {}", credentials.layout);
+    let swap = match credentials.swap_mode {
+        SwapMode::ZramOnly => "zram only",
+        SwapMode::HibernateSupport => "hibernate (swapfile)",
+    };
+    let _ = writeln!(out, "swap mode: {}", swap);
+    let _ = writeln!(out, "username: {}", credentials.username);
+    let _ = writeln!(out, "password: {}", REDACTED);
+    for user in &credentials.extra_users {
+        let _ = writeln!(
+            out,
+            "extra user: {} (groups: {}; password: {})",
+            user.username,
+            if user.groups.is_empty() { "none".to_string() } else { user.groups.join(", ") },
+            REDACTED
+        );
+    }
+    let keys = match credentials.key_source {
+        KeySource::Skip => "skip",
+        KeySource::Archive => "keys archive",
+        KeySource::Secrets => "secret provider",
+    };
+    let _ = writeln!(out, "keys: {}", keys);
+    let _ = writeln!(out, "copy wifi: {}", if credentials.skip_wifi { "no" } else { "yes" });
+    if let Some(locale) = &credentials.locale {
+        let _ = writeln!(out, "timezone: {}", locale.timezone);
+        let _ = writeln!(out, "locale: {}", locale.locale);
+        let _ = writeln!(out, "keyboard: {}", locale.keyboard);
+    }
+    out
+}
+
+/// Hardware of the machine, as seen from the live system
+pub async fn hardware_snapshot() -> String {
+    let mut out = String::new();
+    for (cmd, args) in HARDWARE_COMMANDS {
+        let _ = writeln!(out, "=== {} {} ===", cmd, args.join(" "));
+        match run_capture(cmd, args).await {
+            Ok((_, stdout, stderr)) => {
+                out.push_str(&stdout);
+                out.push_str(&stderr);
+            }
+            Err(e) => {
+                let _ = writeln!(out, "{}", e);
+            }
+        }
+        out.push('\n');
+    }
+    for file in HARDWARE_FILES {
+        let _ = writeln!(out, "=== {} ===", file);
+        match std::fs::read_to_string(file) {
+            Ok(content) => out.push_str(&content),
+            Err(e) => {
+                let _ = writeln!(out, "{}", e);
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Whether the target root is mounted
+pub fn target_mounted() -> bool {
+    std::fs::read_to_string("/proc/mounts")
+        .map(|content| is_mounted(&content, INSTALL_MOUNT_POINT))
+        .unwrap_or(false)
+}
+
+fn is_mounted(mounts: &str, mount_point: &str) -> bool {
+    mounts
+        .lines()
+        .any(|line| line.split_whitespace().nth(1) == Some(mount_point))
+}
+
+/// Name of the log directory for an install finished at `now`
+pub fn log_dir_name(now: chrono::DateTime<chrono::Local>) -> String {
+    format!("install-{}", now.format("%Y%m%d-%H%M%S"))
+}
+
+/// Copy the install logs onto the target, returning the path on the installed system
+///
+/// Returns Ok(None) when the target isn't mounted (the install failed early).
+pub async fn persist(screen_log: &Path, answers: &str) -> Result<Option<String>> {
+    if !target_mounted() {
+        return Ok(None);
+    }
+
+    let staging = staging_dir();
+    std::fs::create_dir_all(&staging).with_context(|| format!("Failed to create {}", staging.display()))?;
+    std::fs::copy(screen_log, staging.join(SCREEN_LOG))
+        .with_context(|| format!("Failed to copy {}", screen_log.display()))?;
+    std::fs::write(staging.join(ANSWERS), answers).context("Failed to write the answers file")?;
+    std::fs::write(staging.join(HARDWARE), hardware_snapshot().await)
+        .context("Failed to write the hardware snapshot")?;
+
+    let name = log_dir_name(chrono::Local::now());
+    let target = format!("{}/{}", INSTALL_LOG_PATH, name);
+    let (ok, _, stderr) = run_capture("sudo", &["install", "-d", "-m", "700", &target]).await?;
+    if !ok {
+        anyhow::bail!("Failed to create {}: {}", target, stderr.trim());
+    }
+    for file in [SCREEN_LOG, ANSWERS, NIXOS_INSTALL_LOG, HARDWARE] {
+        let source = staging.join(file);
+        if !source.exists() {
+            continue;
+        }
+        let source = source.to_string_lossy().to_string();
+        let dest = format!("{}/{}", target, file);
+        let (ok, _, stderr) = run_capture("sudo", &["install", "-m", "600", &source, &dest]).await?;
+        if !ok {
+            anyhow::bail!("Failed to copy {}: {}", file, stderr.trim());
+        }
+    }
+
+    if let Err(e) = std::fs::remove_dir_all(&staging) {
+        tracing::warn!("Failed to remove {}: {}", staging.display(), e);
+    }
+    Ok(Some(target.trim_start_matches(INSTALL_MOUNT_POINT).to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::users::ExtraUser;
+
+    #[test]
+    fn test_answers_redact_passwords() {
+        let disk = DiskInfo {
+            path: "/dev/nvme0n1".to_string(),
+            size: "512G".to_string(),
+            model: Some("Samsung SSD".to_string()),
+            ..Default::default()
+        };
+        let credentials = InstallCredentials {
+            username: "arnold".to_string(),
+            password: "hunter22".to_string(),
+            confirm_password: "hunter22".to_string(),
+            extra_users: vec![ExtraUser {
+                username: "guest".to_string(),
+                password: "guestpass".to_string(),
+                groups: vec!["networkmanager".to_string()],
+            }],
+            key_source: KeySource::Archive,
+            keys_passphrase: "archivepass".to_string(),
+            ..Default::default()
+        };
+        let source = RepoSource::new(Some("https://github.com/me/nixos-config"), Some("v1.0")).unwrap();
+        let text = answers("x1yoga", &disk, &credentials, &source);
+        assert!(text.contains("repository: https://github.com/me/nixos-config\nref: v1.0\n"));
+        assert!(text.contains("host: x1yoga"));
+        assert!(text.contains("disk: /dev/nvme0n1 (512G, Samsung SSD)"));
+        assert!(text.contains("username: arnold"));
+        assert!(text.contains("extra user: guest (groups: networkmanager; password: <redacted>)"));
+        assert!(!text.contains("hunter22"));
+        assert!(!text.contains("guestpass"));
+        assert!(!text.contains("archivepass"));
+    }
+
+    #[test]
+    fn test_is_mounted() {
+        let mounts = "/dev/mapper/cryptroot /mnt btrfs rw 0 0\n/dev/sda1 /mnt/boot vfat rw 0 0\n";
+        assert!(is_mounted(mounts, "/mnt"));
+        assert!(!is_mounted("/dev/sda1 /mnt2 ext4 rw 0 0\n", "/mnt"));
+    }
+
+    #[test]
+    fn test_log_dir_name() {
+        use chrono::TimeZone;
+        let when = chrono::Local.with_ymd_and_hms(2026, 3, 4, 5, 6, 7).unwrap();
+        assert_eq!(log_dir_name(when), "install-20260304-050607");
+    }
+}
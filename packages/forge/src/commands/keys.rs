@@ -1,24 +1,29 @@
 //! Key management commands (Age and SSH keys)
 
 use anyhow::Result;
+use std::path::PathBuf;
 use tokio::sync::mpsc;
 
 use super::runner::{spawn_with_error_handling, CommandRunner};
 use super::CommandMessage;
+use crate::backup::shamir::Share;
+use crate::backup::{audit, keys, paper, rotate, BackupSettings};
+use crate::constants::{app_backup_config_path, forge_data_dir, KEY_ROTATION_LOG_FILE};
 
-/// Start key setup from 1Password
-pub async fn start_setup(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
-    spawn_with_error_handling(tx, "Key setup", "Setup", |tx| async move {
+/// Start key setup from the secret provider
+pub async fn start_setup(tx: mpsc::Sender<CommandMessage>, force: bool) -> Result<()> {
+    spawn_with_error_handling(tx, "Key setup", "Setup", move |tx| async move {
         let runner = CommandRunner::new(&tx);
-        runner
-            .run_simple_operation(
-                "Key Setup (from 1Password)",
-                "keys-setup",
-                &[],
-                "Keys set up successfully",
-                "Setup failed",
-            )
-            .await?;
+        runner.header("Key Setup").await;
+
+        let settings = BackupSettings::load()?;
+        keys::setup(&runner, &settings, force).await?;
+
+        runner.out("").await;
+        runner.out("  Keys set up successfully").await;
+        runner.out("  Local keys will now be used for backup/restore operations.").await;
+        runner.footer().await;
+        runner.done(true).await?;
         Ok(())
     })
 }
@@ -27,15 +32,16 @@ pub async fn start_setup(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
 pub async fn start_backup(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
     spawn_with_error_handling(tx, "Key backup", "Backup", |tx| async move {
         let runner = CommandRunner::new(&tx);
-        runner
-            .run_simple_operation(
-                "Key Backup",
-                "keys-backup",
-                &["--push"],
-                "Keys backed up successfully",
-                "Backup failed",
-            )
-            .await?;
+        runner.header("Key Backup").await;
+
+        let settings = BackupSettings::load()?;
+        let passphrase = keys::load_passphrase(&settings).await?;
+        keys::backup(&runner, &settings, &passphrase).await?;
+
+        runner.out("").await;
+        runner.out("  Keys backed up successfully").await;
+        runner.footer().await;
+        runner.done(true).await?;
         Ok(())
     })
 }
@@ -44,41 +50,114 @@ pub async fn start_backup(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
 pub async fn start_restore(tx: mpsc::Sender<CommandMessage>, force: bool) -> Result<()> {
     spawn_with_error_handling(tx, "Key restore", "Restore", move |tx| async move {
         let runner = CommandRunner::new(&tx);
-        let args: Vec<&str> = if force {
-            vec!["--pull", "--force"]
-        } else {
-            vec!["--pull"]
+        runner.header("Key Restore").await;
+
+        let settings = BackupSettings::load()?;
+        let passphrase = keys::load_passphrase(&settings).await?;
+        keys::restore(&runner, &settings, &passphrase, force).await?;
+
+        runner.out("").await;
+        runner.out("  Keys restored successfully").await;
+        runner.footer().await;
+        runner.done(true).await?;
+        Ok(())
+    })
+}
+
+/// Start age key rotation
+pub async fn start_rotate(tx: mpsc::Sender<CommandMessage>, dry_run: bool) -> Result<()> {
+    spawn_with_error_handling(tx, "Key rotation", "Rotate", move |tx| async move {
+        let runner = CommandRunner::new(&tx);
+        runner.header(if dry_run { "Age Key Rotation (dry run)" } else { "Age Key Rotation" }).await;
+
+        let settings = BackupSettings::load()?;
+        let passphrase = match &settings.keys_passphrase_secret {
+            Some(_) if !dry_run => Some(keys::load_passphrase(&settings).await?),
+            _ => None,
+        };
+        let rotation = rotate::Rotation {
+            config_path: app_backup_config_path(),
+            audit_log: forge_data_dir().join(KEY_ROTATION_LOG_FILE),
+            dry_run,
         };
-        runner
-            .run_simple_operation(
-                "Key Restore",
-                "keys-restore",
-                &args,
-                "Keys restored successfully",
-                "Restore failed",
-            )
-            .await?;
+        rotate::rotate(&runner, &settings, &rotation, passphrase.as_deref()).await?;
+
+        if !dry_run {
+            runner.out("").await;
+            runner.out("  Age key rotated successfully").await;
+        }
+        runner.footer().await;
+        runner.done(true).await?;
+        Ok(())
+    })
+}
+
+/// Start printing the age key as paper shares
+pub async fn start_paper_backup(
+    tx: mpsc::Sender<CommandMessage>,
+    shares: u8,
+    threshold: u8,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    spawn_with_error_handling(tx, "Paper key backup", "Split", move |tx| async move {
+        let runner = CommandRunner::new(&tx);
+        runner.header("Paper Key Backup").await;
+
+        let settings = BackupSettings::load()?;
+        paper::backup(&runner, &settings, shares, threshold, output.as_deref()).await?;
+
+        runner.footer().await;
+        runner.done(true).await?;
         Ok(())
     })
 }
 
-/// Start key status check
+/// Start rebuilding the age key from entered paper shares
+pub async fn start_paper_restore(tx: mpsc::Sender<CommandMessage>, shares: Vec<Share>, force: bool) -> Result<()> {
+    spawn_with_error_handling(tx, "Paper key restore", "Combine", move |tx| async move {
+        let runner = CommandRunner::new(&tx);
+        runner.header("Paper Key Restore").await;
+
+        let settings = BackupSettings::load()?;
+        paper::restore(&runner, &settings, &shares, force).await?;
+
+        runner.out("").await;
+        runner.out("  Age key restored successfully").await;
+        runner.footer().await;
+        runner.done(true).await?;
+        Ok(())
+    })
+}
+
+/// Start key inspection for the Keys screen
 pub async fn start_status(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
     tokio::spawn(async move {
-        let runner = CommandRunner::new(&tx);
-        runner.header("Key Status").await;
+        let result = BackupSettings::load()
+            .map(|settings| Box::new(audit::inspect(&settings)))
+            .map_err(|e| format!("{:#}", e));
+        let _ = tx.send(CommandMessage::KeyReport(result)).await;
+    });
+    Ok(())
+}
 
-        let success = match runner.run("keys-status", &[]).await {
-            Ok(s) => s,
+/// Fix key file permissions, then inspect the keys again
+pub async fn start_fix(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
+    tokio::spawn(async move {
+        let settings = match BackupSettings::load() {
+            Ok(settings) => settings,
             Err(e) => {
-                tracing::error!("Key status check failed: {}", e);
-                runner.err(&e.to_string()).await;
-                false
+                let _ = tx.send(CommandMessage::KeyReport(Err(format!("{:#}", e)))).await;
+                return;
             }
         };
-
-        runner.footer().await;
-        let _ = runner.done(success).await;
+        let fixed = audit::fix_permissions(&audit::inspect(&settings)).map_err(|e| format!("{:#}", e));
+        if let Ok(lines) = &fixed {
+            for line in lines {
+                tracing::info!("Key permissions: {}", line);
+            }
+        }
+        let _ = tx.send(CommandMessage::KeysFixed(fixed)).await;
+        let _ = tx.send(CommandMessage::KeyReport(Ok(Box::new(audit::inspect(&settings))))).await;
     });
     Ok(())
 }
@@ -1,21 +1,31 @@
 //! Command execution module
 
 pub mod apps;
+pub mod bundle;
 pub mod create_host;
 pub mod errors;
 pub mod executor;
 pub mod install;
+pub mod install_log;
 pub mod keys;
+pub mod network;
 pub mod runner;
 pub mod update;
 
 pub use errors::ParsedError;
 
+use crate::backup::audit::KeyReport;
+use crate::backup::history::Snapshot;
+use crate::system::network::AccessPoint;
+use crate::system::verify;
+use forge::notify::attribution::ConfigCommit;
+
 /// Standard step names for consistent messaging
 #[allow(dead_code)]
 pub mod steps {
     // Install steps
     pub const NETWORK: &str = "network";
+    pub const BUNDLE: &str = "bundle";
     pub const FLAKES: &str = "flakes";
     pub const REPOSITORY: &str = "repository";
     pub const DISK: &str = "disk";
@@ -50,6 +60,8 @@ pub enum CommandMessage {
     Stdout(String),
     /// Standard error line
     Stderr(String),
+    /// Output line shown on screen but never written to the screen log
+    Secret(String),
     /// Step completed successfully
     StepComplete { step: String },
     /// Step failed with rich error information
@@ -64,11 +76,25 @@ pub enum CommandMessage {
     UpdatesAvailable {
         nixos_config: bool,
         app_profiles: bool,
-        /// Pending commits for nixos-config (hash, message)
-        commits: Vec<(String, String)>,
+        /// Pending commits for nixos-config, attributed to hosts
+        commits: Vec<ConfigCommit>,
     },
     /// Reboot recommended after update (kernel/bootloader/firmware changes)
     RebootRecommended { reasons: Vec<String> },
     /// Repository clone completed (for install host discovery)
     CloneComplete { success: bool },
+    /// App backup snapshots loaded for the history picker
+    AppSnapshots(Result<Vec<Snapshot>, String>),
+    /// Key inspection finished
+    KeyReport(Result<Box<KeyReport>, String>),
+    /// Key permissions fixed (one line per file changed)
+    KeysFixed(Result<Vec<String>, String>),
+    /// The internet is reachable (install network screen)
+    NetworkOnline,
+    /// Wi-Fi scan finished
+    WifiNetworks(Result<Vec<AccessPoint>, String>),
+    /// Joining a Wi-Fi network failed
+    WifiConnectFailed(String),
+    /// Post-install verification results
+    InstallVerified(Vec<verify::Check>),
 }
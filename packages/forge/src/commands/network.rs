@@ -0,0 +1,70 @@
+//! Wi-Fi setup before a live ISO install
+//!
+//! Runs the [`WifiManager`] calls of the install network screen in the
+//! background and reports back with `CommandMessage`s.
+
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use super::CommandMessage;
+use crate::system::network::{self, WifiManager};
+
+/// Connectivity checks after joining a network
+const CONNECT_CHECK_ATTEMPTS: u32 = 5;
+/// Pause between those checks
+const CONNECT_CHECK_DELAY: Duration = Duration::from_secs(2);
+
+/// Check connectivity, scanning for networks if offline
+pub async fn start_check(tx: mpsc::Sender<CommandMessage>, wifi: Arc<dyn WifiManager>) -> Result<()> {
+    tokio::spawn(async move {
+        if wifi.online().await {
+            let _ = tx.send(CommandMessage::NetworkOnline).await;
+            return;
+        }
+        let result = wifi.scan().await.map_err(|e| format!("{:#}", e));
+        let _ = tx.send(CommandMessage::WifiNetworks(result)).await;
+    });
+    Ok(())
+}
+
+/// Scan for networks again
+pub async fn start_scan(tx: mpsc::Sender<CommandMessage>, wifi: Arc<dyn WifiManager>) -> Result<()> {
+    tokio::spawn(async move {
+        let result = wifi.scan().await.map_err(|e| format!("{:#}", e));
+        let _ = tx.send(CommandMessage::WifiNetworks(result)).await;
+    });
+    Ok(())
+}
+
+/// Join a network and wait until the internet is reachable
+pub async fn start_connect(
+    tx: mpsc::Sender<CommandMessage>,
+    wifi: Arc<dyn WifiManager>,
+    ssid: String,
+    passphrase: Option<String>,
+) -> Result<()> {
+    tokio::spawn(async move {
+        let result = network::connect_and_check(
+            wifi.as_ref(),
+            &ssid,
+            passphrase.as_deref(),
+            CONNECT_CHECK_ATTEMPTS,
+            CONNECT_CHECK_DELAY,
+        )
+        .await;
+        let message = match result {
+            Ok(()) => {
+                tracing::info!("Connected to Wi-Fi network {}", ssid);
+                CommandMessage::NetworkOnline
+            }
+            Err(e) => {
+                tracing::warn!("Wi-Fi connection to {} failed: {:#}", ssid, e);
+                CommandMessage::WifiConnectFailed(format!("{:#}", e))
+            }
+        };
+        let _ = tx.send(message).await;
+    });
+    Ok(())
+}
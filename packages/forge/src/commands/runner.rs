@@ -9,7 +9,7 @@ use anyhow::Result;
 use tokio::sync::mpsc;
 
 use super::errors::{ErrorContext, ParsedError};
-use super::executor::{run_command, run_command_with_timeout};
+use super::executor::{run_command, run_command_transformed_with_timeout};
 use super::CommandMessage;
 
 /// A helper for running commands with consistent formatting and error handling
@@ -23,11 +23,6 @@ impl<'a> CommandRunner<'a> {
         Self { tx }
     }
 
-    /// Get a reference to the underlying sender
-    pub fn tx(&self) -> &mpsc::Sender<CommandMessage> {
-        self.tx
-    }
-
     /// Send a stdout message
     pub async fn out(&self, msg: &str) {
         let _ = self.tx.send(CommandMessage::Stdout(msg.to_string())).await;
@@ -38,6 +33,11 @@ impl<'a> CommandRunner<'a> {
         let _ = self.tx.send(CommandMessage::Stderr(msg.to_string())).await;
     }
 
+    /// Send a line that must stay out of the screen log (e.g. key shares)
+    pub async fn secret(&self, msg: &str) {
+        let _ = self.tx.send(CommandMessage::Secret(msg.to_string())).await;
+    }
+
     /// Print a header with title
     pub async fn header(&self, title: &str) {
         self.out("").await;
@@ -58,9 +58,18 @@ impl<'a> CommandRunner<'a> {
         run_command(self.tx, cmd, args).await
     }
 
-    /// Run a command with a custom timeout (in seconds)
-    pub async fn run_with_timeout(&self, cmd: &str, args: &[&str], timeout_secs: u64) -> Result<bool> {
-        run_command_with_timeout(self.tx, cmd, args, Some(timeout_secs)).await
+    /// Run a command with a custom timeout (in seconds), passing each output line through `transform`
+    pub async fn run_transformed_with_timeout<F>(
+        &self,
+        cmd: &str,
+        args: &[&str],
+        timeout_secs: u64,
+        transform: F,
+    ) -> Result<bool>
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        run_command_transformed_with_timeout(self.tx, cmd, args, Some(timeout_secs), transform).await
     }
 
     /// Send a step complete message
@@ -73,17 +82,28 @@ impl<'a> CommandRunner<'a> {
         Ok(())
     }
 
+    /// Send a step skipped message
+    pub async fn step_skipped(&self, step: &str) -> Result<()> {
+        self.tx
+            .send(CommandMessage::StepSkipped {
+                step: step.to_string(),
+            })
+            .await?;
+        Ok(())
+    }
+
     /// Send a step failed message
     pub async fn step_failed(&self, step: &str, error_msg: &str, operation: &str) -> Result<()> {
+        let error = ParsedError::from_stderr(
+            error_msg,
+            ErrorContext {
+                operation: operation.to_string(),
+            },
+        );
         self.tx
             .send(CommandMessage::StepFailed {
                 step: step.to_string(),
-                error: ParsedError::from_stderr(
-                    error_msg,
-                    ErrorContext {
-                        operation: operation.to_string(),
-                    },
-                ),
+                error: error.with_network_diagnosis().await,
             })
             .await?;
         Ok(())
@@ -94,34 +114,6 @@ impl<'a> CommandRunner<'a> {
         self.tx.send(CommandMessage::Done { success }).await?;
         Ok(())
     }
-
-    /// Run a simple operation with header, command execution, and footer
-    /// Returns the success status
-    pub async fn run_simple_operation(
-        &self,
-        title: &str,
-        cmd: &str,
-        args: &[&str],
-        success_msg: &str,
-        failure_msg: &str,
-    ) -> Result<bool> {
-        self.header(title).await;
-
-        let success = self.run(cmd, args).await?;
-
-        if success {
-            self.out("").await;
-            self.out(&format!("  {}", success_msg)).await;
-        } else {
-            self.out("").await;
-            self.out(&format!("  {}", failure_msg)).await;
-        }
-
-        self.footer().await;
-        self.done(success).await?;
-
-        Ok(success)
-    }
 }
 
 /// Spawn an async command with standard error handling
@@ -168,15 +160,16 @@ where
     tokio::spawn(async move {
         if let Err(e) = f(tx.clone()).await {
             tracing::error!("{} failed: {}", operation, e);
+            let error = ParsedError::from_stderr(
+                &e.to_string(),
+                ErrorContext {
+                    operation: operation.to_string(),
+                },
+            );
             let _ = tx
                 .send(CommandMessage::StepFailed {
                     step: step.to_string(),
-                    error: ParsedError::from_stderr(
-                        &e.to_string(),
-                        ErrorContext {
-                            operation: operation.to_string(),
-                        },
-                    ),
+                    error: error.with_network_diagnosis().await,
                 })
                 .await;
             let _ = tx.send(CommandMessage::Done { success: false }).await;
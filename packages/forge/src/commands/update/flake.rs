@@ -179,7 +179,7 @@ pub async fn parse_flake_changes(dir: &Path) -> Result<Vec<FlakeInputChange>> {
 }
 
 /// Fetch commit messages from GitHub API for each changed input
-async fn fetch_commits_for_changes(changes: &mut Vec<FlakeInputChange>) {
+async fn fetch_commits_for_changes(changes: &mut [FlakeInputChange]) {
     let client = match reqwest::Client::builder()
         .user_agent("forge-nixos-tool")
         .timeout(std::time::Duration::from_secs(10))
@@ -8,20 +8,12 @@ use crate::commands::executor::{get_output, run_capture};
 use crate::commands::CommandMessage;
 
 /// Result of package comparison containing version changes and closure summary
+#[derive(Default)]
 pub struct PackageCompareResult {
     pub changes: Vec<(String, String, String)>,
     pub closure_summary: Option<String>,
 }
 
-impl Default for PackageCompareResult {
-    fn default() -> Self {
-        Self {
-            changes: Vec::new(),
-            closure_summary: None,
-        }
-    }
-}
-
 /// Compare current system generation to previous generation using nvd
 pub async fn parse_package_changes_from_history(
     tx: &mpsc::Sender<CommandMessage>,
@@ -59,6 +59,12 @@ pub const INSTALL_MOUNT_POINT: &str = "/mnt";
 /// Symlink path during installation
 pub const INSTALL_SYMLINK_PATH: &str = "/mnt/etc/nixos";
 
+/// NetworkManager connection profiles on the installed system
+pub const INSTALL_NM_CONNECTIONS_PATH: &str = "/mnt/etc/NetworkManager/system-connections";
+
+/// Install logs on the installed system
+pub const INSTALL_LOG_PATH: &str = "/mnt/var/log/forge";
+
 // =============================================================================
 // Forge Data Paths (relative to home directory)
 // =============================================================================
@@ -66,6 +72,12 @@ pub const INSTALL_SYMLINK_PATH: &str = "/mnt/etc/nixos";
 /// Forge data directory (relative to home)
 pub const FORGE_DATA_DIR: &str = ".local/share/forge";
 
+/// Restore rollback area (relative to home)
+pub const FORGE_ROLLBACK_DIR: &str = ".local/share/forge/rollback";
+
+/// Age key rotation audit log filename (in the forge data dir)
+pub const KEY_ROTATION_LOG_FILE: &str = "key-rotations.log";
+
 /// Forge log filename
 pub const FORGE_LOG_FILE: &str = "forge.log";
 
@@ -85,6 +97,9 @@ pub const BROWSER_BACKUP_DATA_DIR_LEGACY: &str = ".local/share/browser-backup";
 /// App backup config directory (relative to home)
 pub const APP_BACKUP_CONFIG_DIR: &str = ".config/app-backup";
 
+/// Legacy browser backup config directory (relative to home)
+pub const BROWSER_BACKUP_CONFIG_DIR_LEGACY: &str = ".config/browser-backup";
+
 /// App backup config filename
 pub const APP_BACKUP_CONFIG_FILE: &str = "config";
 
@@ -123,10 +138,18 @@ pub fn app_backup_data_dir() -> PathBuf {
         .unwrap_or_default()
 }
 
-/// Get the app backup config file path
+/// Get the app backup config file path, falling back to the legacy location
 pub fn app_backup_config_path() -> PathBuf {
     dirs::home_dir()
-        .map(|h| h.join(APP_BACKUP_CONFIG_DIR).join(APP_BACKUP_CONFIG_FILE))
+        .map(|h| {
+            let new_path = h.join(APP_BACKUP_CONFIG_DIR).join(APP_BACKUP_CONFIG_FILE);
+            let legacy_path = h.join(BROWSER_BACKUP_CONFIG_DIR_LEGACY).join(APP_BACKUP_CONFIG_FILE);
+            if !new_path.exists() && legacy_path.exists() {
+                legacy_path
+            } else {
+                new_path
+            }
+        })
         .unwrap_or_default()
 }
 
@@ -262,6 +285,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn test_buffer_constants_reasonable() {
         assert!(OUTPUT_BUFFER_SIZE > 0);
         assert!(OUTPUT_BUFFER_SIZE <= 10000); // Reasonable upper bound
@@ -2,6 +2,7 @@
 //! Copyright Cybex B.V.
 
 mod app;
+mod backup;
 mod commands;
 mod constants;
 mod system;
@@ -44,6 +45,20 @@ enum Commands {
         hostname: Option<String>,
         /// Target disk device (e.g., /dev/nvme0n1)
         disk: Option<String>,
+        /// Configuration repository to install from (e.g., a fork)
+        #[arg(long, value_name = "URL")]
+        repo: Option<String>,
+        /// Branch, tag or commit to install (default: the repository's default branch)
+        #[arg(long = "ref", value_name = "REF")]
+        git_ref: Option<String>,
+        /// Install without network from a bundle made by 'forge bundle create'
+        #[arg(long, value_name = "BUNDLE", conflicts_with_all = ["repo", "git_ref"])]
+        offline: Option<std::path::PathBuf>,
+    },
+    /// Offline install bundles
+    Bundle {
+        #[command(subcommand)]
+        action: BundleAction,
     },
     /// Create a new host configuration
     CreateHost {
@@ -63,6 +78,38 @@ enum Commands {
         #[command(subcommand)]
         action: KeysAction,
     },
+    /// Diagnose common problems
+    Doctor {
+        #[command(subcommand)]
+        check: DoctorCheck,
+    },
+}
+
+#[derive(Subcommand)]
+enum BundleAction {
+    /// Export a host's configuration and system closure for 'forge install --offline'
+    Create {
+        /// Host to bundle
+        host: String,
+        /// Directory to write the bundle into
+        #[arg(short, long, default_value = ".")]
+        output: std::path::PathBuf,
+        /// Configuration repository to bundle (e.g., a fork)
+        #[arg(long, value_name = "URL")]
+        repo: Option<String>,
+        /// Branch, tag or commit to bundle (default: the repository's default branch)
+        #[arg(long = "ref", value_name = "REF")]
+        git_ref: Option<String>,
+        /// Also pack the bundle into a .tar next to it
+        #[arg(long)]
+        tar: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum DoctorCheck {
+    /// Probe interfaces, routing, DNS, proxy, captive portal and HTTPS access
+    Network,
 }
 
 #[derive(Subcommand)]
@@ -72,21 +119,58 @@ enum AppsAction {
         /// Force backup even if apps are running
         #[arg(short, long)]
         force: bool,
+        /// Only back up these apps (e.g. firefox,slack)
+        #[arg(long, value_delimiter = ',')]
+        only: Vec<String>,
     },
     /// Pull and restore app profiles from GitHub
     Restore {
         /// Force restore even if apps are running
         #[arg(short, long)]
         force: bool,
+        /// Only restore these apps (e.g. firefox,slack)
+        #[arg(long, value_delimiter = ',')]
+        only: Vec<String>,
+        /// Restore an older snapshot (hash from 'forge apps history')
+        #[arg(long, value_name = "SNAPSHOT")]
+        at: Option<String>,
+    },
+    /// Put back the profiles replaced by the last restore
+    UndoRestore {
+        /// Undo even if apps are running
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// List backup snapshots and pick one to restore
+    History {
+        /// Only restore these apps from the picked snapshot
+        #[arg(long, value_delimiter = ',')]
+        only: Vec<String>,
     },
     /// Check for app profile updates
     Status,
+    /// Back up app profiles automatically with a systemd user timer
+    Schedule {
+        /// Hours between backups
+        #[arg(long, value_name = "HOURS", default_value_t = 24, value_parser = clap::value_parser!(u64).range(1..))]
+        every: u64,
+        /// Remove the timer
+        #[arg(long, conflicts_with = "run")]
+        disable: bool,
+        /// Back up now if one is due (run by the timer)
+        #[arg(long, hide = true)]
+        run: bool,
+    },
 }
 
 #[derive(Subcommand)]
 enum KeysAction {
-    /// Setup keys from 1Password (one-time initial setup)
-    Setup,
+    /// Setup keys from the secret provider (one-time initial setup)
+    Setup {
+        /// Force overwrite of existing keys
+        #[arg(short, long)]
+        force: bool,
+    },
     /// Backup keys to passphrase-encrypted archive
     Backup,
     /// Restore keys from passphrase-encrypted archive
@@ -95,8 +179,32 @@ enum KeysAction {
         #[arg(short, long)]
         force: bool,
     },
-    /// Show key status
+    /// Show key fingerprints and audit their permissions
     Status,
+    /// Replace the age key and re-encrypt all backups to it
+    Rotate {
+        /// Only list the files that would be re-encrypted
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Print the age key as Shamir shares for paper backup
+    PaperBackup {
+        /// Number of shares to create
+        #[arg(long, default_value_t = 5)]
+        shares: u8,
+        /// Number of shares needed to restore the key
+        #[arg(long, default_value_t = 3)]
+        threshold: u8,
+        /// Also write the printable sheets to this file
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Rebuild the age key from typed-in paper shares
+    PaperRestore {
+        /// Force overwrite of an existing age key
+        #[arg(short, long)]
+        force: bool,
+    },
 }
 
 #[tokio::main]
@@ -118,8 +226,41 @@ async fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Some(Commands::Install { hostname, disk }) => {
-            run_tui(AppMode::Install(app::InstallState::new(hostname, disk))).await
+        Some(Commands::Install { hostname, disk, repo, git_ref, offline }) => {
+            let offline = offline.map(|path| system::bundle::Bundle::load(&path)).transpose()?;
+            let source = match &offline {
+                Some(bundle) => {
+                    if hostname.as_ref().is_some_and(|h| *h != bundle.manifest.host) {
+                        anyhow::bail!("The bundle is for host '{}'", bundle.manifest.host);
+                    }
+                    bundle.source()
+                }
+                None => system::repo::RepoSource::new(repo.as_deref(), git_ref.as_deref())
+                    .map_err(anyhow::Error::msg)?,
+            };
+            let mut state = if hostname.is_none() && source != system::repo::RepoSource::default() {
+                // List the hosts of the requested repository
+                app::InstallState::CloneRepository {
+                    output: std::collections::VecDeque::new(),
+                }
+            } else {
+                app::InstallState::new(hostname, disk)
+            };
+            if system::is_live_iso_environment() && offline.is_none() {
+                state = app::InstallState::new_network(state);
+            }
+            let mut app = App::new(AppMode::Install(state));
+            app.repo_source = source;
+            app.offline = offline;
+            run_tui_app(app).await
+        }
+        Some(Commands::Bundle {
+            action: BundleAction::Create { host, output, repo, git_ref, tar },
+        }) => {
+            let source = system::repo::RepoSource::new(repo.as_deref(), git_ref.as_deref())
+                .map_err(anyhow::Error::msg)?;
+            commands::bundle::create(&host, &source, &output, tar).await?;
+            Ok(())
         }
         Some(Commands::CreateHost { hostname: _ }) => {
             // Hostname is now entered at the end of the wizard, so we always start with hardware detection
@@ -127,20 +268,34 @@ async fn main() -> Result<()> {
         }
         Some(Commands::Update) => run_tui(AppMode::Update(app::UpdateState::new())).await,
         Some(Commands::Apps { action }) => match action {
-            Some(AppsAction::Backup { force }) => {
-                run_tui(AppMode::Apps(app::AppProfileState::new_backup(force))).await
+            Some(AppsAction::Backup { force, only }) => {
+                backup::profiles::select(&only)?;
+                run_tui(AppMode::Apps(app::AppProfileState::new_backup(force, only))).await
+            }
+            Some(AppsAction::Restore { force, only, at }) => {
+                backup::profiles::select(&only)?;
+                run_tui(AppMode::Apps(app::AppProfileState::new_restore(force, only, at))).await
+            }
+            Some(AppsAction::UndoRestore { force }) => {
+                run_tui(AppMode::Apps(app::AppProfileState::new_undo(force))).await
             }
-            Some(AppsAction::Restore { force }) => {
-                run_tui(AppMode::Apps(app::AppProfileState::new_restore(force))).await
+            Some(AppsAction::History { only }) => {
+                backup::profiles::select(&only)?;
+                run_tui(AppMode::Apps(app::AppProfileState::new_history(only))).await
             }
             Some(AppsAction::Status) => {
                 run_tui(AppMode::Apps(app::AppProfileState::new_status())).await
             }
+            Some(AppsAction::Schedule { every, run: true, .. }) => backup::schedule::run(every).await,
+            Some(AppsAction::Schedule { every, disable, .. }) => {
+                let every_hours = (!disable).then_some(every);
+                run_tui(AppMode::Apps(app::AppProfileState::new_schedule(every_hours))).await
+            }
             None => run_tui(AppMode::Apps(app::AppProfileState::new_menu())).await,
         },
         Some(Commands::Keys { action }) => match action {
-            KeysAction::Setup => {
-                run_tui(AppMode::Keys(app::KeysState::new_setup())).await
+            KeysAction::Setup { force } => {
+                run_tui(AppMode::Keys(app::KeysState::new_setup(force))).await
             }
             KeysAction::Backup => {
                 run_tui(AppMode::Keys(app::KeysState::new_backup())).await
@@ -151,12 +306,39 @@ async fn main() -> Result<()> {
             KeysAction::Status => {
                 run_tui(AppMode::Keys(app::KeysState::new_status())).await
             }
+            KeysAction::Rotate { dry_run } => {
+                run_tui(AppMode::Keys(app::KeysState::new_rotate(dry_run))).await
+            }
+            KeysAction::PaperBackup {
+                shares,
+                threshold,
+                output,
+            } => {
+                run_tui(AppMode::Keys(app::KeysState::new_paper_backup(shares, threshold, output))).await
+            }
+            KeysAction::PaperRestore { force } => {
+                run_tui(AppMode::Keys(app::KeysState::new_paper_restore(force))).await
+            }
         },
+        Some(Commands::Doctor { check: DoctorCheck::Network }) => {
+            let report = system::diagnostics::diagnose().await;
+            for line in report.lines() {
+                println!("{}", line);
+            }
+            if report.cause.is_some() {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
         None => run_tui(AppMode::MainMenu { selected: 0 }).await,
     }
 }
 
 async fn run_tui(initial_mode: AppMode) -> Result<()> {
+    run_tui_app(App::new(initial_mode)).await
+}
+
+async fn run_tui_app(mut app: App) -> Result<()> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -164,9 +346,6 @@ async fn run_tui(initial_mode: AppMode) -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Create app state
-    let mut app = App::new(initial_mode);
-
     // Create command channel
     let (cmd_tx, mut cmd_rx) = mpsc::channel::<CommandMessage>(constants::COMMAND_CHANNEL_SIZE);
     app.set_command_sender(cmd_tx);
@@ -0,0 +1,378 @@
+//! Attribution of incoming config commits to hosts
+//!
+//! Maps the paths a commit touches to the hosts they affect, so a multi-host
+//! repo can tell "3 commits, 1 affects this host":
+//! - `hosts/<name>/...` and `modules/disko/<name>.nix` affect only that host
+//! - other `modules/...` files affect the hosts whose configs import them,
+//!   or every host if they're pulled in by shared code (flake.nix, modules/,
+//!   home/) or not referenced at all
+//! - `modules/iso/...` and Markdown files affect no installed host
+//! - `home/...`, `flake.nix`, `flake.lock` and everything else affect all hosts
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Hosts directory in the config repo
+const HOSTS_DIR: &str = "hosts";
+
+/// Modules directory in the config repo
+const MODULES_DIR: &str = "modules";
+
+/// Per-host disko configs live here as `<host>.nix`
+const DISKO_DIR: &str = "modules/disko";
+
+/// Installer ISO modules (never part of an installed system)
+const ISO_DIR: &str = "modules/iso";
+
+/// Directories whose .nix files are shared by every host
+const SHARED_DIRS: &[&str] = &["modules", "home"];
+
+/// Kernel hostname, preferred over /etc/hostname
+const PROC_HOSTNAME: &str = "/proc/sys/kernel/hostname";
+
+/// Fallback hostname file
+const ETC_HOSTNAME: &str = "/etc/hostname";
+
+/// Which hosts a change affects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scope {
+    /// Every host built from the repo
+    AllHosts,
+    /// Only the named hosts
+    Hosts(BTreeSet<String>),
+    /// No installed host (docs, installer ISO)
+    NoHosts,
+}
+
+impl Scope {
+    /// Combine two scopes (a commit affects the union of its paths' scopes)
+    pub fn union(self, other: Scope) -> Scope {
+        match (self, other) {
+            (Scope::AllHosts, _) | (_, Scope::AllHosts) => Scope::AllHosts,
+            (Scope::NoHosts, s) | (s, Scope::NoHosts) => s,
+            (Scope::Hosts(mut a), Scope::Hosts(b)) => {
+                a.extend(b);
+                Scope::Hosts(a)
+            }
+        }
+    }
+
+    /// Returns true if the given host is affected
+    pub fn affects(&self, host: &str) -> bool {
+        match self {
+            Scope::AllHosts => true,
+            Scope::Hosts(hosts) => hosts.contains(host),
+            Scope::NoHosts => false,
+        }
+    }
+}
+
+/// A pending config commit with the hosts it affects
+#[derive(Debug, Clone)]
+pub struct ConfigCommit {
+    pub hash: String,
+    pub message: String,
+    pub scope: Scope,
+    /// Whether the commit affects the machine we're running on
+    /// (true when the hostname is unknown, to stay on the safe side)
+    pub affects_this_host: bool,
+}
+
+/// Nix sources used to resolve which hosts import a module
+#[derive(Debug, Default)]
+pub struct HostIndex {
+    /// (host name, concatenated .nix sources of hosts/<name>/)
+    hosts: Vec<(String, String)>,
+    /// flake.nix plus every .nix file under the shared directories
+    shared: String,
+}
+
+impl HostIndex {
+    /// Build the index from a checked-out config repo
+    pub fn load(repo_dir: &Path) -> Self {
+        let mut index = HostIndex::default();
+
+        if let Ok(entries) = std::fs::read_dir(repo_dir.join(HOSTS_DIR)) {
+            for entry in entries.flatten() {
+                if entry.path().is_dir() {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    let mut text = String::new();
+                    collect_nix_sources(&entry.path(), &mut text);
+                    index.hosts.push((name, text));
+                }
+            }
+        }
+
+        index.shared = std::fs::read_to_string(repo_dir.join("flake.nix")).unwrap_or_default();
+        for dir in SHARED_DIRS {
+            collect_nix_sources(&repo_dir.join(dir), &mut index.shared);
+        }
+
+        index
+    }
+
+    /// Determine which hosts a single changed path affects
+    pub fn scope_for_path(&self, path: &str) -> Scope {
+        if path.ends_with(".md") || path.starts_with(&format!("{}/", ISO_DIR)) {
+            return Scope::NoHosts;
+        }
+
+        if let Some(rest) = path.strip_prefix(&format!("{}/", HOSTS_DIR)) {
+            if let Some((host, _)) = rest.split_once('/') {
+                return Scope::Hosts(BTreeSet::from([host.to_string()]));
+            }
+        }
+
+        if let Some(file) = path.strip_prefix(&format!("{}/", DISKO_DIR)) {
+            if let Some(host) = file.strip_suffix(".nix") {
+                if self.hosts.iter().any(|(name, _)| name == host) {
+                    return Scope::Hosts(BTreeSet::from([host.to_string()]));
+                }
+            }
+        }
+
+        if path.starts_with(&format!("{}/", MODULES_DIR)) {
+            return self.module_scope(path);
+        }
+
+        Scope::AllHosts
+    }
+
+    /// Scope of a module file based on who references it
+    fn module_scope(&self, path: &str) -> Scope {
+        let needles = module_references(path);
+        let is_referenced = |text: &str| needles.iter().any(|n| references(text, n));
+
+        if is_referenced(&self.shared) {
+            return Scope::AllHosts;
+        }
+
+        let hosts: BTreeSet<String> = self
+            .hosts
+            .iter()
+            .filter(|(_, text)| is_referenced(text))
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        if hosts.is_empty() {
+            // Not imported by any host directly - could be pulled in some way we
+            // don't understand, so assume it affects everyone
+            Scope::AllHosts
+        } else {
+            Scope::Hosts(hosts)
+        }
+    }
+
+    /// Determine which hosts a set of changed paths affects
+    pub fn scope_for_paths<S: AsRef<str>>(&self, paths: &[S]) -> Scope {
+        if paths.is_empty() {
+            // Merge commits and the like: nothing to go on
+            return Scope::AllHosts;
+        }
+
+        paths
+            .iter()
+            .map(|p| self.scope_for_path(p.as_ref()))
+            .fold(Scope::NoHosts, Scope::union)
+    }
+}
+
+/// Append all .nix files below `dir` to `out`
+fn collect_nix_sources(dir: &Path, out: &mut String) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_nix_sources(&path, out);
+        } else if path.extension().is_some_and(|ext| ext == "nix") {
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                out.push_str(&content);
+                out.push('\n');
+            }
+        }
+    }
+}
+
+/// Strings that would appear in a Nix import of the given module path:
+/// the file itself and each enclosing directory (for directory imports)
+fn module_references(path: &str) -> Vec<String> {
+    let mut needles = vec![path.to_string()];
+    let mut current = path;
+    while let Some((parent, _)) = current.rsplit_once('/') {
+        if parent == MODULES_DIR {
+            break;
+        }
+        needles.push(parent.to_string());
+        current = parent;
+    }
+    needles
+}
+
+/// Returns true if `text` contains `needle` as a complete path
+/// (i.e. not as a prefix of a longer path or file name)
+fn references(text: &str, needle: &str) -> bool {
+    text.match_indices(needle).any(|(pos, _)| {
+        text[pos + needle.len()..]
+            .chars()
+            .next()
+            .is_none_or(|c| !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/')))
+    })
+}
+
+/// Parse `git log --pretty=format:%x1e%h|%s --name-only` output into
+/// (hash, message, changed paths) triples
+pub fn parse_git_log_with_files(output: &str) -> Vec<(String, String, Vec<String>)> {
+    output
+        .split('\x1e')
+        .filter(|record| !record.trim().is_empty())
+        .map(|record| {
+            let mut lines = record.lines();
+            let header = lines.next().unwrap_or("");
+            let (hash, message) = header.split_once('|').unwrap_or((header, ""));
+            let files = lines
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(String::from)
+                .collect();
+            (hash.to_string(), message.to_string(), files)
+        })
+        .collect()
+}
+
+/// Hostname of the running machine
+pub fn current_hostname() -> Option<String> {
+    [PROC_HOSTNAME, ETC_HOSTNAME]
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .map(|s| s.trim().to_string())
+        .find(|s| !s.is_empty())
+}
+
+/// Human-readable "N affect this host" suffix for a commit count
+pub fn host_impact_label(total: usize, affecting: usize) -> String {
+    match (total, affecting) {
+        (1, 1) => "affects this host".to_string(),
+        (1, 0) => "does not affect this host".to_string(),
+        (_, 0) => "none affect this host".to_string(),
+        (t, a) if t == a => "all affect this host".to_string(),
+        (_, 1) => "1 affects this host".to_string(),
+        (_, a) => format!("{} affect this host", a),
+    }
+}
+
+/// Build a commit for tests elsewhere in the notify module
+#[cfg(test)]
+pub(crate) fn test_commit(hash: &str, affects_this_host: bool) -> ConfigCommit {
+    ConfigCommit {
+        hash: hash.to_string(),
+        message: "Test commit".to_string(),
+        scope: Scope::AllHosts,
+        affects_this_host,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> HostIndex {
+        HostIndex {
+            hosts: vec![
+                (
+                    "x1yoga".to_string(),
+                    "imports = [ ../../modules/hardware/intel.nix ../../modules/boot/limine-plymouth.nix ];".to_string(),
+                ),
+                (
+                    "xps9320".to_string(),
+                    "imports = [ ../../modules/hardware/intel.nix ../../modules/gaming.nix ../../modules/boot ];".to_string(),
+                ),
+            ],
+            shared: "modules = [ ./modules/common.nix ./modules/disko/${hostname}.nix ];".to_string(),
+        }
+    }
+
+    fn hosts(names: &[&str]) -> Scope {
+        Scope::Hosts(names.iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn test_host_paths() {
+        let index = index();
+        assert_eq!(index.scope_for_path("hosts/x1yoga/default.nix"), hosts(&["x1yoga"]));
+        assert_eq!(index.scope_for_path("modules/disko/xps9320.nix"), hosts(&["xps9320"]));
+        assert_eq!(index.scope_for_path("modules/disko/default.nix"), Scope::AllHosts);
+    }
+
+    #[test]
+    fn test_module_paths() {
+        let index = index();
+        assert_eq!(index.scope_for_path("modules/gaming.nix"), hosts(&["xps9320"]));
+        assert_eq!(index.scope_for_path("modules/hardware/intel.nix"), hosts(&["x1yoga", "xps9320"]));
+        // xps9320 imports the whole boot directory
+        assert_eq!(
+            index.scope_for_path("modules/boot/limine-plymouth.nix"),
+            hosts(&["x1yoga", "xps9320"])
+        );
+        assert_eq!(index.scope_for_path("modules/common.nix"), Scope::AllHosts);
+        // Unreferenced modules are assumed to affect everyone
+        assert_eq!(index.scope_for_path("modules/intune.nix"), Scope::AllHosts);
+        assert_eq!(index.scope_for_path("modules/iso/default.nix"), Scope::NoHosts);
+    }
+
+    #[test]
+    fn test_shared_paths() {
+        let index = index();
+        assert_eq!(index.scope_for_path("flake.lock"), Scope::AllHosts);
+        assert_eq!(index.scope_for_path("home/neovim.nix"), Scope::AllHosts);
+        assert_eq!(index.scope_for_path("README.md"), Scope::NoHosts);
+    }
+
+    #[test]
+    fn test_references_requires_full_path() {
+        assert!(references("../../modules/gaming.nix ]", "modules/gaming.nix"));
+        assert!(!references("../../modules/gaming.nix.bak", "modules/gaming.nix"));
+        assert!(!references("../../modules/boot/x.nix", "modules/boot"));
+        assert!(references("../../modules/boot\n", "modules/boot"));
+    }
+
+    #[test]
+    fn test_scope_for_paths_union() {
+        let index = index();
+        assert_eq!(
+            index.scope_for_paths(&["hosts/x1yoga/default.nix", "README.md"]),
+            hosts(&["x1yoga"])
+        );
+        assert_eq!(
+            index.scope_for_paths(&["hosts/x1yoga/default.nix", "hosts/xps9320/default.nix"]),
+            hosts(&["x1yoga", "xps9320"])
+        );
+        assert_eq!(
+            index.scope_for_paths(&["hosts/x1yoga/default.nix", "flake.lock"]),
+            Scope::AllHosts
+        );
+        assert_eq!(index.scope_for_paths::<&str>(&[]), Scope::AllHosts);
+    }
+
+    #[test]
+    fn test_parse_git_log_with_files() {
+        let output = "\x1eabc1234|Update x1yoga\nhosts/x1yoga/default.nix\n\n\x1edef5678|Bump inputs\nflake.lock\nREADME.md\n";
+        let commits = parse_git_log_with_files(output);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].0, "abc1234");
+        assert_eq!(commits[0].1, "Update x1yoga");
+        assert_eq!(commits[0].2, vec!["hosts/x1yoga/default.nix"]);
+        assert_eq!(commits[1].2, vec!["flake.lock", "README.md"]);
+    }
+
+    #[test]
+    fn test_host_impact_label() {
+        assert_eq!(host_impact_label(3, 1), "1 affects this host");
+        assert_eq!(host_impact_label(3, 2), "2 affect this host");
+        assert_eq!(host_impact_label(3, 3), "all affect this host");
+        assert_eq!(host_impact_label(3, 0), "none affect this host");
+        assert_eq!(host_impact_label(1, 1), "affects this host");
+    }
+}
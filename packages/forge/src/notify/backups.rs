@@ -0,0 +1,166 @@
+//! Record of app profile backups
+//!
+//! forge writes the outcome of each backup run here, manual or scheduled;
+//! forge-notify reads it to warn when the last successful backup is too old.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::paths::backup_state_path;
+
+/// Outcome of the most recent backup runs
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupRecord {
+    /// Last time a backup completed
+    pub last_success: Option<DateTime<Utc>>,
+    /// Last time a backup ran or was skipped
+    pub last_attempt: Option<DateTime<Utc>>,
+    /// Why the last scheduled run did not back up
+    pub last_skip: Option<String>,
+    /// Error of the last failed run
+    pub last_error: Option<String>,
+}
+
+/// The last successful backup is older than allowed
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaleBackup {
+    /// None if no backup has succeeded yet
+    pub last_success: Option<DateTime<Utc>>,
+}
+
+impl StaleBackup {
+    /// Short description for notifications, e.g. "last app backup 3 days ago"
+    pub fn describe(&self, now: DateTime<Utc>) -> String {
+        let Some(last) = self.last_success else {
+            return "no app backup has succeeded yet".to_string();
+        };
+        let hours = now.signed_duration_since(last).num_hours();
+        if hours < 48 {
+            format!("last app backup {} hours ago", hours)
+        } else {
+            format!("last app backup {} days ago", hours / 24)
+        }
+    }
+}
+
+impl BackupRecord {
+    /// Load the record, or an empty one if no backup ran yet
+    pub fn load() -> Result<Self> {
+        Self::load_from(&backup_state_path())
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Save the record to disk
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&backup_state_path())
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn record_success(&mut self, now: DateTime<Utc>) {
+        self.last_success = Some(now);
+        self.last_attempt = Some(now);
+        self.last_skip = None;
+        self.last_error = None;
+    }
+
+    pub fn record_skip(&mut self, now: DateTime<Utc>, reason: &str) {
+        self.last_attempt = Some(now);
+        self.last_skip = Some(reason.to_string());
+    }
+
+    pub fn record_failure(&mut self, now: DateTime<Utc>, error: &str) {
+        self.last_attempt = Some(now);
+        self.last_skip = None;
+        self.last_error = Some(error.to_string());
+    }
+
+    /// Whether a backup is due, `interval` after the last successful one
+    pub fn due(&self, interval: Duration, now: DateTime<Utc>) -> bool {
+        self.last_success
+            .map(|last| now.signed_duration_since(last) >= interval)
+            .unwrap_or(true)
+    }
+
+    /// The last success if it is older than `max_age`
+    ///
+    /// Machines that never backed up aren't reported, so the warning only
+    /// shows up once backups are in use.
+    pub fn stale(&self, max_age: Duration, now: DateTime<Utc>) -> Option<StaleBackup> {
+        if self.last_success.is_none() && self.last_attempt.is_none() {
+            return None;
+        }
+        self.due(max_age, now).then_some(StaleBackup {
+            last_success: self.last_success,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hours: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).unwrap() + Duration::hours(hours)
+    }
+
+    #[test]
+    fn test_stale_after_max_age() {
+        let mut record = BackupRecord::default();
+        assert_eq!(record.stale(Duration::hours(72), at(0)), None);
+        assert!(record.due(Duration::hours(24), at(0)));
+
+        record.record_success(at(0));
+        assert!(!record.due(Duration::hours(24), at(23)));
+        assert!(record.due(Duration::hours(24), at(24)));
+        assert_eq!(record.stale(Duration::hours(72), at(71)), None);
+
+        let stale = record.stale(Duration::hours(72), at(80)).unwrap();
+        assert_eq!(stale.last_success, Some(at(0)));
+        assert_eq!(stale.describe(at(80)), "last app backup 3 days ago");
+        assert_eq!(stale.describe(at(30)), "last app backup 30 hours ago");
+    }
+
+    #[test]
+    fn test_attempts_without_success_are_stale() {
+        let mut record = BackupRecord::default();
+        record.record_skip(at(0), "Firefox is running");
+        let stale = record.stale(Duration::hours(72), at(1)).unwrap();
+        assert_eq!(stale.describe(at(1)), "no app backup has succeeded yet");
+
+        record.record_success(at(2));
+        assert_eq!(record.last_skip, None);
+        record.record_failure(at(3), "push failed");
+        assert_eq!(record.last_success, Some(at(2)));
+        assert_eq!(record.last_error.as_deref(), Some("push failed"));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("forge-backup-state-{}.json", std::process::id()));
+        let mut record = BackupRecord::default();
+        record.record_success(at(0));
+        record.save_to(&path).unwrap();
+        assert_eq!(BackupRecord::load_from(&path).unwrap(), record);
+
+        std::fs::write(&path, "{}").unwrap();
+        assert_eq!(BackupRecord::load_from(&path).unwrap(), BackupRecord::default());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -9,19 +9,20 @@ use std::process::Stdio;
 use std::time::Duration;
 use tokio::process::Command;
 
+use super::attribution::{current_hostname, parse_git_log_with_files, ConfigCommit, HostIndex};
 use super::constants::git_fetch_timeout;
 use super::paths::{app_backup_data_dir, nixos_config_dir};
 
 /// Check for nixos-config repo updates
 ///
-/// Returns a list of (hash, message) pairs for commits on origin/main
-/// that aren't in the local HEAD.
+/// Returns the commits on origin/main that aren't in the local HEAD,
+/// each attributed to the hosts its changed files affect.
 ///
 /// # Arguments
 /// * `timeout` - Optional custom timeout for git fetch. Uses default if None.
 pub async fn check_nixos_config_updates(
     timeout: Option<Duration>,
-) -> Result<Vec<(String, String)>> {
+) -> Result<Vec<ConfigCommit>> {
     let config_dir = nixos_config_dir();
 
     // If no git repo, no updates to check
@@ -43,10 +44,10 @@ pub async fn check_nixos_config_updates(
         _ => return Ok(vec![]), // Timeout or fetch failed
     }
 
-    // Get list of commits on origin/main not in HEAD
+    // Get list of commits on origin/main not in HEAD, with the files they touch
     let (ok, log_output) = run_git_output(
         &config_dir,
-        &["log", "HEAD..origin/main", "--pretty=format:%h|%s"],
+        &["log", "HEAD..origin/main", "--pretty=format:%x1e%h|%s", "--name-only"],
     )
     .await?;
 
@@ -54,15 +55,20 @@ pub async fn check_nixos_config_updates(
         return Ok(vec![]);
     }
 
-    // Parse output into (hash, message) pairs
-    let commits: Vec<(String, String)> = log_output
-        .lines()
-        .map(|line| {
-            let parts: Vec<&str> = line.splitn(2, '|').collect();
-            (
-                parts.first().unwrap_or(&"").to_string(),
-                parts.get(1).unwrap_or(&"").to_string(),
-            )
+    let index = HostIndex::load(&config_dir);
+    let hostname = current_hostname();
+
+    let commits = parse_git_log_with_files(&log_output)
+        .into_iter()
+        .map(|(hash, message, files)| {
+            let scope = index.scope_for_paths(&files);
+            let affects_this_host = hostname.as_deref().map(|h| scope.affects(h)).unwrap_or(true);
+            ConfigCommit {
+                hash,
+                message,
+                scope,
+                affects_this_host,
+            }
         })
         .collect();
 
@@ -0,0 +1,553 @@
+//! User configuration for forge-notify
+//!
+//! Loaded from `~/.config/forge/notify.toml`. Every field is optional; anything
+//! left out falls back to the compiled-in defaults from `constants.rs`.
+//!
+//! ```toml
+//! check_interval_mins = 180
+//! notification_timeout_ms = 10000
+//!
+//! [timeouts]
+//! git_fetch_secs = 10
+//! flake_check_secs = 15
+//! http_client_secs = 10
+//!
+//! [checks.config]
+//! enabled = true
+//! urgency = "normal"
+//! ignore_other_hosts = true
+//!
+//! [checks.flake]
+//! enabled = false
+//!
+//! [checks.backup]
+//! max_age_hours = 48
+//!
+//! [[inputs]]
+//! name = "nixpkgs"
+//! branch = "nixos-25.05"
+//!
+//! [quiet_hours]
+//! start = "22:00"
+//! end = "07:00"
+//!
+//! [[sinks]]
+//! type = "desktop"
+//!
+//! [[sinks]]
+//! type = "ntfy"
+//! topic = "my-forge-updates"
+//! ```
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+use super::constants::{
+    BACKUP_MAX_AGE_HOURS, FLAKE_CHECK_TIMEOUT_SECS, GIT_FETCH_TIMEOUT_SECS, HTTP_CLIENT_TIMEOUT_SECS,
+    NOTIFICATION_TIMEOUT_MS, PRIORITY_INPUTS,
+};
+use super::sinks::SinkConfig;
+
+/// Format used for quiet hour boundaries
+const QUIET_HOURS_FORMAT: &str = "%H:%M";
+
+/// Top-level forge-notify configuration
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotifyConfig {
+    /// Minimum minutes between checks (0 = check on every timer run)
+    pub check_interval_mins: u64,
+    /// How long the desktop notification stays visible (milliseconds)
+    pub notification_timeout_ms: i32,
+    /// Network timeouts
+    pub timeouts: TimeoutConfig,
+    /// Which update checks run and how loudly they notify
+    pub checks: ChecksConfig,
+    /// Flake inputs to watch for upstream updates
+    pub inputs: Vec<WatchedInput>,
+    /// Window during which notifications are held back
+    pub quiet_hours: Option<QuietHours>,
+    /// Where notifications are delivered
+    pub sinks: Vec<SinkConfig>,
+}
+
+/// Network timeouts for the individual checks
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeoutConfig {
+    pub git_fetch_secs: u64,
+    pub flake_check_secs: u64,
+    pub http_client_secs: u64,
+}
+
+/// Per-kind check settings
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChecksConfig {
+    /// nixos-config repo commits
+    pub config: CheckKind,
+    /// App profile backup repo
+    pub apps: CheckKind,
+    /// Flake inputs on GitHub
+    pub flake: CheckKind,
+    /// Age of the last successful app backup
+    pub backup: BackupCheck,
+}
+
+/// Settings for the stale backup warning
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupCheck {
+    pub enabled: bool,
+    pub urgency: Urgency,
+    /// Warn once the last successful backup is older than this
+    pub max_age_hours: u64,
+}
+
+/// Settings for a single kind of update check
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CheckKind {
+    pub enabled: bool,
+    pub urgency: Urgency,
+    /// Don't notify about config commits that only touch other hosts
+    /// (only meaningful for `checks.config`)
+    pub ignore_other_hosts: bool,
+}
+
+/// Notification urgency (mirrors the freedesktop levels)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+/// A flake input to watch, with an optional branch override
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WatchedInput {
+    /// Input name as it appears in flake.lock
+    pub name: String,
+    /// Branch to compare against (defaults to the ref in flake.lock)
+    #[serde(default)]
+    pub branch: Option<String>,
+}
+
+/// Quiet hours as local "HH:MM" times; may wrap past midnight
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            check_interval_mins: 0,
+            notification_timeout_ms: NOTIFICATION_TIMEOUT_MS,
+            timeouts: TimeoutConfig::default(),
+            checks: ChecksConfig::default(),
+            inputs: PRIORITY_INPUTS
+                .iter()
+                .map(|name| WatchedInput {
+                    name: name.to_string(),
+                    branch: None,
+                })
+                .collect(),
+            quiet_hours: None,
+            sinks: vec![SinkConfig::Desktop],
+        }
+    }
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            git_fetch_secs: GIT_FETCH_TIMEOUT_SECS,
+            flake_check_secs: FLAKE_CHECK_TIMEOUT_SECS,
+            http_client_secs: HTTP_CLIENT_TIMEOUT_SECS,
+        }
+    }
+}
+
+impl Default for CheckKind {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            urgency: Urgency::Normal,
+            ignore_other_hosts: false,
+        }
+    }
+}
+
+impl Default for BackupCheck {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            urgency: Urgency::Normal,
+            max_age_hours: BACKUP_MAX_AGE_HOURS,
+        }
+    }
+}
+
+impl BackupCheck {
+    /// Maximum backup age as a chrono Duration
+    pub fn max_age(&self) -> chrono::Duration {
+        chrono::Duration::hours(self.max_age_hours as i64)
+    }
+}
+
+impl TimeoutConfig {
+    /// Git fetch timeout as Duration
+    pub fn git_fetch(&self) -> Duration {
+        Duration::from_secs(self.git_fetch_secs)
+    }
+
+    /// Flake check timeout as Duration
+    pub fn flake_check(&self) -> Duration {
+        Duration::from_secs(self.flake_check_secs)
+    }
+
+    /// HTTP client timeout as Duration
+    pub fn http_client(&self) -> Duration {
+        Duration::from_secs(self.http_client_secs)
+    }
+}
+
+impl QuietHours {
+    /// Parse start and end into times, or None if either is malformed
+    fn bounds(&self) -> Option<(NaiveTime, NaiveTime)> {
+        let start = NaiveTime::parse_from_str(&self.start, QUIET_HOURS_FORMAT).ok()?;
+        let end = NaiveTime::parse_from_str(&self.end, QUIET_HOURS_FORMAT).ok()?;
+        Some((start, end))
+    }
+
+    /// Returns true if `time` falls inside the quiet window
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        let Some((start, end)) = self.bounds() else {
+            return false;
+        };
+
+        if start <= end {
+            time >= start && time < end
+        } else {
+            // Window wraps past midnight (e.g. 22:00 - 07:00)
+            time >= start || time < end
+        }
+    }
+}
+
+impl NotifyConfig {
+    /// Load config from the given path, or return defaults if it doesn't exist
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid config in {}", path.display()))
+    }
+
+    /// Parse config from TOML text
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// Check values that parse fine but make no sense
+    ///
+    /// Returns a list of human-readable problems (empty if the config is valid).
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.notification_timeout_ms <= 0 {
+            errors.push("notification_timeout_ms must be greater than 0".to_string());
+        }
+
+        for (name, value) in [
+            ("timeouts.git_fetch_secs", self.timeouts.git_fetch_secs),
+            ("timeouts.flake_check_secs", self.timeouts.flake_check_secs),
+            ("timeouts.http_client_secs", self.timeouts.http_client_secs),
+        ] {
+            if value == 0 {
+                errors.push(format!("{} must be greater than 0", name));
+            }
+        }
+
+        let mut seen = Vec::new();
+        for input in &self.inputs {
+            if input.name.trim().is_empty() {
+                errors.push("inputs: name must not be empty".to_string());
+                continue;
+            }
+            if seen.contains(&&input.name) {
+                errors.push(format!("inputs: '{}' is listed more than once", input.name));
+            }
+            seen.push(&input.name);
+
+            if matches!(&input.branch, Some(b) if b.trim().is_empty()) {
+                errors.push(format!("inputs: branch for '{}' must not be empty", input.name));
+            }
+        }
+
+        if let Some(quiet) = &self.quiet_hours {
+            for (name, value) in [("start", &quiet.start), ("end", &quiet.end)] {
+                if NaiveTime::parse_from_str(value, QUIET_HOURS_FORMAT).is_err() {
+                    errors.push(format!(
+                        "quiet_hours.{} '{}' is not a valid HH:MM time",
+                        name, value
+                    ));
+                }
+            }
+            if quiet.start == quiet.end {
+                errors.push("quiet_hours.start and quiet_hours.end must differ".to_string());
+            }
+        }
+
+        for (name, kind) in [("apps", &self.checks.apps), ("flake", &self.checks.flake)] {
+            if kind.ignore_other_hosts {
+                errors.push(format!("checks.{}.ignore_other_hosts only applies to checks.config", name));
+            }
+        }
+
+        if self.checks.backup.max_age_hours == 0 {
+            errors.push("checks.backup.max_age_hours must be greater than 0".to_string());
+        }
+
+        if self.sinks.is_empty() {
+            errors.push("sinks: at least one sink must be configured".to_string());
+        }
+        for sink in &self.sinks {
+            errors.extend(sink.validate());
+        }
+
+        errors
+    }
+
+    /// Returns true if a check is due given the time of the last one
+    pub fn check_due(&self, last_check: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+        if self.check_interval_mins == 0 {
+            return true;
+        }
+
+        match last_check {
+            Some(last) => {
+                let elapsed = now.signed_duration_since(last);
+                elapsed >= chrono::Duration::minutes(self.check_interval_mins as i64)
+            }
+            None => true,
+        }
+    }
+
+    /// Returns true if notifications should be held back at this local time
+    pub fn in_quiet_hours(&self, time: NaiveTime) -> bool {
+        self.quiet_hours
+            .as_ref()
+            .map(|q| q.contains(time))
+            .unwrap_or(false)
+    }
+
+    /// Highest configured urgency among the kinds that have updates
+    pub fn urgency_for(&self, status: &super::UpdateStatus) -> Urgency {
+        let mut urgency = Urgency::Low;
+
+        if !status.config_updates.is_empty() {
+            urgency = urgency.max(self.checks.config.urgency);
+        }
+        if status.app_updates {
+            urgency = urgency.max(self.checks.apps.urgency);
+        }
+        if !status.flake_updates.is_empty() {
+            urgency = urgency.max(self.checks.flake.urgency);
+        }
+        if status.stale_backup.is_some() {
+            urgency = urgency.max(self.checks.backup.urgency);
+        }
+
+        urgency
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::backups::StaleBackup;
+    use crate::notify::UpdateStatus;
+
+    fn time(s: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(s, QUIET_HOURS_FORMAT).unwrap()
+    }
+
+    #[test]
+    fn test_default_matches_constants() {
+        let config = NotifyConfig::default();
+        assert_eq!(config.timeouts.git_fetch_secs, GIT_FETCH_TIMEOUT_SECS);
+        assert_eq!(config.notification_timeout_ms, NOTIFICATION_TIMEOUT_MS);
+        assert_eq!(config.inputs.len(), PRIORITY_INPUTS.len());
+        assert!(config.checks.flake.enabled);
+        assert!(config.validate().is_empty());
+    }
+
+    #[test]
+    fn test_parse_empty_is_default() {
+        let config = NotifyConfig::parse("").unwrap();
+        assert_eq!(config.check_interval_mins, 0);
+        assert_eq!(config.inputs[0].name, "nixpkgs");
+    }
+
+    #[test]
+    fn test_parse_full() {
+        let config = NotifyConfig::parse(
+            r#"
+            check_interval_mins = 180
+
+            [timeouts]
+            git_fetch_secs = 30
+
+            [checks.apps]
+            enabled = false
+
+            [checks.config]
+            urgency = "critical"
+            ignore_other_hosts = true
+
+            [checks.backup]
+            max_age_hours = 24
+
+            [[inputs]]
+            name = "nixpkgs"
+            branch = "nixos-25.05"
+
+            [[inputs]]
+            name = "home-manager"
+
+            [quiet_hours]
+            start = "22:00"
+            end = "07:00"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.check_interval_mins, 180);
+        assert_eq!(config.timeouts.git_fetch_secs, 30);
+        assert_eq!(config.timeouts.http_client_secs, HTTP_CLIENT_TIMEOUT_SECS);
+        assert!(!config.checks.apps.enabled);
+        assert!(config.checks.config.enabled);
+        assert_eq!(config.checks.config.urgency, Urgency::Critical);
+        assert!(config.checks.config.ignore_other_hosts);
+        assert!(config.checks.backup.enabled);
+        assert_eq!(config.checks.backup.max_age(), chrono::Duration::hours(24));
+        assert_eq!(config.inputs.len(), 2);
+        assert_eq!(config.inputs[0].branch.as_deref(), Some("nixos-25.05"));
+        assert_eq!(config.inputs[1].branch, None);
+        assert!(config.validate().is_empty());
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_keys() {
+        assert!(NotifyConfig::parse("check_intervall_mins = 5").is_err());
+        assert!(NotifyConfig::parse("[checks.config]\nurgency = \"loud\"").is_err());
+    }
+
+    #[test]
+    fn test_validate_reports_problems() {
+        let config = NotifyConfig::parse(
+            r#"
+            notification_timeout_ms = 0
+
+            [timeouts]
+            http_client_secs = 0
+
+            [[inputs]]
+            name = "nixpkgs"
+
+            [[inputs]]
+            name = "nixpkgs"
+            branch = ""
+
+            [quiet_hours]
+            start = "25:00"
+            end = "07:00"
+            "#,
+        )
+        .unwrap();
+
+        let errors = config.validate();
+        assert_eq!(errors.len(), 5, "{:?}", errors);
+        assert!(errors.iter().any(|e| e.contains("notification_timeout_ms")));
+        assert!(errors.iter().any(|e| e.contains("http_client_secs")));
+        assert!(errors.iter().any(|e| e.contains("more than once")));
+        assert!(errors.iter().any(|e| e.contains("branch")));
+        assert!(errors.iter().any(|e| e.contains("quiet_hours.start")));
+    }
+
+    #[test]
+    fn test_quiet_hours_same_day() {
+        let quiet = QuietHours {
+            start: "12:00".to_string(),
+            end: "13:30".to_string(),
+        };
+        assert!(quiet.contains(time("12:00")));
+        assert!(quiet.contains(time("13:29")));
+        assert!(!quiet.contains(time("13:30")));
+        assert!(!quiet.contains(time("08:00")));
+    }
+
+    #[test]
+    fn test_quiet_hours_wrap_midnight() {
+        let quiet = QuietHours {
+            start: "22:00".to_string(),
+            end: "07:00".to_string(),
+        };
+        assert!(quiet.contains(time("23:15")));
+        assert!(quiet.contains(time("03:00")));
+        assert!(!quiet.contains(time("07:00")));
+        assert!(!quiet.contains(time("12:00")));
+    }
+
+    #[test]
+    fn test_check_due() {
+        let now = Utc::now();
+        let mut config = NotifyConfig::default();
+        assert!(config.check_due(Some(now), now));
+
+        config.check_interval_mins = 60;
+        assert!(config.check_due(None, now));
+        assert!(!config.check_due(Some(now - chrono::Duration::minutes(30)), now));
+        assert!(config.check_due(Some(now - chrono::Duration::minutes(60)), now));
+    }
+
+    #[test]
+    fn test_urgency_for_uses_highest_active_kind() {
+        let mut config = NotifyConfig::default();
+        config.checks.apps.urgency = Urgency::Critical;
+        config.checks.flake.urgency = Urgency::Low;
+
+        let flake_only = UpdateStatus {
+            flake_updates: vec!["nixpkgs".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(config.urgency_for(&flake_only), Urgency::Low);
+
+        let with_apps = UpdateStatus {
+            app_updates: true,
+            flake_updates: vec!["nixpkgs".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(config.urgency_for(&with_apps), Urgency::Critical);
+
+        config.checks.backup.urgency = Urgency::Critical;
+        let stale_backup = UpdateStatus {
+            stale_backup: Some(StaleBackup { last_success: None }),
+            ..Default::default()
+        };
+        assert_eq!(config.urgency_for(&stale_backup), Urgency::Critical);
+    }
+}
@@ -1,6 +1,7 @@
 //! Constants for forge-notify
 //!
-//! Centralized configuration values for timeouts, priority inputs, and other settings.
+//! Centralized default values for timeouts, priority inputs, and other settings.
+//! Users can override most of these in `notify.toml` (see `config.rs`).
 
 use std::time::Duration;
 
@@ -20,6 +21,9 @@ pub const HTTP_CLIENT_TIMEOUT_SECS: u64 = 10;
 /// Duration for desktop notification display (milliseconds)
 pub const NOTIFICATION_TIMEOUT_MS: i32 = 10000;
 
+/// Warn when the last successful app backup is older than this (hours)
+pub const BACKUP_MAX_AGE_HOURS: u64 = 72;
+
 // =============================================================================
 // Duration Helpers
 // =============================================================================
@@ -53,6 +57,7 @@ pub const PRIORITY_INPUTS: &[&str] = &["nixpkgs"];
 // =============================================================================
 
 /// Get the default branch for well-known repositories
+/// Used when flake.lock has no ref and the config has no branch override.
 pub fn default_branch_for_repo(owner: &str, repo: &str) -> &'static str {
     match (owner, repo) {
         ("NixOS", "nixpkgs") => "nixos-unstable",
@@ -93,6 +98,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn test_notification_timeout_is_positive() {
         assert!(NOTIFICATION_TIMEOUT_MS > 0);
     }
@@ -1,17 +1,18 @@
 //! Flake input update detection
 //!
 //! Checks if any flake inputs have newer versions available on GitHub.
-//! To minimize API calls (and avoid rate limiting), only checks nixpkgs
-//! by default since that's where most updates come from.
+//! To minimize API calls (and avoid rate limiting), only checks the inputs
+//! listed in the notify config (nixpkgs by default, since that's where most
+//! updates come from).
 
 use anyhow::Result;
 use serde::Deserialize;
 use std::collections::HashMap;
 
-use super::constants::{
-    default_branch_for_repo, flake_check_timeout, http_client_timeout, PRIORITY_INPUTS,
-};
+use super::config::{NotifyConfig, WatchedInput};
+use super::constants::default_branch_for_repo;
 use super::paths::nixos_config_dir;
+use std::time::Duration;
 
 /// Flake.lock JSON structure
 #[derive(Debug, Deserialize)]
@@ -48,8 +49,8 @@ struct InputConfig {
     current_rev: String,
 }
 
-/// Check for flake input updates (only checks priority inputs to save API calls)
-pub async fn check_flake_updates() -> Result<Vec<String>> {
+/// Check for flake input updates (only checks watched inputs to save API calls)
+pub async fn check_flake_updates(config: &NotifyConfig) -> Result<Vec<String>> {
     let config_dir = nixos_config_dir();
     let lock_path = config_dir.join("flake.lock");
 
@@ -61,8 +62,8 @@ pub async fn check_flake_updates() -> Result<Vec<String>> {
     let content = std::fs::read_to_string(&lock_path)?;
     let lock: FlakeLock = serde_json::from_str(&content)?;
 
-    // Find GitHub inputs to check (only priority inputs)
-    let inputs = extract_priority_inputs(&lock);
+    // Find GitHub inputs to check (only watched inputs)
+    let inputs = extract_watched_inputs(&lock, &config.inputs);
 
     if inputs.is_empty() {
         return Ok(vec![]);
@@ -70,8 +71,8 @@ pub async fn check_flake_updates() -> Result<Vec<String>> {
 
     // Check inputs with timeout (typically just 1 API call for nixpkgs)
     let updates = tokio::time::timeout(
-        flake_check_timeout(),
-        check_inputs_rest(inputs),
+        config.timeouts.flake_check(),
+        check_inputs_rest(inputs, config.timeouts.http_client()),
     )
     .await
     .unwrap_or_else(|_| Ok(vec![]))?;
@@ -79,14 +80,18 @@ pub async fn check_flake_updates() -> Result<Vec<String>> {
     Ok(updates)
 }
 
-/// Extract only priority GitHub inputs from flake.lock (to minimize API calls)
-fn extract_priority_inputs(lock: &FlakeLock) -> Vec<(String, InputConfig)> {
+/// Extract only watched GitHub inputs from flake.lock (to minimize API calls)
+/// A branch override from the config takes precedence over the locked ref.
+fn extract_watched_inputs(lock: &FlakeLock, watched: &[WatchedInput]) -> Vec<(String, InputConfig)> {
     let mut inputs = Vec::new();
 
-    for priority_name in PRIORITY_INPUTS {
-        if let Some(node) = lock.nodes.get(*priority_name) {
-            if let Some(config) = extract_input_config(*priority_name, node) {
-                inputs.push((priority_name.to_string(), config));
+    for input in watched {
+        if let Some(node) = lock.nodes.get(&input.name) {
+            if let Some(mut config) = extract_input_config(&input.name, node) {
+                if let Some(branch) = &input.branch {
+                    config.branch = branch.clone();
+                }
+                inputs.push((input.name.clone(), config));
             }
         }
     }
@@ -140,10 +145,13 @@ fn extract_all_github_inputs(lock: &FlakeLock) -> Vec<(String, InputConfig)> {
 }
 
 /// Check inputs using REST API
-async fn check_inputs_rest(inputs: Vec<(String, InputConfig)>) -> Result<Vec<String>> {
+async fn check_inputs_rest(
+    inputs: Vec<(String, InputConfig)>,
+    http_timeout: Duration,
+) -> Result<Vec<String>> {
     let client = reqwest::Client::builder()
         .user_agent("forge-notify")
-        .timeout(http_client_timeout())
+        .timeout(http_timeout)
         .build()?;
 
     let mut updates = Vec::new();
@@ -199,6 +207,61 @@ async fn check_single_input_rest(client: &reqwest::Client, config: &InputConfig)
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::notify::constants::PRIORITY_INPUTS;
+
+    fn sample_lock() -> FlakeLock {
+        serde_json::from_str(
+            r#"{
+                "nodes": {
+                    "nixpkgs": {
+                        "locked": {"owner": "NixOS", "repo": "nixpkgs", "rev": "abc", "type": "github"},
+                        "original": {"ref": "nixos-unstable"}
+                    },
+                    "home-manager": {
+                        "locked": {"owner": "nix-community", "repo": "home-manager", "rev": "def", "type": "github"},
+                        "original": {}
+                    },
+                    "root": {}
+                }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_extract_watched_inputs_defaults() {
+        let lock = sample_lock();
+        let inputs = extract_watched_inputs(&lock, &NotifyConfig::default().inputs);
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(inputs[0].0, "nixpkgs");
+        assert_eq!(inputs[0].1.branch, "nixos-unstable");
+    }
+
+    #[test]
+    fn test_extract_watched_inputs_branch_override() {
+        let lock = sample_lock();
+        let watched = vec![
+            WatchedInput {
+                name: "nixpkgs".to_string(),
+                branch: Some("nixos-25.05".to_string()),
+            },
+            WatchedInput {
+                name: "home-manager".to_string(),
+                branch: None,
+            },
+            WatchedInput {
+                name: "missing".to_string(),
+                branch: None,
+            },
+        ];
+
+        let inputs = extract_watched_inputs(&lock, &watched);
+        assert_eq!(inputs.len(), 2);
+        assert_eq!(inputs[0].1.branch, "nixos-25.05");
+        // No ref in flake.lock: falls back to the well-known default branch
+        assert_eq!(inputs[1].1.branch, "master");
+        assert_eq!(inputs[1].1.current_rev, "def");
+    }
 
     #[test]
     fn test_priority_inputs_contains_nixpkgs() {
@@ -4,30 +4,55 @@
 //! - NixOS config repo updates (changes from other machines)
 //! - App profile updates (private-settings repo)
 //! - Flake input updates (nixpkgs, home-manager, etc.)
+//! - App profile backups that are overdue
 
+pub mod attribution;
+pub mod backups;
 pub mod checks;
+pub mod config;
 pub mod constants;
 pub mod flake;
 pub mod paths;
+pub mod sinks;
 pub mod state;
 
 use anyhow::Result;
+use chrono::Utc;
+
+use attribution::{host_impact_label, ConfigCommit};
+use backups::{BackupRecord, StaleBackup};
+use config::NotifyConfig;
 
 /// Status of all update checks
 #[derive(Debug, Default)]
 pub struct UpdateStatus {
-    /// Commits available in nixos-config repo (hash, message)
-    pub config_updates: Vec<(String, String)>,
+    /// Commits available in nixos-config repo
+    pub config_updates: Vec<ConfigCommit>,
     /// Whether app profiles have updates
     pub app_updates: bool,
     /// Flake inputs that have updates available
     pub flake_updates: Vec<String>,
+    /// Set when the last successful app backup is too old
+    pub stale_backup: Option<StaleBackup>,
 }
 
 impl UpdateStatus {
     /// Returns true if any updates are available
     pub fn has_updates(&self) -> bool {
-        !self.config_updates.is_empty() || self.app_updates || !self.flake_updates.is_empty()
+        !self.config_updates.is_empty()
+            || self.app_updates
+            || !self.flake_updates.is_empty()
+            || self.stale_backup.is_some()
+    }
+
+    /// Number of pending config commits that affect this host
+    pub fn config_updates_for_host(&self) -> usize {
+        self.config_updates.iter().filter(|c| c.affects_this_host).count()
+    }
+
+    /// Drop config commits that only touch other hosts
+    pub fn retain_this_host(&mut self) {
+        self.config_updates.retain(|c| c.affects_this_host);
     }
 
     /// Build a notification summary message
@@ -37,9 +62,10 @@ impl UpdateStatus {
         if !self.config_updates.is_empty() {
             let count = self.config_updates.len();
             lines.push(format!(
-                "- {} config commit{} available",
+                "- {} config commit{} available ({})",
                 count,
-                if count == 1 { "" } else { "s" }
+                if count == 1 { "" } else { "s" },
+                host_impact_label(count, self.config_updates_for_host())
             ));
         }
 
@@ -52,21 +78,58 @@ impl UpdateStatus {
             lines.push(format!("- Flake inputs: {}", names));
         }
 
+        if let Some(stale) = &self.stale_backup {
+            lines.push(format!("- App backup overdue: {} (run 'forge apps backup')", stale.describe(Utc::now())));
+        }
+
         lines.join("\n")
     }
 }
 
-/// Run all update checks concurrently
-pub async fn check_all_updates() -> Result<UpdateStatus> {
+/// Run all enabled update checks concurrently
+pub async fn check_all_updates(config: &NotifyConfig) -> Result<UpdateStatus> {
+    let git_timeout = Some(config.timeouts.git_fetch());
+
     let (config_result, apps_result, flake_result) = tokio::join!(
-        checks::check_nixos_config_updates(None),
-        checks::check_app_updates(None),
-        flake::check_flake_updates(),
+        async {
+            if config.checks.config.enabled {
+                checks::check_nixos_config_updates(git_timeout).await
+            } else {
+                Ok(vec![])
+            }
+        },
+        async {
+            if config.checks.apps.enabled {
+                checks::check_app_updates(git_timeout).await
+            } else {
+                Ok(false)
+            }
+        },
+        async {
+            if config.checks.flake.enabled {
+                flake::check_flake_updates(config).await
+            } else {
+                Ok(vec![])
+            }
+        },
     );
 
+    let stale_backup = if config.checks.backup.enabled {
+        match BackupRecord::load() {
+            Ok(record) => record.stale(config.checks.backup.max_age(), Utc::now()),
+            Err(e) => {
+                tracing::warn!("Failed to read backup record: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
     Ok(UpdateStatus {
         config_updates: config_result.unwrap_or_default(),
         app_updates: apps_result.unwrap_or(false),
         flake_updates: flake_result.unwrap_or_default(),
+        stale_backup,
     })
 }
@@ -17,6 +17,15 @@ pub const FORGE_LOG_FILE: &str = "forge.log";
 /// Notification state filename
 const NOTIFY_STATE_FILE: &str = "notify-state.json";
 
+/// Scheduled backup record filename
+const BACKUP_STATE_FILE: &str = "backup-state.json";
+
+/// Forge config directory relative to home
+const FORGE_CONFIG_SUBDIR: &str = ".config/forge";
+
+/// forge-notify config filename
+const NOTIFY_CONFIG_FILE: &str = "notify.toml";
+
 /// App backup data directory relative to home
 const APP_BACKUP_DATA_SUBDIR: &str = ".local/share/app-backup";
 
@@ -42,6 +51,9 @@ const FALLBACK_FORGE_DATA_DIR: &str = "/tmp/forge";
 /// Fallback notification state file when home is unavailable
 const FALLBACK_NOTIFY_STATE_FILE: &str = "/tmp/forge-notify-state.json";
 
+/// Fallback forge-notify config file when home is unavailable
+const FALLBACK_NOTIFY_CONFIG_FILE: &str = "/tmp/forge/notify.toml";
+
 // =============================================================================
 // Path Resolution Functions
 // =============================================================================
@@ -67,6 +79,19 @@ pub fn notify_state_path() -> PathBuf {
         .unwrap_or_else(|| PathBuf::from(FALLBACK_NOTIFY_STATE_FILE))
 }
 
+/// Get the app backup record path (written by forge, read by forge-notify)
+pub fn backup_state_path() -> PathBuf {
+    forge_data_dir().join(BACKUP_STATE_FILE)
+}
+
+/// Get the forge-notify config file path
+/// Falls back to /tmp/forge/notify.toml if home directory is unavailable
+pub fn notify_config_path() -> PathBuf {
+    dirs::home_dir()
+        .map(|h| h.join(FORGE_CONFIG_SUBDIR).join(NOTIFY_CONFIG_FILE))
+        .unwrap_or_else(|| PathBuf::from(FALLBACK_NOTIFY_CONFIG_FILE))
+}
+
 /// Get the NixOS config directory
 /// Checks multiple locations in order of preference:
 /// 1. /etc/nixos (if it contains flake.nix)
@@ -132,6 +157,13 @@ mod tests {
         assert!(path_str.ends_with(".json"), "Notify state should be a JSON file");
     }
 
+    #[test]
+    fn test_notify_config_path_is_toml() {
+        let path = notify_config_path();
+        let path_str = path.to_string_lossy();
+        assert!(path_str.ends_with("forge/notify.toml"), "Notify config should be forge/notify.toml");
+    }
+
     #[test]
     fn test_forge_log_path_is_log() {
         let path = forge_log_path();
@@ -154,6 +186,8 @@ mod tests {
         assert!(!FORGE_DATA_SUBDIR.is_empty());
         assert!(!FORGE_LOG_FILE.is_empty());
         assert!(!NOTIFY_STATE_FILE.is_empty());
+        assert!(!BACKUP_STATE_FILE.is_empty());
+        assert!(!NOTIFY_CONFIG_FILE.is_empty());
         assert!(!APP_BACKUP_DATA_SUBDIR.is_empty());
         assert!(!NIXOS_CONFIG_HOME_SUBDIR.is_empty());
         assert!(!NIXOS_CONFIG_SYSTEM.is_empty());
@@ -0,0 +1,43 @@
+//! Desktop notifications via libnotify
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+use notify_rust::{Notification, Urgency as DesktopUrgency};
+
+use super::{Message, NotificationSink};
+use crate::notify::config::Urgency;
+
+/// Icon shown with the desktop notification
+const NOTIFICATION_ICON: &str = "software-update-available";
+
+/// Sends notifications to the desktop notification daemon
+pub struct DesktopSink;
+
+impl NotificationSink for DesktopSink {
+    fn name(&self) -> &'static str {
+        "desktop"
+    }
+
+    fn send<'a>(&'a self, message: &'a Message) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let urgency = match message.urgency {
+                Urgency::Low => DesktopUrgency::Low,
+                Urgency::Normal => DesktopUrgency::Normal,
+                Urgency::Critical => DesktopUrgency::Critical,
+            };
+
+            let mut notification = Notification::new();
+            notification
+                .summary(&message.title)
+                .body(&message.body)
+                .icon(NOTIFICATION_ICON)
+                .urgency(urgency)
+                .timeout(message.timeout_ms);
+
+            // show() blocks on D-Bus; keep it off the runtime so other sinks can proceed
+            tokio::task::spawn_blocking(move || notification.show().map(|_| ())).await??;
+
+            Ok(())
+        })
+    }
+}
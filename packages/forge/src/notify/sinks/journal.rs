@@ -0,0 +1,160 @@
+//! systemd journal entries with structured fields
+//!
+//! Speaks the journal's native datagram protocol directly, so entries carry
+//! FORGE_* fields that can be queried with e.g. `journalctl FORGE_APP_UPDATES=1`.
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use std::os::unix::net::UnixDatagram;
+use std::path::PathBuf;
+
+use super::{Message, NotificationSink};
+use crate::notify::config::Urgency;
+
+/// Native protocol socket of systemd-journald
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// SYSLOG_IDENTIFIER for our entries
+const SYSLOG_IDENTIFIER: &str = "forge-notify";
+
+/// Writes the message to the systemd journal
+pub struct JournalSink {
+    socket_path: PathBuf,
+}
+
+impl JournalSink {
+    pub fn new() -> Self {
+        Self::with_socket(JOURNAL_SOCKET)
+    }
+
+    /// Use a different socket (for tests)
+    pub fn with_socket(path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: path.into(),
+        }
+    }
+}
+
+impl Default for JournalSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Map urgency to a syslog priority (2 = crit, 5 = notice, 6 = info)
+fn syslog_priority(urgency: Urgency) -> &'static str {
+    match urgency {
+        Urgency::Low => "6",
+        Urgency::Normal => "5",
+        Urgency::Critical => "2",
+    }
+}
+
+/// Encode fields in the journal native protocol
+///
+/// Single-line values use `KEY=value\n`; values containing newlines use
+/// `KEY\n` followed by a little-endian u64 length, the raw value and `\n`.
+fn encode_fields(fields: &[(&str, String)]) -> Vec<u8> {
+    let mut buf = Vec::new();
+
+    for (key, value) in fields {
+        if value.contains('\n') {
+            buf.extend_from_slice(key.as_bytes());
+            buf.push(b'\n');
+            buf.extend_from_slice(&(value.len() as u64).to_le_bytes());
+            buf.extend_from_slice(value.as_bytes());
+        } else {
+            buf.extend_from_slice(key.as_bytes());
+            buf.push(b'=');
+            buf.extend_from_slice(value.as_bytes());
+        }
+        buf.push(b'\n');
+    }
+
+    buf
+}
+
+/// Build the journal fields for a message
+fn message_fields(message: &Message) -> Vec<(&'static str, String)> {
+    vec![
+        ("MESSAGE", format!("{}\n{}", message.title, message.body)),
+        ("PRIORITY", syslog_priority(message.urgency).to_string()),
+        ("SYSLOG_IDENTIFIER", SYSLOG_IDENTIFIER.to_string()),
+        ("FORGE_CONFIG_COMMITS", message.config_commits.to_string()),
+        ("FORGE_CONFIG_COMMITS_FOR_HOST", message.config_commits_for_host.to_string()),
+        ("FORGE_APP_UPDATES", (message.app_updates as u8).to_string()),
+        ("FORGE_FLAKE_INPUTS", message.flake_inputs.join(",")),
+    ]
+}
+
+impl NotificationSink for JournalSink {
+    fn name(&self) -> &'static str {
+        "journal"
+    }
+
+    fn send<'a>(&'a self, message: &'a Message) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let payload = encode_fields(&message_fields(message));
+            let socket = UnixDatagram::unbound()?;
+            socket
+                .send_to(&payload, &self.socket_path)
+                .with_context(|| format!("Failed to write to journal socket {}", self.socket_path.display()))?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::config::NotifyConfig;
+    use crate::notify::UpdateStatus;
+
+    #[test]
+    fn test_encode_single_line() {
+        let encoded = encode_fields(&[("PRIORITY", "5".to_string())]);
+        assert_eq!(encoded, b"PRIORITY=5\n");
+    }
+
+    #[test]
+    fn test_encode_multi_line() {
+        let encoded = encode_fields(&[("MESSAGE", "a\nb".to_string())]);
+        let mut expected = b"MESSAGE\n".to_vec();
+        expected.extend_from_slice(&3u64.to_le_bytes());
+        expected.extend_from_slice(b"a\nb\n");
+        assert_eq!(encoded, expected);
+    }
+
+    #[tokio::test]
+    async fn test_journal_sends_structured_fields() {
+        let dir = std::env::temp_dir().join(format!("forge-journal-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket_path = dir.join("socket");
+        let _ = std::fs::remove_file(&socket_path);
+        let receiver = UnixDatagram::bind(&socket_path).unwrap();
+
+        let status = UpdateStatus {
+            flake_updates: vec!["nixpkgs".to_string(), "home-manager".to_string()],
+            ..Default::default()
+        };
+        let message = Message::from_status(&status, &NotifyConfig::default());
+        JournalSink::with_socket(&socket_path).send(&message).await.unwrap();
+
+        let mut buf = vec![0u8; 8192];
+        let n = receiver.recv(&mut buf).unwrap();
+        let payload = String::from_utf8_lossy(&buf[..n]).to_string();
+        assert!(payload.contains("SYSLOG_IDENTIFIER=forge-notify\n"));
+        assert!(payload.contains("PRIORITY=5\n"));
+        assert!(payload.contains("FORGE_FLAKE_INPUTS=nixpkgs,home-manager\n"));
+        assert!(payload.contains("FORGE_APP_UPDATES=0\n"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_journal_missing_socket_fails() {
+        let message = Message::from_status(&UpdateStatus::default(), &NotifyConfig::default());
+        let sink = JournalSink::with_socket("/nonexistent/forge/journal.socket");
+        assert!(sink.send(&message).await.is_err());
+    }
+}
@@ -0,0 +1,395 @@
+//! Notification sinks
+//!
+//! A sink delivers an update notification somewhere: the desktop (libnotify),
+//! a generic JSON webhook, an ntfy topic, or the systemd journal. Which sinks
+//! are used is selected with `[[sinks]]` entries in `notify.toml`.
+
+mod desktop;
+mod journal;
+mod ntfy;
+mod webhook;
+
+pub use desktop::DesktopSink;
+pub use journal::JournalSink;
+pub use ntfy::NtfySink;
+pub use webhook::WebhookSink;
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::config::{NotifyConfig, Urgency};
+use super::UpdateStatus;
+
+/// Notification title used by all sinks
+const NOTIFICATION_TITLE: &str = "Forge Updates Available";
+
+/// Hint appended to the notification body
+const NOTIFICATION_HINT: &str = "Run 'forge update' to apply.";
+
+/// A notification ready to be delivered
+#[derive(Debug, Clone, Serialize)]
+pub struct Message {
+    pub title: String,
+    pub body: String,
+    pub urgency: Urgency,
+    /// How long desktop notifications stay visible (milliseconds)
+    #[serde(skip)]
+    pub timeout_ms: i32,
+    /// Number of pending config commits
+    pub config_commits: usize,
+    /// How many of those affect this host
+    pub config_commits_for_host: usize,
+    /// Whether app profiles have updates
+    pub app_updates: bool,
+    /// Flake inputs that have updates available
+    pub flake_inputs: Vec<String>,
+    /// Whether the last successful app backup is too old
+    pub stale_backup: bool,
+}
+
+impl Message {
+    /// Build a notification message for the given update status
+    pub fn from_status(status: &UpdateStatus, config: &NotifyConfig) -> Self {
+        Self {
+            title: NOTIFICATION_TITLE.to_string(),
+            body: format!("{}\n\n{}", status.summary(), NOTIFICATION_HINT),
+            urgency: config.urgency_for(status),
+            timeout_ms: config.notification_timeout_ms,
+            config_commits: status.config_updates.len(),
+            config_commits_for_host: status.config_updates_for_host(),
+            app_updates: status.app_updates,
+            flake_inputs: status.flake_updates.clone(),
+            stale_backup: status.stale_backup.is_some(),
+        }
+    }
+}
+
+/// Somewhere a notification can be delivered
+pub trait NotificationSink: Send + Sync {
+    /// Short name used in log messages
+    fn name(&self) -> &'static str;
+
+    /// Deliver the message
+    fn send<'a>(&'a self, message: &'a Message) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Sink selection as written in `notify.toml`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum SinkConfig {
+    /// Desktop notification via libnotify
+    Desktop,
+    /// JSON POST to an arbitrary URL
+    Webhook { url: String },
+    /// Publish to an ntfy topic
+    Ntfy {
+        #[serde(default = "default_ntfy_server")]
+        server: String,
+        topic: String,
+        /// Optional access token (sent as a bearer token)
+        #[serde(default)]
+        token: Option<String>,
+    },
+    /// Structured entry in the systemd journal
+    Journal,
+}
+
+fn default_ntfy_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+impl SinkConfig {
+    /// Check values that parse fine but can't work
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        match self {
+            SinkConfig::Desktop | SinkConfig::Journal => {}
+            SinkConfig::Webhook { url } => {
+                if !is_http_url(url) {
+                    errors.push("sinks: webhook url must start with http:// or https://".to_string());
+                }
+            }
+            SinkConfig::Ntfy { server, topic, .. } => {
+                if !is_http_url(server) {
+                    errors.push("sinks: ntfy server must start with http:// or https://".to_string());
+                }
+                if topic.trim().is_empty() || topic.contains('/') {
+                    errors.push("sinks: ntfy topic is not a valid topic name".to_string());
+                }
+            }
+        }
+
+        errors
+    }
+
+    /// Sink type as written in the config (safe to log, unlike URLs and tokens)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SinkConfig::Desktop => "desktop",
+            SinkConfig::Webhook { .. } => "webhook",
+            SinkConfig::Ntfy { .. } => "ntfy",
+            SinkConfig::Journal => "journal",
+        }
+    }
+
+    /// Create the sink described by this config
+    pub fn build(&self, http_timeout: Duration) -> Result<Box<dyn NotificationSink>> {
+        Ok(match self {
+            SinkConfig::Desktop => Box::new(DesktopSink),
+            SinkConfig::Webhook { url } => Box::new(WebhookSink::new(url, http_timeout)?),
+            SinkConfig::Ntfy { server, topic, token } => Box::new(NtfySink::new(
+                server,
+                topic,
+                token.clone(),
+                http_timeout,
+            )?),
+            SinkConfig::Journal => Box::new(JournalSink::new()),
+        })
+    }
+}
+
+fn is_http_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// Build an HTTP client for the webhook-style sinks
+fn http_client(timeout: Duration) -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .user_agent("forge-notify")
+        .timeout(timeout)
+        .build()?)
+}
+
+/// Create all sinks enabled in the config
+///
+/// Sinks that fail to initialise are logged and skipped.
+pub fn build_sinks(config: &NotifyConfig) -> Vec<Box<dyn NotificationSink>> {
+    config
+        .sinks
+        .iter()
+        .filter_map(|sink| match sink.build(config.timeouts.http_client()) {
+            Ok(sink) => Some(sink),
+            Err(e) => {
+                tracing::error!("Failed to set up {} notification sink: {:#}", sink.kind(), e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Deliver a message to every sink
+///
+/// Succeeds if at least one sink delivered the message, so one unreachable
+/// webhook doesn't cause the same update to be re-notified everywhere.
+pub async fn send_all(sinks: &[Box<dyn NotificationSink>], message: &Message) -> Result<()> {
+    let results = futures::future::join_all(sinks.iter().map(|sink| sink.send(message))).await;
+
+    let mut delivered = 0;
+    for (sink, result) in sinks.iter().zip(results) {
+        match result {
+            Ok(()) => {
+                tracing::info!("Notification sent via {}", sink.name());
+                delivered += 1;
+            }
+            Err(e) => tracing::error!("Notification via {} failed: {:#}", sink.name(), e),
+        }
+    }
+
+    if delivered == 0 {
+        anyhow::bail!("No notification sink delivered the message");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    //! Minimal one-shot HTTP server for sink tests
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    /// A request captured by the test server
+    pub struct CapturedRequest {
+        pub head: String,
+        pub body: String,
+    }
+
+    impl CapturedRequest {
+        /// Get a header value (case-insensitive name)
+        pub fn header(&self, name: &str) -> Option<&str> {
+            self.head.lines().find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                key.eq_ignore_ascii_case(name).then(|| value.trim())
+            })
+        }
+    }
+
+    /// Accept one request, reply with `status`, and return what was received
+    pub async fn serve_once(status: u16) -> (String, JoinHandle<CapturedRequest>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut data = Vec::new();
+            let mut buf = [0u8; 4096];
+
+            let (head, body_start) = loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                data.extend_from_slice(&buf[..n]);
+                if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break (String::from_utf8_lossy(&data[..pos]).to_string(), pos + 4);
+                }
+            };
+
+            let length: usize = head
+                .lines()
+                .find_map(|l| {
+                    let (k, v) = l.split_once(':')?;
+                    k.eq_ignore_ascii_case("content-length").then(|| v.trim().parse().ok())?
+                })
+                .unwrap_or(0);
+            while data.len() < body_start + length {
+                let n = socket.read(&mut buf).await.unwrap();
+                data.extend_from_slice(&buf[..n]);
+            }
+
+            let response = format!("HTTP/1.1 {} Test\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+            socket.write_all(response.as_bytes()).await.unwrap();
+
+            CapturedRequest {
+                head,
+                body: String::from_utf8_lossy(&data[body_start..body_start + length]).to_string(),
+            }
+        });
+
+        (url, handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::attribution::test_commit;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    struct CountingSink {
+        fail: bool,
+        calls: Arc<AtomicUsize>,
+    }
+
+    fn counting(fail: bool) -> (Box<dyn NotificationSink>, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        (Box::new(CountingSink { fail, calls: calls.clone() }), calls)
+    }
+
+    impl NotificationSink for CountingSink {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+
+        fn send<'a>(&'a self, _message: &'a Message) -> BoxFuture<'a, Result<()>> {
+            Box::pin(async move {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                if self.fail {
+                    anyhow::bail!("failed");
+                }
+                Ok(())
+            })
+        }
+    }
+
+    fn message() -> Message {
+        let status = UpdateStatus {
+            config_updates: vec![test_commit("abc1234", true)],
+            ..Default::default()
+        };
+        Message::from_status(&status, &NotifyConfig::default())
+    }
+
+    #[test]
+    fn test_message_from_status() {
+        let message = message();
+        assert_eq!(message.title, NOTIFICATION_TITLE);
+        assert!(message.body.contains("1 config commit available (affects this host)"));
+        assert!(message.body.ends_with(NOTIFICATION_HINT));
+        assert_eq!(message.config_commits, 1);
+        assert_eq!(message.config_commits_for_host, 1);
+        assert_eq!(message.urgency, Urgency::Normal);
+    }
+
+    #[test]
+    fn test_parse_sink_configs() {
+        let config = NotifyConfig::parse(
+            r#"
+            [[sinks]]
+            type = "desktop"
+
+            [[sinks]]
+            type = "webhook"
+            url = "https://example.com/hook"
+
+            [[sinks]]
+            type = "ntfy"
+            topic = "forge-updates"
+
+            [[sinks]]
+            type = "journal"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.sinks.len(), 4);
+        assert_eq!(
+            config.sinks[2],
+            SinkConfig::Ntfy {
+                server: "https://ntfy.sh".to_string(),
+                topic: "forge-updates".to_string(),
+                token: None,
+            }
+        );
+        assert!(config.validate().is_empty());
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_sink() {
+        assert!(NotifyConfig::parse("[[sinks]]\ntype = \"pager\"").is_err());
+    }
+
+    #[test]
+    fn test_validate_sinks() {
+        let webhook = SinkConfig::Webhook {
+            url: "example.com".to_string(),
+        };
+        assert_eq!(webhook.validate().len(), 1);
+
+        let ntfy = SinkConfig::Ntfy {
+            server: "https://ntfy.sh".to_string(),
+            topic: "a/b".to_string(),
+            token: None,
+        };
+        assert_eq!(ntfy.validate().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_send_all_succeeds_if_any_sink_delivers() {
+        let (failing, failing_calls) = counting(true);
+        let (working, working_calls) = counting(false);
+        assert!(send_all(&[failing, working], &message()).await.is_ok());
+        // Every sink is tried, even after one fails
+        assert_eq!(failing_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(working_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_send_all_fails_if_every_sink_fails() {
+        let (failing, calls) = counting(true);
+        assert!(send_all(&[failing], &message()).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
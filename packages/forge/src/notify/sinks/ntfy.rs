@@ -0,0 +1,114 @@
+//! ntfy topic publishing
+//!
+//! Works with ntfy.sh and self-hosted ntfy-compatible servers.
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use std::time::Duration;
+
+use super::{http_client, Message, NotificationSink};
+use crate::notify::config::Urgency;
+
+/// Tag shown as an emoji next to the notification
+const NTFY_TAG: &str = "arrows_counterclockwise";
+
+/// Publishes the message to an ntfy topic
+pub struct NtfySink {
+    client: reqwest::Client,
+    url: String,
+    token: Option<String>,
+}
+
+impl NtfySink {
+    pub fn new(server: &str, topic: &str, token: Option<String>, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            client: http_client(timeout)?,
+            url: format!("{}/{}", server.trim_end_matches('/'), topic),
+            token,
+        })
+    }
+}
+
+/// Map urgency to an ntfy priority (1 = min, 3 = default, 5 = max)
+fn ntfy_priority(urgency: Urgency) -> &'static str {
+    match urgency {
+        Urgency::Low => "2",
+        Urgency::Normal => "3",
+        Urgency::Critical => "5",
+    }
+}
+
+impl NotificationSink for NtfySink {
+    fn name(&self) -> &'static str {
+        "ntfy"
+    }
+
+    fn send<'a>(&'a self, message: &'a Message) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let mut request = self
+                .client
+                .post(&self.url)
+                .header("Title", &message.title)
+                .header("Priority", ntfy_priority(message.urgency))
+                .header("Tags", NTFY_TAG)
+                .body(message.body.clone());
+
+            if let Some(token) = &self.token {
+                request = request.bearer_auth(token);
+            }
+
+            request
+                .send()
+                .await
+                .with_context(|| format!("Failed to reach ntfy server {}", self.url))?
+                .error_for_status()?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::attribution::test_commit;
+    use crate::notify::config::NotifyConfig;
+    use crate::notify::sinks::test_support::serve_once;
+    use crate::notify::UpdateStatus;
+
+    fn message(urgency: Urgency) -> Message {
+        let status = UpdateStatus {
+            config_updates: vec![test_commit("abc1234", true)],
+            ..Default::default()
+        };
+        let mut config = NotifyConfig::default();
+        config.checks.config.urgency = urgency;
+        Message::from_status(&status, &config)
+    }
+
+    #[tokio::test]
+    async fn test_ntfy_publishes_to_topic() {
+        let (url, server) = serve_once(200).await;
+        let sink = NtfySink::new(&format!("{}/", url), "forge", None, Duration::from_secs(5)).unwrap();
+
+        sink.send(&message(Urgency::Critical)).await.unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.head.starts_with("POST /forge "));
+        assert_eq!(request.header("title"), Some("Forge Updates Available"));
+        assert_eq!(request.header("priority"), Some("5"));
+        assert_eq!(request.header("authorization"), None);
+        assert!(request.body.contains("1 config commit available"));
+    }
+
+    #[tokio::test]
+    async fn test_ntfy_sends_token() {
+        let (url, server) = serve_once(200).await;
+        let sink = NtfySink::new(&url, "forge", Some("tk_secret".to_string()), Duration::from_secs(5)).unwrap();
+
+        sink.send(&message(Urgency::Low)).await.unwrap();
+
+        let request = server.await.unwrap();
+        assert_eq!(request.header("authorization"), Some("Bearer tk_secret"));
+        assert_eq!(request.header("priority"), Some("2"));
+    }
+}
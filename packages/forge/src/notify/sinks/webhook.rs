@@ -0,0 +1,85 @@
+//! Generic JSON webhook
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use std::time::Duration;
+
+use super::{http_client, Message, NotificationSink};
+
+/// POSTs the message as JSON to a configured URL
+pub struct WebhookSink {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl WebhookSink {
+    pub fn new(url: &str, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            client: http_client(timeout)?,
+            url: url.to_string(),
+        })
+    }
+}
+
+impl NotificationSink for WebhookSink {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn send<'a>(&'a self, message: &'a Message) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            self.client
+                .post(&self.url)
+                .json(message)
+                .send()
+                .await
+                .with_context(|| format!("Failed to reach webhook {}", self.url))?
+                .error_for_status()?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::config::NotifyConfig;
+    use crate::notify::sinks::test_support::serve_once;
+    use crate::notify::UpdateStatus;
+
+    fn message() -> Message {
+        let status = UpdateStatus {
+            app_updates: true,
+            flake_updates: vec!["nixpkgs".to_string()],
+            ..Default::default()
+        };
+        Message::from_status(&status, &NotifyConfig::default())
+    }
+
+    #[tokio::test]
+    async fn test_webhook_posts_json() {
+        let (url, server) = serve_once(200).await;
+        let sink = WebhookSink::new(&format!("{}/hook", url), Duration::from_secs(5)).unwrap();
+
+        sink.send(&message()).await.unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.head.starts_with("POST /hook "));
+        assert_eq!(request.header("content-type"), Some("application/json"));
+
+        let json: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(json["title"], "Forge Updates Available");
+        assert_eq!(json["urgency"], "normal");
+        assert_eq!(json["app_updates"], true);
+        assert_eq!(json["flake_inputs"][0], "nixpkgs");
+    }
+
+    #[tokio::test]
+    async fn test_webhook_reports_http_errors() {
+        let (url, server) = serve_once(500).await;
+        let sink = WebhookSink::new(&url, Duration::from_secs(5)).unwrap();
+
+        assert!(sink.send(&message()).await.is_err());
+        server.await.unwrap();
+    }
+}
@@ -29,6 +29,10 @@ pub struct NotifiedState {
 
     /// Flake inputs we notified about (input_name@rev)
     pub flake_inputs: Vec<String>,
+
+    /// Whether we warned about an overdue app backup (reset once one succeeds)
+    #[serde(default)]
+    pub stale_backup: bool,
 }
 
 impl NotifyState {
@@ -64,7 +68,8 @@ impl NotifyState {
         self.last_check = Some(Utc::now());
 
         // Update config commit - store the first (newest) commit hash
-        if let Some((hash, _)) = status.config_updates.first() {
+        if let Some(commit) = status.config_updates.first() {
+            let hash = &commit.hash;
             self.last_notified.config_commit = Some(hash.clone());
         }
 
@@ -75,6 +80,16 @@ impl NotifyState {
         // Update flake inputs - track current set
         // If updates are empty, clear the list to allow re-notification for new updates
         self.last_notified.flake_inputs = status.flake_updates.clone();
+
+        self.last_notified.stale_backup = status.stale_backup.is_some();
+    }
+
+    /// Forget the overdue backup warning once a backup went through,
+    /// so the next overdue period is reported again
+    pub fn clear_resolved(&mut self, status: &super::UpdateStatus) {
+        if status.stale_backup.is_none() {
+            self.last_notified.stale_backup = false;
+        }
     }
 
     /// Check if we should notify based on current status vs last notified
@@ -85,7 +100,8 @@ impl NotifyState {
         }
 
         // Check if config has new commits we haven't notified about
-        if let Some((current_hash, _)) = status.config_updates.first() {
+        if let Some(commit) = status.config_updates.first() {
+            let current_hash = &commit.hash;
             if self.last_notified.config_commit.as_ref() != Some(current_hash) {
                 return true;
             }
@@ -96,6 +112,11 @@ impl NotifyState {
             return true;
         }
 
+        // Warn about an overdue backup once per overdue period
+        if status.stale_backup.is_some() && !self.last_notified.stale_backup {
+            return true;
+        }
+
         // Check if flake inputs have changed
         if !status.flake_updates.is_empty() {
             // Any input not in the last notified set triggers notification
@@ -120,6 +141,7 @@ impl NotifyState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::notify::attribution::test_commit;
 
     #[test]
     fn test_should_notify_no_updates() {
@@ -132,9 +154,10 @@ mod tests {
     fn test_should_notify_new_config() {
         let state = NotifyState::default();
         let status = super::super::UpdateStatus {
-            config_updates: vec![("abc1234".to_string(), "Test commit".to_string())],
+            config_updates: vec![test_commit("abc1234", true)],
             app_updates: false,
             flake_updates: vec![],
+            stale_backup: None,
         };
         assert!(state.should_notify(&status));
     }
@@ -145,10 +168,50 @@ mod tests {
         state.last_notified.config_commit = Some("abc1234".to_string());
 
         let status = super::super::UpdateStatus {
-            config_updates: vec![("abc1234".to_string(), "Test commit".to_string())],
+            config_updates: vec![test_commit("abc1234", true)],
             app_updates: false,
             flake_updates: vec![],
+            stale_backup: None,
         };
         assert!(!state.should_notify(&status));
     }
+
+    #[test]
+    fn test_should_not_notify_other_host_commits_when_filtered() {
+        let state = NotifyState::default();
+        let mut status = super::super::UpdateStatus {
+            config_updates: vec![test_commit("abc1234", false), test_commit("def5678", false)],
+            app_updates: false,
+            flake_updates: vec![],
+            stale_backup: None,
+        };
+        assert!(status.summary().contains("2 config commits available (none affect this host)"));
+        assert!(state.should_notify(&status));
+
+        status.retain_this_host();
+        assert!(!state.should_notify(&status));
+    }
+
+    #[test]
+    fn test_stale_backup_notified_once_per_period() {
+        let mut state = NotifyState::default();
+        let stale = super::super::UpdateStatus {
+            stale_backup: Some(crate::notify::backups::StaleBackup { last_success: None }),
+            ..Default::default()
+        };
+        assert!(state.should_notify(&stale));
+        state.mark_notified(&stale);
+        assert!(!state.should_notify(&stale));
+
+        state.clear_resolved(&super::super::UpdateStatus::default());
+        assert!(state.should_notify(&stale));
+    }
+
+    #[test]
+    fn test_load_state_without_backup_field() {
+        let state: NotifyState =
+            serde_json::from_str(r#"{"last_check":null,"last_notified":{"config_commit":null,"app_updates":false,"flake_inputs":[]}}"#)
+                .unwrap();
+        assert!(!state.last_notified.stale_backup);
+    }
 }
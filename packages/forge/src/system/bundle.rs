@@ -0,0 +1,268 @@
+//! Offline install bundles
+//!
+//! `forge bundle create <host>` writes a directory holding the configuration
+//! repository as a git bundle and the host's system closure as a signed binary
+//! cache. `forge install --offline <dir>` clones from the git bundle and uses
+//! the cache as the only substituter, so the ISO needs no network. A bundle
+//! packed with `--tar` is extracted to a temporary directory first.
+//!
+//! ```text
+//! forge-bundle-<host>/
+//!   manifest.json
+//!   nixos-config.bundle
+//!   cache/            (nix-cache-info, *.narinfo, nar/)
+//!   forge-offline.sh  (starts the bundled forge on the ISO)
+//! ```
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::repo::{validate_ref, validate_url, RepoSource};
+
+/// Bundle layout version written to the manifest
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// File names inside a bundle directory
+pub const MANIFEST: &str = "manifest.json";
+pub const REPO_BUNDLE: &str = "nixos-config.bundle";
+pub const CACHE_DIR: &str = "cache";
+pub const LAUNCHER: &str = "forge-offline.sh";
+
+/// What a bundle contains, written by `forge bundle create`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    pub host: String,
+    /// Repository the bundle was made from, set as origin on the installed system
+    pub repository: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_ref: Option<String>,
+    /// Commit in the git bundle
+    pub commit: String,
+    /// The host's system.build.toplevel
+    pub system: String,
+    /// git package used to clone on the ISO
+    pub git: String,
+    /// forge package started by the launcher
+    pub forge: String,
+    /// Key the binary cache is signed with
+    pub public_key: String,
+    pub created: String,
+}
+
+/// A bundle directory with a readable manifest
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bundle {
+    pub dir: PathBuf,
+    pub manifest: Manifest,
+}
+
+impl Bundle {
+    /// Open the bundle at `path` (a directory or a `--tar` archive), checking its layout
+    pub fn load(path: &Path) -> Result<Self> {
+        if path.is_file() {
+            let dir = std::env::temp_dir().join(format!("forge-offline-{}", std::process::id()));
+            return Self::load(&extract(path, &dir)?);
+        }
+        let dir = std::fs::canonicalize(path).with_context(|| format!("Bundle {} not found", path.display()))?;
+        let manifest_path = dir.join(MANIFEST);
+        let content = std::fs::read_to_string(&manifest_path)
+            .with_context(|| format!("{} is not a forge bundle (no {})", dir.display(), MANIFEST))?;
+        let manifest: Manifest =
+            serde_json::from_str(&content).with_context(|| format!("Invalid {}", manifest_path.display()))?;
+        if manifest.version != BUNDLE_VERSION {
+            anyhow::bail!(
+                "Bundle version {} is not supported (expected {})",
+                manifest.version,
+                BUNDLE_VERSION
+            );
+        }
+
+        // The paths end up in clone scripts and NIX_CONFIG
+        if let Some(err) = validate_url(&dir.to_string_lossy()) {
+            anyhow::bail!("Bundle path {} can't be used ({}); move it to a plain path", dir.display(), err);
+        }
+        if let Some(err) = validate_ref(&manifest.commit) {
+            anyhow::bail!("Invalid bundle commit: {}", err);
+        }
+
+        let bundle = Bundle { dir, manifest };
+        for required in [bundle.repo_bundle(), bundle.cache_dir().join("nix-cache-info")] {
+            if !required.exists() {
+                anyhow::bail!("Bundle is incomplete: {} is missing", required.display());
+            }
+        }
+        Ok(bundle)
+    }
+
+    pub fn repo_bundle(&self) -> PathBuf {
+        self.dir.join(REPO_BUNDLE)
+    }
+
+    pub fn cache_dir(&self) -> PathBuf {
+        self.dir.join(CACHE_DIR)
+    }
+
+    /// The binary cache as a Nix store URL
+    pub fn cache_url(&self) -> String {
+        format!("file://{}", self.cache_dir().display())
+    }
+
+    /// Clone source: the git bundle, checked out at the bundled commit
+    pub fn source(&self) -> RepoSource {
+        RepoSource {
+            url: self.repo_bundle().to_string_lossy().to_string(),
+            git_ref: Some(self.manifest.commit.clone()),
+        }
+    }
+
+    /// `base` with the bundle's cache as the only substituter
+    pub fn nix_config(&self, base: &str) -> String {
+        format!(
+            "{}\nsubstituters = {}\ntrusted-public-keys = {}",
+            base,
+            self.cache_url(),
+            self.manifest.public_key
+        )
+    }
+}
+
+/// Unpack a bundle archive into `dest`, returning the bundle directory inside it
+fn extract(archive: &Path, dest: &Path) -> Result<PathBuf> {
+    let file = std::fs::File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+    if dest.exists() {
+        std::fs::remove_dir_all(dest).with_context(|| format!("Failed to remove {}", dest.display()))?;
+    }
+    tar::Archive::new(std::io::BufReader::new(file))
+        .unpack(dest)
+        .with_context(|| format!("Failed to extract {} to {}", archive.display(), dest.display()))?;
+
+    // `forge bundle create --tar` packs a single forge-bundle-<host>/ directory
+    let mut dirs = std::fs::read_dir(dest)?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.join(MANIFEST).is_file());
+    match (dirs.next(), dirs.next()) {
+        (Some(dir), None) => Ok(dir),
+        _ => anyhow::bail!("{} does not contain a single forge bundle", archive.display()),
+    }
+}
+
+/// Script that copies forge out of the cache and starts the offline install
+pub fn launcher_script(manifest: &Manifest) -> String {
+    format!(
+        r#"#!/bin/sh
+# Offline install of {host}: copy forge from this bundle's cache and start it
+set -e
+dir="$(cd "$(dirname "$0")" && pwd)"
+nix --extra-experimental-features nix-command copy \
+  --from "file://$dir/{cache}" \
+  --option trusted-public-keys '{key}' \
+  '{forge}'
+exec '{forge}/bin/forge' install --offline "$dir" "$@"
+"#,
+        host = manifest.host,
+        cache = CACHE_DIR,
+        key = manifest.public_key,
+        forge = manifest.forge
+    )
+}
+
+/// Directory name for a host's bundle
+pub fn dir_name(host: &str) -> String {
+    format!("forge-bundle-{}", host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest() -> Manifest {
+        Manifest {
+            version: BUNDLE_VERSION,
+            host: "x1yoga".to_string(),
+            repository: "https://github.com/me/nixos-config".to_string(),
+            git_ref: None,
+            commit: "1111111111111111111111111111111111111111".to_string(),
+            system: "/nix/store/aaaa-nixos-system-x1yoga".to_string(),
+            git: "/nix/store/bbbb-git-2.44.0".to_string(),
+            forge: "/nix/store/cccc-forge-1.0.0".to_string(),
+            public_key: "forge-bundle-x1yoga:AAAA".to_string(),
+            created: "2026-10-19T12:00:00+02:00".to_string(),
+        }
+    }
+
+    fn write_bundle(dir: &Path, manifest: &Manifest) {
+        std::fs::create_dir_all(dir.join(CACHE_DIR)).unwrap();
+        std::fs::write(dir.join(MANIFEST), serde_json::to_string_pretty(manifest).unwrap()).unwrap();
+        std::fs::write(dir.join(REPO_BUNDLE), "").unwrap();
+        std::fs::write(dir.join(CACHE_DIR).join("nix-cache-info"), "StoreDir: /nix/store\n").unwrap();
+    }
+
+    #[test]
+    fn test_launcher_script() {
+        let script = launcher_script(&manifest());
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains("--from \"file://$dir/cache\""));
+        assert!(script.contains("--option trusted-public-keys 'forge-bundle-x1yoga:AAAA'"));
+        assert!(script.contains("exec '/nix/store/cccc-forge-1.0.0/bin/forge' install --offline \"$dir\" \"$@\""));
+    }
+
+    #[test]
+    fn test_load_and_nix_config() {
+        let tmp = std::env::temp_dir().join(format!("forge-bundle-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let dir = tmp.join(dir_name("x1yoga"));
+        write_bundle(&dir, &manifest());
+
+        let bundle = Bundle::load(&dir).unwrap();
+        assert_eq!(bundle.manifest, manifest());
+        let source = bundle.source();
+        assert!(source.url.ends_with("/forge-bundle-x1yoga/nixos-config.bundle"));
+        assert_eq!(source.git_ref.as_deref(), Some(manifest().commit.as_str()));
+
+        let config = bundle.nix_config("experimental-features = nix-command flakes");
+        let lines: Vec<&str> = config.lines().collect();
+        assert_eq!(lines[0], "experimental-features = nix-command flakes");
+        assert!(lines[1].starts_with("substituters = file:///") && lines[1].ends_with("/forge-bundle-x1yoga/cache"));
+        assert_eq!(lines[2], "trusted-public-keys = forge-bundle-x1yoga:AAAA");
+
+        // A missing cache or an unknown version is refused
+        std::fs::remove_file(dir.join(CACHE_DIR).join("nix-cache-info")).unwrap();
+        assert!(Bundle::load(&dir).unwrap_err().to_string().contains("incomplete"));
+        write_bundle(&dir, &Manifest { version: 99, ..manifest() });
+        assert!(Bundle::load(&dir).is_err());
+
+        assert!(Bundle::load(&tmp.join("missing")).is_err());
+
+        // Paths that would break the quoted clone scripts are refused
+        let quoted = tmp.join("it's here");
+        write_bundle(&quoted, &manifest());
+        assert!(Bundle::load(&quoted).unwrap_err().to_string().contains("plain path"));
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_extract_tarball() {
+        let tmp = std::env::temp_dir().join(format!("forge-bundle-extract-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let dir = tmp.join("src").join(dir_name("x1yoga"));
+        write_bundle(&dir, &manifest());
+
+        let archive = tmp.join("forge-bundle-x1yoga.tar");
+        let mut builder = tar::Builder::new(std::fs::File::create(&archive).unwrap());
+        builder.append_dir_all(dir_name("x1yoga"), &dir).unwrap();
+        builder.finish().unwrap();
+
+        let extracted = extract(&archive, &tmp.join("out")).unwrap();
+        assert_eq!(extracted, tmp.join("out").join(dir_name("x1yoga")));
+        assert!(extracted.join(CACHE_DIR).join("nix-cache-info").is_file());
+
+        // Anything else is not a bundle
+        let empty = tmp.join("empty.tar");
+        tar::Builder::new(std::fs::File::create(&empty).unwrap()).finish().unwrap();
+        assert!(extract(&empty, &tmp.join("out2")).unwrap_err().to_string().contains("single forge bundle"));
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
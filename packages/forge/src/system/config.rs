@@ -120,15 +120,41 @@ fn parse_host_description(content: &str) -> String {
 pub struct BrowserBackupConfig {
     pub repo: String,
     pub age_recipient: String,
-    pub age_key_1password: Option<String>,
+    /// Secret provider reference for the age identity
+    pub age_key_secret: Option<String>,
     pub age_key_path: Option<String>,
     pub local_repo_path: String,
     pub backup_retention: u32,
+    pub rollback_max_mb: u64,
+    /// SSH private key included in keys backups
+    pub ssh_key_path: Option<String>,
+    /// Secret provider reference for the SSH key (used by keys setup)
+    pub ssh_key_secret: Option<String>,
+    /// Secret provider reference for the keys archive passphrase
+    pub keys_passphrase_secret: Option<String>,
+    /// Secret provider: `1password` (default), `bitwarden`, `pass` or `file`
+    pub secret_provider: String,
+    /// Directory of the `file` secret provider
+    pub secret_dir: Option<String>,
+    /// Storage backend: `git` (default), `local`, `rsync` or `s3`
+    pub storage_backend: String,
+    pub storage_path: Option<String>,
+    pub rsync_target: Option<String>,
+    pub s3_endpoint: Option<String>,
+    pub s3_bucket: Option<String>,
+    pub s3_prefix: Option<String>,
+    pub s3_region: Option<String>,
+    pub s3_access_key_id: Option<String>,
+    pub s3_secret_access_key: Option<String>,
 }
 
 /// Load browser backup configuration from file
 pub fn load_browser_config(path: &Path) -> Result<BrowserBackupConfig> {
-    let content = std::fs::read_to_string(path)?;
+    Ok(parse_browser_config(&std::fs::read_to_string(path)?))
+}
+
+/// Parse the contents of a backup config file
+pub fn parse_browser_config(content: &str) -> BrowserBackupConfig {
     let mut config = BrowserBackupConfig::default();
     let mut vars = HashMap::new();
 
@@ -145,23 +171,85 @@ pub fn load_browser_config(path: &Path) -> Result<BrowserBackupConfig> {
         }
     }
 
+    // The scripts accept the legacy BROWSER_BACKUP_REPO name as a fallback
     config.repo = vars
-        .get("BROWSER_BACKUP_REPO")
+        .get("APP_BACKUP_REPO")
+        .or_else(|| vars.get("BROWSER_BACKUP_REPO"))
         .cloned()
         .unwrap_or_default();
     config.age_recipient = vars.get("AGE_RECIPIENT").cloned().unwrap_or_default();
-    config.age_key_1password = vars.get("AGE_KEY_1PASSWORD").cloned();
+    // The *_1PASSWORD names predate other secret providers
+    let secret = |name: &str| {
+        vars.get(&format!("{}_SECRET", name))
+            .or_else(|| vars.get(&format!("{}_1PASSWORD", name)))
+            .cloned()
+    };
+    config.age_key_secret = secret("AGE_KEY");
     config.age_key_path = vars.get("AGE_KEY_PATH").cloned();
     config.local_repo_path = vars
         .get("LOCAL_REPO_PATH")
         .cloned()
-        .unwrap_or_else(|| "~/.local/share/browser-backup".to_string());
+        .unwrap_or_else(|| format!("~/{}", crate::constants::APP_BACKUP_DATA_DIR));
     config.backup_retention = vars
         .get("BACKUP_RETENTION")
         .and_then(|v| v.parse().ok())
         .unwrap_or(3);
+    config.rollback_max_mb = vars
+        .get("ROLLBACK_MAX_MB")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1024);
+    config.ssh_key_path = vars.get("SSH_KEY_PATH").cloned();
+    config.ssh_key_secret = secret("SSH_KEY");
+    config.keys_passphrase_secret = secret("KEYS_PASSPHRASE");
+    config.secret_provider = vars
+        .get("SECRET_PROVIDER")
+        .map(|v| v.to_lowercase())
+        .unwrap_or_else(|| "1password".to_string());
+    config.secret_dir = vars.get("SECRET_DIR").cloned();
+    config.storage_backend = vars
+        .get("STORAGE_BACKEND")
+        .map(|v| v.to_lowercase())
+        .unwrap_or_else(|| "git".to_string());
+    config.storage_path = vars.get("STORAGE_PATH").cloned();
+    config.rsync_target = vars.get("RSYNC_TARGET").cloned();
+    config.s3_endpoint = vars.get("S3_ENDPOINT").cloned();
+    config.s3_bucket = vars.get("S3_BUCKET").cloned();
+    config.s3_prefix = vars.get("S3_PREFIX").cloned();
+    config.s3_region = vars.get("S3_REGION").cloned();
+    config.s3_access_key_id = vars.get("S3_ACCESS_KEY_ID").cloned();
+    config.s3_secret_access_key = vars.get("S3_SECRET_ACCESS_KEY").cloned();
+
+    config
+}
 
-    Ok(config)
+/// Set `key` in an env-style config file, keeping every other line as is
+///
+/// The value replaces the first uncommented assignment of `key`, or is
+/// appended when there is none.
+pub fn set_config_value(path: &Path, key: &str, value: &str) -> Result<()> {
+    let content = std::fs::read_to_string(path)?;
+    let assignment = format!("{}=\"{}\"", key, value);
+    let mut replaced = false;
+    let mut lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            let is_key = line
+                .trim()
+                .split_once('=')
+                .is_some_and(|(k, _)| k.trim() == key);
+            if is_key && !replaced {
+                replaced = true;
+                assignment.clone()
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    if !replaced {
+        lines.push(assignment);
+    }
+    std::fs::write(path, lines.join("\n") + "\n")?;
+    Ok(())
 }
 
 /// Expand ~ in paths to home directory
@@ -242,12 +330,71 @@ mod tests {
         let config = BrowserBackupConfig::default();
         assert!(config.repo.is_empty());
         assert!(config.age_recipient.is_empty());
-        assert!(config.age_key_1password.is_none());
+        assert!(config.age_key_secret.is_none());
         assert!(config.age_key_path.is_none());
         assert!(config.local_repo_path.is_empty());
         assert_eq!(config.backup_retention, 0);
     }
 
+    #[test]
+    fn test_load_browser_config_prefers_app_backup_repo() {
+        let dir = std::env::temp_dir().join(format!("forge-config-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config");
+        std::fs::write(
+            &path,
+            "BROWSER_BACKUP_REPO=\"old\"\nAPP_BACKUP_REPO=\"git@example.com:me/settings.git\"\nAGE_RECIPIENT=\"age1abc\"\n",
+        )
+        .unwrap();
+
+        let config = load_browser_config(&path).unwrap();
+        assert_eq!(config.repo, "git@example.com:me/settings.git");
+        assert_eq!(config.age_recipient, "age1abc");
+        assert_eq!(config.local_repo_path, "~/.local/share/app-backup");
+        assert_eq!(config.backup_retention, 3);
+        assert_eq!(config.rollback_max_mb, 1024);
+        assert_eq!(config.storage_backend, "git");
+        assert_eq!(config.secret_provider, "1password");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_browser_config_example() {
+        // As generated by programs.app-backup, which the installer reads from the host config
+        let content = "# App Backup Configuration\n\
+                       APP_BACKUP_REPO=\"https://example.com/me/settings.git\"\n\
+                       BACKUP_RETENTION=5\n\
+                       # STORAGE_BACKEND=\"local\"\n\
+                       #\n\
+                       # Local path to age private key (fallback if 1Password unavailable)\n\
+                       AGE_KEY_PATH=\"~/.config/age/key.txt\"\n\
+                       SSH_KEY_PATH=\"~/.ssh/id_ed25519\"\n";
+        let config = parse_browser_config(content);
+        assert_eq!(config.repo, "https://example.com/me/settings.git");
+        assert_eq!(config.backup_retention, 5);
+        assert_eq!(config.storage_backend, "git");
+        assert_eq!(config.age_key_path.as_deref(), Some("~/.config/age/key.txt"));
+        assert_eq!(config.ssh_key_path.as_deref(), Some("~/.ssh/id_ed25519"));
+    }
+
+    #[test]
+    fn test_set_config_value() {
+        let dir = std::env::temp_dir().join(format!("forge-config-set-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config");
+        std::fs::write(&path, "# AGE_RECIPIENT=\"example\"\nAGE_RECIPIENT=\"age1old\"\nBACKUP_RETENTION=3\n").unwrap();
+
+        set_config_value(&path, "AGE_RECIPIENT", "age1new").unwrap();
+        set_config_value(&path, "STORAGE_BACKEND", "local").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "# AGE_RECIPIENT=\"example\"\nAGE_RECIPIENT=\"age1new\"\nBACKUP_RETENTION=3\nSTORAGE_BACKEND=\"local\"\n"
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_host_config_clone() {
         let config = HostConfig {
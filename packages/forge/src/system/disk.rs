@@ -40,16 +40,133 @@ pub struct PartitionInfo {
     pub fstype: String,
     pub label: Option<String>,
     pub os_type: Option<OsType>,
+    pub mountpoint: Option<String>,
 }
 
+/// SMART overall health as reported by smartctl
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiskHealth {
+    /// smartctl missing, not permitted, or the device doesn't report SMART
+    #[default]
+    Unknown,
+    Passed,
+    Failing,
+}
+
+impl std::fmt::Display for DiskHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiskHealth::Unknown => write!(f, "SMART n/a"),
+            DiskHealth::Passed => write!(f, "SMART ok"),
+            DiskHealth::Failing => write!(f, "SMART FAILING"),
+        }
+    }
+}
+
+/// Mount points that mean the disk carries the system we're running from
+const LIVE_SYSTEM_MOUNTS: &[&str] = &[
+    "/",
+    "/boot",
+    "/boot/efi",
+    "/nix/store",
+    "/nix/.ro-store",
+    "/iso",
+    "/run/initramfs/live",
+];
+
+/// Where the installer mounts the target system
+const INSTALL_MOUNT_ROOT: &str = "/mnt";
+
 /// Information about a disk device
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct DiskInfo {
     pub path: String,
     pub size: String,
     pub size_bytes: u64,
     pub model: Option<String>,
     pub partitions: Vec<PartitionInfo>,
+    /// Bus the disk hangs off (nvme, usb, sata, ...)
+    pub transport: Option<String>,
+    pub removable: bool,
+    /// Disk holds the running (live) system
+    pub live_system: bool,
+    /// Mount points of the disk and everything on it (LUKS, LVM included)
+    pub mounted: Vec<String>,
+    /// Partition table type (gpt, dos)
+    pub partition_table: Option<String>,
+    pub health: DiskHealth,
+}
+
+impl DiskInfo {
+    /// Reason this disk must not be installed to, if any
+    pub fn blocker(&self) -> Option<String> {
+        if self.live_system {
+            return Some("This disk holds the running system (the installer itself)".to_string());
+        }
+        let busy: Vec<&str> = self
+            .mounted
+            .iter()
+            .map(String::as_str)
+            .filter(|m| !is_under_install_root(m))
+            .collect();
+        if !busy.is_empty() {
+            return Some(format!("Disk is in use ({}); unmount it first", busy.join(", ")));
+        }
+        None
+    }
+
+    /// Reasons to ask twice before installing to this disk
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.removable || self.transport.as_deref() == Some("usb") {
+            warnings.push("Removable/USB disk - make sure it is the intended target".to_string());
+        }
+        if self.health == DiskHealth::Failing {
+            warnings.push("SMART reports this disk as failing".to_string());
+        }
+        if self.mounted.iter().any(|m| is_under_install_root(m)) {
+            warnings.push(format!(
+                "Still mounted under {} from an earlier install attempt",
+                INSTALL_MOUNT_ROOT
+            ));
+        }
+        let systems: Vec<String> = self
+            .partitions
+            .iter()
+            .filter_map(|p| p.os_type.as_ref())
+            .filter(|os| **os != OsType::Unknown)
+            .map(|os| os.to_string())
+            .collect();
+        if !systems.is_empty() {
+            warnings.push(format!("Contains {} - it will be erased", systems.join(", ")));
+        }
+        warnings
+    }
+
+    /// Short transport/table/health summary for disk lists
+    pub fn details(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(tran) = &self.transport {
+            parts.push(tran.to_uppercase());
+        }
+        if self.removable {
+            parts.push("removable".to_string());
+        }
+        if let Some(table) = &self.partition_table {
+            parts.push(table.to_uppercase());
+        }
+        parts.push(self.health.to_string());
+        if self.live_system {
+            parts.push("LIVE".to_string());
+        } else if !self.mounted.is_empty() {
+            parts.push("mounted".to_string());
+        }
+        parts.join(" · ")
+    }
+}
+
+fn is_under_install_root(mountpoint: &str) -> bool {
+    mountpoint == INSTALL_MOUNT_ROOT || mountpoint.starts_with(&format!("{}/", INSTALL_MOUNT_ROOT))
 }
 
 /// JSON structure for lsblk output
@@ -67,16 +184,45 @@ struct BlockDevice {
     device_type: Option<String>,
     fstype: Option<String>,
     label: Option<String>,
+    tran: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    rm: bool,
+    pttype: Option<String>,
+    mountpoint: Option<String>,
     #[serde(default)]
     children: Vec<BlockDevice>,
 }
 
+/// lsblk prints flags as true/false, 1/0 or "1"/"0" depending on version
+fn deserialize_flag<'de, D>(deserializer: D) -> std::result::Result<bool, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Bool(b) => b,
+        serde_json::Value::Number(n) => n.as_u64() == Some(1),
+        serde_json::Value::String(s) => s.trim() == "1",
+        _ => false,
+    })
+}
+
+impl BlockDevice {
+    /// Mount points of this device and all of its descendants
+    fn mountpoints(&self) -> Vec<String> {
+        let mut mounts: Vec<String> = self.mountpoint.iter().cloned().collect();
+        for child in &self.children {
+            mounts.extend(child.mountpoints());
+        }
+        mounts
+    }
+}
+
 /// Get list of available disks (excluding loop, ram, rom, zram devices)
 pub fn get_available_disks() -> Result<Vec<DiskInfo>> {
     // Use JSON output for reliable parsing (handles model names with spaces)
     // Include children to get partition info
     let output = Command::new("lsblk")
-        .args(["-J", "-o", "NAME,SIZE,MODEL,TYPE,FSTYPE,LABEL"])
+        .args(["-J", "-o", "NAME,SIZE,MODEL,TYPE,FSTYPE,LABEL,TRAN,RM,PTTYPE,MOUNTPOINT"])
         .output()?;
 
     let stdout = String::from_utf8_lossy(&output.stdout);
@@ -92,50 +238,68 @@ pub fn get_available_disks() -> Result<Vec<DiskInfo>> {
     };
 
     for device in lsblk.blockdevices {
-        // Check if it's a disk
-        if device.device_type.as_deref() != Some("disk") {
+        let Some(mut disk) = disk_from_device(&device) else {
             continue;
+        };
+        // Detect OS type by probing partitions that aren't already in use
+        for part in disk.partitions.iter_mut().filter(|p| p.mountpoint.is_none()) {
+            part.os_type = detect_os_type(&part.path, &part.fstype);
         }
+        disk.health = smart_health(&disk.path);
+        disks.push(disk);
+    }
 
-        let name = &device.name;
-
-        // Skip non-physical devices
-        if name.starts_with("loop")
-            || name.starts_with("ram")
-            || name.starts_with("zram")
-            || name.starts_with("sr")
-            || name.starts_with("fd")
-        {
-            continue;
-        }
+    // Sort by size (largest first)
+    disks.sort_by_key(|d| std::cmp::Reverse(d.size_bytes));
 
-        let path = format!("/dev/{}", name);
-        let size = device.size.clone().unwrap_or_default();
-        let size_bytes = parse_size(&size);
+    Ok(disks)
+}
 
-        // Clean up model name (remove extra whitespace)
-        let model = device
-            .model
-            .as_ref()
-            .map(|m| m.trim().to_string())
-            .filter(|m| !m.is_empty());
+/// Build disk info from an lsblk device, skipping non-disks and virtual devices
+fn disk_from_device(device: &BlockDevice) -> Option<DiskInfo> {
+    // Check if it's a disk
+    if device.device_type.as_deref() != Some("disk") {
+        return None;
+    }
 
-        // Process partitions (children)
-        let partitions = process_partitions(&device.children);
+    let name = &device.name;
 
-        disks.push(DiskInfo {
-            path,
-            size,
-            size_bytes,
-            model,
-            partitions,
-        });
+    // Skip non-physical devices
+    if name.starts_with("loop")
+        || name.starts_with("ram")
+        || name.starts_with("zram")
+        || name.starts_with("sr")
+        || name.starts_with("fd")
+    {
+        return None;
     }
 
-    // Sort by size (largest first)
-    disks.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes));
-
-    Ok(disks)
+    let size = device.size.clone().unwrap_or_default();
+    let size_bytes = parse_size(&size);
+
+    // Clean up model name (remove extra whitespace)
+    let model = device
+        .model
+        .as_ref()
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty());
+
+    let mounted = device.mountpoints();
+    let live_system = mounted.iter().any(|m| LIVE_SYSTEM_MOUNTS.contains(&m.as_str()));
+
+    Some(DiskInfo {
+        path: format!("/dev/{}", name),
+        size,
+        size_bytes,
+        model,
+        partitions: process_partitions(&device.children),
+        transport: device.tran.clone().filter(|t| !t.is_empty()),
+        removable: device.rm,
+        live_system,
+        mounted,
+        partition_table: device.pttype.clone().filter(|t| !t.is_empty()),
+        health: DiskHealth::Unknown,
+    })
 }
 
 /// Process partition children from lsblk output
@@ -143,25 +307,42 @@ fn process_partitions(children: &[BlockDevice]) -> Vec<PartitionInfo> {
     children
         .iter()
         .filter(|child| child.device_type.as_deref() == Some("part"))
-        .map(|child| {
-            let path = format!("/dev/{}", child.name);
-            let fstype = child.fstype.clone().unwrap_or_default();
-            let label = child.label.clone();
-
-            // Detect OS type based on filesystem and by probing
-            let os_type = detect_os_type(&path, &fstype);
-
-            PartitionInfo {
-                path,
-                size: child.size.clone().unwrap_or_default(),
-                fstype,
-                label,
-                os_type,
-            }
+        .map(|child| PartitionInfo {
+            path: format!("/dev/{}", child.name),
+            size: child.size.clone().unwrap_or_default(),
+            fstype: child.fstype.clone().unwrap_or_default(),
+            label: child.label.clone(),
+            os_type: None,
+            // A LUKS partition is "mounted" through its opened mapping
+            mountpoint: child.mountpoints().into_iter().next(),
         })
         .collect()
 }
 
+/// Ask smartctl for the overall health verdict (needs root, so sudo -n)
+fn smart_health(disk_path: &str) -> DiskHealth {
+    match Command::new("sudo")
+        .args(["-n", "smartctl", "-H", "-j", disk_path])
+        .output()
+    {
+        // smartctl uses its exit code as a bit mask, so judge by the JSON only
+        Ok(output) => parse_smart_health(&String::from_utf8_lossy(&output.stdout)),
+        Err(_) => DiskHealth::Unknown,
+    }
+}
+
+/// Parse `smartctl -H -j` output
+fn parse_smart_health(json: &str) -> DiskHealth {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(json) else {
+        return DiskHealth::Unknown;
+    };
+    match value.pointer("/smart_status/passed").and_then(|v| v.as_bool()) {
+        Some(true) => DiskHealth::Passed,
+        Some(false) => DiskHealth::Failing,
+        None => DiskHealth::Unknown,
+    }
+}
+
 /// Detect OS type on a partition
 fn detect_os_type(partition_path: &str, fstype: &str) -> Option<OsType> {
     // NTFS is almost always Windows
@@ -293,13 +474,13 @@ fn get_available_disks_text_fallback() -> Result<Vec<DiskInfo>> {
             path,
             size: size.to_string(),
             size_bytes,
-            model: None,       // Can't reliably parse model in text mode
-            partitions: vec![], // No partition info in text fallback mode
+            // Model, partitions and mounts aren't reliably available in text mode
+            ..Default::default()
         });
     }
 
     // Sort by size (largest first)
-    disks.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes));
+    disks.sort_by_key(|d| std::cmp::Reverse(d.size_bytes));
 
     Ok(disks)
 }
@@ -311,14 +492,14 @@ fn parse_size(size: &str) -> u64 {
         return 0;
     }
 
-    let (num_str, unit) = if size.ends_with('T') {
-        (&size[..size.len() - 1], 1024u64 * 1024 * 1024 * 1024)
-    } else if size.ends_with('G') {
-        (&size[..size.len() - 1], 1024u64 * 1024 * 1024)
-    } else if size.ends_with('M') {
-        (&size[..size.len() - 1], 1024u64 * 1024)
-    } else if size.ends_with('K') {
-        (&size[..size.len() - 1], 1024u64)
+    let (num_str, unit) = if let Some(num) = size.strip_suffix('T') {
+        (num, 1024u64 * 1024 * 1024 * 1024)
+    } else if let Some(num) = size.strip_suffix('G') {
+        (num, 1024u64 * 1024 * 1024)
+    } else if let Some(num) = size.strip_suffix('M') {
+        (num, 1024u64 * 1024)
+    } else if let Some(num) = size.strip_suffix('K') {
+        (num, 1024u64)
     } else {
         (size, 1u64)
     };
@@ -493,6 +674,7 @@ VERSION="1.0"
             size_bytes: 1024 * 1024 * 1024 * 1024,
             model: Some("Samsung SSD".to_string()),
             partitions: vec![],
+            ..Default::default()
         };
         let cloned = disk.clone();
         assert_eq!(cloned.path, "/dev/nvme0n1");
@@ -508,6 +690,7 @@ VERSION="1.0"
             fstype: "vfat".to_string(),
             label: Some("EFI".to_string()),
             os_type: None,
+            mountpoint: None,
         };
         let cloned = partition.clone();
         assert_eq!(cloned.path, "/dev/nvme0n1p1");
@@ -523,8 +706,137 @@ VERSION="1.0"
             size_bytes: 500 * 1024 * 1024 * 1024,
             model: None,
             partitions: vec![],
+            ..Default::default()
         };
         let disk2 = disk1.clone();
         assert_eq!(disk1, disk2);
     }
+
+    fn lsblk_disk(json: &str) -> Option<DiskInfo> {
+        let device: BlockDevice = serde_json::from_str(json).unwrap();
+        disk_from_device(&device)
+    }
+
+    #[test]
+    fn test_disk_from_device_live_usb() {
+        let disk = lsblk_disk(
+            r#"{"name":"sdb","size":"28.7G","model":" Cruzer ","type":"disk","tran":"usb","rm":true,"pttype":"dos","mountpoint":null,
+                "children":[{"name":"sdb1","size":"1.2G","type":"part","fstype":"iso9660","mountpoint":"/iso"},
+                            {"name":"sdb2","size":"3M","type":"part","fstype":"vfat","mountpoint":null}]}"#,
+        )
+        .unwrap();
+        assert_eq!(disk.path, "/dev/sdb");
+        assert_eq!(disk.model.as_deref(), Some("Cruzer"));
+        assert_eq!(disk.transport.as_deref(), Some("usb"));
+        assert!(disk.removable);
+        assert_eq!(disk.partition_table.as_deref(), Some("dos"));
+        assert!(disk.live_system);
+        assert_eq!(disk.mounted, vec!["/iso".to_string()]);
+        assert_eq!(disk.partitions[0].mountpoint.as_deref(), Some("/iso"));
+        assert!(disk.blocker().unwrap().contains("running system"));
+    }
+
+    #[test]
+    fn test_disk_from_device_nested_luks_mount() {
+        let disk = lsblk_disk(
+            r#"{"name":"nvme0n1","size":"1.8T","type":"disk","tran":"nvme","rm":"0","pttype":"gpt",
+                "children":[{"name":"nvme0n1p2","size":"1.8T","type":"part","fstype":"crypto_LUKS",
+                             "children":[{"name":"cryptroot","type":"crypt","fstype":"btrfs","mountpoint":"/data"}]}]}"#,
+        )
+        .unwrap();
+        assert!(!disk.removable);
+        assert!(!disk.live_system);
+        assert_eq!(disk.mounted, vec!["/data".to_string()]);
+        assert_eq!(disk.partitions[0].mountpoint.as_deref(), Some("/data"));
+        assert!(disk.blocker().unwrap().contains("/data"));
+    }
+
+    #[test]
+    fn test_disk_from_device_skips_non_disks() {
+        assert!(lsblk_disk(r#"{"name":"loop0","size":"1G","type":"loop"}"#).is_none());
+        assert!(lsblk_disk(r#"{"name":"zram0","size":"8G","type":"disk"}"#).is_none());
+    }
+
+    #[test]
+    fn test_rm_flag_formats() {
+        for (raw, expected) in [("true", true), ("false", false), ("1", true), ("0", false), ("\"1\"", true), ("\"0\"", false), ("null", false)] {
+            let disk = lsblk_disk(&format!(r#"{{"name":"sda","type":"disk","rm":{}}}"#, raw)).unwrap();
+            assert_eq!(disk.removable, expected, "rm = {}", raw);
+        }
+        // Missing column
+        assert!(!lsblk_disk(r#"{"name":"sda","type":"disk"}"#).unwrap().removable);
+    }
+
+    #[test]
+    fn test_parse_smart_health() {
+        assert_eq!(parse_smart_health(r#"{"smart_status":{"passed":true}}"#), DiskHealth::Passed);
+        assert_eq!(parse_smart_health(r#"{"smart_status":{"passed":false}}"#), DiskHealth::Failing);
+        assert_eq!(parse_smart_health(r#"{"smartctl":{"exit_status":2}}"#), DiskHealth::Unknown);
+        assert_eq!(parse_smart_health(""), DiskHealth::Unknown);
+        assert_eq!(parse_smart_health("sudo: a password is required"), DiskHealth::Unknown);
+    }
+
+    #[test]
+    fn test_plain_internal_disk_is_safe() {
+        let disk = DiskInfo {
+            path: "/dev/nvme0n1".to_string(),
+            transport: Some("nvme".to_string()),
+            partition_table: Some("gpt".to_string()),
+            health: DiskHealth::Passed,
+            ..Default::default()
+        };
+        assert_eq!(disk.blocker(), None);
+        assert!(disk.warnings().is_empty());
+        assert_eq!(disk.details(), "NVME · GPT · SMART ok");
+    }
+
+    #[test]
+    fn test_leftover_install_mounts_warn_but_do_not_block() {
+        let disk = DiskInfo {
+            mounted: vec!["/mnt".to_string(), "/mnt/boot".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(disk.blocker(), None);
+        assert_eq!(disk.warnings().len(), 1);
+        assert!(disk.warnings()[0].contains("earlier install"));
+
+        // /mntx is not the install root
+        let disk = DiskInfo {
+            mounted: vec!["/mntx".to_string()],
+            ..Default::default()
+        };
+        assert!(disk.blocker().is_some());
+    }
+
+    #[test]
+    fn test_swap_in_use_blocks() {
+        let disk = DiskInfo {
+            mounted: vec!["[SWAP]".to_string()],
+            ..Default::default()
+        };
+        assert!(disk.blocker().unwrap().contains("[SWAP]"));
+    }
+
+    #[test]
+    fn test_risky_disk_warnings() {
+        let disk = DiskInfo {
+            transport: Some("usb".to_string()),
+            health: DiskHealth::Failing,
+            partitions: vec![PartitionInfo {
+                path: "/dev/sda3".to_string(),
+                size: "200G".to_string(),
+                fstype: "ntfs".to_string(),
+                label: None,
+                os_type: Some(OsType::Windows),
+                mountpoint: None,
+            }],
+            ..Default::default()
+        };
+        let warnings = disk.warnings();
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].contains("USB"));
+        assert!(warnings[1].contains("failing"));
+        assert!(warnings[2].contains("Windows"));
+        assert_eq!(disk.blocker(), None);
+    }
 }
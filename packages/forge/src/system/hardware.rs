@@ -6,6 +6,7 @@ use std::process::Command;
 
 /// CPU vendor types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum CpuVendor {
     AMD,
     Intel,
@@ -24,6 +25,7 @@ impl std::fmt::Display for CpuVendor {
 
 /// GPU vendor types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum GpuVendor {
     NVIDIA,
     AMD,
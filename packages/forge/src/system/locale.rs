@@ -0,0 +1,364 @@
+//! Timezone, locale and keyboard layout choices
+//!
+//! Lists come from the live system's zoneinfo, glibc and xkb data, with small
+//! built-in lists when those files are missing from the ISO.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::LazyLock;
+
+/// Fallbacks matching modules/common.nix
+pub const DEFAULT_TIMEZONE: &str = "Europe/Amsterdam";
+pub const DEFAULT_LOCALE: &str = "en_US.UTF-8";
+pub const DEFAULT_KEYBOARD: &str = "us";
+
+const ZONEINFO_DIRS: &[&str] = &["/etc/zoneinfo", "/usr/share/zoneinfo"];
+const SUPPORTED_LOCALES_FILES: &[&str] = &[
+    "/run/current-system/sw/share/i18n/SUPPORTED",
+    "/usr/share/i18n/SUPPORTED",
+];
+const XKB_RULES_FILES: &[&str] = &[
+    "/run/current-system/sw/share/X11/xkb/rules/base.lst",
+    "/usr/share/X11/xkb/rules/base.lst",
+];
+
+const FALLBACK_LOCALES: &[&str] = &[
+    "de_DE.UTF-8",
+    "en_GB.UTF-8",
+    "en_US.UTF-8",
+    "es_ES.UTF-8",
+    "fr_FR.UTF-8",
+    "it_IT.UTF-8",
+    "nl_NL.UTF-8",
+    "pt_BR.UTF-8",
+];
+const FALLBACK_KEYBOARDS: &[(&str, &str)] = &[
+    ("be", "Belgian"),
+    ("de", "German"),
+    ("fr", "French"),
+    ("gb", "English (UK)"),
+    ("nl", "Dutch"),
+    ("us", "English (US)"),
+];
+
+/// Marks the settings block written into a host's default.nix
+const BLOCK_HEADER: &str = "# Locale and keyboard (auto-generated by Forge installer)";
+
+/// Formats that `i18n.extraLocaleSettings` sets apart from the main locale
+const LC_VARS: &[&str] = &[
+    "LC_ADDRESS",
+    "LC_IDENTIFICATION",
+    "LC_MEASUREMENT",
+    "LC_MONETARY",
+    "LC_NAME",
+    "LC_NUMERIC",
+    "LC_PAPER",
+    "LC_TELEPHONE",
+    "LC_TIME",
+];
+
+/// Matches a previously written settings block
+static BLOCK_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(
+        r#"(?ms)^\s*# Locale and keyboard \(auto-generated by Forge installer\).*?^\s*console\.useXkbConfig\s*=\s*.*?;\s*$\n?"#,
+    )
+    .expect("locale block regex pattern is statically validated")
+});
+
+/// One entry in a selection list
+#[derive(Debug, Clone, PartialEq)]
+pub struct Choice {
+    /// Value written to the config, e.g. "Europe/Amsterdam"
+    pub value: String,
+    /// Description shown next to it (may be empty)
+    pub label: String,
+}
+
+impl Choice {
+    fn new(value: &str, label: &str) -> Self {
+        Self {
+            value: value.to_string(),
+            label: label.to_string(),
+        }
+    }
+
+    /// Case-insensitive match against value and label
+    fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        query.is_empty()
+            || self.value.to_lowercase().contains(&query)
+            || self.label.to_lowercase().contains(&query)
+    }
+}
+
+/// Timezone, locale and keyboard layout for the new system
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocaleSettings {
+    pub timezone: String,
+    pub locale: String,
+    pub keyboard: String,
+}
+
+impl LocaleSettings {
+    /// Settings of the live environment, falling back to the repo defaults
+    pub fn detect() -> Self {
+        let localectl = Command::new("localectl")
+            .arg("status")
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+            .unwrap_or_default();
+
+        let timezone = std::fs::read_link("/etc/localtime")
+            .ok()
+            .and_then(|target| timezone_from_link(&target))
+            .unwrap_or_else(|| DEFAULT_TIMEZONE.to_string());
+        let locale = ["LC_ALL", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find_map(|value| normalize_locale(&value))
+            .unwrap_or_else(|| DEFAULT_LOCALE.to_string());
+        let keyboard = parse_localectl_layout(&localectl).unwrap_or_else(|| DEFAULT_KEYBOARD.to_string());
+
+        Self {
+            timezone,
+            locale,
+            keyboard,
+        }
+    }
+}
+
+/// Timezones from the system zoneinfo database
+pub fn timezones() -> Vec<Choice> {
+    let mut dirs: Vec<PathBuf> = std::env::var_os("TZDIR").map(PathBuf::from).into_iter().collect();
+    dirs.extend(ZONEINFO_DIRS.iter().map(PathBuf::from));
+
+    let zones = dirs
+        .iter()
+        .flat_map(|dir| [dir.join("zone1970.tab"), dir.join("zone.tab")])
+        .find_map(|file| std::fs::read_to_string(file).ok())
+        .map(|content| parse_zone_tab(&content))
+        .unwrap_or_default();
+
+    let mut choices = vec![Choice::new("UTC", "Coordinated Universal Time")];
+    if zones.is_empty() {
+        choices.push(Choice::new(DEFAULT_TIMEZONE, ""));
+    }
+    choices.extend(zones);
+    choices
+}
+
+/// UTF-8 locales glibc can generate
+pub fn locales() -> Vec<Choice> {
+    let locales = read_first(SUPPORTED_LOCALES_FILES)
+        .map(|content| parse_supported_locales(&content))
+        .unwrap_or_default();
+    if locales.is_empty() {
+        return FALLBACK_LOCALES.iter().map(|l| Choice::new(l, "")).collect();
+    }
+    locales
+}
+
+/// XKB keyboard layouts
+pub fn keyboard_layouts() -> Vec<Choice> {
+    let mut files: Vec<PathBuf> = std::env::var_os("XKB_CONFIG_ROOT")
+        .map(|root| Path::new(&root).join("rules/base.lst"))
+        .into_iter()
+        .collect();
+    files.extend(XKB_RULES_FILES.iter().map(PathBuf::from));
+
+    let layouts = files
+        .iter()
+        .find_map(|file| std::fs::read_to_string(file).ok())
+        .map(|content| parse_xkb_layouts(&content))
+        .unwrap_or_default();
+    if layouts.is_empty() {
+        return FALLBACK_KEYBOARDS.iter().map(|(v, l)| Choice::new(v, l)).collect();
+    }
+    layouts
+}
+
+/// Choices matching a search query
+pub fn filter<'a>(choices: &'a [Choice], query: &str) -> Vec<&'a Choice> {
+    choices.iter().filter(|c| c.matches(query)).collect()
+}
+
+fn read_first(paths: &[&str]) -> Option<String> {
+    paths.iter().find_map(|p| std::fs::read_to_string(p).ok())
+}
+
+/// Zone names from zone1970.tab / zone.tab, sorted
+fn parse_zone_tab(content: &str) -> Vec<Choice> {
+    let mut zones: Vec<Choice> = content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let zone = fields.get(2)?.trim();
+            (!zone.is_empty()).then(|| Choice::new(zone, fields.get(3).map(|c| c.trim()).unwrap_or("")))
+        })
+        .collect();
+    zones.sort_by(|a, b| a.value.cmp(&b.value));
+    zones.dedup_by(|a, b| a.value == b.value);
+    zones
+}
+
+/// UTF-8 locale names from glibc's SUPPORTED list ("en_US.UTF-8/UTF-8 \")
+fn parse_supported_locales(content: &str) -> Vec<Choice> {
+    content
+        .lines()
+        .filter_map(|line| line.split('/').next())
+        .map(str::trim)
+        .filter(|name| name.ends_with(".UTF-8"))
+        .map(|name| Choice::new(name, ""))
+        .collect()
+}
+
+/// Layouts from the "! layout" section of xkb's base.lst
+fn parse_xkb_layouts(content: &str) -> Vec<Choice> {
+    let mut in_layouts = false;
+    let mut layouts = Vec::new();
+    for line in content.lines() {
+        if let Some(section) = line.strip_prefix('!') {
+            in_layouts = section.trim() == "layout";
+            continue;
+        }
+        if !in_layouts {
+            continue;
+        }
+        let line = line.trim();
+        if let Some((name, description)) = line.split_once(char::is_whitespace) {
+            layouts.push(Choice::new(name, description.trim()));
+        }
+    }
+    layouts.sort_by(|a, b| a.value.cmp(&b.value));
+    layouts
+}
+
+/// "Europe/Amsterdam" from a /etc/localtime link target
+fn timezone_from_link(target: &Path) -> Option<String> {
+    let target = target.to_string_lossy();
+    let (_, zone) = target.split_once("zoneinfo/")?;
+    (!zone.is_empty()).then(|| zone.to_string())
+}
+
+/// "en_US.UTF-8" from LANG-style values like "en_US.utf8"
+fn normalize_locale(value: &str) -> Option<String> {
+    let (name, charset) = value.split_once('.')?;
+    let charset = charset.split('@').next().unwrap_or_default().to_lowercase();
+    (charset == "utf8" || charset == "utf-8").then(|| format!("{}.UTF-8", name))
+}
+
+/// First X11 layout from `localectl status`
+fn parse_localectl_layout(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("X11 Layout:"))
+        .and_then(|layouts| layouts.trim().split(',').next())
+        .filter(|layout| !layout.is_empty())
+        .map(str::to_string)
+}
+
+/// Write the settings block into a host's default.nix, replacing an earlier one
+pub fn inject_host_settings(content: &str, settings: &LocaleSettings) -> String {
+    let cleaned = BLOCK_RE.replace(content, "").to_string();
+    let Some(pos) = cleaned.rfind('}') else {
+        return cleaned;
+    };
+    // The chosen locale also covers formats, which common.nix otherwise defaults
+    let formats: String = LC_VARS
+        .iter()
+        .map(|var| format!("    {} = \"{}\";\n", var, settings.locale))
+        .collect();
+    let block = format!(
+        r#"
+  {}
+  time.timeZone = "{}";
+  i18n.defaultLocale = "{}";
+  i18n.extraLocaleSettings = {{
+{}  }};
+  services.xserver.xkb.layout = "{}";
+  console.useXkbConfig = true;
+"#,
+        BLOCK_HEADER, settings.timezone, settings.locale, formats, settings.keyboard
+    );
+    let mut result = cleaned[..pos].to_string();
+    result.push_str(&block);
+    result.push_str(&cleaned[pos..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> LocaleSettings {
+        LocaleSettings {
+            timezone: "America/New_York".to_string(),
+            locale: "en_US.UTF-8".to_string(),
+            keyboard: "us".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_zone_tab_lists_sorted_zones() {
+        let content = "# comment\nNL\t+5222+00454\tEurope/Amsterdam\nUS\t+404251-0740023\tAmerica/New_York\tEastern (most areas)\n";
+        let zones = parse_zone_tab(content);
+        assert_eq!(zones[0], Choice::new("America/New_York", "Eastern (most areas)"));
+        assert_eq!(zones[1].value, "Europe/Amsterdam");
+    }
+
+    #[test]
+    fn test_supported_locales_keep_utf8_only() {
+        let content = "C.UTF-8/UTF-8 \\\nen_US.UTF-8/UTF-8 \\\nen_US/ISO-8859-1 \\\n";
+        let values: Vec<String> = parse_supported_locales(content).into_iter().map(|c| c.value).collect();
+        assert_eq!(values, vec!["C.UTF-8", "en_US.UTF-8"]);
+    }
+
+    #[test]
+    fn test_xkb_layouts_come_from_layout_section() {
+        let content = "! model\n  pc105  Generic 105-key PC\n\n! layout\n  us              English (US)\n  nl              Dutch\n\n! variant\n  intl  us: English (US, intl.)\n";
+        let layouts = parse_xkb_layouts(content);
+        assert_eq!(layouts, vec![Choice::new("nl", "Dutch"), Choice::new("us", "English (US)")]);
+    }
+
+    #[test]
+    fn test_detects_live_values() {
+        assert_eq!(
+            timezone_from_link(Path::new("/etc/zoneinfo/Europe/Amsterdam")).as_deref(),
+            Some("Europe/Amsterdam")
+        );
+        assert_eq!(normalize_locale("nl_NL.utf8").as_deref(), Some("nl_NL.UTF-8"));
+        assert_eq!(normalize_locale("C"), None);
+        assert_eq!(
+            parse_localectl_layout("   System Locale: LANG=en_US.UTF-8\n       X11 Layout: de,us\n").as_deref(),
+            Some("de")
+        );
+    }
+
+    #[test]
+    fn test_choices_match_value_or_label() {
+        let choice = Choice::new("nl", "Dutch");
+        assert!(choice.matches("dut"));
+        assert!(choice.matches("NL"));
+        assert!(choice.matches(""));
+        assert!(!choice.matches("german"));
+    }
+
+    #[test]
+    fn test_host_settings_replace_earlier_block() {
+        let content = "{ config, lib, ... }:\n\n{\n  networking.hostName = \"x1yoga\";\n}\n";
+        let once = inject_host_settings(content, &settings());
+        assert!(once.contains("time.timeZone = \"America/New_York\";"));
+        assert!(once.contains("    LC_TIME = \"en_US.UTF-8\";\n"));
+        assert!(once.trim_end().ends_with('}'));
+
+        let mut changed = settings();
+        changed.keyboard = "nl".to_string();
+        let twice = inject_host_settings(&once, &changed);
+        assert_eq!(twice.matches(BLOCK_HEADER).count(), 1);
+        assert!(twice.contains("services.xserver.xkb.layout = \"nl\";"));
+        assert_eq!(twice.matches("i18n.extraLocaleSettings").count(), 1);
+    }
+}
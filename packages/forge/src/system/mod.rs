@@ -1,9 +1,15 @@
 //! System utilities
 
+pub mod bundle;
 pub mod config;
+pub mod diagnostics;
 pub mod disk;
 pub mod hardware;
+pub mod locale;
 pub mod network;
+pub mod repo;
+pub mod users;
+pub mod verify;
 
 /// Check if we're running from a NixOS Live ISO environment
 pub fn is_live_iso_environment() -> bool {
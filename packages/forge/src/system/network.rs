@@ -1,23 +1,381 @@
 //! Network connectivity utilities
+//!
+//! Also the Wi-Fi side of the live ISO install: scanning and joining
+//! networks through NetworkManager ([`WifiManager`]), and helpers for
+//! carrying the connections over to the installed system (keyfiles).
 
 #![allow(dead_code)]
 
 use anyhow::Result;
+use futures::future::BoxFuture;
 use std::process::Command;
+use std::time::Duration;
 
-/// Check if network is available by pinging github.com
-pub fn check_connectivity() -> Result<bool> {
-    let status = Command::new("ping")
-        .args(["-c", "1", "-W", "5", "github.com"])
-        .stdout(std::process::Stdio::null())
-        .stderr(std::process::Stdio::null())
-        .status()?;
-
-    Ok(status.success())
-}
+use crate::commands::executor::{run_capture, run_capture_sensitive};
+use crate::system::diagnostics;
 
 /// Get current hostname
 pub fn get_hostname() -> Result<String> {
     let output = Command::new("hostname").output()?;
     Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
+
+/// NetworkManager connection type of Wi-Fi profiles
+pub const WIFI_CONNECTION_TYPE: &str = "802-11-wireless";
+
+/// A connection profile listed by `nmcli -t -f NAME,UUID,TYPE,FILENAME connection show`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Connection {
+    pub name: String,
+    pub uuid: String,
+    pub kind: String,
+    /// Keyfile the profile is stored in (empty for unsaved profiles)
+    pub filename: String,
+}
+
+/// A Wi-Fi network seen in a scan
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessPoint {
+    pub ssid: String,
+    /// Signal strength in percent
+    pub signal: u8,
+    /// Security as shown by nmcli (`WPA2`, `WPA1 WPA2`, empty for open)
+    pub security: String,
+    /// Currently connected
+    pub in_use: bool,
+}
+
+impl AccessPoint {
+    pub fn secured(&self) -> bool {
+        !self.security.is_empty() && self.security != "--"
+    }
+}
+
+/// Scanning and joining Wi-Fi networks
+///
+/// [`NetworkManager`] drives nmcli; tests use [`FakeWifi`].
+pub trait WifiManager: Send + Sync {
+    /// Whether the internet (github.com) is reachable
+    fn online<'a>(&'a self) -> BoxFuture<'a, bool>;
+
+    /// Networks in range, strongest first
+    fn scan<'a>(&'a self) -> BoxFuture<'a, Result<Vec<AccessPoint>>>;
+
+    fn connect<'a>(&'a self, ssid: &'a str, passphrase: Option<&'a str>) -> BoxFuture<'a, Result<()>>;
+}
+
+/// nmcli-backed Wi-Fi
+pub struct NetworkManager;
+
+impl WifiManager for NetworkManager {
+    fn online<'a>(&'a self) -> BoxFuture<'a, bool> {
+        Box::pin(diagnostics::github_reachable())
+    }
+
+    fn scan<'a>(&'a self) -> BoxFuture<'a, Result<Vec<AccessPoint>>> {
+        Box::pin(async move {
+            let (success, stdout, stderr) = run_capture(
+                "nmcli",
+                &["-t", "-f", "IN-USE,SIGNAL,SECURITY,SSID", "device", "wifi", "list", "--rescan", "yes"],
+            )
+            .await?;
+            if !success {
+                anyhow::bail!("Wi-Fi scan failed: {}", stderr.trim());
+            }
+            Ok(parse_access_points(&stdout))
+        })
+    }
+
+    fn connect<'a>(&'a self, ssid: &'a str, passphrase: Option<&'a str>) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let mut args = vec!["device", "wifi", "connect", ssid];
+            if let Some(passphrase) = passphrase {
+                args.extend(["password", passphrase]);
+            }
+            let (success, _, stderr) = run_capture_sensitive("nmcli", &args).await?;
+            if !success {
+                anyhow::bail!("{}", stderr.trim().trim_start_matches("Error: "));
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Join a network and wait for the internet to become reachable
+///
+/// DHCP and DNS may need a moment after NetworkManager reports success, so
+/// the connectivity check is tried `attempts` times, `delay` apart.
+pub async fn connect_and_check(
+    wifi: &dyn WifiManager,
+    ssid: &str,
+    passphrase: Option<&str>,
+    attempts: u32,
+    delay: Duration,
+) -> Result<()> {
+    wifi.connect(ssid, passphrase).await?;
+    for attempt in 0..attempts {
+        if attempt > 0 {
+            tokio::time::sleep(delay).await;
+        }
+        if wifi.online().await {
+            return Ok(());
+        }
+    }
+    anyhow::bail!("Connected to {} but github.com is not reachable", ssid)
+}
+
+/// Parse `nmcli -t -f IN-USE,SIGNAL,SECURITY,SSID device wifi list`
+///
+/// Hidden networks are dropped and each SSID is listed once, with its
+/// strongest access point.
+pub fn parse_access_points(output: &str) -> Vec<AccessPoint> {
+    let mut networks: Vec<AccessPoint> = Vec::new();
+    for line in output.lines() {
+        let [in_use, signal, security, ssid] = split_terse(line).try_into().unwrap_or_default();
+        if ssid.is_empty() {
+            continue;
+        }
+        let network = AccessPoint {
+            in_use: in_use.trim() == "*",
+            signal: signal.parse().unwrap_or(0),
+            security,
+            ssid,
+        };
+        match networks.iter_mut().find(|n| n.ssid == network.ssid) {
+            Some(existing) => {
+                existing.in_use |= network.in_use;
+                if network.signal > existing.signal {
+                    existing.signal = network.signal;
+                    existing.security = network.security;
+                }
+            }
+            None => networks.push(network),
+        }
+    }
+    networks.sort_by_key(|n| std::cmp::Reverse(n.signal));
+    networks
+}
+
+/// Parse nmcli terse output (fields separated by `:`, with `\:` and `\\` escaped)
+pub fn parse_connections(output: &str) -> Vec<Connection> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields = split_terse(line);
+            match fields.as_slice() {
+                [name, uuid, kind, filename] => Some(Connection {
+                    name: name.clone(),
+                    uuid: uuid.clone(),
+                    kind: kind.clone(),
+                    filename: filename.clone(),
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Unescape a single nmcli terse value
+pub fn unescape_terse(value: &str) -> String {
+    split_terse(value).join(":")
+}
+
+fn split_terse(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    fields.last_mut().unwrap().push(next);
+                }
+            }
+            ':' => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// Store `psk` inside a keyfile that keeps it with an agent or nowhere
+///
+/// Profiles created interactively on the live system may leave the secret
+/// to the desktop's agent; on the new system it has to be in the file.
+/// Keyfiles without a `[wifi-security]` section or with a psk are returned
+/// unchanged.
+pub fn with_psk(keyfile: &str, psk: &str) -> String {
+    let has_psk = section_lines(keyfile, "wifi-security").any(|l| l.trim_start().starts_with("psk="));
+    if psk.is_empty() || has_psk || !keyfile.lines().any(|l| l.trim() == "[wifi-security]") {
+        return keyfile.to_string();
+    }
+
+    let mut out = Vec::new();
+    let mut in_section = false;
+    for line in keyfile.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_section = trimmed == "[wifi-security]";
+        } else if in_section && trimmed.starts_with("psk-flags=") {
+            continue;
+        }
+        out.push(line.to_string());
+        if trimmed == "[wifi-security]" {
+            out.push(format!("psk={}", psk));
+        }
+    }
+    let mut result = out.join("\n");
+    result.push('\n');
+    result
+}
+
+fn section_lines<'a>(keyfile: &'a str, section: &'a str) -> impl Iterator<Item = &'a str> {
+    let header = format!("[{}]", section);
+    keyfile
+        .lines()
+        .skip_while(move |l| l.trim() != header)
+        .skip(1)
+        .take_while(|l| !l.trim().starts_with('['))
+}
+
+/// Scripted Wi-Fi for tests: goes online once the right passphrase is used
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct FakeWifi {
+    pub networks: Vec<AccessPoint>,
+    /// Passphrase for secured networks
+    pub passphrase: String,
+    /// Connectivity checks that fail after connecting (slow DHCP)
+    pub offline_checks: u32,
+    state: std::sync::Arc<std::sync::Mutex<FakeWifiState>>,
+}
+
+#[cfg(test)]
+#[derive(Default)]
+struct FakeWifiState {
+    connected: Option<String>,
+    checks: u32,
+}
+
+#[cfg(test)]
+impl FakeWifi {
+    pub fn connected(&self) -> Option<String> {
+        self.state.lock().unwrap().connected.clone()
+    }
+}
+
+#[cfg(test)]
+impl WifiManager for FakeWifi {
+    fn online<'a>(&'a self) -> BoxFuture<'a, bool> {
+        Box::pin(async move {
+            let mut state = self.state.lock().unwrap();
+            if state.connected.is_none() {
+                return false;
+            }
+            state.checks += 1;
+            state.checks > self.offline_checks
+        })
+    }
+
+    fn scan<'a>(&'a self) -> BoxFuture<'a, Result<Vec<AccessPoint>>> {
+        Box::pin(async move { Ok(self.networks.clone()) })
+    }
+
+    fn connect<'a>(&'a self, ssid: &'a str, passphrase: Option<&'a str>) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let Some(network) = self.networks.iter().find(|n| n.ssid == ssid) else {
+                anyhow::bail!("No network with SSID '{}' found", ssid);
+            };
+            if network.secured() && passphrase != Some(self.passphrase.as_str()) {
+                anyhow::bail!("Secrets were required, but not provided");
+            }
+            self.state.lock().unwrap().connected = Some(ssid.to_string());
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network(ssid: &str, signal: u8, security: &str) -> AccessPoint {
+        AccessPoint {
+            ssid: ssid.to_string(),
+            signal,
+            security: security.to_string(),
+            in_use: false,
+        }
+    }
+
+    #[test]
+    fn test_parse_access_points() {
+        let output = " :54:WPA2:Office\n\
+                      *:82:WPA1 WPA2:Home\\:5G\n\
+                       :30::\n\
+                       :67:WPA2:Office\n\
+                       :40::Cafe\n";
+        let networks = parse_access_points(output);
+        assert_eq!(
+            networks.iter().map(|n| (n.ssid.as_str(), n.signal)).collect::<Vec<_>>(),
+            vec![("Home:5G", 82), ("Office", 67), ("Cafe", 40)]
+        );
+        assert!(networks[0].in_use);
+        assert!(networks[1].secured());
+        assert!(!networks[2].secured());
+    }
+
+    #[tokio::test]
+    async fn test_connect_and_check() {
+        let wifi = FakeWifi {
+            networks: vec![network("Home", 80, "WPA2"), network("Cafe", 40, "")],
+            passphrase: "hunter22".to_string(),
+            offline_checks: 2,
+            ..Default::default()
+        };
+        assert!(!wifi.online().await);
+
+        let err = connect_and_check(&wifi, "Home", Some("wrong"), 3, Duration::ZERO).await.unwrap_err();
+        assert!(err.to_string().contains("Secrets were required"));
+        assert_eq!(wifi.connected(), None);
+
+        // Still offline after the first check, online by the third
+        connect_and_check(&wifi, "Home", Some("hunter22"), 3, Duration::ZERO).await.unwrap();
+        assert_eq!(wifi.connected().as_deref(), Some("Home"));
+
+        let slow = FakeWifi {
+            networks: wifi.networks.clone(),
+            offline_checks: 5,
+            ..Default::default()
+        };
+        let err = connect_and_check(&slow, "Cafe", None, 3, Duration::ZERO).await.unwrap_err();
+        assert!(err.to_string().contains("not reachable"));
+    }
+
+    #[test]
+    fn test_parse_connections() {
+        let output = "Home\\:5G:0b7c-11:802-11-wireless:/etc/NetworkManager/system-connections/Home\\:5G.nmconnection\n\
+                      Wired connection 1:4f2a-22:802-3-ethernet:/run/NetworkManager/system-connections/Wired.nmconnection\n\
+                      lo:9d1e-33:loopback:\n";
+        let connections = parse_connections(output);
+        assert_eq!(connections.len(), 3);
+        assert_eq!(connections[0].name, "Home:5G");
+        assert_eq!(connections[0].kind, WIFI_CONNECTION_TYPE);
+        assert_eq!(connections[0].filename, "/etc/NetworkManager/system-connections/Home:5G.nmconnection");
+        assert_eq!(connections[2].filename, "");
+        assert_eq!(unescape_terse("pass\\:word\\\\"), "pass:word\\");
+    }
+
+    #[test]
+    fn test_with_psk() {
+        let agent_owned = "[connection]\nid=Home\n\n[wifi-security]\nkey-mgmt=wpa-psk\npsk-flags=1\n\n[ipv4]\nmethod=auto\n";
+        assert_eq!(
+            with_psk(agent_owned, "hunter22"),
+            "[connection]\nid=Home\n\n[wifi-security]\npsk=hunter22\nkey-mgmt=wpa-psk\n\n[ipv4]\nmethod=auto\n"
+        );
+
+        let stored = "[wifi-security]\nkey-mgmt=wpa-psk\npsk=secret\n";
+        assert_eq!(with_psk(stored, "other"), stored);
+        let open = "[wifi]\nssid=Cafe\n";
+        assert_eq!(with_psk(open, "x"), open);
+    }
+}
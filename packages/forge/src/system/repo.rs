@@ -0,0 +1,312 @@
+//! Configuration repository source for installs
+//!
+//! Installs clone the default repository's default branch unless a fork and/or
+//! a branch, tag or commit is chosen (`forge install --repo --ref` or the
+//! host-selection screen). After cloning, the checked-out commit is compared
+//! with what the remote says the ref points at.
+
+/// GitHub repository URL for the NixOS configuration
+pub const DEFAULT_REPO_URL: &str = "https://github.com/Arnld81nl/nixos-config.git";
+
+/// git package to use instead of nix-shell (set for offline installs)
+static GIT_PACKAGE: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
+/// Characters that would break out of the single-quoted shell arguments
+const UNSAFE_CHARS: &[char] = &['\'', '"', '\\', '`', '$', ';', '&', '|', '<', '>'];
+
+/// Where to clone the configuration from
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepoSource {
+    pub url: String,
+    /// Branch, tag or commit; None for the remote's default branch
+    pub git_ref: Option<String>,
+}
+
+impl Default for RepoSource {
+    fn default() -> Self {
+        RepoSource {
+            url: DEFAULT_REPO_URL.to_string(),
+            git_ref: None,
+        }
+    }
+}
+
+impl std::fmt::Display for RepoSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.url)?;
+        if let Some(git_ref) = &self.git_ref {
+            write!(f, " @ {}", git_ref)?;
+        }
+        Ok(())
+    }
+}
+
+impl RepoSource {
+    /// Build a source from user input; empty values fall back to the defaults
+    pub fn new(url: Option<&str>, git_ref: Option<&str>) -> Result<Self, String> {
+        let url = url.map(str::trim).filter(|u| !u.is_empty()).unwrap_or(DEFAULT_REPO_URL);
+        let git_ref = git_ref.map(str::trim).filter(|r| !r.is_empty());
+        if let Some(err) = validate_url(url) {
+            return Err(err);
+        }
+        if let Some(err) = git_ref.and_then(validate_ref) {
+            return Err(err);
+        }
+        Ok(RepoSource {
+            url: url.to_string(),
+            git_ref: git_ref.map(str::to_string),
+        })
+    }
+
+    /// Shell commands (run with git available) that clone this source into `dir`
+    pub fn clone_script(&self, dir: &str) -> String {
+        match &self.git_ref {
+            None => format!("git clone --depth 1 '{}' '{}'", self.url, dir),
+            // Full clone: the ref may be any branch, tag or (abbreviated) commit
+            Some(git_ref) => format!(
+                "git clone '{}' '{}' && git -C '{}' checkout -q '{}'",
+                self.url, dir, dir, git_ref
+            ),
+        }
+    }
+
+    /// Shell commands that print the clone's origin URL, its HEAD and the remote's refs
+    pub fn check_script(&self, dir: &str) -> String {
+        format!(
+            "git -C '{}' remote get-url origin && git -C '{}' rev-parse HEAD && git ls-remote '{}'",
+            dir, dir, self.url
+        )
+    }
+
+    /// Check the output of `check_script`, returning the checked-out commit
+    ///
+    /// With `fresh` (just cloned) and no ref, HEAD must also match the remote's
+    /// default branch; an older clone is allowed to lag behind it.
+    pub fn verify(&self, output: &str, fresh: bool) -> Result<String, String> {
+        let mut lines = output.lines().map(str::trim);
+        let origin = lines.next().unwrap_or("");
+        let head = lines.next().unwrap_or("").to_lowercase();
+        let ls_remote: Vec<&str> = lines.collect();
+
+        if normalize_url(origin) != normalize_url(&self.url) {
+            return Err(format!("Cloned from {}, expected {}", origin, self.url));
+        }
+        if !is_commit_id(&head) || head.len() != 40 {
+            return Err(format!("Unexpected HEAD '{}'", head));
+        }
+
+        let expected = match &self.git_ref {
+            None if !fresh => return Ok(head),
+            None => remote_commit(&ls_remote, "HEAD"),
+            Some(git_ref) => resolve_ref(&ls_remote, git_ref),
+        };
+        match (expected, &self.git_ref) {
+            (Some(expected), _) if expected == head => Ok(head),
+            (Some(expected), git_ref) => Err(format!(
+                "Checked out {}, but {} is at {}",
+                short(&head),
+                git_ref.as_deref().unwrap_or("the default branch"),
+                short(&expected)
+            )),
+            (None, Some(git_ref)) if is_commit_id(git_ref) => {
+                if head.starts_with(&git_ref.to_lowercase()) {
+                    Ok(head)
+                } else {
+                    Err(format!("Checked out {}, expected commit {}", short(&head), git_ref))
+                }
+            }
+            (None, Some(git_ref)) => Err(format!("'{}' is not a branch, tag or commit of {}", git_ref, self.url)),
+            (None, None) => Err(format!("{} has no default branch", self.url)),
+        }
+    }
+}
+
+/// Run git scripts with git from `package` (a store path) rather than nix-shell
+pub fn use_git(package: &str) {
+    let _ = GIT_PACKAGE.set(package.to_string());
+}
+
+/// Command line running the shell `script` with git available
+pub fn git_shell(script: &str) -> Vec<String> {
+    git_shell_with(GIT_PACKAGE.get().map(String::as_str), script)
+}
+
+fn git_shell_with(package: Option<&str>, script: &str) -> Vec<String> {
+    match package {
+        Some(package) => vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            format!("PATH='{}/bin':\"$PATH\"; {}", package, script),
+        ],
+        None => vec![
+            "nix-shell".to_string(),
+            "-p".to_string(),
+            "git".to_string(),
+            "--run".to_string(),
+            script.to_string(),
+        ],
+    }
+}
+
+/// Abbreviated commit id for messages
+pub fn short(commit: &str) -> &str {
+    &commit[..commit.len().min(12)]
+}
+
+fn normalize_url(url: &str) -> &str {
+    let url = url.trim().trim_end_matches('/');
+    url.strip_suffix(".git").unwrap_or(url)
+}
+
+/// Whether `value` looks like a (possibly abbreviated) commit id
+pub fn is_commit_id(value: &str) -> bool {
+    (4..=40).contains(&value.len()) && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn remote_commit(ls_remote: &[&str], name: &str) -> Option<String> {
+    ls_remote.iter().find_map(|line| {
+        let (commit, refname) = line.split_once('\t')?;
+        (refname == name).then(|| commit.trim().to_lowercase())
+    })
+}
+
+/// Commit a branch or tag points at, per `git ls-remote` (annotated tags peeled)
+pub fn resolve_ref(ls_remote: &[&str], git_ref: &str) -> Option<String> {
+    let candidates = [
+        format!("refs/tags/{}^{{}}", git_ref),
+        format!("refs/tags/{}", git_ref),
+        format!("refs/heads/{}", git_ref),
+        format!("{}^{{}}", git_ref),
+        git_ref.to_string(),
+    ];
+    candidates.iter().find_map(|name| remote_commit(ls_remote, name))
+}
+
+/// Reason a repository URL can't be used, if any
+pub fn validate_url(url: &str) -> Option<String> {
+    if url.is_empty() {
+        return Some("Repository URL cannot be empty".to_string());
+    }
+    if url.chars().any(|c| c.is_whitespace() || UNSAFE_CHARS.contains(&c)) || url.starts_with('-') {
+        return Some("Repository URL contains invalid characters".to_string());
+    }
+    let known = ["https://", "http://", "ssh://", "git://", "file://", "git@", "/"];
+    if !known.iter().any(|prefix| url.starts_with(prefix)) {
+        return Some("Repository URL must be https://, ssh://, git@host:path or an absolute path".to_string());
+    }
+    None
+}
+
+/// Reason a git ref can't be used, if any (subset of git check-ref-format)
+pub fn validate_ref(git_ref: &str) -> Option<String> {
+    let valid_chars = git_ref
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '/' | '+'));
+    if !valid_chars
+        || git_ref.starts_with(['-', '/', '.'])
+        || git_ref.ends_with(['/', '.'])
+        || git_ref.ends_with(".lock")
+        || git_ref.contains("..")
+        || git_ref.contains("//")
+    {
+        return Some(format!("'{}' is not a valid branch, tag or commit", git_ref));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEAD: &str = "1111111111111111111111111111111111111111";
+    const BRANCH: &str = "2222222222222222222222222222222222222222";
+    const TAG: &str = "3333333333333333333333333333333333333333";
+    const TAG_COMMIT: &str = "4444444444444444444444444444444444444444";
+
+    fn ls_remote() -> String {
+        format!(
+            "{HEAD}\tHEAD\n{HEAD}\trefs/heads/main\n{BRANCH}\trefs/heads/feature/x\n{TAG}\trefs/tags/v1.0\n{TAG_COMMIT}\trefs/tags/v1.0^{{}}\n"
+        )
+    }
+
+    fn output(origin: &str, head: &str) -> String {
+        format!("{}\n{}\n{}", origin, head, ls_remote())
+    }
+
+    #[test]
+    fn test_new_defaults_and_validation() {
+        assert_eq!(RepoSource::new(None, None).unwrap(), RepoSource::default());
+        assert_eq!(RepoSource::new(Some("  "), Some("")).unwrap(), RepoSource::default());
+        let fork = RepoSource::new(Some("https://github.com/me/nixos-config"), Some("feature/x")).unwrap();
+        assert_eq!(fork.git_ref.as_deref(), Some("feature/x"));
+        assert_eq!(fork.to_string(), "https://github.com/me/nixos-config @ feature/x");
+
+        assert!(RepoSource::new(Some("github.com/me/repo"), None).is_err());
+        assert!(RepoSource::new(Some("https://x/y'; rm -rf /"), None).is_err());
+        assert!(RepoSource::new(None, Some("--upload-pack=evil")).is_err());
+        assert!(RepoSource::new(None, Some("a..b")).is_err());
+        assert!(RepoSource::new(None, Some("v1.0")).is_ok());
+    }
+
+    #[test]
+    fn test_clone_script() {
+        let default = RepoSource::default();
+        assert_eq!(
+            default.clone_script("/tmp/nixos-config"),
+            format!("git clone --depth 1 '{}' '/tmp/nixos-config'", DEFAULT_REPO_URL)
+        );
+        let tagged = RepoSource::new(None, Some("v1.0")).unwrap();
+        assert!(tagged.clone_script("/tmp/c").ends_with("git -C '/tmp/c' checkout -q 'v1.0'"));
+    }
+
+    #[test]
+    fn test_git_shell() {
+        assert_eq!(git_shell_with(None, "git status"), ["nix-shell", "-p", "git", "--run", "git status"]);
+        assert_eq!(
+            git_shell_with(Some("/nix/store/abc-git"), "git status"),
+            ["sh", "-c", "PATH='/nix/store/abc-git/bin':\"$PATH\"; git status"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_ref() {
+        let listing = ls_remote();
+        let lines: Vec<&str> = listing.lines().collect();
+        assert_eq!(resolve_ref(&lines, "feature/x").as_deref(), Some(BRANCH));
+        // Annotated tags resolve to the commit they point at
+        assert_eq!(resolve_ref(&lines, "v1.0").as_deref(), Some(TAG_COMMIT));
+        assert_eq!(resolve_ref(&lines, "missing"), None);
+    }
+
+    #[test]
+    fn test_verify_default_branch() {
+        let source = RepoSource::default();
+        assert_eq!(source.verify(&output(DEFAULT_REPO_URL, HEAD), true), Ok(HEAD.to_string()));
+        // A fresh clone must match the remote; an existing one may lag behind
+        assert!(source.verify(&output(DEFAULT_REPO_URL, BRANCH), true).is_err());
+        assert!(source.verify(&output(DEFAULT_REPO_URL, BRANCH), false).is_ok());
+        // .git suffix and trailing slash don't matter, the repository does
+        assert!(source.verify(&output("https://github.com/Arnld81nl/nixos-config/", HEAD), true).is_ok());
+        assert!(source.verify(&output("https://github.com/me/nixos-config.git", HEAD), true).is_err());
+    }
+
+    #[test]
+    fn test_verify_refs() {
+        let url = "https://github.com/me/nixos-config.git";
+        let branch = RepoSource::new(Some(url), Some("feature/x")).unwrap();
+        assert!(branch.verify(&output(url, BRANCH), false).is_ok());
+        let err = branch.verify(&output(url, HEAD), false).unwrap_err();
+        assert!(err.contains("feature/x is at 222222222222"));
+
+        let tag = RepoSource::new(Some(url), Some("v1.0")).unwrap();
+        assert!(tag.verify(&output(url, TAG_COMMIT), true).is_ok());
+
+        let commit = RepoSource::new(Some(url), Some("abcdef12")).unwrap();
+        let abcdef = "abcdef1234567890abcdef1234567890abcdef12";
+        assert!(commit.verify(&output(url, abcdef), true).is_ok());
+        assert!(commit.verify(&output(url, HEAD), true).is_err());
+
+        let missing = RepoSource::new(Some(url), Some("nope")).unwrap();
+        assert!(missing.verify(&output(url, HEAD), true).unwrap_err().contains("not a branch, tag or commit"));
+    }
+}
@@ -0,0 +1,259 @@
+//! User accounts for the installed system
+//!
+//! Passwords never reach the configuration repository: the installer hashes them
+//! in-process (SHA-512 crypt), stores the hashes as root-only files on the target
+//! system and points `users.users.<name>.hashedPasswordFile` at them. With
+//! `users.mutableUsers` (see modules/common.nix) that file only sets the initial
+//! password; later changes go through `passwd`.
+
+use rand::Rng;
+use sha2::{Digest, Sha512};
+use std::sync::LazyLock;
+
+/// Where the password hash files live on the installed system
+pub const PASSWORD_DIR: &str = "/var/lib/forge/passwords";
+
+/// Groups that can be given to additional users
+pub const EXTRA_GROUPS: &[&str] = &["wheel", "networkmanager", "docker"];
+
+/// Groups a new additional user starts with
+pub const DEFAULT_EXTRA_GROUPS: &[&str] = &["networkmanager"];
+
+/// Rounds for SHA-512 crypt (glibc's default of 5000 is far too cheap today)
+const SHA512_ROUNDS: u32 = 656_000;
+const SHA512_DEFAULT_ROUNDS: u32 = 5_000;
+const SHA512_MIN_ROUNDS: u32 = 1_000;
+const SHA512_MAX_ROUNDS: u32 = 999_999_999;
+const SALT_LENGTH: usize = 16;
+
+/// Alphabet used by crypt(3) for salts and hashes
+const CRYPT_ALPHABET: &[u8] = b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+const BLOCK_HEADER: &str = "# User accounts (auto-generated by Forge installer)";
+const BLOCK_FOOTER: &str = "# End of user accounts";
+
+/// Matches a previously written accounts block
+static BLOCK_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(
+        r#"(?ms)^\s*# User accounts \(auto-generated by Forge installer\).*?^\s*# End of user accounts\s*$\n?"#,
+    )
+    .expect("User accounts block regex pattern is statically validated")
+});
+
+/// An account created next to the primary user
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtraUser {
+    pub username: String,
+    pub password: String,
+    pub groups: Vec<String>,
+}
+
+/// Path of a user's password hash file on the installed system
+pub fn password_file(username: &str) -> String {
+    format!("{}/{}", PASSWORD_DIR, username)
+}
+
+/// Hash a password for /etc/shadow and `hashedPasswordFile` (SHA-512 crypt, random salt)
+pub fn hash_password(password: &str) -> String {
+    let mut rng = rand::thread_rng();
+    let salt: String = (0..SALT_LENGTH)
+        .map(|_| CRYPT_ALPHABET[rng.gen_range(0..CRYPT_ALPHABET.len())] as char)
+        .collect();
+    sha512_crypt(password.as_bytes(), salt.as_bytes(), Some(SHA512_ROUNDS))
+}
+
+/// SHA-512 crypt as specified by Ulrich Drepper (`$6$`), compatible with glibc crypt(3)
+fn sha512_crypt(password: &[u8], salt: &[u8], rounds: Option<u32>) -> String {
+    let salt = &salt[..salt.len().min(SALT_LENGTH)];
+    let rounds_value = rounds
+        .unwrap_or(SHA512_DEFAULT_ROUNDS)
+        .clamp(SHA512_MIN_ROUNDS, SHA512_MAX_ROUNDS);
+
+    // Digest B: password, salt, password
+    let digest_b = Sha512::new()
+        .chain_update(password)
+        .chain_update(salt)
+        .chain_update(password)
+        .finalize();
+
+    // Digest A
+    let mut ctx = Sha512::new().chain_update(password).chain_update(salt);
+    let mut remaining = password.len();
+    while remaining > 64 {
+        ctx.update(digest_b);
+        remaining -= 64;
+    }
+    ctx.update(&digest_b[..remaining]);
+    let mut bits = password.len();
+    while bits > 0 {
+        if bits & 1 == 1 {
+            ctx.update(digest_b);
+        } else {
+            ctx.update(password);
+        }
+        bits >>= 1;
+    }
+    let mut digest_a = ctx.finalize();
+
+    // Byte sequence P: digest of the password repeated once per password byte
+    let mut ctx = Sha512::new();
+    for _ in 0..password.len() {
+        ctx.update(password);
+    }
+    let digest_p = ctx.finalize();
+    let p_bytes: Vec<u8> = digest_p.iter().cycle().take(password.len()).copied().collect();
+
+    // Byte sequence S: digest of the salt repeated 16 + A[0] times
+    let mut ctx = Sha512::new();
+    for _ in 0..16 + digest_a[0] as usize {
+        ctx.update(salt);
+    }
+    let digest_s = ctx.finalize();
+    let s_bytes = &digest_s[..salt.len()];
+
+    for i in 0..rounds_value {
+        let mut ctx = Sha512::new();
+        if i % 2 == 1 {
+            ctx.update(&p_bytes);
+        } else {
+            ctx.update(digest_a);
+        }
+        if i % 3 != 0 {
+            ctx.update(s_bytes);
+        }
+        if i % 7 != 0 {
+            ctx.update(&p_bytes);
+        }
+        if i % 2 == 1 {
+            ctx.update(digest_a);
+        } else {
+            ctx.update(&p_bytes);
+        }
+        digest_a = ctx.finalize();
+    }
+
+    const ORDER: [(usize, usize, usize); 21] = [
+        (0, 21, 42), (22, 43, 1), (44, 2, 23), (3, 24, 45), (25, 46, 4), (47, 5, 26), (6, 27, 48),
+        (28, 49, 7), (50, 8, 29), (9, 30, 51), (31, 52, 10), (53, 11, 32), (12, 33, 54), (34, 55, 13),
+        (56, 14, 35), (15, 36, 57), (37, 58, 16), (59, 17, 38), (18, 39, 60), (40, 61, 19), (62, 20, 41),
+    ];
+    let mut encoded = String::with_capacity(86);
+    for (a, b, c) in ORDER {
+        encode_crypt64(&mut encoded, digest_a[a], digest_a[b], digest_a[c], 4);
+    }
+    encode_crypt64(&mut encoded, 0, 0, digest_a[63], 2);
+
+    let rounds_part = match rounds {
+        Some(_) => format!("rounds={}$", rounds_value),
+        None => String::new(),
+    };
+    format!("$6${}{}${}", rounds_part, String::from_utf8_lossy(salt), encoded)
+}
+
+/// Append `count` crypt-base64 characters of a 24-bit group
+fn encode_crypt64(out: &mut String, high: u8, mid: u8, low: u8, count: usize) {
+    let mut word = ((high as u32) << 16) | ((mid as u32) << 8) | low as u32;
+    for _ in 0..count {
+        out.push(CRYPT_ALPHABET[(word & 0x3f) as usize] as char);
+        word >>= 6;
+    }
+}
+
+/// Insert (or replace) the accounts block at the end of a host's default.nix
+///
+/// The primary user is declared in modules/common.nix; only its password file is set
+/// here. Additional users are declared in full.
+pub fn inject_host_users(content: &str, primary: &str, extra_users: &[ExtraUser]) -> String {
+    let cleaned = BLOCK_RE.replace(content, "").to_string();
+    let Some(pos) = cleaned.rfind('}') else {
+        return cleaned;
+    };
+
+    let mut block = format!(
+        "\n  {}\n  users.users.{}.hashedPasswordFile = \"{}\";\n",
+        BLOCK_HEADER,
+        primary,
+        password_file(primary)
+    );
+    for user in extra_users {
+        let groups: Vec<String> = user.groups.iter().map(|g| format!("\"{}\"", g)).collect();
+        block.push_str(&format!(
+            "  users.users.{} = {{\n    isNormalUser = true;\n    extraGroups = [ {} ];\n    hashedPasswordFile = \"{}\";\n  }};\n",
+            user.username,
+            groups.join(" "),
+            password_file(&user.username)
+        ));
+    }
+    block.push_str(&format!("  {}\n", BLOCK_FOOTER));
+
+    let mut result = cleaned[..pos].trim_end().to_string();
+    result.push('\n');
+    result.push_str(&block);
+    result.push_str(&cleaned[pos..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vectors from the SHA-crypt specification (cross-checked with openssl passwd -6)
+    #[test]
+    fn test_sha512_crypt_spec_vectors() {
+        assert_eq!(
+            sha512_crypt(b"Hello world!", b"saltstring", None),
+            "$6$saltstring$svn8UoSVapNtMuq1ukKS4tPQd8iKwSMHWjl/O817G3uBnIFNjnQJuesI68u4OTLiBFdcbYEdFCoEOfaS35inz1"
+        );
+        assert_eq!(
+            sha512_crypt(b"Hello world!", b"saltstringsaltstring", Some(10_000)),
+            "$6$rounds=10000$saltstringsaltst$OW1/O6BYHV6BcXZu8QVeXbDWra3Oeqh0sbHbbMCVNSnCM/UrjmM0Dp8vOuZeHBy/YTBmSK6H9qs/y3RnOaw5v."
+        );
+        assert_eq!(
+            sha512_crypt(b"This is just a test", b"toolongsaltstring", Some(5_000)),
+            "$6$rounds=5000$toolongsaltstrin$lQ8jolhgVRVhY4b5pZKaysCLi0QBxGoNeKQzQ3glMhwllF7oGDZxUhx1yxdYcz/e1JSbq3y6JMxxl8audkUEm0"
+        );
+        assert_eq!(
+            sha512_crypt(b"the minimum number is still observed", b"roundstoolow", Some(10)),
+            "$6$rounds=1000$roundstoolow$kUMsbe306n21p9R.FRkW3IGn.S9NPN0x50YhH1xhLsPuWGsUSklZt58jaTfF4ZEQpyUNGc0dqbpBYYBaHHrsX."
+        );
+    }
+
+    #[test]
+    fn test_sha512_crypt_long_password() {
+        // Longer than one digest, exercises the repeated-B and P sequence paths
+        let password = "a".repeat(100);
+        let hash = sha512_crypt(password.as_bytes(), b"abcdefgh", None);
+        assert!(hash.starts_with("$6$abcdefgh$"));
+        assert_eq!(hash.len(), "$6$abcdefgh$".len() + 86);
+    }
+
+    #[test]
+    fn test_inject_host_users_primary_only() {
+        let content = "{ config, ... }:\n{\n  networking.hostName = \"x1yoga\";\n}\n";
+        let result = inject_host_users(content, "arnold", &[]);
+        assert!(result.contains(
+            "users.users.arnold.hashedPasswordFile = \"/var/lib/forge/passwords/arnold\";"
+        ));
+        assert!(result.contains("networking.hostName = \"x1yoga\";"));
+        assert!(result.trim_end().ends_with('}'));
+    }
+
+    #[test]
+    fn test_inject_host_users_extra_users_and_idempotent() {
+        let content = "{ config, ... }:\n{\n  networking.hostName = \"x1yoga\";\n}\n";
+        let extra = vec![ExtraUser {
+            username: "guest".to_string(),
+            password: "secret123".to_string(),
+            groups: vec!["networkmanager".to_string(), "docker".to_string()],
+        }];
+        let once = inject_host_users(content, "arnold", &extra);
+        assert!(once.contains("users.users.guest = {"));
+        assert!(once.contains("extraGroups = [ \"networkmanager\" \"docker\" ];"));
+        assert!(once.contains("hashedPasswordFile = \"/var/lib/forge/passwords/guest\";"));
+        assert!(!once.contains("secret123"));
+
+        let twice = inject_host_users(&once, "arnold", &[]);
+        assert_eq!(twice.matches(BLOCK_HEADER).count(), 1);
+        assert!(!twice.contains("users.users.guest"));
+    }
+}
@@ -0,0 +1,525 @@
+//! Post-install verification
+//!
+//! Inspects the freshly installed system under /mnt before the installer offers
+//! a reboot: bootloader files on the ESP, the /etc/nixos symlink and repository
+//! ownership, the declared filesystems against what is mounted, the LUKS device
+//! and the user accounts.
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::commands::executor::run_capture;
+use crate::constants::{INSTALL_MOUNT_POINT, INSTALL_SYMLINK_PATH, PRIMARY_USER_GID, PRIMARY_USER_UID};
+
+/// Filesystem types that are never mounted under /mnt during an install
+const VIRTUAL_FS_TYPES: &[&str] = &["tmpfs", "ramfs", "proc", "sysfs", "devtmpfs", "devpts", "efivarfs"];
+
+/// Applied to `nixosConfigurations.<host>.config`; keeps only what the checks need
+const EVAL_APPLY: &str = "c: { \
+    fileSystems = map (fs: { inherit (fs) mountPoint device fsType; }) (builtins.attrValues c.fileSystems); \
+    luks = builtins.mapAttrs (name: dev: dev.device) c.boot.initrd.luks.devices; \
+}";
+
+/// One line of the verification summary
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl Check {
+    fn pass(name: &str, detail: impl Into<String>) -> Self {
+        Check { name: name.to_string(), passed: true, detail: detail.into() }
+    }
+
+    fn fail(name: &str, detail: impl Into<String>) -> Self {
+        Check { name: name.to_string(), passed: false, detail: detail.into() }
+    }
+
+    /// Summary line for output views
+    pub fn line(&self) -> String {
+        format!("{} {:<18} {}", if self.passed { "✓" } else { "✗" }, self.name, self.detail)
+    }
+}
+
+/// What the installer set up and expects to find
+pub struct Expected<'a> {
+    pub hostname: &'a str,
+    /// Configuration repository on the target (under /mnt)
+    pub config_dir: &'a str,
+    /// Where /etc/nixos should point on the installed system
+    pub symlink_target: &'a str,
+    pub usernames: &'a [&'a str],
+    pub encrypted: bool,
+    /// NIX_CONFIG for evaluating the flake as root
+    pub nix_config: &'a str,
+}
+
+/// A filesystem the installed system will mount
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeclaredFs {
+    pub mount_point: String,
+    pub device: Option<String>,
+    pub fs_type: String,
+}
+
+/// Parts of the evaluated host configuration
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct EvaluatedConfig {
+    #[serde(rename = "fileSystems")]
+    pub file_systems: Vec<DeclaredFs>,
+    /// LUKS mapper name -> device path
+    pub luks: std::collections::BTreeMap<String, String>,
+}
+
+/// A mount below the install root, with the path it has on the installed system
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mount {
+    pub source: String,
+    pub target: String,
+    pub fs_type: String,
+}
+
+/// A configured LUKS device and what the live system sees of it
+#[derive(Debug, Clone, PartialEq)]
+pub struct LuksDevice {
+    pub name: String,
+    pub device: String,
+    pub device_exists: bool,
+    pub opened: bool,
+}
+
+/// Everything gathered from the target
+#[derive(Debug, Clone)]
+pub struct TargetState {
+    /// Files on the ESP, relative to its mount point
+    pub esp_files: Result<Vec<String>, String>,
+    pub limine_conf: Option<String>,
+    pub symlink: Result<String, String>,
+    /// First file in the repository not owned by the primary user
+    pub foreign_owned: Result<Option<String>, String>,
+    pub config: Result<EvaluatedConfig, String>,
+    pub mounts: Vec<Mount>,
+    pub luks: Vec<LuksDevice>,
+    pub passwd: Result<String, String>,
+    pub shadow: Result<String, String>,
+}
+
+/// Inspect the target and evaluate every check
+pub async fn verify(expected: &Expected<'_>) -> Vec<Check> {
+    checks(&inspect(expected).await, expected)
+}
+
+/// Gather the state of the installed system
+pub async fn inspect(expected: &Expected<'_>) -> TargetState {
+    let esp = format!("{}/boot", INSTALL_MOUNT_POINT);
+    // The ESP is mounted root-only (umask 0077)
+    let esp_files = sudo_read("find", &[&esp, "-type", "f", "-printf", "%P\\n"])
+        .await
+        .map(|out| out.lines().map(str::to_string).collect::<Vec<_>>());
+    let limine_conf = match &esp_files {
+        Ok(files) => match files.iter().find(|f| f.to_lowercase().ends_with("limine.conf")) {
+            Some(file) => sudo_read("cat", &[&format!("{}/{}", esp, file)]).await.ok(),
+            None => None,
+        },
+        Err(_) => None,
+    };
+
+    let symlink = std::fs::read_link(INSTALL_SYMLINK_PATH)
+        .map(|target| target.to_string_lossy().to_string())
+        .map_err(|e| format!("{}: {}", INSTALL_SYMLINK_PATH, e));
+
+    let foreign_owned = sudo_read(
+        "find",
+        &[
+            expected.config_dir,
+            "(",
+            "!",
+            "-uid",
+            &PRIMARY_USER_UID.to_string(),
+            "-o",
+            "!",
+            "-gid",
+            &PRIMARY_USER_GID.to_string(),
+            ")",
+            "-print",
+            "-quit",
+        ],
+    )
+    .await
+    .map(|out| out.lines().next().map(str::to_string));
+
+    let config = evaluate_config(expected).await;
+
+    let mounts = std::fs::read_to_string("/proc/mounts")
+        .map(|content| parse_mounts(&content, INSTALL_MOUNT_POINT))
+        .unwrap_or_default();
+
+    let luks = match &config {
+        Ok(config) => config
+            .luks
+            .iter()
+            .map(|(name, device)| LuksDevice {
+                name: name.clone(),
+                device: device.clone(),
+                device_exists: Path::new(device).exists(),
+                opened: Path::new("/dev/mapper").join(name).exists(),
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    let passwd = std::fs::read_to_string(format!("{}/etc/passwd", INSTALL_MOUNT_POINT))
+        .map_err(|e| format!("/etc/passwd: {}", e));
+    let shadow = sudo_read("cat", &[&format!("{}/etc/shadow", INSTALL_MOUNT_POINT)]).await;
+
+    TargetState {
+        esp_files,
+        limine_conf,
+        symlink,
+        foreign_owned,
+        config,
+        mounts,
+        luks,
+        passwd,
+        shadow,
+    }
+}
+
+/// Run a command with sudo and return its stdout
+async fn sudo_read(cmd: &str, args: &[&str]) -> Result<String, String> {
+    let mut full = vec![cmd];
+    full.extend_from_slice(args);
+    match run_capture("sudo", &full).await {
+        Ok((true, stdout, _)) => Ok(stdout),
+        Ok((false, _, stderr)) => Err(first_line(&stderr, &format!("{} failed", cmd))),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Evaluate the host's filesystems and LUKS devices from the installed repository
+async fn evaluate_config(expected: &Expected<'_>) -> Result<EvaluatedConfig, String> {
+    let attr = format!("{}#nixosConfigurations.{}.config", expected.config_dir, expected.hostname);
+    let nix_config = format!("NIX_CONFIG={}", expected.nix_config);
+    let output = sudo_read("env", &[&nix_config, "nix", "eval", "--json", &attr, "--apply", EVAL_APPLY]).await?;
+    serde_json::from_str(&output).map_err(|e| format!("Unexpected nix eval output: {}", e))
+}
+
+fn first_line(text: &str, fallback: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or(fallback)
+        .to_string()
+}
+
+/// Mounts below `root` from /proc/mounts, with their paths on the installed system
+pub fn parse_mounts(content: &str, root: &str) -> Vec<Mount> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 3 {
+                return None;
+            }
+            // /proc/mounts escapes spaces and tabs as octal
+            let mount_point = fields[1].replace("\\040", " ").replace("\\011", "\t");
+            let target = if mount_point == root {
+                "/".to_string()
+            } else {
+                mount_point.strip_prefix(root)?.strip_prefix('/').map(|rest| format!("/{}", rest))?
+            };
+            Some(Mount {
+                source: fields[0].to_string(),
+                target,
+                fs_type: fields[2].to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Evaluate all checks against the gathered state
+pub fn checks(state: &TargetState, expected: &Expected<'_>) -> Vec<Check> {
+    let mut checks = vec![
+        check_bootloader(&state.esp_files, state.limine_conf.as_deref()),
+        check_symlink(&state.symlink, expected.symlink_target),
+        check_ownership(&state.foreign_owned),
+        check_filesystems(&state.config, &state.mounts),
+        check_luks(&state.config, &state.luks, expected.encrypted),
+    ];
+    for user in expected.usernames {
+        checks.push(check_user(user, &state.passwd, &state.shadow));
+    }
+    checks
+}
+
+/// Boot entries in a limine.conf (one `protocol:` line per entry)
+pub fn count_limine_entries(conf: &str) -> usize {
+    conf.lines()
+        .filter(|line| line.trim_start().to_lowercase().starts_with("protocol:"))
+        .count()
+}
+
+fn check_bootloader(esp_files: &Result<Vec<String>, String>, limine_conf: Option<&str>) -> Check {
+    const NAME: &str = "Bootloader";
+    let files = match esp_files {
+        Ok(files) => files,
+        Err(e) => return Check::fail(NAME, format!("Cannot read the ESP: {}", e)),
+    };
+    let Some(binary) = files.iter().find(|f| {
+        let lower = f.to_lowercase();
+        lower.starts_with("efi/") && lower.ends_with(".efi")
+    }) else {
+        return Check::fail(NAME, "No EFI binary on the ESP");
+    };
+    let entries = limine_conf.map(count_limine_entries).unwrap_or(0)
+        + files
+            .iter()
+            .filter(|f| f.starts_with("loader/entries/") && f.ends_with(".conf"))
+            .count();
+    if entries == 0 {
+        return Check::fail(NAME, format!("{} found, but no boot entries", binary));
+    }
+    Check::pass(NAME, format!("{}, {} boot entr{}", binary, entries, if entries == 1 { "y" } else { "ies" }))
+}
+
+fn check_symlink(symlink: &Result<String, String>, expected: &str) -> Check {
+    const NAME: &str = "/etc/nixos symlink";
+    match symlink {
+        Ok(target) if target.trim_end_matches('/') == expected.trim_end_matches('/') => {
+            Check::pass(NAME, format!("-> {}", target))
+        }
+        Ok(target) => Check::fail(NAME, format!("Points to {}, expected {}", target, expected)),
+        Err(e) => Check::fail(NAME, e.clone()),
+    }
+}
+
+fn check_ownership(foreign_owned: &Result<Option<String>, String>) -> Check {
+    const NAME: &str = "Repository owner";
+    let owner = format!("{}:{}", PRIMARY_USER_UID, PRIMARY_USER_GID);
+    match foreign_owned {
+        Ok(None) => Check::pass(NAME, format!("All files owned by {}", owner)),
+        Ok(Some(path)) => Check::fail(NAME, format!("{} is not owned by {}", path, owner)),
+        Err(e) => Check::fail(NAME, e.clone()),
+    }
+}
+
+fn check_filesystems(config: &Result<EvaluatedConfig, String>, mounts: &[Mount]) -> Check {
+    const NAME: &str = "Filesystems";
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => return Check::fail(NAME, format!("Cannot evaluate the configuration: {}", e)),
+    };
+    let declared: Vec<&DeclaredFs> = config
+        .file_systems
+        .iter()
+        .filter(|fs| !VIRTUAL_FS_TYPES.contains(&fs.fs_type.as_str()))
+        .collect();
+
+    let mut problems = Vec::new();
+    for fs in &declared {
+        match mounts.iter().rev().find(|m| m.target == fs.mount_point) {
+            None => problems.push(format!("{} not mounted", fs.mount_point)),
+            Some(m) if fs.fs_type != "auto" && m.fs_type != fs.fs_type => {
+                problems.push(format!("{} is {}, configured as {}", fs.mount_point, m.fs_type, fs.fs_type))
+            }
+            Some(_) => {}
+        }
+        if let Some(device) = fs.device.as_deref().filter(|d| d.starts_with("/dev/")) {
+            if !Path::new(device).exists() {
+                problems.push(format!("{} device {} not found", fs.mount_point, device));
+            }
+        }
+    }
+    for m in mounts {
+        if !declared.iter().any(|fs| fs.mount_point == m.target) {
+            problems.push(format!("{} is mounted but not configured", m.target));
+        }
+    }
+
+    if problems.is_empty() {
+        Check::pass(NAME, format!("{} configured filesystems mounted", declared.len()))
+    } else {
+        Check::fail(NAME, problems.join("; "))
+    }
+}
+
+fn check_luks(config: &Result<EvaluatedConfig, String>, devices: &[LuksDevice], encrypted: bool) -> Check {
+    const NAME: &str = "LUKS device";
+    if !encrypted {
+        return Check::pass(NAME, "Not used by this layout");
+    }
+    if let Err(e) = config {
+        return Check::fail(NAME, format!("Cannot evaluate the configuration: {}", e));
+    }
+    if devices.is_empty() {
+        return Check::fail(NAME, "No boot.initrd.luks.devices configured");
+    }
+    let problems: Vec<String> = devices
+        .iter()
+        .filter_map(|d| {
+            if !d.device_exists {
+                Some(format!("{} not found for {}", d.device, d.name))
+            } else if !d.opened {
+                Some(format!("/dev/mapper/{} is not open", d.name))
+            } else {
+                None
+            }
+        })
+        .collect();
+    if problems.is_empty() {
+        let found: Vec<String> = devices.iter().map(|d| format!("{} ({})", d.name, d.device)).collect();
+        Check::pass(NAME, found.join(", "))
+    } else {
+        Check::fail(NAME, problems.join("; "))
+    }
+}
+
+fn check_user(username: &str, passwd: &Result<String, String>, shadow: &Result<String, String>) -> Check {
+    let name = format!("User {}", username);
+    let passwd = match passwd {
+        Ok(content) => content,
+        Err(e) => return Check::fail(&name, e.clone()),
+    };
+    let Some(entry) = find_entry(passwd, username) else {
+        return Check::fail(&name, "No passwd entry");
+    };
+    let shell = entry.get(6).copied().unwrap_or("");
+    if shell.ends_with("/nologin") || shell.ends_with("/false") {
+        return Check::fail(&name, format!("Login shell is {}", shell));
+    }
+
+    let shadow = match shadow {
+        Ok(content) => content,
+        Err(e) => return Check::fail(&name, format!("Cannot read /etc/shadow: {}", e)),
+    };
+    match find_entry(shadow, username).and_then(|fields| fields.get(1).copied()) {
+        Some(hash) if hash.starts_with('$') => Check::pass(&name, "Can log in"),
+        Some(hash) if hash.starts_with('!') => Check::fail(&name, "Account is locked"),
+        Some(_) => Check::fail(&name, "No password hash set"),
+        None => Check::fail(&name, "No shadow entry"),
+    }
+}
+
+/// Colon-separated fields of a passwd/shadow line for `username`
+fn find_entry<'a>(content: &'a str, username: &str) -> Option<Vec<&'a str>> {
+    content
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .find(|fields| fields.first() == Some(&username))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mount(target: &str, fs_type: &str) -> Mount {
+        Mount {
+            source: "/dev/mapper/cryptroot".to_string(),
+            target: target.to_string(),
+            fs_type: fs_type.to_string(),
+        }
+    }
+
+    fn declared(mount_point: &str, fs_type: &str) -> DeclaredFs {
+        DeclaredFs {
+            mount_point: mount_point.to_string(),
+            device: None,
+            fs_type: fs_type.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_mounts_below_root() {
+        let content = "\
+/dev/mapper/cryptroot /mnt btrfs rw,subvol=/@ 0 0
+/dev/nvme0n1p1 /mnt/boot vfat rw,fmask=0077 0 0
+/dev/mapper/cryptroot /mnt/var/log btrfs rw 0 0
+/dev/sda1 /mnt2 ext4 rw 0 0
+proc /proc proc rw 0 0
+";
+        let mounts = parse_mounts(content, "/mnt");
+        let targets: Vec<&str> = mounts.iter().map(|m| m.target.as_str()).collect();
+        assert_eq!(targets, vec!["/", "/boot", "/var/log"]);
+        assert_eq!(mounts[1].fs_type, "vfat");
+    }
+
+    #[test]
+    fn test_count_limine_entries() {
+        let conf = "timeout: 5\n/+NixOS\n//Generation 1\n  protocol: linux\n  kernel_path: boot():/limine/kernels/a\n//Generation 2\n  protocol: linux\n";
+        assert_eq!(count_limine_entries(conf), 2);
+        assert_eq!(count_limine_entries("timeout: 5\n"), 0);
+    }
+
+    #[test]
+    fn test_check_bootloader() {
+        let files = Ok(vec!["EFI/limine/BOOTX64.EFI".to_string(), "limine/limine.conf".to_string()]);
+        assert!(check_bootloader(&files, Some("  protocol: linux\n")).passed);
+        assert!(!check_bootloader(&files, Some("timeout: 5\n")).passed);
+        assert!(!check_bootloader(&Ok(vec!["limine/limine.conf".to_string()]), Some("protocol: linux")).passed);
+
+        let systemd_boot = Ok(vec!["EFI/systemd/systemd-bootx64.efi".to_string(), "loader/entries/nixos-1.conf".to_string()]);
+        assert!(check_bootloader(&systemd_boot, None).passed);
+    }
+
+    #[test]
+    fn test_check_symlink() {
+        assert!(check_symlink(&Ok("/home/arnold/nixos-config".to_string()), "/home/arnold/nixos-config").passed);
+        assert!(!check_symlink(&Ok("/tmp/nixos-config".to_string()), "/home/arnold/nixos-config").passed);
+        assert!(!check_symlink(&Err("missing".to_string()), "/home/arnold/nixos-config").passed);
+    }
+
+    #[test]
+    fn test_check_filesystems() {
+        let config = Ok(EvaluatedConfig {
+            file_systems: vec![declared("/", "btrfs"), declared("/boot", "vfat"), declared("/tmp", "tmpfs")],
+            luks: Default::default(),
+        });
+        let mounts = vec![mount("/", "btrfs"), mount("/boot", "vfat")];
+        assert!(check_filesystems(&config, &mounts).passed);
+
+        let wrong_type = vec![mount("/", "ext4"), mount("/boot", "vfat")];
+        let check = check_filesystems(&config, &wrong_type);
+        assert!(!check.passed);
+        assert!(check.detail.contains("/ is ext4"));
+
+        let extra = vec![mount("/", "btrfs"), mount("/boot", "vfat"), mount("/data", "ext4")];
+        assert!(check_filesystems(&config, &extra).detail.contains("/data is mounted but not configured"));
+
+        let missing = vec![mount("/", "btrfs")];
+        assert!(check_filesystems(&config, &missing).detail.contains("/boot not mounted"));
+    }
+
+    #[test]
+    fn test_check_luks() {
+        let config = Ok(EvaluatedConfig::default());
+        let device = |exists, opened| LuksDevice {
+            name: "cryptroot".to_string(),
+            device: "/dev/disk/by-partlabel/disk-main-luks".to_string(),
+            device_exists: exists,
+            opened,
+        };
+        assert!(check_luks(&config, &[], false).passed);
+        assert!(!check_luks(&config, &[], true).passed);
+        assert!(check_luks(&config, &[device(true, true)], true).passed);
+        assert!(!check_luks(&config, &[device(false, true)], true).passed);
+        assert!(!check_luks(&config, &[device(true, false)], true).passed);
+    }
+
+    #[test]
+    fn test_check_user() {
+        let passwd = Ok("root:x:0:0::/root:/run/current-system/sw/bin/bash\narnold:x:1000:100::/home/arnold:/run/current-system/sw/bin/fish\nnobody:x:65534:65534::/var/empty:/run/current-system/sw/bin/nologin\n".to_string());
+        let shadow = Ok("root:!:1::::::\narnold:$6$rounds=656000$salt$hash:1::::::\nnobody:!:1::::::\n".to_string());
+        assert!(check_user("arnold", &passwd, &shadow).passed);
+        assert_eq!(check_user("guest", &passwd, &shadow).detail, "No passwd entry");
+        assert!(check_user("nobody", &passwd, &shadow).detail.starts_with("Login shell"));
+
+        let locked = Ok("arnold:!$6$salt$hash:1::::::\n".to_string());
+        assert_eq!(check_user("arnold", &passwd, &locked).detail, "Account is locked");
+        let empty = Ok("arnold::1::::::\n".to_string());
+        assert_eq!(check_user("arnold", &passwd, &empty).detail, "No password hash set");
+    }
+}
@@ -1,6 +1,7 @@
 //! NixOS configuration template generators
 
-use crate::app::NewHostConfig;
+use crate::app::{DiskLayout, NewHostConfig, PartitionScheme};
+use crate::system::disk::DiskInfo;
 use crate::system::hardware::{CpuInfo, CpuVendor, FormFactor, GpuInfo, GpuVendor};
 
 /// Generate the host's default.nix configuration
@@ -214,9 +215,11 @@ fn generate_initrd_modules(gpu: &GpuInfo) -> String {
 }
 
 /// Generate disko configuration for the host
-pub fn generate_disko_config(hostname: &str, disk_path: &str) -> String {
-    format!(
-        r#"# Disko configuration for {hostname}
+pub fn generate_disko_config(hostname: &str, disk: &DiskInfo, layout: &DiskLayout) -> String {
+    if layout.is_shared() {
+        return format!(
+            r#"# Disko configuration for {hostname}
+# Layout: {marker}
 {{ ... }}:
 
 {{
@@ -224,12 +227,212 @@ pub fn generate_disko_config(hostname: &str, disk_path: &str) -> String {
 
   disko.devices.disk.main.device = "{disk_path}";
 }}
+"#,
+            hostname = hostname,
+            marker = layout_marker(layout),
+            disk_path = disk.path,
+        );
+    }
+
+    let scheme = layout.scheme;
+    let home_quota = if scheme == PartitionScheme::LuksBtrfsHomeQuota {
+        home_quota_gb(disk.size_bytes)
+    } else {
+        None
+    };
+    let filesystem = if scheme.btrfs() {
+        btrfs_content(home_quota)
+    } else {
+        EXT4_CONTENT.to_string()
+    };
+    let root = if scheme.encrypted() {
+        format!(
+            "luks = {{\n  label = \"cryptroot\";\n  size = \"100%\";\n{}}};\n",
+            indent(&luks_content("cryptroot", &filesystem), 2)
+        )
+    } else {
+        format!(
+            "root = {{\n  label = \"nixos\";\n  size = \"100%\";\n{}}};\n",
+            indent(&filesystem, 2)
+        )
+    };
+    let swap = match layout.swap_gb {
+        Some(gb) if scheme.encrypted() => format!(
+            "swap = {{\n  label = \"cryptswap\";\n  size = \"{}G\";\n{}}};\n",
+            gb,
+            indent(&luks_content("cryptswap", SWAP_CONTENT), 2)
+        ),
+        Some(gb) => format!(
+            "swap = {{\n  label = \"swap\";\n  size = \"{}G\";\n{}}};\n",
+            gb,
+            indent(SWAP_CONTENT, 2)
+        ),
+        None => String::new(),
+    };
+
+    let mut extra = String::new();
+    let mounts: &[&str] = if scheme.btrfs() {
+        &["/", "/home", "/nix", "/var/log"]
+    } else {
+        &["/"]
+    };
+    extra.push_str("  # Mount filesystems in initrd so they wait for the root device\n");
+    for mount in mounts {
+        extra.push_str(&format!("  fileSystems.\"{}\".neededForBoot = true;\n", mount));
+    }
+    if scheme.encrypted() {
+        extra.push_str("\n  # Kernel modules needed for LUKS and device mapper\n");
+        extra.push_str("  boot.initrd.availableKernelModules = [ \"dm_mod\" \"dm_crypt\" \"cryptd\" \"aesni_intel\" ];\n");
+    }
+    if scheme.btrfs() {
+        extra.push_str("\n  boot.initrd.supportedFilesystems = [ \"btrfs\" ];\n");
+    } else {
+        extra.push_str("\n  # No btrfs filesystems to scrub\n");
+        extra.push_str("  services.btrfs.autoScrub.enable = false;\n");
+    }
+    if let (PartitionScheme::LuksBtrfsHomeQuota, None) = (scheme, home_quota) {
+        extra.push_str("\n  # Disk size was unknown, so @home has no quota yet\n");
+    }
+    extra.push_str("\n  boot.initrd.systemd.emergencyAccess = true;\n");
+
+    format!(
+        r#"# Disko configuration for {hostname}
+# Layout: {marker}
+{{ ... }}:
+
+{{
+  disko.devices = {{
+    disk.main = {{
+      type = "disk";
+      device = "{disk_path}";
+      content = {{
+        type = "gpt";
+        partitions = {{
+{esp}{swap}{root}        }};
+      }};
+    }};
+  }};
+
+{extra}}}
 "#,
         hostname = hostname,
-        disk_path = disk_path,
+        marker = layout_marker(layout),
+        disk_path = disk.path,
+        esp = indent(ESP_PARTITION, 10),
+        swap = indent(&swap, 10),
+        root = indent(&root, 10),
+        extra = extra,
+    )
+}
+
+/// Layout recorded in a host's disko file; files without a marker use the shared layout
+pub fn parse_disko_layout(content: &str) -> DiskLayout {
+    let Some(marker) = content.lines().find_map(|l| l.trim().strip_prefix("# Layout:")) else {
+        return DiskLayout::default();
+    };
+    let mut parts = marker.split(',').map(str::trim);
+    let scheme = parts.next().and_then(PartitionScheme::from_slug).unwrap_or_default();
+    let swap_gb = parts.find_map(|p| p.strip_prefix("swap ")?.strip_suffix('G')?.parse().ok());
+    DiskLayout { scheme, swap_gb }
+}
+
+fn layout_marker(layout: &DiskLayout) -> String {
+    match layout.swap_gb {
+        Some(gb) => format!("{}, swap {}G", layout.scheme.slug(), gb),
+        None => layout.scheme.slug().to_string(),
+    }
+}
+
+/// Quota for @home: 60% of the disk, leaving room for /nix and logs
+fn home_quota_gb(size_bytes: u64) -> Option<u64> {
+    (size_bytes > 0).then(|| (size_bytes / 1_000_000_000 * 3 / 5).max(10))
+}
+
+const ESP_PARTITION: &str = r#"ESP = {
+  label = "ESP";
+  size = "2G";
+  type = "EF00";
+  content = {
+    type = "filesystem";
+    format = "vfat";
+    mountpoint = "/boot";
+    mountOptions = [ "umask=0077" "nofail" "x-systemd.device-timeout=30s" ];
+  };
+};
+"#;
+
+const EXT4_CONTENT: &str = r#"content = {
+  type = "filesystem";
+  format = "ext4";
+  mountpoint = "/";
+  mountOptions = [ "noatime" ];
+};
+"#;
+
+const SWAP_CONTENT: &str = r#"content = {
+  type = "swap";
+};
+"#;
+
+/// LUKS2 container around `inner`, opened as /dev/mapper/<name>
+fn luks_content(name: &str, inner: &str) -> String {
+    format!(
+        r#"content = {{
+  type = "luks";
+  name = "{name}";
+  passwordFile = "/tmp/luks-password";
+  extraOpenArgs = [ "--allow-discards" "--perf-no_read_workqueue" "--perf-no_write_workqueue" ];
+  settings = {{
+    allowDiscards = true;
+    bypassWorkqueues = true;
+  }};
+{inner}}};
+"#,
+        name = name,
+        inner = indent(inner, 2),
+    )
+}
+
+fn btrfs_content(home_quota: Option<u64>) -> String {
+    let home_quota = home_quota
+        .map(|gb| format!("      quota = \"{}G\";\n", gb))
+        .unwrap_or_default();
+    format!(
+        r#"content = {{
+  type = "btrfs";
+  extraArgs = [ "-f" "-L" "nixos" ];
+  subvolumes = {{
+    "@" = {{
+      mountpoint = "/";
+      mountOptions = [ "compress=zstd" "noatime" ];
+    }};
+    "@home" = {{
+      mountpoint = "/home";
+      mountOptions = [ "compress=zstd" "noatime" ];
+{home_quota}    }};
+    "@nix" = {{
+      mountpoint = "/nix";
+      mountOptions = [ "compress=zstd" "noatime" ];
+    }};
+    "@var-log" = {{
+      mountpoint = "/var/log";
+      mountOptions = [ "compress=zstd" "noatime" ];
+    }};
+  }};
+}};
+"#,
+        home_quota = home_quota,
     )
 }
 
+/// Indent every non-empty line by `spaces`
+fn indent(text: &str, spaces: usize) -> String {
+    let pad = " ".repeat(spaces);
+    text.lines()
+        .map(|line| if line.is_empty() { "\n".to_string() } else { format!("{}{}\n", pad, line) })
+        .collect()
+}
+
 /// Generate hardware-configuration.nix template
 pub fn generate_hardware_config(cpu: &CpuInfo, hostname: &str) -> String {
     let kvm_module = match cpu.vendor {
@@ -273,14 +476,70 @@ pub fn generate_hardware_config(cpu: &CpuInfo, hostname: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::system::disk::DiskInfo;
     use crate::system::hardware::GpuInfo;
 
+    fn disk() -> DiskInfo {
+        DiskInfo {
+            path: "/dev/nvme0n1".to_string(),
+            size: "1TB".to_string(),
+            size_bytes: 1_000_000_000_000,
+            model: None,
+            partitions: vec![],
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn test_generate_disko_config() {
-        let config = generate_disko_config("testhost", "/dev/nvme0n1");
+        let config = generate_disko_config("testhost", &disk(), &DiskLayout::default());
         assert!(config.contains("testhost"));
         assert!(config.contains("/dev/nvme0n1"));
+        assert!(config.contains("imports = [ ./default.nix ];"));
+    }
+
+    #[test]
+    fn test_generate_disko_config_layouts() {
+        let ext4 = DiskLayout {
+            scheme: PartitionScheme::LuksExt4,
+            swap_gb: Some(18),
+        };
+        let config = generate_disko_config("testhost", &disk(), &ext4);
+        assert!(!config.contains("./default.nix"));
+        assert!(config.contains("device = \"/dev/nvme0n1\";"));
+        assert!(config.contains("format = \"ext4\";"));
+        assert!(config.contains("name = \"cryptswap\";"));
+        assert!(config.contains("size = \"18G\";"));
+        assert!(config.contains("autoScrub.enable = false"));
+        assert_eq!(config.matches('{').count(), config.matches('}').count());
+
+        let plain = DiskLayout {
+            scheme: PartitionScheme::Btrfs,
+            swap_gb: None,
+        };
+        let config = generate_disko_config("vm", &disk(), &plain);
+        assert!(!config.contains("luks"));
+        assert!(config.contains("\"@home\""));
+
+        let quota = DiskLayout {
+            scheme: PartitionScheme::LuksBtrfsHomeQuota,
+            swap_gb: None,
+        };
+        let config = generate_disko_config("testhost", &disk(), &quota);
+        assert!(config.contains("quota = \"600G\";"));
+    }
+
+    #[test]
+    fn test_parse_disko_layout_round_trips() {
+        for scheme in PartitionScheme::ALL {
+            for swap_gb in [None, Some(34)] {
+                let layout = DiskLayout { scheme, swap_gb };
+                let config = generate_disko_config("testhost", &disk(), &layout);
+                assert_eq!(parse_disko_layout(&config), layout);
+            }
+        }
+        // Hosts created before layouts were recorded
+        let legacy = "{ ... }:\n{\n  imports = [ ./default.nix ];\n}\n";
+        assert_eq!(parse_disko_layout(legacy), DiskLayout::default());
     }
 
     #[test]
@@ -303,7 +562,9 @@ mod tests {
                 size_bytes: 0,
                 model: None,
                 partitions: vec![],
+                ..Default::default()
             },
+            layout: DiskLayout::default(),
         };
 
         let result = generate_host_default_nix(&config);
@@ -332,7 +593,9 @@ mod tests {
                 size_bytes: 0,
                 model: None,
                 partitions: vec![],
+                ..Default::default()
             },
+            layout: DiskLayout::default(),
         };
 
         let result = generate_host_default_nix(&config);
@@ -21,6 +21,24 @@ pub fn draw(frame: &mut Frame, app: &App) {
             screens::main_menu::draw(frame, *selected, app);
         }
         AppMode::Install(state) => match state {
+            InstallState::Network {
+                networks,
+                selected,
+                passphrase,
+                busy,
+                error,
+                ..
+            } => {
+                screens::install::draw_network(
+                    frame,
+                    networks,
+                    *selected,
+                    passphrase.as_deref(),
+                    busy.as_deref(),
+                    error.as_deref(),
+                    app,
+                );
+            }
             InstallState::CloneRepository { output } => {
                 let output_vec: Vec<String> = output.iter().cloned().collect();
                 screens::install::draw_clone_repository(frame, &output_vec, app);
@@ -28,12 +46,17 @@ pub fn draw(frame: &mut Frame, app: &App) {
             InstallState::SelectHost { selected } => {
                 screens::install::draw_host_selection(frame, *selected, &app.hosts, app);
             }
+            InstallState::EditSource { selected, url, git_ref, field, error } => {
+                screens::install::draw_host_selection(frame, *selected, &app.hosts, app);
+                screens::install::draw_source_editor(frame, url, git_ref, *field, error.as_deref());
+            }
             InstallState::SelectDisk {
                 host,
                 disks,
                 selected,
+                confirm,
             } => {
-                screens::install::draw_disk_selection(frame, host, disks, *selected, app);
+                screens::install::draw_disk_selection(frame, host, disks, *selected, *confirm, app);
             }
             InstallState::EnterCredentials {
                 host,
@@ -46,6 +69,14 @@ pub fn draw(frame: &mut Frame, app: &App) {
                     frame, host, disk, credentials, active_field, error.as_deref(), app,
                 );
             }
+            InstallState::ExtraUsers { host, credentials, selected, form, error, .. } => {
+                screens::install::draw_extra_users(
+                    frame, host, credentials, *selected, form.as_ref(), error.as_deref(), app,
+                );
+            }
+            InstallState::SelectLayout { host, disk, credentials, selected } => {
+                screens::install::draw_select_layout(frame, host, disk, &credentials.layout, *selected, app);
+            }
             InstallState::SelectSwapMode {
                 host,
                 disk,
@@ -55,6 +86,12 @@ pub fn draw(frame: &mut Frame, app: &App) {
             } => {
                 screens::install::draw_select_swap_mode(frame, host, disk, *selected, *ram_gb, app);
             }
+            InstallState::SelectKeySource { host, disk, selected, passphrase, .. } => {
+                screens::install::draw_select_key_source(frame, host, disk, *selected, passphrase.as_deref(), app);
+            }
+            InstallState::SelectLocale { host, disk, field, choices, filter, selected, .. } => {
+                screens::install::draw_select_locale(frame, host, disk, *field, choices, filter, *selected, app);
+            }
             InstallState::Overview { host, disk, credentials, input, hardware_config, .. } => {
                 screens::install::draw_overview(frame, host, disk, credentials, input, hardware_config.as_ref(), app);
             }
@@ -73,9 +110,10 @@ pub fn draw(frame: &mut Frame, app: &App) {
                 success,
                 output,
                 scroll_offset,
+                checks,
             } => {
                 let output_vec: Vec<String> = output.iter().cloned().collect();
-                screens::install::draw_complete(frame, *success, &output_vec, *scroll_offset, app);
+                screens::install::draw_complete(frame, *success, &output_vec, *scroll_offset, checks, app);
             }
         },
         AppMode::Update(state) => match state {
@@ -102,14 +140,23 @@ pub fn draw(frame: &mut Frame, app: &App) {
             }
         },
         AppMode::Apps(state) => match state {
-            AppProfileState::Menu { selected } => {
-                screens::apps::draw_menu(frame, *selected, app);
+            AppProfileState::Menu { selected, apps } => {
+                screens::apps::draw_menu(frame, *selected, apps, app);
+            }
+            AppProfileState::History {
+                snapshots,
+                selected,
+                loading,
+                error,
+                apps,
+            } => {
+                screens::apps::draw_history(frame, snapshots, *selected, *loading, error.as_deref(), apps, app);
             }
             AppProfileState::Running {
-                operation, output, ..
+                operation, output, at, ..
             } => {
                 let output_vec: Vec<String> = output.iter().cloned().collect();
-                screens::apps::draw_running(frame, operation, &output_vec, app);
+                screens::apps::draw_running(frame, operation, at.as_deref(), &output_vec, app);
             }
             AppProfileState::Status { output } => {
                 let output_vec: Vec<String> = output.iter().cloned().collect();
@@ -119,9 +166,10 @@ pub fn draw(frame: &mut Frame, app: &App) {
                 success,
                 output,
                 scroll_offset,
+                undo_available,
             } => {
                 let output_vec: Vec<String> = output.iter().cloned().collect();
-                screens::apps::draw_complete(frame, *success, &output_vec, *scroll_offset, app);
+                screens::apps::draw_complete(frame, *success, &output_vec, *scroll_offset, *undo_available, app);
             }
         },
         AppMode::Keys(state) => match state {
@@ -131,6 +179,19 @@ pub fn draw(frame: &mut Frame, app: &App) {
                 let output_vec: Vec<String> = output.iter().cloned().collect();
                 screens::keys::draw_running(frame, operation, &output_vec, app);
             }
+            KeysState::Status {
+                report,
+                loading,
+                error,
+                fixed,
+            } => {
+                screens::keys::draw_status(frame, report.as_deref(), *loading, error.as_deref(), fixed, app);
+            }
+            KeysState::PaperRestore {
+                shares, input, error, ..
+            } => {
+                screens::keys::draw_paper_restore(frame, shares, input, error.as_deref(), app);
+            }
             KeysState::Complete {
                 success,
                 output,
@@ -178,11 +239,15 @@ pub fn draw(frame: &mut Frame, app: &App) {
                 form_factor,
                 disks,
                 selected,
+                confirm,
             } => {
                 screens::create_host::draw_select_disk(
-                    frame, cpu, gpu, form_factor, disks, *selected, app,
+                    frame, cpu, gpu, form_factor, disks, *selected, *confirm, app,
                 );
             }
+            CreateHostState::SelectLayout { disk, layout, selected, .. } => {
+                screens::install::draw_select_layout(frame, "New host", disk, layout, *selected, app);
+            }
             CreateHostState::EnterHostname {
                 cpu,
                 gpu,
@@ -190,6 +255,7 @@ pub fn draw(frame: &mut Frame, app: &App) {
                 disk,
                 input,
                 error,
+                ..
             } => {
                 screens::create_host::draw_enter_hostname(
                     frame, cpu, gpu, form_factor, disk, input, error.as_deref(), app,
@@ -330,7 +396,10 @@ fn draw_update_dialog(frame: &mut Frame, updates: &PendingUpdates) {
 
     // Calculate dialog size based on content
     let popup_width = 55;
-    let popup_height = if both { 13 } else { 10 };
+    let mut popup_height = if both { 13 } else { 10 };
+    if updates.nixos_config && !updates.commits.is_empty() {
+        popup_height += 1; // Host attribution line
+    }
     let x = area.x + (area.width.saturating_sub(popup_width)) / 2;
     let y = area.y + (area.height.saturating_sub(popup_height)) / 2;
     let popup_area = Rect::new(x, y, popup_width, popup_height);
@@ -359,6 +428,14 @@ fn draw_update_dialog(frame: &mut Frame, updates: &PendingUpdates) {
         )));
     }
 
+    // Which of the pending config commits matter for this machine
+    if updates.nixos_config && !updates.commits.is_empty() {
+        lines.push(Line::from(Span::styled(
+            format!("NixOS config: {}", updates.commit_summary()),
+            theme::dim(),
+        )));
+    }
+
     lines.push(Line::from(""));
 
     // Build menu options in order:
@@ -456,10 +533,14 @@ fn draw_commit_list(frame: &mut Frame, updates: &PendingUpdates) {
     // Build content lines
     let mut lines = vec![Line::from("")];
 
-    // Show commit count
-    let commit_count = updates.commits.len();
+    // Show commit count and how many affect this host
+    let hint = if updates.commits_for_host() < updates.commits.len() {
+        " (others dimmed)"
+    } else {
+        ""
+    };
     lines.push(Line::from(Span::styled(
-        format!("{} new commit{} available:", commit_count, if commit_count == 1 { "" } else { "s" }),
+        format!("{}{}:", updates.commit_summary(), hint),
         theme::text(),
     )));
     lines.push(Line::from(""));
@@ -475,8 +556,10 @@ fn draw_commit_list(frame: &mut Frame, updates: &PendingUpdates) {
         let is_current = i == updates.selected_commit;
         let style = if is_current {
             theme::selected()
-        } else {
+        } else if commit.affects_this_host {
             theme::text()
+        } else {
+            theme::dim()
         };
 
         // Truncate message to fit
@@ -3,23 +3,25 @@
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
     Frame,
 };
 
-use crate::app::{App, AppOp, APP_MENU_ITEMS};
+use crate::app::{App, AppChoice, AppOp, APP_MENU_ITEMS};
+use crate::backup::history::Snapshot;
 use crate::ui::layout::centered_rect;
 use crate::ui::theme;
 use crate::ui::widgets::{LogView, MenuList};
 
-/// Draw app profiles menu
-pub fn draw_menu(frame: &mut Frame, selected: usize, _app: &App) {
+/// Draw app profiles menu with the app checklist below the actions
+pub fn draw_menu(frame: &mut Frame, selected: usize, apps: &[AppChoice], _app: &App) {
     let area = frame.area();
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(5),
-            Constraint::Min(10),
+            Constraint::Length(APP_MENU_ITEMS.len() as u16 + 2),
+            Constraint::Min(apps.len() as u16 + 2),
             Constraint::Length(3),
         ])
         .split(centered_rect(60, 80, area));
@@ -38,25 +40,157 @@ pub fn draw_menu(frame: &mut Frame, selected: usize, _app: &App) {
     frame.render_widget(header, chunks[0]);
 
     // Menu
-    let menu = MenuList::new(APP_MENU_ITEMS.to_vec(), selected);
+    let in_menu = selected < APP_MENU_ITEMS.len();
+    let menu = MenuList::new(APP_MENU_ITEMS.to_vec(), selected).active(in_menu);
     frame.render_widget(menu, chunks[1]);
 
+    // App checklist
+    let app_selected = selected.checked_sub(APP_MENU_ITEMS.len());
+    let items: Vec<ListItem> = apps
+        .iter()
+        .enumerate()
+        .map(|(i, choice)| {
+            let is_selected = app_selected == Some(i);
+            let style = if is_selected {
+                theme::selected()
+            } else {
+                theme::text()
+            };
+            let mut spans = vec![
+                Span::styled(if is_selected { "> " } else { "  " }, style),
+                Span::styled(if choice.checked { "[x] " } else { "[ ] " }, style),
+                Span::styled(choice.name, style),
+            ];
+            if !choice.installed {
+                spans.push(Span::styled("  (not installed)", theme::dim()));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let checklist = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(Span::styled(" Apps ", theme::title())),
+    );
+    let mut state = ListState::default().with_selected(app_selected);
+    frame.render_stateful_widget(checklist, chunks[2], &mut state);
+
     // Footer
-    let footer = Paragraph::new(Line::from(vec![
+    let mut hints = vec![
         Span::styled("[", theme::dim()),
         Span::styled("↑↓", theme::key_hint()),
         Span::styled("] Navigate  [", theme::dim()),
+        Span::styled("Space", theme::key_hint()),
+        Span::styled("] Toggle app  [", theme::dim()),
         Span::styled("Enter", theme::key_hint()),
         Span::styled("] Select  [", theme::dim()),
         Span::styled("Esc", theme::key_hint()),
         Span::styled("] Back", theme::dim()),
+    ];
+    if !apps.iter().any(|a| a.checked) {
+        hints = vec![Span::styled("Select at least one app to back up or restore", theme::warning())];
+    }
+    let footer = Paragraph::new(Line::from(hints)).alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[3]);
+}
+
+/// Draw the backup snapshot picker
+pub fn draw_history(
+    frame: &mut Frame,
+    snapshots: &[Snapshot],
+    selected: usize,
+    loading: bool,
+    error: Option<&str>,
+    apps: &[String],
+    app: &App,
+) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(10),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    // Header
+    let header = Paragraph::new(Line::from(Span::styled(
+        " Restore an Older Backup ",
+        theme::title(),
+    )))
+    .alignment(Alignment::Center)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border_active()),
+    );
+    frame.render_widget(header, chunks[0]);
+
+    let scope = if apps.is_empty() {
+        " Snapshots (all apps) ".to_string()
+    } else {
+        format!(" Snapshots (restoring: {}) ", apps.join(", "))
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme::border())
+        .title(Span::styled(scope, theme::title()));
+
+    if loading || error.is_some() || snapshots.is_empty() {
+        let message = if loading {
+            let spinner_char = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏']
+                [app.spinner_state % 10];
+            Span::styled(format!(" {} Loading backup history...", spinner_char), theme::dim())
+        } else if let Some(error) = error {
+            Span::styled(format!(" {}", error), theme::error())
+        } else {
+            Span::styled(" No backups found", theme::dim())
+        };
+        frame.render_widget(Paragraph::new(Line::from(message)).block(block), chunks[1]);
+    } else {
+        let items: Vec<ListItem> = snapshots
+            .iter()
+            .enumerate()
+            .map(|(i, snapshot)| {
+                let (prefix, style) = if i == selected {
+                    ("> ", theme::selected())
+                } else {
+                    ("  ", theme::text())
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(prefix, style),
+                    Span::styled(snapshot.summary(), style),
+                ]))
+            })
+            .collect();
+        let mut state = ListState::default().with_selected(Some(selected));
+        frame.render_stateful_widget(List::new(items).block(block), chunks[1], &mut state);
+    }
+
+    // Footer
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("[", theme::dim()),
+        Span::styled("↑↓", theme::key_hint()),
+        Span::styled("] Navigate  [", theme::dim()),
+        Span::styled("Enter", theme::key_hint()),
+        Span::styled("] Restore snapshot  [", theme::dim()),
+        Span::styled("Esc", theme::key_hint()),
+        Span::styled("] Back", theme::dim()),
     ]))
     .alignment(Alignment::Center);
     frame.render_widget(footer, chunks[2]);
 }
 
 /// Draw running operation screen
-pub fn draw_running(frame: &mut Frame, operation: &AppOp, output: &[String], app: &App) {
+pub fn draw_running(
+    frame: &mut Frame,
+    operation: &AppOp,
+    at: Option<&str>,
+    output: &[String],
+    app: &App,
+) {
     let area = frame.area();
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -68,9 +202,13 @@ pub fn draw_running(frame: &mut Frame, operation: &AppOp, output: &[String], app
         .split(area);
 
     // Header
-    let title = match operation {
-        AppOp::Backup => " Backing Up App Profiles ",
-        AppOp::Restore => " Restoring App Profiles ",
+    let title = match (operation, at) {
+        (AppOp::Backup, _) => " Backing Up App Profiles ".to_string(),
+        (AppOp::Restore, None) => " Restoring App Profiles ".to_string(),
+        (AppOp::Restore, Some(at)) => format!(" Restoring App Profiles from {} ", at),
+        (AppOp::Undo, _) => " Undoing App Profile Restore ".to_string(),
+        (AppOp::Schedule { every_hours: Some(_) }, _) => " Scheduling App Backups ".to_string(),
+        (AppOp::Schedule { every_hours: None }, _) => " Removing App Backup Schedule ".to_string(),
     };
     let header = Paragraph::new(Line::from(Span::styled(title, theme::title())))
         .alignment(Alignment::Center)
@@ -145,6 +283,7 @@ pub fn draw_complete(
     success: bool,
     output: &[String],
     scroll_offset: Option<usize>,
+    undo_available: bool,
     _app: &App,
 ) {
     let area = frame.area();
@@ -180,15 +319,19 @@ pub fn draw_complete(
     frame.render_widget(log, chunks[1]);
 
     // Footer
-    let footer = Paragraph::new(Line::from(vec![
+    let mut hints = vec![
         Span::styled("[", theme::dim()),
         Span::styled("↑↓", theme::key_hint()),
         Span::styled("] Scroll  [", theme::dim()),
         Span::styled("Enter", theme::key_hint()),
         Span::styled("] Done  [", theme::dim()),
-        Span::styled("q", theme::key_hint()),
-        Span::styled("] Quit", theme::dim()),
-    ]))
-    .alignment(Alignment::Center);
+    ];
+    if undo_available {
+        hints.push(Span::styled("u", theme::key_hint()));
+        hints.push(Span::styled("] Undo restore  [", theme::dim()));
+    }
+    hints.push(Span::styled("q", theme::key_hint()));
+    hints.push(Span::styled("] Quit", theme::dim()));
+    let footer = Paragraph::new(Line::from(hints)).alignment(Alignment::Center);
     frame.render_widget(footer, chunks[2]);
 }
@@ -12,9 +12,11 @@ use crate::app::App;
 use crate::system::disk::DiskInfo;
 use crate::system::hardware::{CpuInfo, FormFactor, GpuInfo};
 use crate::ui::layout::centered_rect;
+use crate::ui::screens::install::draw_disk_notice;
 use crate::ui::theme;
 
 /// Draw hostname entry screen (comes after disk selection)
+#[allow(clippy::too_many_arguments)]
 pub fn draw_enter_hostname(
     frame: &mut Frame,
     cpu: &CpuInfo,
@@ -108,6 +110,7 @@ pub fn draw_enter_hostname(
 }
 
 /// Draw disk selection screen with partition tree view
+#[allow(clippy::too_many_arguments)]
 pub fn draw_select_disk(
     frame: &mut Frame,
     cpu: &CpuInfo,
@@ -115,6 +118,7 @@ pub fn draw_select_disk(
     form_factor: &FormFactor,
     disks: &[DiskInfo],
     selected: usize,
+    confirm: bool,
     _app: &App,
 ) {
     let area = frame.area();
@@ -124,6 +128,7 @@ pub fn draw_select_disk(
             Constraint::Length(5),
             Constraint::Length(4),
             Constraint::Min(12),
+            Constraint::Length(6),
             Constraint::Length(3),
         ])
         .split(centered_rect(80, 85, area));
@@ -165,7 +170,7 @@ pub fn draw_select_disk(
                 .title(Span::styled(" Available Disks ", theme::title())),
         );
         frame.render_widget(message, chunks[2]);
-        draw_footer(frame, chunks[3], &["Esc Back"]);
+        draw_footer(frame, chunks[4], &["Esc Back"]);
         return;
     }
 
@@ -187,6 +192,7 @@ pub fn draw_select_disk(
                 disk.path.clone(),
                 disk.size.clone(),
                 disk.model.clone().unwrap_or_default(),
+                disk.details(),
             ])
             .style(style),
         );
@@ -204,6 +210,7 @@ pub fn draw_select_disk(
                 .as_ref()
                 .map(|os| format!(" ({})", os))
                 .unwrap_or_default();
+            let mount_str = part.mountpoint.clone().unwrap_or_default();
 
             rows.push(
                 Row::new(vec![
@@ -211,6 +218,7 @@ pub fn draw_select_disk(
                     format!("   {} {}", tree_char, device_short),
                     part.size.clone(),
                     format!("{}{}", part.fstype, os_str),
+                    mount_str,
                 ])
                 .style(theme::dim()),
             );
@@ -218,7 +226,7 @@ pub fn draw_select_disk(
     }
 
     // Disk table with partitions
-    let header = Row::new(vec!["", "Device", "Size", "Type/Model"])
+    let header = Row::new(vec!["", "Device", "Size", "Type/Model", "Details/Mount"])
         .style(theme::title())
         .bottom_margin(1);
 
@@ -229,6 +237,7 @@ pub fn draw_select_disk(
             Constraint::Length(20),
             Constraint::Length(10),
             Constraint::Min(25),
+            Constraint::Length(36),
         ],
     )
     .header(header)
@@ -240,5 +249,10 @@ pub fn draw_select_disk(
     );
     frame.render_widget(table, chunks[2]);
 
-    draw_footer(frame, chunks[3], &["↑↓ Navigate", "Enter Select", "Esc Back"]);
+    if let Some(disk) = disks.get(selected) {
+        draw_disk_notice(frame, chunks[3], disk, confirm);
+    }
+
+    let enter_hint = if confirm { "Enter Use anyway" } else { "Enter Select" };
+    draw_footer(frame, chunks[4], &["↑↓ Navigate", enter_hint, "Esc Back"]);
 }
@@ -61,6 +61,10 @@ pub fn draw_review(frame: &mut Frame, config: &NewHostConfig, _app: &App) {
             Span::styled(&config.disk.path, theme::text()),
             Span::styled(format!(" ({}, {})", config.disk.size, disk_model), theme::dim()),
         ]),
+        Line::from(vec![
+            Span::styled("  Layout:      ", theme::dim()),
+            Span::styled(config.layout.to_string(), theme::text()),
+        ]),
         Line::from(""),
     ])
     .block(
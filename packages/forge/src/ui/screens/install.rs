@@ -3,17 +3,160 @@
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Row, Table},
+    widgets::{Block, Borders, Clear, Paragraph, Row, Table},
     Frame,
 };
 
-use crate::app::{App, CredentialField, InstallCredentials, StepStatus, SwapMode};
+use crate::app::{
+    App, CredentialField, DiskLayout, InstallCredentials, KeySource, LocaleField, PartitionScheme, SourceField, StepStatus,
+    SwapMode, UserForm, UserFormField,
+};
 use crate::system::config::HostConfig;
 use crate::system::disk::DiskInfo;
+use crate::system::locale::{self, Choice};
+use crate::system::network::AccessPoint;
+use crate::system::repo;
+use crate::system::users::EXTRA_GROUPS;
+use crate::system::verify::Check;
 use crate::ui::layout::{centered_rect, host_selection_layout, progress_layout};
 use crate::ui::theme;
 use crate::ui::widgets::{LogView, MenuList, ProgressSteps};
 
+/// Draw Wi-Fi connection screen (live ISO without network)
+pub fn draw_network(
+    frame: &mut Frame,
+    networks: &[AccessPoint],
+    selected: usize,
+    passphrase: Option<&str>,
+    busy: Option<&str>,
+    error: Option<&str>,
+    app: &App,
+) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(5),
+            Constraint::Min(8),
+            Constraint::Length(5),
+            Constraint::Length(3),
+        ])
+        .split(centered_rect(70, 80, area));
+
+    // Header
+    let header = Paragraph::new(vec![
+        Line::from(""),
+        Line::from(Span::styled("Connect to Wi-Fi", theme::title())),
+        Line::from(Span::styled(
+            "The installer needs internet access to download packages",
+            theme::dim(),
+        )),
+    ])
+    .alignment(Alignment::Center)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border_active()),
+    );
+    frame.render_widget(header, chunks[0]);
+
+    // Network table
+    let header_row = Row::new(vec!["", "Network", "Signal", "Security"])
+        .style(theme::title())
+        .bottom_margin(1);
+    let rows: Vec<Row> = networks
+        .iter()
+        .enumerate()
+        .map(|(i, network)| {
+            let prefix = if i == selected { ">" } else { " " };
+            let style = if i == selected {
+                theme::selected()
+            } else {
+                theme::text()
+            };
+            let name = if network.in_use {
+                format!("{} (connected)", network.ssid)
+            } else {
+                network.ssid.clone()
+            };
+            let security = if network.secured() { network.security.as_str() } else { "Open" };
+            Row::new(vec![
+                prefix.to_string(),
+                name,
+                format!("{} {:>3}%", signal_bars(network.signal), network.signal),
+                security.to_string(),
+            ])
+            .style(style)
+        })
+        .collect();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(2),
+            Constraint::Min(20),
+            Constraint::Length(10),
+            Constraint::Length(14),
+        ],
+    )
+    .header(header_row)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(Span::styled(" Networks ", theme::title())),
+    );
+    frame.render_widget(table, chunks[1]);
+
+    // Passphrase prompt or status
+    let spinner_chars = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+    let mut lines = vec![];
+    if let Some(busy) = busy {
+        let spinner = spinner_chars[app.spinner_state % spinner_chars.len()];
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {} ", spinner), theme::info()),
+            Span::styled(busy, theme::text()),
+        ]));
+    } else if let Some(input) = passphrase {
+        let ssid = networks.get(selected).map(|n| n.ssid.as_str()).unwrap_or_default();
+        lines.push(Line::from(Span::styled(format!("  Passphrase for {}:", ssid), theme::dim())));
+        lines.push(Line::from(vec![
+            Span::styled("  > ", theme::info()),
+            Span::styled(format!("{}_", "*".repeat(input.chars().count())), theme::selected()),
+        ]));
+    } else if networks.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  No networks found - check that Wi-Fi is enabled and press r to rescan",
+            theme::dim(),
+        )));
+    } else {
+        lines.push(Line::from(Span::styled("  Select a network and press Enter", theme::dim())));
+    }
+    if let Some(err) = error {
+        lines.push(Line::from(Span::styled(format!("  ⚠ {}", err), theme::error())));
+    }
+    let status = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).border_style(theme::border()));
+    frame.render_widget(status, chunks[2]);
+
+    // Footer
+    let hints: &[&str] = if busy.is_some() {
+        &["Esc Back"]
+    } else if passphrase.is_some() {
+        &["Enter Connect", "Esc Cancel"]
+    } else {
+        &["↑↓ Navigate", "Enter Connect", "r Rescan", "Esc Back"]
+    };
+    draw_footer(frame, chunks[3], hints);
+}
+
+fn signal_bars(signal: u8) -> &'static str {
+    match signal {
+        75.. => "▂▄▆█",
+        50..=74 => "▂▄▆_",
+        25..=49 => "▂▄__",
+        _ => "▂___",
+    }
+}
+
 /// Draw repository cloning screen
 pub fn draw_clone_repository(frame: &mut Frame, output: &[String], app: &App) {
     let area = frame.area();
@@ -64,11 +207,12 @@ pub fn draw_clone_repository(frame: &mut Frame, output: &[String], app: &App) {
 }
 
 /// Draw hostname selection screen
-pub fn draw_host_selection(frame: &mut Frame, selected: usize, hosts: &[HostConfig], _app: &App) {
+pub fn draw_host_selection(frame: &mut Frame, selected: usize, hosts: &[HostConfig], app: &App) {
     let area = frame.area();
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
+            Constraint::Length(3),
             Constraint::Length(3),
             Constraint::Min(10),
             Constraint::Length(3),
@@ -78,8 +222,36 @@ pub fn draw_host_selection(frame: &mut Frame, selected: usize, hosts: &[HostConf
     // Header
     draw_header(frame, chunks[0], "Select Target Host");
 
+    // Repository (or offline bundle) the hosts come from
+    let source = &app.repo_source;
+    let (title, location, git_ref) = match &app.offline {
+        Some(bundle) => (
+            " Offline bundle ",
+            bundle.dir.to_string_lossy().to_string(),
+            repo::short(&bundle.manifest.commit).to_string(),
+        ),
+        None => (
+            " Repository ",
+            source.url.clone(),
+            source.git_ref.clone().unwrap_or_else(|| "default branch".to_string()),
+        ),
+    };
+    let source_line = Paragraph::new(Line::from(vec![
+        Span::styled("  ", theme::dim()),
+        Span::styled(location, theme::text()),
+        Span::styled("  @ ", theme::dim()),
+        Span::styled(git_ref, theme::text()),
+    ]))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(Span::styled(title, theme::title())),
+    );
+    frame.render_widget(source_line, chunks[1]);
+
     // Split content into list and preview
-    let (list_area, preview_area) = host_selection_layout(chunks[1]);
+    let (list_area, preview_area) = host_selection_layout(chunks[2]);
 
     // Host list with "New host configuration" as first option, then existing hosts
     let mut items: Vec<String> = vec!["+ New host configuration".to_string()];
@@ -93,7 +265,50 @@ pub fn draw_host_selection(frame: &mut Frame, selected: usize, hosts: &[HostConf
     draw_host_preview(frame, preview_area, selected, hosts);
 
     // Footer
-    draw_footer(frame, chunks[2], &["↑↓ Navigate", "Enter Select", "Esc Back"]);
+    if app.offline.is_some() {
+        draw_footer(frame, chunks[3], &["↑↓ Navigate", "Enter Select", "Esc Back"]);
+    } else {
+        draw_footer(frame, chunks[3], &["↑↓ Navigate", "Enter Select", "s Repository", "Esc Back"]);
+    }
+}
+
+/// Popup for changing the repository URL and ref, over the host list
+pub fn draw_source_editor(frame: &mut Frame, url: &str, git_ref: &str, field: SourceField, error: Option<&str>) {
+    let area = centered_rect(70, 40, frame.area());
+    frame.render_widget(Clear, area);
+
+    let field_line = |label: &'static str, value: &str, active: bool, placeholder: &'static str| {
+        let (text, style) = match (value.is_empty(), active) {
+            (true, true) => ("_".to_string(), theme::selected()),
+            (true, false) => (placeholder.to_string(), theme::dim()),
+            (false, true) => (format!("{}_", value), theme::selected()),
+            (false, false) => (value.to_string(), theme::text()),
+        };
+        Line::from(vec![Span::styled(label, theme::dim()), Span::styled(text, style)])
+    };
+
+    let mut lines = vec![
+        Line::from(""),
+        field_line("  URL: ", url, field == SourceField::Url, "default repository"),
+        Line::from(""),
+        field_line("  Ref: ", git_ref, field == SourceField::Ref, "default branch"),
+        Line::from(""),
+        Line::from(Span::styled("  Branch, tag or commit. Leave empty for the default.", theme::dim())),
+    ];
+    if let Some(err) = error {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(format!("  {}", err), theme::error())));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("  Tab Switch field  Enter Clone  Esc Cancel", theme::dim())));
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border_active())
+            .title(Span::styled(" Configuration Repository ", theme::title())),
+    );
+    frame.render_widget(popup, area);
 }
 
 /// Draw the host preview panel
@@ -199,6 +414,7 @@ pub fn draw_disk_selection(
     host: &str,
     disks: &[DiskInfo],
     selected: usize,
+    confirm: bool,
     _app: &App,
 ) {
     let area = frame.area();
@@ -207,9 +423,10 @@ pub fn draw_disk_selection(
         .constraints([
             Constraint::Length(5),
             Constraint::Min(10),
+            Constraint::Length(6),
             Constraint::Length(3),
         ])
-        .split(centered_rect(70, 80, area));
+        .split(centered_rect(75, 85, area));
 
     // Header
     draw_header(frame, chunks[0], &format!("Select Disk for {}", host));
@@ -234,12 +451,12 @@ pub fn draw_disk_selection(
                 .title(Span::styled(" Available Disks ", theme::title())),
         );
         frame.render_widget(message, chunks[1]);
-        draw_footer(frame, chunks[2], &["Esc Back"]);
+        draw_footer(frame, chunks[3], &["Esc Back"]);
         return;
     }
 
     // Disk table
-    let header = Row::new(vec!["", "Device", "Size", "Model"])
+    let header = Row::new(vec!["", "Device", "Size", "Model", "Details"])
         .style(theme::title())
         .bottom_margin(1);
 
@@ -258,6 +475,7 @@ pub fn draw_disk_selection(
                 disk.path.clone(),
                 disk.size.clone(),
                 disk.model.clone().unwrap_or_default(),
+                disk.details(),
             ])
             .style(style)
         })
@@ -270,6 +488,7 @@ pub fn draw_disk_selection(
             Constraint::Length(15),
             Constraint::Length(10),
             Constraint::Min(20),
+            Constraint::Length(36),
         ],
     )
     .header(header)
@@ -282,8 +501,57 @@ pub fn draw_disk_selection(
 
     frame.render_widget(table, chunks[1]);
 
+    if let Some(disk) = disks.get(selected) {
+        draw_disk_notice(frame, chunks[2], disk, confirm);
+    }
+
     // Footer
-    draw_footer(frame, chunks[2], &["↑↓ Navigate", "Enter Select", "Esc Back"]);
+    let enter_hint = if confirm { "Enter Use anyway" } else { "Enter Select" };
+    draw_footer(frame, chunks[3], &["↑↓ Navigate", enter_hint, "Esc Back"]);
+}
+
+/// Why the selected disk is refused or needs a second Enter
+pub fn draw_disk_notice(frame: &mut Frame, area: Rect, disk: &DiskInfo, confirm: bool) {
+    let (lines, border) = if let Some(reason) = disk.blocker() {
+        (
+            vec![
+                Line::from(Span::styled(format!(" ✗ {}", reason), theme::error())),
+                Line::from(Span::styled(" This disk can't be selected.", theme::dim())),
+            ],
+            theme::error(),
+        )
+    } else {
+        let warnings = disk.warnings();
+        if warnings.is_empty() {
+            (
+                vec![Line::from(Span::styled(
+                    format!(" ✓ {} will be erased", disk.path),
+                    theme::success(),
+                ))],
+                theme::border(),
+            )
+        } else {
+            let mut lines: Vec<Line> = warnings
+                .into_iter()
+                .map(|w| Line::from(Span::styled(format!(" ⚠ {}", w), theme::warning())))
+                .collect();
+            let hint = if confirm {
+                " Press Enter again to erase this disk anyway."
+            } else {
+                " Press Enter to review, then Enter again to confirm."
+            };
+            lines.push(Line::from(Span::styled(hint, theme::text())));
+            (lines, theme::warning())
+        }
+    };
+
+    let notice = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(border)
+            .title(Span::styled(" Selected Disk ", theme::title())),
+    );
+    frame.render_widget(notice, area);
 }
 
 /// Draw credentials entry screen
@@ -424,6 +692,211 @@ pub fn draw_enter_credentials(
     );
 }
 
+/// Draw additional users list, or the add-user form when open
+pub fn draw_extra_users(
+    frame: &mut Frame,
+    host: &str,
+    credentials: &InstallCredentials,
+    selected: usize,
+    form: Option<&UserForm>,
+    error: Option<&str>,
+    _app: &App,
+) {
+    let area = frame.area();
+    let center = centered_rect(65, 70, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(4),
+            Constraint::Min(10),
+            Constraint::Length(3),
+        ])
+        .split(center);
+
+    // Header
+    draw_header(frame, chunks[0], "Additional Users");
+
+    let info = Paragraph::new(vec![Line::from(vec![
+        Span::styled("  Host: ", theme::dim()),
+        Span::styled(host, theme::text()),
+        Span::styled("  |  Primary user: ", theme::dim()),
+        Span::styled(&credentials.username, theme::text()),
+    ])])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border()),
+    );
+    frame.render_widget(info, chunks[1]);
+
+    let mut lines = vec![Line::from("")];
+    let (title, footer): (&str, &[&str]) = if let Some(form) = form {
+        let style = |field: UserFormField| {
+            if form.field == field {
+                theme::selected()
+            } else {
+                theme::text()
+            }
+        };
+        let cursor = |field: UserFormField| if form.field == field { "_" } else { "" };
+        lines.push(Line::from(vec![
+            Span::styled("  Username:         ", theme::dim()),
+            Span::styled(
+                format!("{}{}", form.username, cursor(UserFormField::Username)),
+                style(UserFormField::Username),
+            ),
+        ]));
+        lines.push(Line::from(vec![
+            Span::styled("  Password:         ", theme::dim()),
+            Span::styled(
+                format!("{}{}", "*".repeat(form.password.len()), cursor(UserFormField::Password)),
+                style(UserFormField::Password),
+            ),
+        ]));
+        lines.push(Line::from(vec![
+            Span::styled("  Confirm Password: ", theme::dim()),
+            Span::styled(
+                format!(
+                    "{}{}",
+                    "*".repeat(form.confirm_password.len()),
+                    cursor(UserFormField::ConfirmPassword)
+                ),
+                style(UserFormField::ConfirmPassword),
+            ),
+        ]));
+        lines.push(Line::from(""));
+        let mut groups = vec![Span::styled("  Groups:           ", theme::dim())];
+        for (i, (group, on)) in EXTRA_GROUPS.iter().zip(&form.groups).enumerate() {
+            let mark = if *on { "[x]" } else { "[ ]" };
+            groups.push(Span::styled(format!("{} {}", mark, group), style(UserFormField::Group(i))));
+            groups.push(Span::raw("  "));
+        }
+        lines.push(Line::from(groups));
+        (" New User ", &["Tab/↑↓ Switch field", "Space Toggle group", "Enter Add", "Esc Cancel"])
+    } else {
+        for (i, user) in credentials.extra_users.iter().enumerate() {
+            let style = if selected == i { theme::selected() } else { theme::text() };
+            let indicator = if selected == i { ">" } else { " " };
+            lines.push(Line::from(vec![
+                Span::styled(format!(" {} ", indicator), style),
+                Span::styled(user.username.clone(), style),
+                Span::styled(format!("  ({})", user.groups.join(", ")), theme::dim()),
+            ]));
+        }
+        let add_row = credentials.extra_users.len();
+        for (i, label) in [(add_row, "+ Add user"), (add_row + 1, "Continue")] {
+            let style = if selected == i { theme::selected() } else { theme::text() };
+            let indicator = if selected == i { ">" } else { " " };
+            lines.push(Line::from(vec![
+                Span::styled(format!(" {} ", indicator), style),
+                Span::styled(label, style),
+            ]));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "  Passwords are stored hashed, outside the config repository",
+            theme::dim(),
+        )));
+        (" Users ", &["↑↓ Navigate", "Enter Select", "a Add", "d Remove", "Esc Back"])
+    };
+
+    if let Some(err) = error {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(format!("  ⚠ {}", err), theme::error())));
+    }
+
+    let body = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(Span::styled(title, theme::title())),
+    );
+    frame.render_widget(body, chunks[2]);
+
+    draw_footer(frame, chunks[3], footer);
+}
+
+/// Draw disk layout selection (install and create-host)
+pub fn draw_select_layout(
+    frame: &mut Frame,
+    host: &str,
+    disk: &DiskInfo,
+    layout: &DiskLayout,
+    selected: usize,
+    _app: &App,
+) {
+    let area = frame.area();
+    let center = centered_rect(65, 70, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(4),
+            Constraint::Length(17),
+            Constraint::Min(3),
+        ])
+        .split(center);
+
+    // Header
+    draw_header(frame, chunks[0], "Select Disk Layout");
+
+    // Host/Disk info
+    let info = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled("  Host: ", theme::dim()),
+            Span::styled(host, theme::text()),
+            Span::styled("  |  Disk: ", theme::dim()),
+            Span::styled(&disk.path, theme::text()),
+            Span::styled(format!(" ({})", disk.size), theme::dim()),
+        ]),
+    ])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border()),
+    );
+    frame.render_widget(info, chunks[1]);
+
+    let mut lines = vec![Line::from("")];
+    for (i, scheme) in PartitionScheme::ALL.iter().enumerate() {
+        let style = if selected == i {
+            theme::selected()
+        } else {
+            theme::text()
+        };
+        let indicator = if selected == i { ">" } else { " " };
+        lines.push(Line::from(vec![
+            Span::styled(format!(" {} ", indicator), style),
+            Span::styled(scheme.label(), style),
+        ]));
+        lines.push(Line::from(vec![
+            Span::styled("     ", theme::dim()),
+            Span::styled(scheme.description(), theme::dim()),
+        ]));
+        lines.push(Line::from(""));
+    }
+    let swap_box = if layout.swap_gb.is_some() { "[x]" } else { "[ ]" };
+    let swap_size = layout.swap_gb.map(|gb| format!(" ({} GB)", gb)).unwrap_or_default();
+    lines.push(Line::from(vec![
+        Span::styled(format!("   {} Dedicated swap partition{}", swap_box, swap_size), theme::text()),
+        Span::styled(" (Tab to toggle)", theme::dim()),
+    ]));
+
+    let options = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(Span::styled(" Partition Scheme ", theme::title())),
+    );
+    frame.render_widget(options, chunks[2]);
+
+    // Footer
+    draw_footer(frame, chunks[3], &["↑↓ Navigate", "Tab Toggle Swap", "Enter Select", "Esc Back"]);
+}
+
 /// Draw swap mode selection screen
 pub fn draw_select_swap_mode(
     frame: &mut Frame,
@@ -525,6 +998,174 @@ pub fn draw_select_swap_mode(
     draw_footer(frame, chunks[3], &["↑↓ Navigate", "Enter Select", "Esc Back"]);
 }
 
+/// Draw key provisioning selection screen
+pub fn draw_select_key_source(
+    frame: &mut Frame,
+    host: &str,
+    disk: &DiskInfo,
+    selected: usize,
+    passphrase: Option<&str>,
+    _app: &App,
+) {
+    let area = frame.area();
+    let center = centered_rect(65, 60, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(4),
+            Constraint::Length(15),
+            Constraint::Min(3),
+        ])
+        .split(center);
+
+    // Header
+    draw_header(frame, chunks[0], "Provision Keys");
+
+    // Host/Disk info
+    let info = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled("  Host: ", theme::dim()),
+            Span::styled(host, theme::text()),
+            Span::styled("  |  Disk: ", theme::dim()),
+            Span::styled(&disk.path, theme::text()),
+            Span::styled(format!(" ({})", disk.size), theme::dim()),
+        ]),
+    ])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border()),
+    );
+    frame.render_widget(info, chunks[1]);
+
+    let choices = [
+        ("Skip", "Restore keys after first boot with 'forge keys restore'"),
+        ("Keys Archive", "Decrypt the keys backup from the backup storage (asks for its passphrase)"),
+        ("Secret Provider", "Fetch the keys like 'forge keys setup'"),
+    ];
+    let mut lines = vec![Line::from("")];
+    for (i, (name, description)) in choices.iter().enumerate() {
+        let style = if selected == i {
+            theme::selected()
+        } else {
+            theme::text()
+        };
+        let indicator = if selected == i { ">" } else { " " };
+        lines.push(Line::from(vec![
+            Span::styled(format!(" {} ", indicator), style),
+            Span::styled(*name, style),
+        ]));
+        lines.push(Line::from(vec![
+            Span::styled("     ", theme::dim()),
+            Span::styled(*description, theme::dim()),
+        ]));
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(Span::styled(
+        "  Uses the host's backup settings; failures are shown but don't stop the install",
+        theme::dim(),
+    )));
+    if let Some(input) = passphrase {
+        lines.push(Line::from(Span::styled("  Keys archive passphrase:", theme::dim())));
+        lines.push(Line::from(vec![
+            Span::styled("  > ", theme::info()),
+            Span::styled(format!("{}_", "*".repeat(input.chars().count())), theme::selected()),
+        ]));
+    }
+
+    let options = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(Span::styled(" Age & SSH Keys ", theme::title())),
+    );
+    frame.render_widget(options, chunks[2]);
+
+    // Footer
+    if passphrase.is_some() {
+        draw_footer(frame, chunks[3], &["Enter Confirm", "Esc Cancel"]);
+    } else {
+        draw_footer(frame, chunks[3], &["↑↓ Navigate", "Enter Select", "Esc Back"]);
+    }
+}
+
+/// Draw searchable timezone / locale / keyboard layout list
+#[allow(clippy::too_many_arguments)]
+pub fn draw_select_locale(
+    frame: &mut Frame,
+    host: &str,
+    disk: &DiskInfo,
+    field: LocaleField,
+    choices: &[Choice],
+    filter: &str,
+    selected: usize,
+    _app: &App,
+) {
+    let area = frame.area();
+    let center = centered_rect(65, 80, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(6),
+            Constraint::Length(3),
+        ])
+        .split(center);
+
+    // Header
+    draw_header(frame, chunks[0], field.title());
+
+    // Host/Disk info
+    let info = Paragraph::new(Line::from(vec![
+        Span::styled("  Host: ", theme::dim()),
+        Span::styled(host, theme::text()),
+        Span::styled("  |  Disk: ", theme::dim()),
+        Span::styled(&disk.path, theme::text()),
+        Span::styled(format!(" ({})", disk.size), theme::dim()),
+    ]))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border()),
+    );
+    frame.render_widget(info, chunks[1]);
+
+    // Search input
+    let search = Paragraph::new(Line::from(vec![
+        Span::styled("  Search: ", theme::dim()),
+        Span::styled(format!("{}_", filter), theme::selected()),
+    ]))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border_active()),
+    );
+    frame.render_widget(search, chunks[2]);
+
+    let visible = locale::filter(choices, filter);
+    let items: Vec<String> = visible
+        .iter()
+        .map(|c| {
+            if c.label.is_empty() {
+                c.value.clone()
+            } else {
+                format!("{:<24} {}", c.value, c.label)
+            }
+        })
+        .collect();
+    let title = format!(" {} of {} ", visible.len(), choices.len());
+    let list = MenuList::new(items.iter().map(String::as_str).collect(), selected).title(&title);
+    frame.render_widget(list, chunks[3]);
+
+    // Footer
+    draw_footer(frame, chunks[4], &["Type to search", "↑↓ Navigate", "Enter Select", "Esc Back"]);
+}
+
 /// Draw overview/confirmation screen
 pub fn draw_overview(
     frame: &mut Frame,
@@ -539,7 +1180,7 @@ pub fn draw_overview(
     let center = centered_rect(70, 70, area);
 
     // Calculate details height based on whether we have hardware info
-    let details_height = if hardware_config.is_some() { 12 } else { 8 };
+    let details_height = if hardware_config.is_some() { 18 } else { 14 };
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -578,9 +1219,21 @@ pub fn draw_overview(
         Span::styled(format!(" ({})", disk.size), theme::dim()),
     ]));
 
+    let extra_users: Vec<&str> = credentials.extra_users.iter().map(|u| u.username.as_str()).collect();
+    let extra_users = if extra_users.is_empty() {
+        String::new()
+    } else {
+        format!(" (+ {})", extra_users.join(", "))
+    };
     detail_lines.push(Line::from(vec![
         Span::styled("  User:     ", theme::dim()),
         Span::styled(&credentials.username, theme::text()),
+        Span::styled(extra_users, theme::dim()),
+    ]));
+
+    detail_lines.push(Line::from(vec![
+        Span::styled("  Layout:   ", theme::dim()),
+        Span::styled(credentials.layout.to_string(), theme::text()),
     ]));
 
     // Show swap mode selection
@@ -593,6 +1246,38 @@ pub fn draw_overview(
         Span::styled(swap_mode_text, theme::text()),
     ]));
 
+    let key_source_text = match credentials.key_source {
+        KeySource::Skip => "Skip (restore after first boot)",
+        KeySource::Archive => "Keys archive",
+        KeySource::Secrets => "Secret provider",
+    };
+    detail_lines.push(Line::from(vec![
+        Span::styled("  Keys:     ", theme::dim()),
+        Span::styled(key_source_text, theme::text()),
+    ]));
+
+    if let Some(settings) = &credentials.locale {
+        detail_lines.push(Line::from(vec![
+            Span::styled("  Timezone: ", theme::dim()),
+            Span::styled(&settings.timezone, theme::text()),
+        ]));
+        detail_lines.push(Line::from(vec![
+            Span::styled("  Locale:   ", theme::dim()),
+            Span::styled(&settings.locale, theme::text()),
+        ]));
+        detail_lines.push(Line::from(vec![
+            Span::styled("  Keyboard: ", theme::dim()),
+            Span::styled(&settings.keyboard, theme::text()),
+        ]));
+    }
+
+    let wifi_box = if credentials.skip_wifi { "[ ]" } else { "[x]" };
+    detail_lines.push(Line::from(vec![
+        Span::styled("  Wi-Fi:    ", theme::dim()),
+        Span::styled(format!("{} Copy saved connections", wifi_box), theme::text()),
+        Span::styled(" (Tab to toggle)", theme::dim()),
+    ]));
+
     // Add hardware info if available (new host)
     if let Some(hw) = hardware_config {
         detail_lines.push(Line::from(vec![
@@ -637,7 +1322,7 @@ pub fn draw_overview(
     frame.render_widget(prompt, chunks[2]);
 
     // Footer
-    draw_footer(frame, chunks[3], &["Type 'yes' + Enter", "Esc Cancel"]);
+    draw_footer(frame, chunks[3], &["Type 'yes' + Enter", "Tab Toggle Wi-Fi", "Esc Cancel"]);
 }
 
 /// Draw running installation screen
@@ -700,23 +1385,29 @@ pub fn draw_complete(
     success: bool,
     output: &[String],
     scroll_offset: Option<usize>,
+    checks: &[Check],
     _app: &App,
 ) {
     let area = frame.area();
+    let verified = checks.iter().all(|c| c.passed);
+    let checks_height = if checks.is_empty() { 0 } else { checks.len() as u16 + 2 };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),
+            Constraint::Length(checks_height),
             Constraint::Min(10),
             Constraint::Length(3),
         ])
         .split(area);
 
     // Header
-    let (title, style) = if success {
-        (" ✓ Installation Complete ", theme::success())
-    } else {
+    let (title, style) = if !success {
         (" ✗ Installation Failed ", theme::error())
+    } else if !verified {
+        (" ✗ Installation Verification Failed ", theme::error())
+    } else {
+        (" ✓ Installation Complete ", theme::success())
     };
     let header = Paragraph::new(Line::from(Span::styled(title, style)))
         .alignment(Alignment::Center)
@@ -727,15 +1418,42 @@ pub fn draw_complete(
         );
     frame.render_widget(header, chunks[0]);
 
+    // Verification summary
+    if !checks.is_empty() {
+        let name_width = checks.iter().map(|c| c.name.chars().count()).max().unwrap_or(0);
+        let lines: Vec<Line> = checks
+            .iter()
+            .map(|check| {
+                let (mark, style) = if check.passed {
+                    ("✓", theme::success())
+                } else {
+                    ("✗", theme::error())
+                };
+                Line::from(vec![
+                    Span::styled(format!(" {} ", mark), style),
+                    Span::styled(format!("{:<width$}  ", check.name, width = name_width), theme::text()),
+                    Span::styled(check.detail.clone(), if check.passed { theme::dim() } else { theme::error() }),
+                ])
+            })
+            .collect();
+        let summary = Paragraph::new(lines).block(
+            Block::default()
+                .title(" Verification ")
+                .borders(Borders::ALL)
+                .border_style(if verified { theme::border() } else { theme::error() }),
+        );
+        frame.render_widget(summary, chunks[1]);
+    }
+
     // Output log
     let mut log = LogView::new(output).title(" Output ");
     if let Some(offset) = scroll_offset {
         log = log.scroll_offset(offset);
     }
-    frame.render_widget(log, chunks[1]);
+    frame.render_widget(log, chunks[2]);
 
-    // Footer - show reboot option on success
-    let footer = if success {
+    // Footer - reboot is only offered once the installed system checked out
+    let footer = if success && verified {
         Paragraph::new(Line::from(vec![
             Span::styled("[", theme::dim()),
             Span::styled("↑↓", theme::key_hint()),
@@ -756,7 +1474,7 @@ pub fn draw_complete(
             Span::styled("] Quit", theme::dim()),
         ]))
     };
-    frame.render_widget(footer.alignment(Alignment::Center), chunks[2]);
+    frame.render_widget(footer.alignment(Alignment::Center), chunks[3]);
 }
 
 fn draw_header(frame: &mut Frame, area: Rect, title: &str) {
@@ -8,6 +8,8 @@ use ratatui::{
 };
 
 use crate::app::{App, KeysOp};
+use crate::backup::audit::{FileState, KeyReport};
+use crate::backup::shamir::Share;
 use crate::ui::theme;
 use crate::ui::widgets::LogView;
 
@@ -25,10 +27,13 @@ pub fn draw_running(frame: &mut Frame, operation: &KeysOp, output: &[String], ap
 
     // Header
     let title = match operation {
-        KeysOp::Setup => " Setting Up Keys (from 1Password) ",
+        KeysOp::Setup => " Setting Up Keys ",
         KeysOp::Backup => " Backing Up Keys ",
         KeysOp::Restore => " Restoring Keys ",
-        KeysOp::Status => " Key Status ",
+        KeysOp::Rotate { dry_run: false } => " Rotating Age Key ",
+        KeysOp::Rotate { dry_run: true } => " Age Key Rotation (dry run) ",
+        KeysOp::PaperBackup { .. } => " Paper Key Backup ",
+        KeysOp::PaperRestore { .. } => " Restoring Age Key from Paper ",
     };
     let header = Paragraph::new(Line::from(Span::styled(title, theme::title())))
         .alignment(Alignment::Center)
@@ -56,6 +61,238 @@ pub fn draw_running(frame: &mut Frame, operation: &KeysOp, output: &[String], ap
     frame.render_widget(footer, chunks[2]);
 }
 
+/// Draw the key inspection and permissions audit
+pub fn draw_status(
+    frame: &mut Frame,
+    report: Option<&KeyReport>,
+    loading: bool,
+    error: Option<&str>,
+    fixed: &[String],
+    app: &App,
+) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(10),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    // Header
+    let header = Paragraph::new(Line::from(Span::styled(" Key Status ", theme::title())))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    let title = if loading {
+        let spinner_char = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏']
+            [app.spinner_state % 10];
+        format!(" {} Inspecting keys... ", spinner_char)
+    } else {
+        " Keys ".to_string()
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme::border())
+        .title(Span::styled(title, theme::title()));
+
+    let mut lines = Vec::new();
+    if let Some(error) = error {
+        lines.push(Line::from(Span::styled(format!(" {}", error), theme::error())));
+        lines.push(Line::from(""));
+    }
+    if let Some(report) = report {
+        report_lines(report, &mut lines);
+    }
+    if !fixed.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(" Fixed", theme::title())));
+        for line in fixed {
+            lines.push(Line::from(Span::styled(format!("   {}", line), theme::success())));
+        }
+    }
+    frame.render_widget(Paragraph::new(lines).block(block), chunks[1]);
+
+    // Footer
+    let mut hints = Vec::new();
+    if report.is_some_and(|r| r.fixable() > 0) && !loading {
+        hints.push(Span::styled("[", theme::dim()));
+        hints.push(Span::styled("f", theme::key_hint()));
+        hints.push(Span::styled("] Fix permissions  ", theme::dim()));
+    }
+    hints.extend([
+        Span::styled("[", theme::dim()),
+        Span::styled("r", theme::key_hint()),
+        Span::styled("] Refresh  [", theme::dim()),
+        Span::styled("Enter", theme::key_hint()),
+        Span::styled("] Done  [", theme::dim()),
+        Span::styled("Esc", theme::key_hint()),
+        Span::styled("] Back", theme::dim()),
+    ]);
+    let footer = Paragraph::new(Line::from(hints)).alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[2]);
+}
+
+/// The Age, SSH and problems sections of a key report
+fn report_lines(report: &KeyReport, lines: &mut Vec<Line<'static>>) {
+    lines.push(Line::from(Span::styled(" Age identity", theme::title())));
+    match (&report.age, &report.age_path) {
+        (Some(age), _) => {
+            lines.push(file_line(&age.file));
+            let public = age.public_key.clone().unwrap_or_else(|| "(unreadable)".to_string());
+            lines.push(field("Public key", public));
+            lines.push(if age.matches_recipient {
+                Line::from(Span::styled("   ✓ Matches AGE_RECIPIENT", theme::success()))
+            } else {
+                Line::from(Span::styled(
+                    format!("   ✗ AGE_RECIPIENT is {}", report.recipient),
+                    theme::error(),
+                ))
+            });
+        }
+        (None, Some(path)) => lines.push(missing(&format!("{} not found", path.display()))),
+        (None, None) => lines.push(missing("AGE_KEY_PATH not configured")),
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(" SSH key", theme::title())));
+    match (&report.ssh, &report.ssh_path) {
+        (Some(ssh), _) => {
+            lines.push(file_line(&ssh.file));
+            if let Some(public) = &ssh.public_file {
+                lines.push(file_line(public));
+            }
+            if let Some(key) = &ssh.public_key {
+                lines.push(field("Type", key.key_type.clone()));
+                lines.push(field("Fingerprint", key.fingerprint.clone()));
+                lines.push(field("Comment", key.comment.clone()));
+            }
+        }
+        (None, Some(path)) => lines.push(missing(&format!("{} not found", path.display()))),
+        (None, None) => lines.push(missing("SSH_KEY_PATH not configured")),
+    }
+    if let Some(dir) = &report.ssh_dir {
+        lines.push(file_line(dir));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(" Permissions", theme::title())));
+    if report.problems.is_empty() {
+        lines.push(Line::from(Span::styled("   ✓ No problems found", theme::success())));
+    }
+    for problem in &report.problems {
+        let style = if problem.fix_mode.is_some() {
+            theme::warning()
+        } else {
+            theme::error()
+        };
+        lines.push(Line::from(vec![
+            Span::styled("   ! ", style),
+            Span::styled(problem.path.display().to_string(), theme::text()),
+            Span::styled(format!("  {}", problem.message), style),
+        ]));
+    }
+}
+
+fn file_line(file: &FileState) -> Line<'static> {
+    let style = if file.owned { theme::dim() } else { theme::error() };
+    Line::from(vec![
+        Span::styled(format!("   {}", file.path.display()), theme::text()),
+        Span::styled(format!("  {:04o} {}", file.mode, file.owner), style),
+    ])
+}
+
+fn field(label: &str, value: String) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(format!("   {:<12}", label), theme::dim()),
+        Span::styled(value, theme::text()),
+    ])
+}
+
+fn missing(message: &str) -> Line<'static> {
+    Line::from(Span::styled(format!("   ✗ {}", message), theme::warning()))
+}
+
+/// Draw the paper share entry screen
+pub fn draw_paper_restore(frame: &mut Frame, shares: &[Share], input: &str, error: Option<&str>, _app: &App) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(10),
+            Constraint::Length(2),
+        ])
+        .split(area);
+
+    // Header
+    let header = Paragraph::new(Line::from(Span::styled(" Restore Age Key from Paper ", theme::title())))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    // Entered shares and input
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            "  Type the share lines of each sheet, in any order. Dashes and case don't matter.",
+            theme::dim(),
+        )),
+        Line::from(""),
+    ];
+    match shares.first() {
+        Some(first) => {
+            lines.push(Line::from(Span::styled(
+                format!("  Set {:04X}: {} of {} shares entered", first.set_id, shares.len(), first.threshold),
+                theme::text(),
+            )));
+            for share in shares {
+                lines.push(Line::from(vec![
+                    Span::styled("    ✓ ", theme::success()),
+                    Span::styled(format!("Share {}", share.index), theme::text()),
+                ]));
+            }
+        }
+        None => lines.push(Line::from(Span::styled("  No shares entered yet", theme::dim()))),
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(format!("  Share {}: ", shares.len() + 1), theme::dim()),
+        Span::styled(format!("{}_", input), theme::selected()),
+    ]));
+    if let Some(err) = error {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(format!("  {}", err), theme::error())));
+    }
+    let body = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border()),
+    );
+    frame.render_widget(body, chunks[1]);
+
+    // Footer
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("[", theme::dim()),
+        Span::styled("Enter", theme::key_hint()),
+        Span::styled("] Add share  [", theme::dim()),
+        Span::styled("Esc", theme::key_hint()),
+        Span::styled("] Back", theme::dim()),
+    ]))
+    .alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[2]);
+}
+
 /// Draw completion screen (shows output log)
 pub fn draw_complete(
     frame: &mut Frame,
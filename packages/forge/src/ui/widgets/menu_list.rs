@@ -13,6 +13,7 @@ pub struct MenuList<'a> {
     items: Vec<&'a str>,
     selected: usize,
     title: Option<&'a str>,
+    active: bool,
 }
 
 impl<'a> MenuList<'a> {
@@ -28,9 +29,16 @@ impl<'a> MenuList<'a> {
             items,
             selected: clamped_selected,
             title: None,
+            active: true,
         }
     }
 
+    /// Whether the list has focus; an inactive list shows no selection
+    pub fn active(mut self, active: bool) -> Self {
+        self.active = active;
+        self
+    }
+
     pub fn title(mut self, title: &'a str) -> Self {
         self.title = Some(title);
         self
@@ -44,8 +52,9 @@ impl Widget for MenuList<'_> {
             .iter()
             .enumerate()
             .map(|(i, item)| {
-                let prefix = if i == self.selected { "> " } else { "  " };
-                let style = if i == self.selected {
+                let is_selected = self.active && i == self.selected;
+                let prefix = if is_selected { "> " } else { "  " };
+                let style = if is_selected {
                     theme::selected()
                 } else {
                     theme::text()
@@ -68,7 +77,7 @@ impl Widget for MenuList<'_> {
         let list = List::new(items).block(block);
 
         // Use StatefulWidget to highlight selected item
-        let mut state = ListState::default().with_selected(Some(self.selected));
+        let mut state = ListState::default().with_selected(self.active.then_some(self.selected));
         StatefulWidget::render(list, area, buf, &mut state);
     }
 }
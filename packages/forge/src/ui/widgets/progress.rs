@@ -37,8 +37,7 @@ impl Widget for ProgressSteps<'_> {
         let lines: Vec<Line> = self
             .steps
             .iter()
-            .enumerate()
-            .map(|(_i, step)| {
+            .map(|step| {
                 let (icon, style) = match step.status {
                     StepState::Pending => ("[ ]", theme::dim()),
                     StepState::Running => {